@@ -24,6 +24,10 @@ async fn main() {
                     "Descargando nativo: {}/{}",
                     progress.current, progress.total
                 ),
+                DownloadProgressType::Server => println!(
+                    "Descargando servidor: {}/{}",
+                    progress.current, progress.total
+                ),
                 DownloadProgressType::Manifest => println!(
                     "Descargando Manifesto: {}/{}",
                     progress.current, progress.total
@@ -0,0 +1,47 @@
+#ifndef PROTON_H
+#define PROTON_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define PROTON_OK 0
+#define PROTON_ERROR -1
+#define PROTON_CANCELLED -2
+
+/* Tipos de progreso */
+#define PROTON_PROGRESS_LIBRARY 0
+#define PROTON_PROGRESS_ASSET 1
+#define PROTON_PROGRESS_NATIVE 2
+#define PROTON_PROGRESS_CLIENT 3
+#define PROTON_PROGRESS_SERVER 4
+#define PROTON_PROGRESS_MANIFEST 5
+
+typedef struct ProtonDownloader ProtonDownloader;
+
+/* Se llama desde hilos internos de proton. No hay ninguna llamada después de
+ * que vuelvan proton_downloader_wait o proton_downloader_free: a partir de
+ * ahí user_data se puede liberar. */
+typedef void (*ProtonProgressCallback)(void *user_data, int kind, size_t current, size_t total,
+                                       uint64_t bytes);
+
+ProtonDownloader *proton_downloader_new(const char *game_path, const char *version_id);
+int proton_downloader_set_progress_callback(ProtonDownloader *downloader,
+                                            ProtonProgressCallback callback, void *user_data);
+int proton_downloader_start(ProtonDownloader *downloader);
+/* cancel puede llamarse desde otro hilo mientras wait está bloqueado. wait
+ * devuelve PROTON_ERROR si se llama desde dentro de un runtime de tokio. */
+int proton_downloader_wait(ProtonDownloader *downloader);
+int proton_downloader_cancel(const ProtonDownloader *downloader);
+/* No debe solaparse con ninguna otra llamada sobre el mismo descargador */
+void proton_downloader_free(ProtonDownloader *downloader);
+const char *proton_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
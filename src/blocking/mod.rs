@@ -0,0 +1,104 @@
+use once_cell::sync::Lazy;
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::Sender;
+
+use crate::downloaders::MinecraftDownloader;
+use crate::errors::ProtonError;
+use crate::manifest;
+use crate::types::{
+    DownloadProgress, FailedFile, MojangVersionInfo, MojangVersionManifest, NormalizedVersion,
+    VersionFilter, VersionTypes,
+};
+
+// Runtime propio de la fachada síncrona
+pub(crate) static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("proton-blocking")
+        .build()
+        .expect("Failed to build blocking runtime")
+});
+
+/// Ejecuta `future` en el runtime propio. Desde dentro de otro runtime de
+/// tokio bloquear el hilo haría entrar en pánico a tokio: se devuelve un error.
+pub(crate) fn block_on<T>(
+    future: impl Future<Output = Result<T, ProtonError>>,
+) -> Result<T, ProtonError> {
+    check_blocking_allowed()?;
+    RUNTIME.block_on(future)
+}
+
+/// Error si el hilo actual ya está dentro de un runtime de tokio
+pub(crate) fn check_blocking_allowed() -> Result<(), ProtonError> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Err(ProtonError::Other(
+            "the blocking API cannot be called from inside an async runtime".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_manifest() -> Result<MojangVersionManifest, ProtonError> {
+    block_on(manifest::get_manifest())
+}
+
+pub fn list_versions(filter: &VersionFilter) -> Result<Vec<MojangVersionInfo>, ProtonError> {
+    block_on(manifest::list_versions(filter))
+}
+
+pub fn resolve_version_data(version_id: &str) -> Result<NormalizedVersion, ProtonError> {
+    block_on(manifest::resolve_version_data(version_id))
+}
+
+pub fn resolve_version_matching(pattern: &str) -> Result<NormalizedVersion, ProtonError> {
+    block_on(manifest::resolve_version_matching(pattern))
+}
+
+pub fn resolve_latest(version_type: VersionTypes) -> Result<NormalizedVersion, ProtonError> {
+    block_on(manifest::resolve_latest(version_type))
+}
+
+impl MinecraftDownloader {
+    /// Versión síncrona de `download_all`. El progreso se puede leer desde
+    /// otro hilo con `Receiver::blocking_recv`.
+    pub fn download_all_blocking(
+        &mut self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<(), ProtonError> {
+        block_on(self.download_all(progress_tx))
+    }
+
+    /// Versión síncrona de `verify`
+    pub fn verify_blocking(&self) -> Result<Vec<FailedFile>, ProtonError> {
+        block_on(self.verify())
+    }
+
+    /// Versión síncrona de `retry_failed`
+    pub fn retry_failed_blocking(&self, failed: Vec<FailedFile>) -> Result<(), ProtonError> {
+        block_on(self.retry_failed(failed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+
+    #[test]
+    fn runs_outside_an_async_runtime() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let env = runtime.block_on(MockEnvironment::start());
+
+        let version = resolve_version_data(FIXTURE_VERSION).unwrap();
+        assert_eq!(version.id, FIXTURE_VERSION);
+        env.downloader().download_all_blocking(None).unwrap();
+        let client = format!("versions/{FIXTURE_VERSION}/{FIXTURE_VERSION}.jar");
+        assert!(env.fs.get(env.game_dir.join(client)).is_some());
+    }
+
+    #[tokio::test]
+    async fn refuses_to_block_inside_an_async_runtime() {
+        let error = resolve_version_data(FIXTURE_VERSION).unwrap_err();
+        assert!(error.to_string().contains("async runtime"), "{error}");
+    }
+}
@@ -0,0 +1,334 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::downloaders::ConcurrencyPreset;
+use crate::endpoints::{EndpointConfig, env_var, set_endpoints};
+use crate::errors::ProtonError;
+use crate::filesystem::set_durable_writes;
+use crate::http::{HttpOptions, IpFamily, set_http_options, set_proxy};
+use crate::manifest::{MetadataSource, set_metadata_source};
+
+/// Nombre del archivo de configuración dentro del directorio de configuración
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Servidores alternativos; los no indicados usan los de Mojang
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mirrors {
+    pub manifest: Option<String>,
+    pub resources: Option<String>,
+    pub libraries: Option<String>,
+    /// Maven del que se descargan los instaladores de Forge
+    pub forge: Option<String>,
+    /// Repositorios donde buscar las librerías que den 404; si está vacía se
+    /// usan los de `DEFAULT_MAVEN_REPOSITORIES`
+    pub maven: Vec<String>,
+}
+
+/// Valores por defecto del launcher leídos de `config.toml`. Las variables de
+/// entorno `PROTON_*` tienen prioridad sobre el archivo.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProtonConfig {
+    pub game_dir: Option<PathBuf>,
+    pub java_path: Option<PathBuf>,
+    /// Memoria en MB para las instancias que no indican la suya
+    pub min_memory_mb: Option<u32>,
+    pub max_memory_mb: Option<u32>,
+    pub concurrency: ConcurrencyPreset,
+    pub proxy: Option<String>,
+    pub user_agent: Option<String>,
+    /// Cabeceras para todas las peticiones (p. ej. `Authorization` de un mirror privado)
+    pub headers: BTreeMap<String, String>,
+    /// Archivos PEM con CAs adicionales (p. ej. la de un mirror corporativo)
+    pub ca_certificates: Vec<PathBuf>,
+    /// Hosts cuyo certificado no se verifica; solo para mirrors propios
+    pub insecure_hosts: Vec<String>,
+    pub ip_family: IpFamily,
+    /// Sincronizar con el disco cada archivo escrito (más lento, a prueba de cortes de luz)
+    pub durable_writes: bool,
+    pub metadata_source: MetadataSource,
+    pub mirrors: Mirrors,
+}
+
+impl ProtonConfig {
+    /// `PROTON_CONFIG` o `config.toml` en el directorio de configuración del usuario
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os("PROTON_CONFIG") {
+            return PathBuf::from(path);
+        }
+
+        let base = if cfg!(target_os = "windows") {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            std::env::var_os("HOME").map(|home| {
+                PathBuf::from(home)
+                    .join("Library")
+                    .join("Application Support")
+            })
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                })
+        };
+
+        base.unwrap_or_else(std::env::temp_dir)
+            .join("proton")
+            .join(CONFIG_FILE)
+    }
+
+    /// Carga la configuración de `default_path()`; si el archivo no existe se
+    /// parte de los valores por defecto
+    pub async fn load() -> Result<Self, ProtonError> {
+        Self::load_from(&Self::default_path()).await
+    }
+
+    /// Carga la configuración de un archivo concreto y aplica las variables de entorno
+    pub async fn load_from(path: &Path) -> Result<Self, ProtonError> {
+        let config = match tokio::fs::read_to_string(path).await {
+            Ok(content) => Self::parse(&content)
+                .map_err(|e| ProtonError::InvalidConfig(format!("{}: {e}", path.display())))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        config.with_env()
+    }
+
+    /// Interpreta el contenido de un `config.toml` sin aplicar el entorno
+    pub fn parse(content: &str) -> Result<Self, ProtonError> {
+        toml::from_str(content).map_err(|e| ProtonError::InvalidConfig(e.to_string()))
+    }
+
+    fn with_env(self) -> Result<Self, ProtonError> {
+        self.with_vars(env_var)
+    }
+
+    /// Aplica las variables `PROTON_*` que devuelva `var`
+    fn with_vars(mut self, var: impl Fn(&str) -> Option<String>) -> Result<Self, ProtonError> {
+        if let Some(dir) = var("PROTON_GAME_DIR") {
+            self.game_dir = Some(dir.into());
+        }
+        if let Some(java) = var("PROTON_JAVA_PATH") {
+            self.java_path = Some(java.into());
+        }
+        if let Some(mb) = var("PROTON_MIN_MEMORY_MB") {
+            self.min_memory_mb = Some(parse_memory_mb("PROTON_MIN_MEMORY_MB", &mb)?);
+        }
+        if let Some(mb) = var("PROTON_MAX_MEMORY_MB") {
+            self.max_memory_mb = Some(parse_memory_mb("PROTON_MAX_MEMORY_MB", &mb)?);
+        }
+        if let Some(preset) = var("PROTON_CONCURRENCY") {
+            self.concurrency = preset.parse()?;
+        }
+        if let Some(proxy) = var("PROTON_PROXY") {
+            self.proxy = Some(proxy);
+        }
+        if let Some(user_agent) = var("PROTON_USER_AGENT") {
+            self.user_agent = Some(user_agent);
+        }
+        if let Some(family) = var("PROTON_IP_FAMILY") {
+            self.ip_family = family.parse()?;
+        }
+        if let Some(source) = var("PROTON_METADATA_SOURCE") {
+            self.metadata_source = source.parse()?;
+        }
+        if let Some(url) = var("PROTON_MANIFEST_URL") {
+            self.mirrors.manifest = Some(url);
+        }
+        if let Some(url) = var("PROTON_RESOURCES_URL") {
+            self.mirrors.resources = Some(url);
+        }
+        if let Some(url) = var("PROTON_LIBRARIES_URL") {
+            self.mirrors.libraries = Some(url);
+        }
+
+        Ok(self)
+    }
+
+    /// Endpoints resultantes de aplicar los mirrors configurados
+    pub fn endpoints(&self) -> EndpointConfig {
+        let mut endpoints = EndpointConfig::default();
+        if let Some(url) = &self.mirrors.manifest {
+            endpoints = endpoints.manifest_url(url);
+        }
+        if let Some(url) = &self.mirrors.resources {
+            endpoints = endpoints.resources_base_url(url);
+        }
+        if let Some(url) = &self.mirrors.libraries {
+            endpoints = endpoints.libraries_base_url(url);
+        }
+        if let Some(url) = &self.mirrors.forge {
+            endpoints = endpoints.forge_maven_url(url);
+        }
+        if !self.mirrors.maven.is_empty() {
+            endpoints = endpoints.maven_repositories(self.mirrors.maven.clone());
+        }
+        endpoints
+    }
+
+    /// Aplica mirrors, servidor de metadatos, proxy, cabeceras y certificados a
+    /// la configuración global. Lo relativo a HTTP se aplica a las peticiones
+    /// que empiecen después. Las opciones HTTP se construyen desde cero, así
+    /// que aplicar la misma configuración dos veces no duplica certificados
+    /// ni hosts, y los endpoints solo se tocan si hay mirrors.
+    pub fn apply(&self) -> Result<(), ProtonError> {
+        if self.user_agent.is_some()
+            || !self.headers.is_empty()
+            || !self.ca_certificates.is_empty()
+            || !self.insecure_hosts.is_empty()
+            || self.ip_family != IpFamily::Auto
+        {
+            let mut http = HttpOptions::default();
+            if let Some(user_agent) = &self.user_agent {
+                http = http.user_agent(user_agent);
+            }
+            if !self.headers.is_empty() {
+                http.headers = self.headers.clone().into_iter().collect();
+            }
+            for path in &self.ca_certificates {
+                http = http.root_certificate_pem(path);
+            }
+            for host in &self.insecure_hosts {
+                http = http.danger_accept_invalid_certs_for(host);
+            }
+            http = http.ip_family(self.ip_family);
+            set_http_options(http)?;
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| ProtonError::InvalidConfig(format!("proxy '{proxy}': {e}")))?;
+            set_proxy(proxy);
+        }
+        if self.durable_writes {
+            set_durable_writes(true);
+        }
+        if self.mirrors != Mirrors::default() {
+            set_endpoints(self.endpoints());
+        }
+        set_metadata_source(self.metadata_source);
+        Ok(())
+    }
+}
+
+fn parse_memory_mb(name: &str, value: &str) -> Result<u32, ProtonError> {
+    value
+        .parse()
+        .map_err(|_| ProtonError::InvalidConfig(format!("{name}: '{value}' is not a number of MB")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::endpoints;
+    use crate::http::http_options;
+    use crate::testing::exclusive;
+    use std::collections::HashMap;
+
+    #[test]
+    fn parses_a_full_config() {
+        let config = ProtonConfig::parse(
+            r#"
+            game_dir = "/games/minecraft"
+            max_memory_mb = 4096
+            concurrency = "conservative"
+            ip_family = "prefer-ipv4"
+            metadata_source = "launcher-meta"
+            insecure_hosts = ["mirror.local"]
+
+            [headers]
+            Authorization = "Bearer token"
+
+            [mirrors]
+            libraries = "https://mirror.local/libraries"
+            maven = ["https://maven.local/"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.game_dir, Some(PathBuf::from("/games/minecraft")));
+        assert_eq!(config.min_memory_mb, None);
+        assert_eq!(config.max_memory_mb, Some(4096));
+        assert_eq!(config.concurrency, ConcurrencyPreset::Conservative);
+        assert_eq!(config.ip_family, IpFamily::PreferIpv4);
+        assert_eq!(config.metadata_source, MetadataSource::LauncherMeta);
+        assert_eq!(config.headers["Authorization"], "Bearer token");
+        let endpoints = config.endpoints();
+        assert_eq!(
+            endpoints.libraries_base_url,
+            "https://mirror.local/libraries"
+        );
+        assert_eq!(
+            endpoints.manifest_url,
+            EndpointConfig::default().manifest_url
+        );
+        assert_eq!(endpoints.maven_repositories, ["https://maven.local/"]);
+    }
+
+    #[test]
+    fn rejects_unknown_values() {
+        for content in [
+            "ip_family = \"ipv5\"",
+            "max_memory_mb = \"lots\"",
+            "[mirrors]\nmanifest = 1",
+        ] {
+            let result = ProtonConfig::parse(content);
+            assert!(
+                matches!(result, Err(ProtonError::InvalidConfig(_))),
+                "{content}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let vars = HashMap::from([
+            ("PROTON_GAME_DIR", "/env/game"),
+            ("PROTON_MAX_MEMORY_MB", "2048"),
+            ("PROTON_IP_FAMILY", "ipv4-only"),
+            ("PROTON_MANIFEST_URL", "https://mirror.local/manifest.json"),
+        ]);
+        let config = ProtonConfig::parse("game_dir = \"/file/game\"\nmax_memory_mb = 1024")
+            .unwrap()
+            .with_vars(|name| vars.get(name).map(|value| value.to_string()))
+            .unwrap();
+
+        assert_eq!(config.game_dir, Some(PathBuf::from("/env/game")));
+        assert_eq!(config.max_memory_mb, Some(2048));
+        assert_eq!(config.ip_family, IpFamily::Ipv4Only);
+        assert_eq!(
+            config.mirrors.manifest.as_deref(),
+            Some("https://mirror.local/manifest.json")
+        );
+
+        let invalid = ProtonConfig::default()
+            .with_vars(|name| (name == "PROTON_MIN_MEMORY_MB").then(|| "2G".to_string()));
+        assert!(matches!(invalid, Err(ProtonError::InvalidConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn apply_is_idempotent_and_keeps_unset_endpoints() {
+        let _guard = exclusive().await;
+        let (previous_http, previous_endpoints) = (http_options(), endpoints());
+        let custom = EndpointConfig::default().manifest_url("http://127.0.0.1:1/manifest.json");
+        set_endpoints(custom.clone());
+
+        let config = ProtonConfig {
+            insecure_hosts: vec!["mirror.local".to_string()],
+            ..ProtonConfig::default()
+        };
+        let applied = config.apply().and_then(|()| config.apply());
+        let http = http_options();
+        let current_endpoints = endpoints();
+        set_http_options(previous_http).unwrap();
+        set_endpoints(previous_endpoints);
+
+        applied.unwrap();
+        assert_eq!(http.insecure_hosts, ["mirror.local"]);
+        // Sin mirrors no se pisan los endpoints fijados por el programa
+        assert_eq!(current_endpoints, custom);
+    }
+}
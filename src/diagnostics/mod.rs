@@ -0,0 +1,133 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// Incidencia no fatal durante descargas o resolución de metadatos, pensada
+/// para mostrarse en interfaces gráficas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Diagnostic {
+    /// Un intento de descarga falló y se reintentará
+    DownloadRetry {
+        url: String,
+        attempt: usize,
+        error: String,
+    },
+    /// El archivo descargado no coincide con el hash publicado
+    HashMismatch {
+        url: String,
+        attempt: usize,
+        expected: String,
+        actual: String,
+    },
+    /// Un archivo existente estaba corrupto y se vuelve a descargar
+    CorruptedFile { path: PathBuf },
+    /// Un servidor de metadatos falló y se probará el siguiente
+    MirrorFallback { url: String, error: String },
+    /// Sin conexión con los servidores, se usa la copia en caché
+    CachedMetadataUsed { url: String, cache_path: PathBuf },
+    /// La concurrencia adaptativa cambió
+    ConcurrencyAdjusted { from: usize, to: usize },
+    /// CPU o disco saturados: se reduce la concurrencia
+    HostSaturated {
+        cpu_percent: u8,
+        io_percent: Option<u8>,
+    },
+}
+
+static DIAGNOSTICS: Lazy<RwLock<Option<UnboundedSender<Diagnostic>>>> =
+    Lazy::new(|| RwLock::new(None));
+
+/// Registra (o quita con `None`) el canal que recibe los diagnósticos
+pub fn set_diagnostics_sender(sender: Option<UnboundedSender<Diagnostic>>) {
+    *DIAGNOSTICS.write().unwrap_or_else(|e| e.into_inner()) = sender;
+}
+
+/// Crea un canal nuevo de diagnósticos y devuelve su receptor
+pub fn diagnostics_channel() -> UnboundedReceiver<Diagnostic> {
+    let (tx, rx) = unbounded_channel();
+    set_diagnostics_sender(Some(tx));
+    rx
+}
+
+pub(crate) fn emit(diagnostic: Diagnostic) {
+    if let Some(sender) = DIAGNOSTICS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+    {
+        // Si el receptor se cerró simplemente se descarta
+        let _ = sender.send(diagnostic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{MemoryFs, with_file_system};
+    use crate::testing::{exclusive, sha1};
+    use crate::utilities::download_file;
+    use std::sync::Arc;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn hash_mismatches_reach_the_channel() {
+        let _guard = exclusive().await;
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"library".to_vec()))
+            .mount(&server)
+            .await;
+        let url = format!("{}/library.jar", server.uri());
+        let expected = sha1(b"other");
+        let mut rx = diagnostics_channel();
+
+        let result = with_file_system(
+            Arc::new(MemoryFs::new()),
+            download_file(
+                url.clone(),
+                &PathBuf::from("/library.jar"),
+                Some(&expected),
+                7,
+            ),
+        )
+        .await;
+        set_diagnostics_sender(None);
+
+        assert!(result.is_err());
+        // Otros tests pueden emitir a la vez; solo cuentan los de esta URL
+        let mut received = Vec::new();
+        while let Some(diagnostic) = rx.recv().await {
+            if matches!(&diagnostic, Diagnostic::HashMismatch { url: got, .. } if *got == url) {
+                received.push(diagnostic);
+            }
+        }
+        let attempts: Vec<_> = received
+            .iter()
+            .map(|diagnostic| match diagnostic {
+                Diagnostic::HashMismatch { attempt, .. } => *attempt,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(attempts, [1, 2, 3]);
+
+        // Los frontends reciben JSON etiquetado por `kind`
+        assert_eq!(
+            serde_json::to_value(&received[0]).unwrap(),
+            serde_json::json!({
+                "kind": "hashMismatch",
+                "url": url,
+                "attempt": 1,
+                "expected": expected,
+                "actual": sha1(b"library"),
+            })
+        );
+    }
+}
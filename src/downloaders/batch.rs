@@ -0,0 +1,360 @@
+use super::lock::DirLock;
+use super::{MinecraftDownloader, calculate_optimal_downloads};
+use crate::endpoints::endpoints;
+use crate::errors::{ErrorKind, ProtonError};
+use crate::filesystem::{FileSystem, scoped_file_system};
+use crate::types::{DownloadProgress, NormalizedVersion};
+use crate::utilities::{download_file, save_hash_cache};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{OnceCell, Semaphore};
+
+// Resultado de una descarga compartida; todas las versiones que esperan el
+// archivo reciben el mismo error
+type SharedResult = Arc<OnceCell<Result<(), Arc<ProtonError>>>>;
+
+/// Descargas de un lote: un único presupuesto de conexiones para todas las
+/// versiones y cada archivo compartido (librería, asset, índice) se baja una
+/// sola vez aunque lo pidan varias
+pub(super) struct SharedDownloads {
+    budget: Semaphore,
+    files: Mutex<HashMap<(PathBuf, String), SharedResult>>,
+}
+
+impl SharedDownloads {
+    fn new(concurrency: usize) -> Self {
+        Self {
+            budget: Semaphore::new(concurrency.max(1)),
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn download(
+        &self,
+        url: String,
+        path: &PathBuf,
+        expected_hash: Option<&str>,
+        expected_size: u64,
+    ) -> Result<(), ProtonError> {
+        let cell = {
+            let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+            let key = (
+                path.clone(),
+                expected_hash.unwrap_or_default().to_lowercase(),
+            );
+            Arc::clone(files.entry(key).or_default())
+        };
+
+        // Quien llega después espera al primero y recibe su resultado
+        cell.get_or_init(|| async {
+            let _permit = self
+                .budget
+                .acquire()
+                .await
+                .map_err(|_| Arc::new(ProtonError::Cancelled))?;
+            download_with_fallback(url, path, expected_hash, expected_size)
+                .await
+                .map_err(Arc::new)
+        })
+        .await
+        .clone()
+        .map_err(ProtonError::Shared)
+    }
+}
+
+/// `download_file`; si la URL es de un repositorio maven y da 404, prueba la
+/// misma ruta en los demás repositorios configurados antes de fallar
+pub(super) async fn download_with_fallback(
+    url: String,
+    path: &PathBuf,
+    expected_hash: Option<&str>,
+    expected_size: u64,
+) -> Result<(), ProtonError> {
+    let error = match download_file(url.clone(), path, expected_hash, expected_size).await {
+        Err(e) if e.kind() == ErrorKind::NotFound => e,
+        result => return result,
+    };
+
+    for fallback in endpoints().library_fallback_urls(&url) {
+        warn!("{url} not found, trying {fallback}");
+        match download_file(fallback.clone(), path, expected_hash, expected_size).await {
+            Ok(()) => {
+                info!("Downloaded {path:?} from {fallback}");
+                return Ok(());
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(error)
+}
+
+/// `download_with_fallback`, pasando por las descargas del lote si lo hay
+pub(super) async fn fetch(
+    shared: Option<&SharedDownloads>,
+    url: String,
+    path: &PathBuf,
+    expected_hash: Option<&str>,
+    expected_size: u64,
+) -> Result<(), ProtonError> {
+    match shared {
+        Some(shared) => {
+            shared
+                .download(url, path, expected_hash, expected_size)
+                .await
+        }
+        None => download_with_fallback(url, path, expected_hash, expected_size).await,
+    }
+}
+
+/// Instala varias versiones (p. ej. las de una lista de instancias) a la vez,
+/// con un único límite de descargas simultáneas para todas y sin bajar dos
+/// veces lo que comparten. El progreso de cada versión llega por el mismo
+/// canal, distinguido por `DownloadProgressInfo::version`.
+pub struct BatchDownloader {
+    downloaders: Vec<MinecraftDownloader>,
+    concurrency: usize,
+    file_system: Option<Arc<dyn FileSystem>>,
+}
+
+impl BatchDownloader {
+    pub fn new() -> Self {
+        Self {
+            downloaders: Vec::new(),
+            concurrency: calculate_optimal_downloads(),
+            file_system: None,
+        }
+    }
+
+    /// Añade una versión a instalar en `game_path`
+    pub fn version(self, game_path: PathBuf, version: NormalizedVersion) -> Self {
+        self.downloader(MinecraftDownloader::new(game_path, version))
+    }
+
+    /// Añade un descargador ya configurado (servidor, filtros de assets,
+    /// tolerancia a fallos); su límite de concurrencia queda bajo el del lote
+    /// y su sistema de archivos se sustituye por el del lote
+    pub fn downloader(mut self, downloader: MinecraftDownloader) -> Self {
+        self.downloaders.push(downloader);
+        self
+    }
+
+    /// Descargas simultáneas en total, entre todas las versiones
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit;
+        self
+    }
+
+    /// Sistema de archivos de todo el lote en lugar del global; las descargas
+    /// compartidas necesitan que todas las versiones escriban en el mismo
+    pub fn file_system(mut self, fs: Arc<dyn FileSystem>) -> Self {
+        self.file_system = Some(fs);
+        self
+    }
+
+    /// Instala todas las versiones. Si alguna falla se espera al resto; los
+    /// archivos fallidos de todas se devuelven juntos en `PartialFailure`.
+    pub async fn download_all(
+        &mut self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<(), ProtonError> {
+        let fs = self.file_system.clone();
+        scoped_file_system(fs, async {
+            let mut game_paths: Vec<PathBuf> = self
+                .downloaders
+                .iter()
+                .map(|downloader| downloader.game_path.clone())
+                .collect();
+            game_paths.sort();
+            game_paths.dedup();
+
+            let mut locks = Vec::with_capacity(game_paths.len());
+            for game_path in &game_paths {
+                match DirLock::acquire(game_path).await {
+                    Ok(lock) => locks.push(lock),
+                    Err(e) => {
+                        for lock in locks {
+                            lock.release().await;
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+
+            let shared = Arc::new(SharedDownloads::new(self.concurrency));
+            info!(
+                "Installing {} version(s) with a shared limit of {} downloads",
+                self.downloaders.len(),
+                self.concurrency
+            );
+            let results =
+                futures::future::join_all(self.downloaders.iter_mut().map(|downloader| {
+                    downloader.shared = Some(Arc::clone(&shared));
+                    downloader.download_all_locked(progress_tx.clone())
+                }))
+                .await;
+            for downloader in &mut self.downloaders {
+                downloader.shared = None;
+            }
+
+            save_hash_cache().await;
+            for lock in locks {
+                lock.release().await;
+            }
+
+            let mut failures = Vec::new();
+            for result in results {
+                match result {
+                    Ok(()) => {}
+                    Err(ProtonError::PartialFailure(files)) => failures.extend(files),
+                    Err(e) => return Err(e),
+                }
+            }
+            if failures.is_empty() {
+                Ok(())
+            } else {
+                Err(ProtonError::PartialFailure(failures))
+            }
+        })
+        .await
+    }
+}
+
+impl Default for BatchDownloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::set_endpoints;
+    use crate::filesystem::MemoryFs;
+    use crate::filesystem::file_system;
+    use crate::testing::{MockEnvironment, fixture_asset_index, fixture_version_details, sha1};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    #[tokio::test]
+    async fn missing_libraries_are_fetched_from_the_next_repository() {
+        let env = MockEnvironment::start().await;
+        let server = &env.server;
+        let jar_path = "org/proton/fallback/1.0/fallback-1.0.jar";
+        let content = b"fallback library".to_vec();
+        Mock::given(method("GET"))
+            .and(path(format!("/maven/{jar_path}")))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(content.clone()))
+            .mount(server.server())
+            .await;
+        // El primer repositorio tampoco lo tiene: se sigue con el siguiente
+        set_endpoints(server.endpoints().maven_repositories(vec![
+            format!("{}/empty", server.uri()),
+            format!("{}/maven", server.uri()),
+        ]));
+
+        let mut version = server.version();
+        let library = &mut version.libraries[0];
+        library.url = format!("{}/libraries/{jar_path}", server.uri());
+        library.path = jar_path.to_string();
+        library.sha1 = sha1(&content);
+        library.size = content.len() as u64;
+        let fs = MemoryFs::new();
+        let game_path = PathBuf::from("/game");
+        MinecraftDownloader::new(game_path.clone(), version)
+            .file_system(Arc::new(fs.clone()))
+            .download_all(None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fs.get(game_path.join("libraries").join(jar_path)),
+            Some(content)
+        );
+        let requests = server.server().received_requests().await.unwrap();
+        let tried: Vec<_> = requests
+            .iter()
+            .map(|request| request.url.path())
+            .filter(|path| path.ends_with(jar_path))
+            .collect();
+        assert_eq!(
+            tried,
+            [
+                format!("/libraries/{jar_path}"),
+                format!("/empty/{jar_path}"),
+                format!("/maven/{jar_path}"),
+            ]
+        );
+    }
+    #[tokio::test]
+    async fn shared_files_are_fetched_once_for_all_versions() {
+        let env = MockEnvironment::start().await;
+        let first = env.server.version();
+        let mut second = first.clone();
+        second.id = "1.0-proton-second".to_string();
+        // No está en el manifest: se instala desde su JSON local
+        let mut details = fixture_version_details(&env.server.uri());
+        details.id = second.id.clone();
+        let json_dir = env.game_dir.join("versions").join(&second.id);
+        env.run(async {
+            file_system().create_dir_all(&json_dir).await?;
+            let json = serde_json::to_vec(&details).map_err(std::io::Error::other)?;
+            file_system()
+                .write(&json_dir.join(format!("{}.json", second.id)), &json)
+                .await
+        })
+        .await
+        .unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1024);
+        let mut batch = BatchDownloader::new()
+            .file_system(Arc::new(env.fs.clone()))
+            .version(env.game_dir.clone(), first.clone())
+            .version(env.game_dir.clone(), second.clone());
+        batch.download_all(Some(tx)).await.unwrap();
+
+        let mut versions = std::collections::HashSet::new();
+        while let Some(progress) = rx.recv().await {
+            versions.insert(progress.info.version.to_string());
+        }
+        assert_eq!(
+            versions,
+            [first.id.clone(), second.id.clone()].into_iter().collect()
+        );
+
+        // Cada versión tiene su jar de cliente y extrae sus nativos en
+        // `natives/<id>`; librerías, índice y assets se comparten
+        let requests = env.server.server().received_requests().await.unwrap();
+        let fetches = |file_path: &str| {
+            requests
+                .iter()
+                .filter(|request| request.url.path() == file_path)
+                .count()
+        };
+        assert_eq!(fetches("/client.jar"), 2);
+        assert_eq!(
+            fetches("/libraries/org/proton/natives/1.0/natives-1.0-natives.jar"),
+            2
+        );
+        let mut shared = vec![
+            "/libraries/org/proton/library/1.0/library-1.0.jar".to_string(),
+            "/indexes/fixture.json".to_string(),
+        ];
+        shared.extend(
+            fixture_asset_index()
+                .objects
+                .values()
+                .map(|asset| format!("/resources/{}/{}", &asset.hash[..2], asset.hash)),
+        );
+        for file_path in &shared {
+            assert_eq!(fetches(file_path), 1, "{file_path}");
+        }
+        for version in [&first, &second] {
+            let jar = format!("versions/{0}/{0}.jar", version.id);
+            assert!(env.fs.get(env.game_dir.join(jar)).is_some());
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use super::MinecraftDownloader;
+use super::natives_state::NativesState;
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::plan::{DownloadPlan, PlannedDownload, VersionDelta};
+use crate::types::{DownloadProgressType, NormalizedVersion};
+use crate::utilities::verify_file_hash;
+use std::path::Path;
+
+/// Compara la versión instalada con la destino y con lo que hay en disco:
+/// `added` queda solo con lo que de verdad falta (lo que ya comparte otra
+/// versión o dejó una instalación a medias no cuenta) y `removed` solo con
+/// lo que existe. Después `download_all` de la versión destino descarga
+/// exactamente `added`.
+pub async fn diff_versions(
+    game_dir: impl AsRef<Path>,
+    installed: &NormalizedVersion,
+    target: &NormalizedVersion,
+) -> Result<VersionDelta, ProtonError> {
+    let game_dir = game_dir.as_ref();
+    let current = MinecraftDownloader::new(game_dir.to_path_buf(), installed.clone());
+    let next = MinecraftDownloader::new(game_dir.to_path_buf(), target.clone());
+    let mut delta = current
+        .download_plan()
+        .await?
+        .diff(&next.download_plan().await?);
+    keep_missing(game_dir, &next, &mut delta).await;
+
+    // Los jars de nativos no se conservan: cuenta si se extrajeron
+    let current_natives = NativesState::load(&current.natives_dir).await;
+    let mut removed = Vec::new();
+    for file in std::mem::take(&mut delta.removed) {
+        let present = if file.download_type == DownloadProgressType::Native {
+            current_natives.extracted.contains_key(&file.sha1)
+        } else {
+            file_system().exists(&game_dir.join(&file.path)).await
+        };
+        if present {
+            removed.push(file);
+        }
+    }
+    delta.removed = removed;
+
+    Ok(delta)
+}
+
+/// Como `diff_versions` cuando no se sabe qué versión hay instalada: todos
+/// los archivos de `target` que falten en disco, sin `removed`
+pub(crate) async fn diff_from_disk(
+    game_dir: &Path,
+    target: &NormalizedVersion,
+) -> Result<VersionDelta, ProtonError> {
+    let next = MinecraftDownloader::new(game_dir.to_path_buf(), target.clone());
+    let mut delta = DownloadPlan::default().diff(&next.download_plan().await?);
+    keep_missing(game_dir, &next, &mut delta).await;
+    Ok(delta)
+}
+
+/// Deja en `added` solo lo que no está ya en disco o extraído
+async fn keep_missing(game_dir: &Path, next: &MinecraftDownloader, delta: &mut VersionDelta) {
+    let next_natives = NativesState::load(&next.natives_dir).await;
+    let mut added = Vec::new();
+    for file in std::mem::take(&mut delta.added) {
+        let present = if file.download_type == DownloadProgressType::Native {
+            next_natives
+                .is_up_to_date(&next.natives_dir, &file.sha1)
+                .await
+        } else {
+            on_disk(game_dir, &file).await
+        };
+        if present {
+            delta.unchanged += 1;
+        } else {
+            added.push(file);
+        }
+    }
+    delta.added = added;
+}
+
+async fn on_disk(game_dir: &Path, file: &PlannedDownload) -> bool {
+    verify_file_hash(&game_dir.join(&file.path), &file.sha1, file.size)
+        .await
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::with_file_system;
+    use crate::testing::{MockEnvironment, sha1};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn upgrades_only_add_what_changed() {
+        let env = MockEnvironment::start().await;
+        let (server, fs, game_path) = (&env.server, &env.fs, &env.game_dir);
+        let installed = server.version();
+        MinecraftDownloader::new(game_path.clone(), installed.clone())
+            .file_system(Arc::new(fs.clone()))
+            .download_all(None)
+            .await
+            .unwrap();
+
+        // La siguiente versión cambia una librería y comparte los assets
+        let mut target = installed.clone();
+        target.id = "next".to_string();
+        let library = &mut target.libraries[0];
+        let old_library = PathBuf::from("libraries").join(&library.path);
+        library.path = library.path.replace("1.0", "2.0");
+        library.sha1 = sha1(b"library 2.0");
+        library.size = 11;
+        let new_library = PathBuf::from("libraries").join(&library.path);
+
+        let (delta, after) = with_file_system(Arc::new(fs.clone()), async {
+            let delta = diff_versions(&game_path, &installed, &target).await?;
+            file_system()
+                .create_dir_all(game_path.join(&new_library).parent().unwrap())
+                .await?;
+            file_system()
+                .write(&game_path.join(&new_library), b"library 2.0")
+                .await?;
+            let after = diff_versions(&game_path, &installed, &target).await?;
+            Ok::<_, ProtonError>((delta, after))
+        })
+        .await
+        .unwrap();
+
+        let added: Vec<_> = delta.added.iter().map(|file| &file.path).collect();
+        assert!(added.contains(&&new_library), "{added:?}");
+        assert!(
+            !delta
+                .added
+                .iter()
+                .any(|file| file.download_type == DownloadProgressType::Asset)
+        );
+        assert!(delta.unchanged > 0);
+        assert!(delta.removed.iter().any(|file| file.path == old_library));
+        // Lo que ya está en disco no se vuelve a descargar
+        assert!(!after.added.iter().any(|file| file.path == new_library));
+        assert_eq!(after.unchanged, delta.unchanged + 1);
+    }
+}
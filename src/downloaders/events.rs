@@ -0,0 +1,152 @@
+use super::MinecraftDownloader;
+use crate::errors::ProtonError;
+use crate::types::DownloadProgress;
+use futures::{Stream, StreamExt};
+use log::warn;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::sync::mpsc::{Receiver, channel};
+
+// Eventos que se acumulan si quien consume el stream va más lento
+const EVENT_BUFFER: usize = 256;
+
+/// Evento de `download_all_stream`
+#[derive(Debug)]
+pub enum DownloadEvent {
+    Progress(DownloadProgress),
+    /// Resultado de `download_all`; siempre es el último evento
+    Finished(Result<(), ProtonError>),
+}
+
+type Download<'a> = Pin<Box<dyn Future<Output = Result<(), ProtonError>> + Send + 'a>>;
+
+struct StreamState<'a> {
+    download: Option<Download<'a>>,
+    rx: Receiver<DownloadProgress>,
+    result: Option<Result<(), ProtonError>>,
+}
+
+impl MinecraftDownloader {
+    /// `download_all` como `Stream` de eventos, en lugar de pasar un canal.
+    /// La descarga avanza mientras se consume el stream.
+    pub fn download_all_stream(&mut self) -> impl Stream<Item = DownloadEvent> + Send + '_ {
+        let (tx, rx) = channel(EVENT_BUFFER);
+        let state = StreamState {
+            download: Some(Box::pin(self.download_all(Some(tx)))),
+            rx,
+            result: None,
+        };
+
+        futures::stream::unfold(state, |mut state| async move {
+            if let Some(download) = state.download.as_mut() {
+                tokio::select! {
+                    Some(progress) = state.rx.recv() => {
+                        return Some((DownloadEvent::Progress(progress), state));
+                    }
+                    result = download => {
+                        state.download = None;
+                        state.result = Some(result);
+                    }
+                }
+            }
+
+            // Los eventos ya enviados salen antes que el resultado; los de
+            // tareas que sigan en marcha tras un error se descartan
+            if let Ok(progress) = state.rx.try_recv() {
+                return Some((DownloadEvent::Progress(progress), state));
+            }
+            let result = state.result.take()?;
+            Some((DownloadEvent::Finished(result), state))
+        })
+    }
+
+    /// `download_all` avisando a un callback en lugar de a un canal; recibe los
+    /// mismos eventos que `download_all_stream`. El callback se ejecuta en un
+    /// hilo aparte con una cola sin límite, así que aunque sea lento no frena
+    /// las descargas. Vuelve cuando el callback ha recibido `Finished`.
+    pub async fn download_all_with_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(DownloadEvent) + Send + Sync + 'static,
+    {
+        let (queue, events) = std::sync::mpsc::channel();
+        let caller = tokio::task::spawn_blocking(move || events.into_iter().for_each(callback));
+
+        let mut stream = std::pin::pin!(self.download_all_stream());
+        while let Some(event) = stream.next().await {
+            // Si el callback entró en pánico se sigue descargando sin avisar
+            let _ = queue.send(event);
+        }
+        drop(queue);
+
+        if let Err(e) = caller.await {
+            warn!("Download callback panicked: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFs;
+    use crate::testing::MockEnvironment;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn stream_ends_with_the_result() {
+        let env = MockEnvironment::start().await;
+        let server = &env.server;
+        let mut downloader = env.downloader();
+
+        let events: Vec<_> = downloader.download_all_stream().collect().await;
+
+        let (last, progress) = events.split_last().unwrap();
+        assert!(matches!(last, DownloadEvent::Finished(Ok(()))));
+        assert!(!progress.is_empty());
+        assert!(
+            progress
+                .iter()
+                .all(|event| matches!(event, DownloadEvent::Progress(_)))
+        );
+
+        // Un error también llega como último evento
+        let mut version = server.version();
+        version.client_jar.url = format!("{}/missing.jar", server.uri());
+        let mut failing = MinecraftDownloader::new(PathBuf::from("/game"), version)
+            .file_system(Arc::new(MemoryFs::new()));
+        let events: Vec<_> = failing.download_all_stream().collect().await;
+        assert!(matches!(
+            events.last(),
+            Some(DownloadEvent::Finished(Err(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn slow_callbacks_do_not_hold_back_downloads() {
+        let env = MockEnvironment::start().await;
+        let fs = &env.fs;
+        let jar = env
+            .game_dir
+            .join("libraries/org/proton/library/1.0/library-1.0.jar");
+        let mut downloader = env.downloader();
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let received = Arc::clone(&events);
+        let observed = fs.clone();
+        downloader
+            .download_all_with_callback(move |event| {
+                let mut received = received.lock().unwrap();
+                if received.is_empty() {
+                    // Mientras el primer evento se atiende, la descarga sigue
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                    received.push(observed.get(&jar).is_some());
+                }
+                if let DownloadEvent::Finished(result) = event {
+                    received.push(result.is_ok());
+                }
+            })
+            .await;
+
+        assert_eq!(*events.lock().unwrap(), [true, true]);
+    }
+}
@@ -0,0 +1,117 @@
+use super::MinecraftDownloader;
+use crate::errors::ProtonError;
+use crate::filesystem::{file_system, scoped_file_system};
+use crate::plan::{DownloadListFormat, DownloadPlan, PlannedDownload};
+use crate::types::DownloadProgressType;
+use std::path::{Path, PathBuf};
+
+impl MinecraftDownloader {
+    /// Escribe en `path` las URLs, rutas y SHA-1 de todo lo que instalaría
+    /// `download_all`, para bajarlo con otra herramienta (p. ej. `aria2c -i`)
+    /// y comprobarlo después con `verify`. Devuelve el número de archivos.
+    pub async fn export_download_list(
+        &self,
+        path: impl AsRef<Path>,
+        format: DownloadListFormat,
+    ) -> Result<usize, ProtonError> {
+        let fs = self.file_system.clone();
+        scoped_file_system(fs, async {
+            let plan = self.download_plan().await?;
+            let list = plan.to_download_list(&self.game_path, format);
+
+            let path = path.as_ref();
+            let fs = file_system();
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs.create_dir_all(parent).await?;
+            }
+            fs.write(path, list.as_bytes()).await?;
+
+            Ok(plan.files.len())
+        })
+        .await
+    }
+
+    /// Plan de la versión con los filtros de assets y el server jar aplicados
+    pub(super) async fn download_plan(&self) -> Result<DownloadPlan, ProtonError> {
+        let mut assets = self.load_asset_index().await?;
+        assets
+            .objects
+            .retain(|name, asset| self.asset_filters.iter().all(|f| f.keeps(name, asset)));
+        let mut plan = DownloadPlan::new(&self.game_version, Some(&assets));
+
+        if self.include_server
+            && let Some(server) = &self.game_version.server_jar
+        {
+            let id = &self.game_version.id;
+            plan.files.push(PlannedDownload {
+                name: format!("server-{id}"),
+                url: server.url.clone(),
+                path: PathBuf::from("versions").join(id).join("server.jar"),
+                sha1: server.sha1.clone(),
+                size: server.size,
+                download_type: DownloadProgressType::Server,
+            });
+        }
+
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filesystem::FileSystem;
+    use crate::plan::{DownloadListFormat, DownloadPlan};
+    use crate::testing::MockEnvironment;
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn lists_every_planned_file_without_downloading() {
+        let env = MockEnvironment::start().await;
+        let downloader = env.downloader();
+
+        let aria2 = downloader
+            .export_download_list("/exports/list.txt", DownloadListFormat::Aria2)
+            .await
+            .unwrap();
+        let json = downloader
+            .export_download_list("/exports/list.json", DownloadListFormat::Json)
+            .await
+            .unwrap();
+
+        assert!(aria2 > 0);
+        assert_eq!(aria2, json);
+        let list =
+            String::from_utf8(env.fs.read(Path::new("/exports/list.txt")).await.unwrap()).unwrap();
+        let entries: Vec<Vec<&str>> = list
+            .lines()
+            .collect::<Vec<_>>()
+            .chunks(4)
+            .map(<[&str]>::to_vec)
+            .collect();
+        assert_eq!(entries.len(), aria2);
+        for entry in &entries {
+            assert!(entry[0].starts_with(&env.server.uri()), "{entry:?}");
+            assert_eq!(entry[1], "  dir=/game");
+            assert!(entry[2].starts_with("  out=") && !entry[2].contains('\\'));
+            let sha1 = entry[3].strip_prefix("  checksum=sha-1=").unwrap();
+            assert_eq!(sha1.len(), 40, "{entry:?}");
+        }
+
+        let plan: DownloadPlan =
+            serde_json::from_slice(&env.fs.read(Path::new("/exports/list.json")).await.unwrap())
+                .unwrap();
+        assert_eq!(plan.files.len(), json);
+        assert!(plan.files.iter().all(|file| file.path.starts_with("/game")));
+
+        // Solo se piden los metadatos, nunca los archivos del plan
+        let requests = env.server.server().received_requests().await.unwrap();
+        assert!(
+            requests
+                .iter()
+                .all(|request| !request.url.path().ends_with(".jar"))
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use super::MinecraftDownloader;
+use super::import::collect_files;
+use super::lock::DirLock;
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::manifest::{resolve_version_data, resolve_version_from_file};
+use crate::types::NormalizedVersion;
+use crate::utilities::{move_file, temp_path};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// Registro, dentro de `versions/<id>`, de los archivos que generó el
+/// instalador de la versión fuera de sus librerías (p. ej. las salidas de los
+/// procesadores de Forge). `gc` no los borra mientras la versión exista.
+pub const EXTRA_FILES: &str = "extra_files.json";
+
+/// Resultado de `gc`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    /// Archivos y directorios borrados (o que se borrarían en modo prueba)
+    pub removed: Vec<PathBuf>,
+    /// Espacio liberado en bytes
+    pub freed_bytes: u64,
+    pub dry_run: bool,
+}
+
+/// Lo que referencian las versiones conservadas
+#[derive(Default)]
+struct Referenced {
+    versions: HashSet<String>,
+    libraries: HashSet<PathBuf>,
+    /// Directorios de `libraries/` que se conservan enteros
+    library_dirs: Vec<PathBuf>,
+    asset_indexes: HashSet<String>,
+    objects: HashSet<String>,
+    log_configs: HashSet<String>,
+}
+
+/// Borra las librerías, nativos, assets e índices que no usa ninguna versión
+/// instalada en `versions/` ni ninguna de `keep_versions` (versiones fijadas
+/// aunque su carpeta no exista). Con `dry_run` solo se informa. Para
+/// desinstalar una versión basta con borrar `versions/<id>` y llamar a `gc`.
+pub async fn gc(
+    game_dir: impl AsRef<Path>,
+    keep_versions: &[String],
+    dry_run: bool,
+) -> Result<GcReport, ProtonError> {
+    let game_dir = game_dir.as_ref();
+    let lock = DirLock::acquire(game_dir).await?;
+    let result = gc_locked(game_dir, keep_versions, dry_run).await;
+    lock.release().await;
+    result
+}
+
+async fn gc_locked(
+    game_dir: &Path,
+    keep_versions: &[String],
+    dry_run: bool,
+) -> Result<GcReport, ProtonError> {
+    // Un JSON ilegible aborta: no se sabría qué archivos necesita su versión
+    let mut referenced = Referenced::default();
+    for version in kept_versions(game_dir, keep_versions).await? {
+        let downloader = MinecraftDownloader::new(game_dir.to_path_buf(), version);
+        let version = &downloader.game_version;
+        referenced.versions.insert(version.id.clone());
+        for library in &version.libraries {
+            // Las rutas del JSON usan `/`: se normalizan al separador local
+            let relative: PathBuf = Path::new(&library.path).components().collect();
+            referenced
+                .libraries
+                .insert(downloader.libraries_dir.join(relative));
+        }
+        for path in load_extra_files(game_dir, &version.id).await? {
+            referenced.libraries.insert(game_dir.join(path));
+        }
+        referenced.library_dirs.extend(
+            fml_output_dirs(&version.arguments.game)
+                .into_iter()
+                .map(|dir| downloader.libraries_dir.join(dir)),
+        );
+        if let Some(logging) = &version.logging {
+            referenced.log_configs.insert(logging.id.clone());
+        }
+        referenced
+            .asset_indexes
+            .insert(version.asset_index.id.clone());
+        for (_, asset) in downloader.load_asset_index().await?.into_vec() {
+            referenced.objects.insert(asset.hash);
+        }
+    }
+
+    let fs = file_system();
+    let mut report = GcReport {
+        dry_run,
+        ..GcReport::default()
+    };
+
+    let libraries_dir = game_dir.join("libraries");
+    for (path, len) in files_under(&libraries_dir).await? {
+        if !referenced.libraries.contains(&path)
+            && !referenced
+                .library_dirs
+                .iter()
+                .any(|dir| path.starts_with(dir))
+        {
+            remove(&mut report, path, len).await?;
+        }
+    }
+
+    let objects_dir = game_dir.join("assets").join("objects");
+    for (path, len) in files_under(&objects_dir).await? {
+        if !referenced.objects.contains(&file_name(&path)) {
+            remove(&mut report, path, len).await?;
+        }
+    }
+
+    let indexes_dir = game_dir.join("assets").join("indexes");
+    for (path, len) in files_under(&indexes_dir).await? {
+        let id = file_name(&path);
+        if !referenced
+            .asset_indexes
+            .contains(id.strip_suffix(".json").unwrap_or(&id))
+        {
+            remove(&mut report, path, len).await?;
+        }
+    }
+
+    let log_configs_dir = game_dir.join("assets").join("log_configs");
+    for (path, len) in files_under(&log_configs_dir).await? {
+        if !referenced.log_configs.contains(&file_name(&path)) {
+            remove(&mut report, path, len).await?;
+        }
+    }
+
+    // Cada versión extrae sus nativos en `natives/<id>`
+    let natives_dir = game_dir.join("natives");
+    if fs.exists(&natives_dir).await {
+        for dir in fs.read_dir(&natives_dir).await? {
+            if referenced.versions.contains(&file_name(&dir)) {
+                continue;
+            }
+            let len = files_under(&dir).await?.iter().map(|(_, len)| len).sum();
+            remove(&mut report, dir, len).await?;
+        }
+    }
+
+    if !dry_run {
+        for dir in [libraries_dir, objects_dir] {
+            remove_empty_dirs(&dir).await?;
+        }
+    }
+
+    info!(
+        "Garbage collection {} {} path(s), {} bytes",
+        if dry_run { "would remove" } else { "removed" },
+        report.removed.len(),
+        report.freed_bytes
+    );
+    Ok(report)
+}
+
+/// Guarda en `versions/<version_id>/extra_files.json` los archivos que el
+/// instalador generó para la versión. Las rutas fuera de `game_dir` se ignoran.
+pub async fn record_extra_files(
+    game_dir: impl AsRef<Path>,
+    version_id: &str,
+    files: &[PathBuf],
+) -> Result<(), ProtonError> {
+    let game_dir = game_dir.as_ref();
+    let mut relative: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|file| file.strip_prefix(game_dir).ok())
+        .map(Path::to_path_buf)
+        .collect();
+    relative.sort();
+    relative.dedup();
+
+    let fs = file_system();
+    let path = game_dir.join("versions").join(version_id).join(EXTRA_FILES);
+    let temp = temp_path(&path);
+    let json = serde_json::to_vec_pretty(&relative).map_err(std::io::Error::other)?;
+    fs.write(&temp, &json).await?;
+    move_file(&temp, &path).await?;
+    Ok(())
+}
+
+/// Archivos registrados con `record_extra_files`, relativos a `game_dir`
+async fn load_extra_files(game_dir: &Path, version_id: &str) -> Result<Vec<PathBuf>, ProtonError> {
+    let fs = file_system();
+    let path = game_dir.join("versions").join(version_id).join(EXTRA_FILES);
+    if !fs.exists(&path).await {
+        return Ok(Vec::new());
+    }
+    // Igual que con el JSON de la versión: ilegible aborta
+    let content = fs.read(&path).await?;
+    let files: Vec<PathBuf> = serde_json::from_slice(&content)
+        .map_err(|e| ProtonError::Other(format!("Invalid {path:?}: {e}")))?;
+    Ok(files
+        .into_iter()
+        .filter(|file| file.components().all(|c| matches!(c, Component::Normal(_))))
+        .collect())
+}
+
+/// Directorios de `libraries/` con las salidas de los procesadores de Forge y
+/// NeoForge 1.13+. No figuran en las librerías del JSON: el juego los localiza
+/// con los argumentos `--fml.*`, así que se deducen de ellos para las
+/// versiones instaladas con el instalador oficial.
+fn fml_output_dirs(game_args: &[String]) -> Vec<PathBuf> {
+    let value = |name: &str| {
+        game_args
+            .windows(2)
+            .find(|pair| pair[0] == name)
+            .map(|pair| pair[1].as_str())
+            .filter(|value| is_path_segment(value))
+    };
+    let minecraft = value("--fml.mcVersion");
+    let mut dirs = Vec::new();
+
+    if let (Some(forge), Some(minecraft)) = (value("--fml.forgeVersion"), minecraft) {
+        let group = value("--fml.forgeGroup").unwrap_or("net.minecraftforge");
+        let mut dir: PathBuf = group.split('.').filter(|part| !part.is_empty()).collect();
+        dir.push("forge");
+        dir.push(format!("{minecraft}-{forge}"));
+        dirs.push(dir);
+    }
+    if let Some(neoforge) = value("--fml.neoForgeVersion") {
+        dirs.push(["net", "neoforged", "neoforge", neoforge].iter().collect());
+    }
+    for mappings in [value("--fml.mcpVersion"), value("--fml.neoFormVersion")]
+        .into_iter()
+        .flatten()
+    {
+        let client: PathBuf = ["net", "minecraft", "client"].iter().collect();
+        if let Some(minecraft) = minecraft {
+            dirs.push(client.join(format!("{minecraft}-{mappings}")));
+        }
+        dirs.push(client.join(mappings));
+    }
+    dirs
+}
+
+fn is_path_segment(value: &str) -> bool {
+    !value.is_empty()
+        && value != "."
+        && value != ".."
+        && !value.contains(['/', '\\'])
+        && !value.starts_with('$')
+}
+
+/// Versiones instaladas en `versions/` más las fijadas que no lo estén
+async fn kept_versions(
+    game_dir: &Path,
+    keep_versions: &[String],
+) -> Result<Vec<NormalizedVersion>, ProtonError> {
+    let fs = file_system();
+    let versions_dir = game_dir.join("versions");
+    let mut versions = Vec::new();
+    let mut installed = HashSet::new();
+
+    if fs.exists(&versions_dir).await {
+        for dir in fs.read_dir(&versions_dir).await? {
+            let id = file_name(&dir);
+            let json = dir.join(format!("{id}.json"));
+            if fs.exists(&json).await {
+                versions.push(resolve_version_from_file(&json).await?);
+                installed.insert(id);
+            }
+        }
+    }
+
+    for id in keep_versions {
+        if !installed.contains(id) {
+            versions.push(resolve_version_data(id).await?);
+        }
+    }
+    Ok(versions)
+}
+
+async fn files_under(dir: &Path) -> Result<Vec<(PathBuf, u64)>, ProtonError> {
+    let mut files = Vec::new();
+    if file_system().exists(dir).await {
+        collect_files(dir, &mut files).await?;
+    }
+    Ok(files)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+async fn remove(report: &mut GcReport, path: PathBuf, len: u64) -> Result<(), ProtonError> {
+    if !report.dry_run {
+        let fs = file_system();
+        if fs.metadata(&path).await?.is_dir {
+            fs.remove_dir_all(&path).await?;
+        } else {
+            fs.remove_file(&path).await?;
+        }
+    }
+    report.freed_bytes += len;
+    report.removed.push(path);
+    Ok(())
+}
+
+/// Borra los directorios vacíos bajo `dir` (sin borrar `dir`)
+async fn remove_empty_dirs(dir: &Path) -> Result<(), ProtonError> {
+    let fs = file_system();
+    if !fs.exists(dir).await {
+        return Ok(());
+    }
+
+    // Recorrido en preorden; al invertirlo los hijos van antes que el padre
+    let mut dirs = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs.read_dir(&current).await? {
+            if fs.metadata(&entry).await?.is_dir {
+                pending.push(entry.clone());
+                dirs.push(entry);
+            }
+        }
+    }
+
+    for dir in dirs.into_iter().rev() {
+        if fs.read_dir(&dir).await?.is_empty() {
+            fs.remove_dir_all(&dir).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const VERSION: &str = "1.20.1-forge-47.2.0";
+
+    async fn write(path: &Path, content: &[u8]) {
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(path, content).await.unwrap();
+    }
+
+    fn forge_version_json() -> Vec<u8> {
+        let version = json!({
+            "id": VERSION,
+            "type": "release",
+            "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+            "minimumLauncherVersion": 21,
+            "releaseTime": "2023-06-12T00:00:00+00:00",
+            "time": "2023-06-12T00:00:00+00:00",
+            "assets": "empty",
+            "assetIndex": { "id": "empty", "sha1": "", "size": 0, "url": "" },
+            "downloads": { "client": { "sha1": "", "size": 0, "url": "" } },
+            "libraries": [{
+                "name": "org.proton:library:1.0",
+                "downloads": {
+                    "artifact": {
+                        "path": "org/proton/library/1.0/library-1.0.jar",
+                        "sha1": "",
+                        "size": 0,
+                        "url": "",
+                    },
+                },
+            }],
+            "arguments": {
+                "game": [
+                    "--fml.forgeVersion", "47.2.0",
+                    "--fml.mcVersion", "1.20.1",
+                    "--fml.forgeGroup", "net.minecraftforge",
+                    "--fml.mcpVersion", "20230612.114412",
+                ],
+                "jvm": [],
+            },
+        });
+        serde_json::to_vec(&version).unwrap()
+    }
+
+    #[tokio::test]
+    async fn keeps_forge_processor_outputs() {
+        let game_dir = std::env::temp_dir().join(format!("proton-gc-{}", uuid::Uuid::new_v4()));
+        let libraries = game_dir.join("libraries");
+        write(
+            &game_dir
+                .join("versions")
+                .join(VERSION)
+                .join(format!("{VERSION}.json")),
+            &forge_version_json(),
+        )
+        .await;
+        write(
+            &game_dir.join("assets").join("indexes").join("empty.json"),
+            br#"{"objects":{}}"#,
+        )
+        .await;
+
+        let library = libraries.join("org/proton/library/1.0/library-1.0.jar");
+        let forge_client =
+            libraries.join("net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar");
+        let srg = libraries.join(
+            "net/minecraft/client/1.20.1-20230612.114412/client-1.20.1-20230612.114412-srg.jar",
+        );
+        let recorded = libraries.join("net/minecraft/client/1.20.1/client-1.20.1-mappings.txt");
+        let orphan = libraries.join("org/proton/orphan/1.0/orphan-1.0.jar");
+        for path in [&library, &forge_client, &srg, &recorded, &orphan] {
+            write(path, b"jar").await;
+        }
+        record_extra_files(&game_dir, VERSION, std::slice::from_ref(&recorded))
+            .await
+            .unwrap();
+
+        let report = gc(&game_dir, &[], false).await.unwrap();
+
+        assert_eq!(report.removed, vec![orphan.clone()]);
+        assert!(!orphan.exists());
+        for path in [&library, &forge_client, &srg, &recorded] {
+            assert!(path.exists(), "{path:?} was removed");
+        }
+        tokio::fs::remove_dir_all(&game_dir).await.unwrap();
+    }
+
+    #[test]
+    fn fml_arguments_outside_libraries_are_ignored() {
+        let args: Vec<String> = ["--fml.mcVersion", "1.20.1", "--fml.forgeVersion", ".."]
+            .map(String::from)
+            .to_vec();
+        assert!(fml_output_dirs(&args).is_empty());
+    }
+}
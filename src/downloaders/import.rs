@@ -0,0 +1,241 @@
+use super::MinecraftDownloader;
+use super::lock::DirLock;
+use super::natives_state::NativesState;
+use crate::errors::ProtonError;
+use crate::filesystem::{file_system, scoped_file_system};
+use crate::plan::PlannedDownload;
+use crate::types::DownloadProgressType;
+use crate::utilities::{file_sha1, verify_file_hash};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Resultado de `import_artifacts`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    /// Archivos del plan copiados o enlazados desde el directorio
+    pub imported: usize,
+    /// Archivos del plan que ya estaban instalados
+    pub already_installed: usize,
+    /// Archivos que no estaban en el directorio y habrá que descargar
+    pub missing: Vec<PlannedDownload>,
+}
+
+impl MinecraftDownloader {
+    /// Busca en `dir` (recursivamente) archivos cuyo SHA-1 coincida con algún
+    /// archivo pendiente de la versión y los coloca en su sitio, con un enlace
+    /// duro si se puede o copiándolos si no. El directorio no se modifica;
+    /// después `download_all` solo descarga lo que siga faltando.
+    pub async fn import_artifacts(
+        &self,
+        dir: impl AsRef<Path>,
+    ) -> Result<ImportReport, ProtonError> {
+        let fs = self.file_system.clone();
+        scoped_file_system(fs, async {
+            let lock = DirLock::acquire(&self.game_path).await?;
+            let result = self.import_artifacts_locked(dir.as_ref()).await;
+            lock.release().await;
+            result
+        })
+        .await
+    }
+
+    async fn import_artifacts_locked(&self, dir: &Path) -> Result<ImportReport, ProtonError> {
+        let mut candidates = Vec::new();
+        collect_files(dir, &mut candidates).await?;
+
+        // El índice va primero: sin él no se conocen los assets (p. ej. sin red)
+        let index = &self.game_version.asset_index;
+        let index_path = self.asset_index_path(&index.id);
+        let mut index_imported = false;
+        if !verify_file_hash(&index_path, &index.sha1, index.size)
+            .await
+            .unwrap_or(false)
+        {
+            for (path, len) in &candidates {
+                if *len == index.size && file_sha1(path).await? == index.sha1 {
+                    place(path, &index_path).await?;
+                    index_imported = true;
+                    break;
+                }
+            }
+        }
+
+        let plan = self.download_plan().await?;
+        let natives = NativesState::load(&self.natives_dir).await;
+        let mut report = ImportReport::default();
+        let mut pending: HashMap<String, Vec<&PlannedDownload>> = HashMap::new();
+        for file in &plan.files {
+            if index_imported && self.game_path.join(&file.path) == index_path {
+                report.imported += 1;
+                continue;
+            }
+            let installed = if file.download_type == DownloadProgressType::Native {
+                natives.is_up_to_date(&self.natives_dir, &file.sha1).await
+            } else {
+                verify_file_hash(&self.game_path.join(&file.path), &file.sha1, file.size)
+                    .await
+                    .unwrap_or(false)
+            };
+            if installed {
+                report.already_installed += 1;
+            } else {
+                pending
+                    .entry(file.sha1.to_lowercase())
+                    .or_default()
+                    .push(file);
+            }
+        }
+
+        // Solo se calcula el hash de los archivos con un tamaño esperado
+        let sizes: HashSet<u64> = pending.values().flatten().map(|file| file.size).collect();
+        for (path, len) in &candidates {
+            if pending.is_empty() {
+                break;
+            }
+            if !sizes.contains(len) {
+                continue;
+            }
+            let Some(files) = pending.remove(&file_sha1(path).await?) else {
+                continue;
+            };
+            for file in files {
+                let target = self.game_path.join(&file.path);
+                match place(path, &target).await {
+                    Ok(()) => report.imported += 1,
+                    Err(e) => {
+                        warn!("Failed to import {path:?} as {}: {e}", file.name);
+                        report.missing.push(file.clone());
+                    }
+                }
+            }
+        }
+
+        report
+            .missing
+            .extend(pending.into_values().flatten().cloned());
+        info!(
+            "Imported {} file(s) from {:?}, {} still missing",
+            report.imported,
+            dir,
+            report.missing.len()
+        );
+        Ok(report)
+    }
+}
+
+/// Archivos bajo `dir` con su tamaño
+pub(super) async fn collect_files(
+    dir: &Path,
+    files: &mut Vec<(PathBuf, u64)>,
+) -> Result<(), ProtonError> {
+    let fs = file_system();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs.read_dir(&dir).await? {
+            let metadata = fs.metadata(&entry).await?;
+            if metadata.is_dir {
+                pending.push(entry);
+            } else {
+                files.push((entry, metadata.len));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Enlaza `from` en `to` o, si no se puede (otro disco), lo copia
+async fn place(from: &Path, to: &Path) -> Result<(), ProtonError> {
+    let fs = file_system();
+    if let Some(parent) = to.parent() {
+        fs.create_dir_all(parent).await?;
+    }
+    if fs.exists(to).await {
+        fs.remove_file(to).await?;
+    }
+    if fs.hard_link(from, to).await.is_err() {
+        fs.copy(from, to).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{FileSystem, MemoryFs};
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment, fixture_asset_index};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn imports_matching_files_and_downloads_the_rest() {
+        let env = MockEnvironment::start().await;
+        let (server, game_path) = (&env.server, &env.game_dir);
+        let client = game_path
+            .join("versions")
+            .join(FIXTURE_VERSION)
+            .join(format!("{FIXTURE_VERSION}.jar"));
+        let library = game_path.join("libraries/org/proton/library/1.0/library-1.0.jar");
+        let index = fixture_asset_index();
+        let lang = &index.objects["minecraft/lang/en_us.json"].hash;
+        let lang = game_path.join("assets/objects").join(&lang[..2]).join(lang);
+
+        // Una instalación completa de la que sacar los archivos
+        let online = MemoryFs::new();
+        MinecraftDownloader::new(game_path.clone(), server.version())
+            .file_system(Arc::new(online.clone()))
+            .download_all(None)
+            .await
+            .unwrap();
+
+        // Los mismos archivos con otros nombres, como en una memoria USB
+        let offline = MemoryFs::new();
+        offline
+            .create_dir_all(Path::new("/usb/nested"))
+            .await
+            .unwrap();
+        for (source, name) in [
+            (&client, "/usb/minecraft.jar"),
+            (&library, "/usb/nested/lib.jar"),
+            (&lang, "/usb/nested/en_us.json"),
+        ] {
+            let content = online.get(source).unwrap();
+            offline.write(Path::new(name), &content).await.unwrap();
+        }
+        offline
+            .write(Path::new("/usb/unrelated.txt"), b"not in the plan")
+            .await
+            .unwrap();
+
+        let mut downloader = MinecraftDownloader::new(game_path.clone(), server.version())
+            .file_system(Arc::new(offline.clone()));
+        let report = downloader.import_artifacts("/usb").await.unwrap();
+        assert_eq!(report.imported, 3);
+        assert_eq!(report.already_installed, 0);
+        assert!(
+            report
+                .missing
+                .iter()
+                .all(|file| !file.path.ends_with("library-1.0.jar"))
+        );
+        assert_eq!(offline.get(&library), online.get(&library));
+        // El directorio de origen no se toca
+        assert!(offline.get("/usb/nested/lib.jar").is_some());
+
+        let requests = || async {
+            let requests = server.server().received_requests().await.unwrap();
+            let count = |suffix: &str| {
+                requests
+                    .iter()
+                    .filter(|request| request.url.path().ends_with(suffix))
+                    .count()
+            };
+            (count("/client.jar"), count("/library-1.0.jar"))
+        };
+        let before = requests().await;
+        downloader.download_all(None).await.unwrap();
+        assert_eq!(requests().await, before);
+        assert!(downloader.verify().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+use super::MinecraftDownloader;
+use super::natives_state::NativesState;
+use super::verify::{FileCheck, run_checks};
+use crate::errors::{ErrorKind, ProtonError};
+use crate::filesystem::file_system;
+use crate::plan::PlannedDownload;
+use crate::types::{DownloadProgressType, FailedFile};
+use crate::utilities::{file_sha1, move_file, save_hash_cache, temp_path, verify_file_hash};
+use ring::digest::{SHA256, digest};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Nombre del registro de instalación dentro de `versions/<id>`
+pub const INSTALL_MANIFEST: &str = "install-manifest.json";
+
+static MANIFEST_KEY: RwLock<Option<hmac::Key>> = RwLock::new(None);
+
+/// Firma los registros de instalación con HMAC-SHA256 bajo `key` (o vuelve a
+/// la suma SHA-256 con `None`). Con clave, los registros sin firmar o
+/// firmados con otra clave se rechazan.
+pub fn set_install_manifest_key(key: Option<&[u8]>) {
+    *MANIFEST_KEY.write().unwrap_or_else(|e| e.into_inner()) =
+        key.map(|key| hmac::Key::new(hmac::HMAC_SHA256, key));
+}
+
+fn manifest_key() -> Option<hmac::Key> {
+    MANIFEST_KEY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Archivo extraído de un jar de nativos
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledFile {
+    /// Ruta relativa al directorio del juego
+    pub path: PathBuf,
+    pub sha1: String,
+    pub size: u64,
+}
+
+/// Registro de todo lo que instaló `download_all`, con su hash. Permite
+/// comprobar la instalación con `verify_installation` sin consultar los
+/// metadatos remotos. Sin clave la suma SHA-256 solo detecta un registro
+/// dañado; con `set_install_manifest_key` queda firmado y tampoco se puede
+/// editar sin la clave.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallManifest {
+    pub version: String,
+    /// Segundos desde la época Unix
+    pub created: u64,
+    /// Archivos descargados; los nativos apuntan al jar, que no se conserva
+    pub files: Vec<PlannedDownload>,
+    /// Archivos extraídos de cada jar de nativos, por el SHA-1 del jar
+    pub natives: BTreeMap<String, Vec<InstalledFile>>,
+    /// Si `checksum` es un HMAC-SHA256 en vez de una suma SHA-256
+    pub keyed: bool,
+    /// SHA-256 (o HMAC) del registro con este campo vacío
+    pub checksum: String,
+}
+
+impl InstallManifest {
+    fn path(game_dir: &Path, version_id: &str) -> PathBuf {
+        game_dir
+            .join("versions")
+            .join(version_id)
+            .join(INSTALL_MANIFEST)
+    }
+
+    fn unsigned_json(&self) -> Result<Vec<u8>, ProtonError> {
+        let unsigned = Self {
+            checksum: String::new(),
+            ..self.clone()
+        };
+        Ok(serde_json::to_vec(&unsigned).map_err(std::io::Error::other)?)
+    }
+
+    fn sign(&mut self, key: Option<&hmac::Key>) -> Result<(), ProtonError> {
+        self.keyed = key.is_some();
+        let json = self.unsigned_json()?;
+        self.checksum = match key {
+            Some(key) => hex::encode(hmac::sign(key, &json)),
+            None => hex::encode(digest(&SHA256, &json)),
+        };
+        Ok(())
+    }
+
+    fn check(&self, key: Option<&hmac::Key>) -> Result<(), String> {
+        let json = self.unsigned_json().map_err(|e| e.to_string())?;
+        match (key, self.keyed) {
+            (Some(key), true) => {
+                let tag = hex::decode(&self.checksum).map_err(|e| e.to_string())?;
+                hmac::verify(key, &json, &tag).map_err(|_| "signature mismatch".to_string())
+            }
+            (Some(_), false) => Err("not signed".to_string()),
+            (None, true) => Err("signed, but no key is set".to_string()),
+            (None, false) if hex::encode(digest(&SHA256, &json)) == self.checksum => Ok(()),
+            (None, false) => Err("checksum mismatch".to_string()),
+        }
+    }
+
+    /// Lee el registro de una versión y comprueba su suma de control o firma
+    pub async fn load(game_dir: impl AsRef<Path>, version_id: &str) -> Result<Self, ProtonError> {
+        let path = Self::path(game_dir.as_ref(), version_id);
+        let invalid =
+            |reason: String| ProtonError::InvalidInstallManifest(format!("{path:?}: {reason}"));
+
+        let content = file_system()
+            .read(&path)
+            .await
+            .map_err(|e| invalid(e.to_string()))?;
+        let manifest: Self =
+            serde_json::from_slice(&content).map_err(|e| invalid(e.to_string()))?;
+        manifest.check(manifest_key().as_ref()).map_err(invalid)?;
+        Ok(manifest)
+    }
+}
+
+impl MinecraftDownloader {
+    /// Escribe `versions/<id>/install-manifest.json` con lo que se acaba de
+    /// instalar. Solo usa la versión ya resuelta y el disco, sin red.
+    pub(super) async fn write_install_manifest(&self) -> Result<(), ProtonError> {
+        let fs = file_system();
+        let id = &self.game_version.id;
+        let mut files = self.download_plan().await?.files;
+
+        // El JSON de la versión no viene en el plan: su hash se toma del disco.
+        // No tiene URL propia (las versiones locales no la tienen), así que
+        // si se daña se recupera reinstalando la versión.
+        let json = PathBuf::from("versions")
+            .join(id)
+            .join(format!("{id}.json"));
+        let json_path = self.game_path.join(&json);
+        if fs.exists(&json_path).await {
+            files.push(PlannedDownload {
+                name: format!("manifest-{id}"),
+                url: String::new(),
+                sha1: file_sha1(&json_path).await?,
+                size: fs.metadata(&json_path).await?.len,
+                path: json,
+                download_type: DownloadProgressType::Manifest,
+            });
+        }
+
+        let state = NativesState::load(&self.natives_dir).await;
+        let mut natives = BTreeMap::new();
+        for native in &self.game_version.natives {
+            let Some(extracted) = state.extracted.get(&native.sha1) else {
+                continue;
+            };
+            let mut installed = Vec::with_capacity(extracted.len());
+            for file in extracted {
+                installed.push(InstalledFile {
+                    path: PathBuf::from("natives").join(id).join(&file.path),
+                    sha1: file_sha1(&self.natives_dir.join(&file.path)).await?,
+                    size: file.size,
+                });
+            }
+            natives.insert(native.sha1.clone(), installed);
+        }
+
+        let mut manifest = InstallManifest {
+            version: id.clone(),
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            files,
+            natives,
+            keyed: false,
+            checksum: String::new(),
+        };
+        manifest.sign(manifest_key().as_ref())?;
+
+        let path = InstallManifest::path(&self.game_path, id);
+        let temp = temp_path(&path);
+        let json = serde_json::to_vec_pretty(&manifest).map_err(std::io::Error::other)?;
+        fs.write(&temp, &json).await?;
+        move_file(&temp, &path).await?;
+        Ok(())
+    }
+}
+
+/// Comprueba una versión instalada contra su `install-manifest.json`, sin red. Los
+/// archivos que fallan se pueden pasar a `retry_failed`.
+pub async fn verify_installation(
+    game_dir: impl AsRef<Path>,
+    version_id: &str,
+) -> Result<Vec<FailedFile>, ProtonError> {
+    let game_dir = game_dir.as_ref();
+    let manifest = InstallManifest::load(game_dir, version_id).await?;
+    let mut broken = Vec::new();
+    let mut checks = Vec::new();
+
+    for file in manifest.files {
+        let path = game_dir.join(&file.path);
+        if file.download_type != DownloadProgressType::Native {
+            checks.push(FileCheck {
+                name: file.name,
+                url: file.url,
+                path,
+                sha1: file.sha1,
+                size: file.size,
+                download_type: file.download_type,
+            });
+            continue;
+        }
+
+        // Basta un archivo extraído dañado para volver a extraer el jar
+        let mut intact = manifest.natives.contains_key(&file.sha1);
+        for extracted in manifest.natives.get(&file.sha1).into_iter().flatten() {
+            let extracted_path = game_dir.join(&extracted.path);
+            if !verify_file_hash(&extracted_path, &extracted.sha1, extracted.size)
+                .await
+                .unwrap_or(false)
+            {
+                intact = false;
+                break;
+            }
+        }
+        if !intact {
+            broken.push(FailedFile {
+                name: file.name,
+                url: file.url,
+                path,
+                sha1: file.sha1,
+                download_type: DownloadProgressType::Native,
+                error: "natives modified".to_string(),
+                kind: ErrorKind::Integrity,
+            });
+        }
+    }
+
+    broken.extend(run_checks(checks, None).await);
+    save_hash_cache().await;
+    Ok(broken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{FileSystem, with_file_system};
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn verifies_offline_against_the_manifest() {
+        let env = MockEnvironment::start().await;
+        let (fs, game_path) = (&env.fs, &env.game_dir);
+        env.downloader().download_all(None).await.unwrap();
+        // Sin servidor de metadatos: solo queda el registro
+        env.server.server().reset().await;
+
+        let library = game_path.join("libraries/org/proton/library/1.0/library-1.0.jar");
+        let manifest_path = InstallManifest::path(game_path, FIXTURE_VERSION);
+        let (clean, broken, tampered) = with_file_system(Arc::new(fs.clone()), async {
+            let clean = verify_installation(&game_path, FIXTURE_VERSION).await?;
+
+            file_system().write(&library, b"corrupted").await?;
+            let broken = verify_installation(&game_path, FIXTURE_VERSION).await?;
+
+            let mut manifest = InstallManifest::load(&game_path, FIXTURE_VERSION).await?;
+            manifest.files.clear();
+            let json = serde_json::to_vec(&manifest).map_err(std::io::Error::other)?;
+            file_system().write(&manifest_path, &json).await?;
+            let tampered = verify_installation(&game_path, FIXTURE_VERSION).await;
+            Ok::<_, ProtonError>((clean, broken, tampered))
+        })
+        .await
+        .unwrap();
+
+        assert!(clean.is_empty(), "{clean:?}");
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].path, library);
+        assert_eq!(broken[0].kind, ErrorKind::Integrity);
+        assert!(matches!(
+            tampered,
+            Err(ProtonError::InvalidInstallManifest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn the_manifest_is_written_without_network() {
+        let env = MockEnvironment::start().await;
+        env.downloader().download_all(None).await.unwrap();
+        env.server.server().reset().await;
+
+        let manifest = env
+            .run(async {
+                env.downloader().write_install_manifest().await?;
+                InstallManifest::load(&env.game_dir, FIXTURE_VERSION).await
+            })
+            .await
+            .unwrap();
+
+        let requests = env.server.server().received_requests().await.unwrap();
+        assert!(requests.is_empty(), "{requests:?}");
+        let json = manifest
+            .files
+            .iter()
+            .find(|file| file.name == format!("manifest-{FIXTURE_VERSION}"))
+            .unwrap();
+        assert_eq!(
+            json.path,
+            Path::new("versions")
+                .join(FIXTURE_VERSION)
+                .join(format!("{FIXTURE_VERSION}.json"))
+        );
+        assert!(!manifest.keyed);
+    }
+
+    #[tokio::test]
+    async fn keyed_manifests_need_the_same_key() {
+        let env = MockEnvironment::start().await;
+        set_install_manifest_key(Some(b"launcher secret"));
+        let game_path = &env.game_dir;
+        let path = InstallManifest::path(game_path, FIXTURE_VERSION);
+        let verify = |key: Option<&'static [u8]>| {
+            set_install_manifest_key(key);
+            env.run(verify_installation(game_path, FIXTURE_VERSION))
+        };
+
+        env.downloader().download_all(None).await.unwrap();
+        let signed = verify(Some(b"launcher secret")).await;
+        let wrong_key = verify(Some(b"another secret")).await;
+        let no_key = verify(None).await;
+        // Sin la clave solo se puede dejar un registro con la suma sin firmar
+        let mut forged: InstallManifest =
+            serde_json::from_slice(&env.fs.get(&path).unwrap()).unwrap();
+        forged.files.clear();
+        forged.sign(None).unwrap();
+        env.fs
+            .write(&path, &serde_json::to_vec(&forged).unwrap())
+            .await
+            .unwrap();
+        let unsigned = verify(Some(b"launcher secret")).await;
+        set_install_manifest_key(None);
+
+        assert!(signed.unwrap().is_empty());
+        for (result, reason) in [
+            (wrong_key, "signature mismatch"),
+            (no_key, "signed, but no key is set"),
+            (unsigned, "not signed"),
+        ] {
+            match result {
+                Err(ProtonError::InvalidInstallManifest(message)) => {
+                    assert!(message.ends_with(reason), "{message}")
+                }
+                other => panic!("expected {reason}, got {other:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::system::{host_name, process_running};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Archivo de bloqueo en la raíz del directorio del juego
+const LOCK_FILE: &str = ".proton.lock";
+
+// Un bloqueo de otro equipo (directorio compartido en red) no se puede
+// comprobar por pid: pasado este tiempo se da por abandonado
+const FOREIGN_LOCK_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+
+// Un archivo ilegible más antiguo que esto no es un bloqueo a medio escribir,
+// sino uno truncado por un cierre inesperado o un corte de luz
+const UNREADABLE_LOCK_GRACE: Duration = Duration::from_secs(10);
+
+/// Contenido del archivo de bloqueo
+#[derive(Debug, Serialize, Deserialize)]
+struct LockOwner {
+    pid: u32,
+    host: Option<String>,
+    /// Segundos desde la época Unix
+    created: u64,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: host_name(),
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// El proceso que tomó el bloqueo ya no existe
+    fn is_stale(&self) -> bool {
+        if self.host == host_name() {
+            return !process_running(self.pid);
+        }
+        let created = UNIX_EPOCH + Duration::from_secs(self.created);
+        SystemTime::now()
+            .duration_since(created)
+            .is_ok_and(|age| age > FOREIGN_LOCK_TTL)
+    }
+}
+
+/// Bloqueo consultivo de un directorio del juego mientras se instala o
+/// repara. Hay que liberarlo con `release`; si se suelta sin liberar (p. ej.
+/// al cancelar la tarea) el archivo se borra en segundo plano.
+pub(crate) struct DirLock {
+    path: PathBuf,
+    released: bool,
+}
+
+impl DirLock {
+    /// Toma el bloqueo de `dir`, o falla con `AlreadyLocked` si otro proceso
+    /// (u otra descarga de este) lo tiene. Los bloqueos abandonados se retiran.
+    pub async fn acquire(dir: &Path) -> Result<Self, ProtonError> {
+        let fs = file_system();
+        fs.create_dir_all(dir).await?;
+        let path = dir.join(LOCK_FILE);
+        let owner = serde_json::to_vec(&LockOwner::current()).map_err(std::io::Error::other)?;
+
+        // Segundo intento tras retirar un bloqueo abandonado
+        for _ in 0..2 {
+            match fs.write_new(&path, &owner).await {
+                Ok(()) => {
+                    return Ok(Self {
+                        path,
+                        released: false,
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            let content = match fs.read(&path).await {
+                Ok(content) => content,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            match serde_json::from_slice::<LockOwner>(&content) {
+                Ok(current) if !current.is_stale() => return Err(locked(dir, current.pid)),
+                Ok(current) => {
+                    warn!("Removing stale lock of process {} in {dir:?}", current.pid)
+                }
+                // Un archivo ilegible reciente suele ser un bloqueo a medio escribir
+                Err(_) if !unreadable_lock_expired(&path).await => return Err(locked(dir, 0)),
+                Err(_) => warn!("Removing unreadable lock in {dir:?}"),
+            }
+            remove_stale(&path, &content).await?;
+        }
+
+        Err(locked(dir, 0))
+    }
+
+    pub async fn release(mut self) {
+        self.released = true;
+        if let Err(e) = file_system().remove_file(&self.path).await {
+            warn!("Failed to remove lock {:?}: {e}", self.path);
+        }
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let path = std::mem::take(&mut self.path);
+        // Se captura aquí: la tarea nueva no hereda el sistema de esta
+        let fs = file_system();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = fs.remove_file(&path).await;
+            });
+        } else if fs.is_local() {
+            // Sin runtime no hay tarea en segundo plano: se borra en el acto
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+async fn unreadable_lock_expired(path: &Path) -> bool {
+    let modified = match file_system().metadata(path).await {
+        Ok(metadata) => metadata.modified,
+        Err(_) => return false,
+    };
+    modified
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > UNREADABLE_LOCK_GRACE)
+}
+
+/// Retira el bloqueo abandonado cuyo contenido es `stale`. Se aparta primero
+/// con un nombre único: si entretanto otro proceso lo retiró y tomó uno
+/// nuevo, lo apartado no coincide y se devuelve a su sitio.
+async fn remove_stale(path: &Path, stale: &[u8]) -> Result<(), ProtonError> {
+    let fs = file_system();
+    let aside = path.with_extension(format!("lock.stale-{}", uuid::Uuid::new_v4().simple()));
+    match fs.rename(path, &aside).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    }
+
+    let content = fs.read(&aside).await?;
+    if content != stale {
+        // Si ya hay otro bloqueo, el suyo manda
+        match fs.write_new(path, &content).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    fs.remove_file(&aside).await?;
+    Ok(())
+}
+
+fn locked(dir: &Path, pid: u32) -> ProtonError {
+    ProtonError::AlreadyLocked {
+        path: dir.to_path_buf(),
+        pid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("proton-lock-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn truncated_lock_blocks_only_during_grace_period() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LOCK_FILE);
+        std::fs::write(&path, b"").unwrap();
+
+        let error = DirLock::acquire(&dir).await.err().unwrap();
+        assert!(matches!(error, ProtonError::AlreadyLocked { pid: 0, .. }));
+
+        let old = SystemTime::now() - UNREADABLE_LOCK_GRACE * 2;
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let lock = DirLock::acquire(&dir).await.unwrap();
+        let owner: LockOwner = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(owner.pid, std::process::id());
+
+        lock.release().await;
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fresh_lock_taken_meanwhile_is_restored() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LOCK_FILE);
+        std::fs::write(&path, b"fresh").unwrap();
+
+        remove_stale(&path, b"stale").await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"fresh");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,17 +1,62 @@
-use crate::errors::ProtonError;
+mod batch;
+mod diff;
+mod events;
+mod export;
+mod gc;
+mod import;
+mod install_manifest;
+mod lock;
+mod natives_state;
+mod overall;
+mod resume;
+mod staging;
+mod verify;
+
+pub use batch::BatchDownloader;
+pub(crate) use diff::diff_from_disk;
+pub use diff::diff_versions;
+pub use events::DownloadEvent;
+pub use gc::{EXTRA_FILES, GcReport, gc, record_extra_files};
+pub use import::ImportReport;
+pub use install_manifest::{
+    INSTALL_MANIFEST, InstallManifest, InstalledFile, set_install_manifest_key, verify_installation,
+};
+pub use resume::ResumeState;
+
+use crate::diagnostics::{Diagnostic, emit};
+use crate::endpoints::endpoints;
+use crate::errors::{ErrorKind, ProtonError};
+use crate::filesystem::{FileSystem, file_system, scoped_file_system, task_file_system};
 use crate::manifest::{resolve_asset_index, resolve_version_data, resolve_version_in_manifest};
+use crate::plan::{DownloadPlan, PlannedDownload};
+use crate::system::{LoadMonitor, SystemLoad, process_running, system_memory};
 use crate::types::{
-    DownloadProgress, DownloadProgressInfo, DownloadProgressType, NormalizedVersion,
-    RESOURCES_BASE_URL,
+    Asset, AssetFilter, DownloadProgress, DownloadProgressInfo, DownloadProgressType, FailedFile,
+    NormalizedVersion, VersionAssets,
 };
-use crate::utilities::{download_file, extract_native};
+use crate::utilities::{
+    TempGuard, download_file, extract_native, remove_orphan_temp_files, safe_join, save_hash_cache,
+};
+use batch::{SharedDownloads, download_with_fallback, fetch};
 use futures::stream::{FuturesUnordered, StreamExt};
-use std::path::PathBuf;
+use lock::DirLock;
+use log::{info, warn};
+use natives_state::NativesState;
+use overall::{OverallTotals, forward_overall};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc::Sender;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::{AcquireError, Mutex, OwnedSemaphorePermit, Semaphore, watch};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 /// Configuración adaptativa de descargas
 struct AdaptiveConfig {
@@ -23,6 +68,8 @@ struct AdaptiveConfig {
     sample_size: usize,
     performance_threshold_ms: u64,
     adjustment_interval_secs: u64,
+    /// Fuera del cerrojo de la configuración: muestrearlo bloquea
+    load_monitor: Arc<std::sync::Mutex<LoadMonitor>>,
 }
 
 impl AdaptiveConfig {
@@ -37,6 +84,7 @@ impl AdaptiveConfig {
             sample_size: 8,
             performance_threshold_ms: 1000,
             adjustment_interval_secs: 5,
+            load_monitor: Arc::new(std::sync::Mutex::new(LoadMonitor::new())),
         }
     }
 
@@ -58,7 +106,10 @@ impl AdaptiveConfig {
         config
     }
 
-    fn record_and_adjust(&mut self, duration: Duration) {
+    /// Registra la duración de una descarga. Si toca reajustar devuelve el
+    /// monitor de carga para muestrearlo sin el cerrojo y pasar el resultado
+    /// a `adjust_concurrency`; solo una tarea recibe cada turno.
+    fn record(&mut self, duration: Duration) -> Option<Arc<std::sync::Mutex<LoadMonitor>>> {
         self.performance_samples.push(duration);
 
         if self.performance_samples.len() > self.sample_size {
@@ -68,19 +119,33 @@ impl AdaptiveConfig {
         if self.last_adjustment.elapsed().as_secs() >= self.adjustment_interval_secs
             && self.performance_samples.len() >= self.sample_size / 2
         {
-            self.adjust_concurrency();
+            self.last_adjustment = Instant::now();
+            return Some(Arc::clone(&self.load_monitor));
         }
+        None
     }
 
-    fn adjust_concurrency(&mut self) {
+    fn adjust_concurrency(&mut self, load: SystemLoad) {
         if self.performance_samples.is_empty() {
             return;
         }
 
         let total_ms: u128 = self.performance_samples.iter().map(|d| d.as_millis()).sum();
         let avg_ms = total_ms / self.performance_samples.len() as u128;
+        let previous = self.current_concurrent;
 
-        if avg_ms > self.performance_threshold_ms as u128 {
+        if load.is_saturated() {
+            // Equipo saturado (CPU o disco): bajar aunque la red vaya bien
+            warn!(
+                "System saturated (cpu {}%, io {:?}%), reducing concurrency",
+                load.cpu_percent, load.io_percent
+            );
+            emit(Diagnostic::HostSaturated {
+                cpu_percent: load.cpu_percent,
+                io_percent: load.io_percent,
+            });
+            self.current_concurrent = (self.current_concurrent / 2).max(self.min_concurrent);
+        } else if avg_ms > self.performance_threshold_ms as u128 {
             // Rendimiento bajo, reducir concurrencia
             self.current_concurrent = (self.current_concurrent * 8 / 10).max(self.min_concurrent);
         } else if avg_ms < (self.performance_threshold_ms / 2) as u128 {
@@ -88,11 +153,138 @@ impl AdaptiveConfig {
             self.current_concurrent = (self.current_concurrent * 11 / 10).min(self.max_concurrent);
         }
 
+        if self.current_concurrent != previous {
+            emit(Diagnostic::ConcurrencyAdjusted {
+                from: previous,
+                to: self.current_concurrent,
+            });
+        }
+
         self.last_adjustment = Instant::now();
         self.performance_samples.clear();
     }
 }
 
+/// Muestrea la carga del equipo en el pool de hilos bloqueantes (refresco de
+/// sysinfo y lectura de `/proc/pressure/io`)
+async fn sample_load(monitor: Arc<std::sync::Mutex<LoadMonitor>>) -> Option<SystemLoad> {
+    tokio::task::spawn_blocking(move || monitor.lock().unwrap_or_else(|e| e.into_inner()).sample())
+        .await
+        .ok()
+}
+
+/// Perfil de concurrencia inicial del descargador
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConcurrencyPreset {
+    /// Ajuste adaptativo según CPU y memoria
+    #[default]
+    Adaptive,
+    /// El doble de descargas simultáneas
+    Aggressive,
+    /// Menos descargas simultáneas, para conexiones lentas
+    Conservative,
+}
+
+impl FromStr for ConcurrencyPreset {
+    type Err = ProtonError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "adaptive" => Ok(ConcurrencyPreset::Adaptive),
+            "aggressive" => Ok(ConcurrencyPreset::Aggressive),
+            "conservative" => Ok(ConcurrencyPreset::Conservative),
+            other => Err(ProtonError::InvalidConfig(format!(
+                "unknown concurrency preset '{other}'"
+            ))),
+        }
+    }
+}
+
+/// Orden en que `download_all` descarga cada categoría de archivos
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DownloadPriority {
+    /// Todo a la vez; los assets solo esperan a su índice
+    #[default]
+    Concurrent,
+    /// Cliente, índice de assets, nativos y librerías primero; los assets
+    /// empiezan cuando el juego ya se puede lanzar
+    CriticalFirst,
+}
+
+impl FromStr for DownloadPriority {
+    type Err = ProtonError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "concurrent" => Ok(DownloadPriority::Concurrent),
+            "critical-first" => Ok(DownloadPriority::CriticalFirst),
+            other => Err(ProtonError::InvalidConfig(format!(
+                "unknown download priority '{other}'"
+            ))),
+        }
+    }
+}
+
+/// Orden de la cola de assets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetOrder {
+    /// El del índice, sin ordenar
+    #[default]
+    Unordered,
+    /// Los pequeños primero: el contador de progreso avanza deprisa
+    SmallestFirst,
+    /// Los grandes primero: menos tiempo total en conexiones con mucha latencia
+    LargestFirst,
+    /// Alterna grandes y pequeños para repartir ancho de banda y peticiones
+    Interleaved,
+}
+
+impl AssetOrder {
+    fn sort(self, entries: &mut Vec<(String, Asset)>) {
+        // El hash desempata para que el orden no dependa del HashMap
+        let by_size = |(_, a): &(String, Asset), (_, b): &(String, Asset)| {
+            a.size.cmp(&b.size).then_with(|| a.hash.cmp(&b.hash))
+        };
+        match self {
+            AssetOrder::Unordered => {}
+            AssetOrder::SmallestFirst => entries.sort_by(by_size),
+            AssetOrder::LargestFirst => entries.sort_by(|a, b| by_size(b, a)),
+            AssetOrder::Interleaved => {
+                entries.sort_by(by_size);
+                let mut sorted = std::mem::take(entries).into_iter();
+                let mut largest = true;
+                while let Some(entry) = if largest {
+                    sorted.next_back()
+                } else {
+                    sorted.next()
+                } {
+                    entries.push(entry);
+                    largest = !largest;
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for AssetOrder {
+    type Err = ProtonError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "unordered" => Ok(AssetOrder::Unordered),
+            "smallest-first" => Ok(AssetOrder::SmallestFirst),
+            "largest-first" => Ok(AssetOrder::LargestFirst),
+            "interleaved" => Ok(AssetOrder::Interleaved),
+            other => Err(ProtonError::InvalidConfig(format!(
+                "unknown asset order '{other}'"
+            ))),
+        }
+    }
+}
+
 /// Calcula el número óptimo de descargas basado en el sistema
 fn calculate_optimal_downloads() -> usize {
     let cpu_cores = std::thread::available_parallelism()
@@ -111,32 +303,134 @@ fn calculate_optimal_downloads() -> usize {
 
 /// Obtiene memoria disponible aproximada en GB
 fn get_available_memory_gb() -> f64 {
-    #[cfg(target_os = "linux")]
-    {
-        if let Ok(meminfo) = std::fs::read_to_string("/proc/meminfo") {
-            for line in meminfo.lines() {
-                if line.starts_with("MemAvailable:") {
-                    if let Some(kb_str) = line.split_whitespace().nth(1) {
-                        if let Ok(kb) = kb_str.parse::<u64>() {
-                            return (kb as f64) / (1024.0 * 1024.0);
-                        }
-                    }
-                }
-            }
+    // 8 GB si el sistema operativo no informa la memoria
+    system_memory().map_or(8.0, |memory| memory.available_gb())
+}
+
+/// Fallo de una tarea individual junto con el contexto necesario para reintentarla
+struct TaskFailure {
+    file: FailedFile,
+    error: ProtonError,
+}
+
+impl TaskFailure {
+    fn new(mut file: FailedFile, error: ProtonError) -> Self {
+        file.error = error.to_string();
+        file.kind = error.kind();
+        Self { file, error }
+    }
+
+    /// El error con el archivo que falló, para que el mensaje diga cuál fue
+    fn into_error(self) -> ProtonError {
+        ProtonError::download(
+            self.file.name,
+            self.file.url,
+            self.file.path,
+            self.file.download_type,
+            self.error,
+        )
+    }
+}
+
+/// Tarea que se aborta al soltarla: si `download_all` se cancela o vence su
+/// plazo, no quedan descargas sueltas en segundo plano. Hereda el sistema de
+/// archivos de la tarea que la lanza.
+struct ScopedTask<T>(JoinHandle<T>);
+
+fn spawn_scoped<F>(future: F) -> ScopedTask<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    ScopedTask(tokio::spawn(scoped_file_system(task_file_system(), future)))
+}
+
+impl<T> Future for ScopedTask<T> {
+    type Output = Result<T, tokio::task::JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+impl<T> Drop for ScopedTask<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+type DownloadTasks = FuturesUnordered<ScopedTask<Result<(), TaskFailure>>>;
+
+/// Espera todas las tareas; en modo tolerante acumula los fallos en lugar de abortar
+async fn drain_tasks(
+    mut tasks: DownloadTasks,
+    tolerate_failures: bool,
+) -> Result<Vec<FailedFile>, ProtonError> {
+    let mut failures = Vec::new();
+
+    while let Some(res) = tasks.next().await {
+        match res? {
+            Ok(()) => {}
+            Err(failure) if tolerate_failures => failures.push(failure.file),
+            Err(failure) => return Err(failure.into_error()),
         }
     }
 
-    // Fallback para otros sistemas
-    8.0
+    Ok(failures)
+}
+
+/// Permisos de descarga de una fase. La capacidad sigue a
+/// `current_concurrent` mientras la fase avanza: al liberar un permiso se
+/// descarta si sobra o se añade otro si falta.
+struct DownloadSlots {
+    semaphore: Arc<Semaphore>,
+    capacity: AtomicUsize,
+}
+
+impl DownloadSlots {
+    fn new(capacity: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(capacity)),
+            capacity: AtomicUsize::new(capacity),
+        }
+    }
+
+    async fn acquire(&self) -> Result<OwnedSemaphorePermit, AcquireError> {
+        Arc::clone(&self.semaphore).acquire_owned().await
+    }
+
+    fn release(&self, permit: OwnedSemaphorePermit, limit: usize) {
+        let shrink = self
+            .capacity
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |capacity| {
+                (capacity > limit.max(1)).then(|| capacity - 1)
+            })
+            .is_ok();
+        if shrink {
+            permit.forget();
+            return;
+        }
+
+        let grow = self
+            .capacity
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |capacity| {
+                (capacity < limit).then(|| capacity + 1)
+            })
+            .is_ok();
+        if grow {
+            self.semaphore.add_permits(1);
+        }
+        drop(permit);
+    }
 }
 
 /// Macro para crear infraestructura de descarga adaptativa
 macro_rules! create_adaptive_infrastructure {
     ($total:expr, $game_version:expr, $config:expr) => {{
         let current_limit = $config.lock().await.current_concurrent;
-        let semaphore = Arc::new(Semaphore::new(current_limit));
+        let semaphore = Arc::new(DownloadSlots::new(current_limit));
         let completed = Arc::new(AtomicUsize::new(0));
-        let tasks = FuturesUnordered::new();
+        let tasks: DownloadTasks = FuturesUnordered::new();
         let game_version = Arc::new($game_version.clone());
         (semaphore, completed, tasks, game_version, $total)
     }};
@@ -151,58 +445,101 @@ macro_rules! create_monitored_task {
         $progress_tx:expr,
         $game_version:expr,
         $config:expr,
+        $shared:expr,
+        $only:expr,
         $total:expr,
         $download_type:expr,
         $name:expr,
         $url:expr,
         $path:expr,
         $hash:expr,
+        $size:expr,
         $post_process:expr
     ) => {
         let semaphore = Arc::clone(&$semaphore);
         let completed = Arc::clone(&$completed);
         let config = Arc::clone(&$config);
+        let shared = $shared.clone();
         let tx = $progress_tx.clone();
         let game_version = Arc::clone(&$game_version);
-        let info = DownloadProgressInfo {
-            name: $name,
-            version: game_version.clone(),
+        let url: String = $url;
+        let path: PathBuf = $path;
+        let hash: Option<String> = $hash;
+        let size: u64 = $size;
+        let skipped = $only.as_ref().is_some_and(|only| {
+            hash.as_ref()
+                .is_some_and(|hash| !only.contains(&hash.to_lowercase()))
+        });
+        let info = DownloadProgressInfo::new($name, game_version.clone());
+        let failed = FailedFile {
+            name: info.name.clone(),
+            url: url.clone(),
+            path: path.clone(),
+            sha1: hash.clone().unwrap_or_default(),
+            download_type: $download_type,
+            error: String::new(),
+            kind: ErrorKind::Other,
         };
 
-        $tasks.push(tokio::spawn(async move {
+        $tasks.push(spawn_scoped(async move {
+            // Fuera de `only` ya está instalado: cuenta como hecho sin descargarlo
+            if skipped {
+                let count = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(tx) = tx {
+                    let progress = DownloadProgress::new(info, $download_type, count, $total, size);
+                    let _ = tx.send(progress).await;
+                }
+                return Ok(());
+            }
+
             let start_time = Instant::now();
-            let permit = semaphore
-                .acquire_owned()
-                .await
-                .map_err(|_| ProtonError::Other("Failed to acquire download permit".to_string()))?;
+            let permit = match semaphore.acquire().await {
+                Ok(permit) => permit,
+                Err(_) => {
+                    return Err(TaskFailure::new(
+                        failed,
+                        ProtonError::Other("Failed to acquire download permit".to_string()),
+                    ));
+                }
+            };
 
-            let result = download_file($url, &$path, $hash).await;
+            // El post-procesamiento solo se ejecuta si la descarga fue correcta
+            let result = match fetch(shared.as_deref(), url, &path, hash.as_deref(), size).await {
+                Ok(()) => $post_process,
+                Err(e) => Err(e),
+            };
             let download_duration = start_time.elapsed();
+            let bytes = match &result {
+                Ok(()) => file_system().metadata(&path).await.map_or(0, |m| m.len),
+                Err(_) => 0,
+            };
 
-            // Registrar tiempo para ajuste adaptativo
+            // Registrar tiempo para ajuste adaptativo; la carga se mide sin
+            // el cerrojo para no detener al resto de tareas
+            let due = config.lock().await.record(download_duration);
+            if let Some(monitor) = due
+                && let Some(load) = sample_load(monitor).await
             {
-                let mut config_guard = config.lock().await;
-                config_guard.record_and_adjust(download_duration);
+                config.lock().await.adjust_concurrency(load);
             }
-
-            // Post-procesamiento
-            $post_process?;
+            let limit = config.lock().await.current_concurrent;
 
             let count = completed.fetch_add(1, Ordering::Relaxed) + 1;
 
             if let Some(tx) = tx {
                 let _ = tx
-                    .send(DownloadProgress {
-                        current: count,
-                        total: $total,
+                    .send(DownloadProgress::new(
                         info,
-                        download_type: $download_type,
-                    })
+                        $download_type,
+                        count,
+                        $total,
+                        bytes,
+                    ))
                     .await;
             }
 
-            drop(permit);
-            result
+            semaphore.release(permit, limit);
+            result.map_err(|error| TaskFailure::new(failed, error))
         }));
     };
 }
@@ -215,6 +552,20 @@ pub struct MinecraftDownloader {
     libraries_dir: PathBuf,
     asset_index_dir: PathBuf,
     adaptive_config: Arc<Mutex<AdaptiveConfig>>,
+    tolerate_failures: bool,
+    include_server: bool,
+    asset_filters: Vec<AssetFilter>,
+    /// Descargas compartidas con el resto de versiones de un `BatchDownloader`
+    shared: Option<Arc<SharedDownloads>>,
+    /// SHA-1 de los únicos archivos que descarga `download_all`, si se limitó
+    only: Option<Arc<HashSet<String>>>,
+    priority: DownloadPriority,
+    asset_order: AssetOrder,
+    deadline: Option<Duration>,
+    cancel: CancellationToken,
+    playable: Arc<watch::Sender<bool>>,
+    /// Sistema de archivos propio; `None` usa el global
+    file_system: Option<Arc<dyn FileSystem>>,
 }
 
 impl MinecraftDownloader {
@@ -232,6 +583,17 @@ impl MinecraftDownloader {
             libraries_dir,
             asset_index_dir,
             adaptive_config: Arc::new(Mutex::new(AdaptiveConfig::new())),
+            tolerate_failures: false,
+            include_server: false,
+            asset_filters: Vec::new(),
+            shared: None,
+            only: None,
+            priority: DownloadPriority::default(),
+            asset_order: AssetOrder::default(),
+            deadline: None,
+            cancel: CancellationToken::new(),
+            playable: Arc::new(watch::Sender::new(false)),
+            file_system: None,
         }
     }
 
@@ -250,42 +612,210 @@ impl MinecraftDownloader {
         downloader
     }
 
+    /// Reinicia la concurrencia con el perfil indicado
+    pub fn concurrency(mut self, preset: ConcurrencyPreset) -> Self {
+        self.adaptive_config = Arc::new(Mutex::new(match preset {
+            ConcurrencyPreset::Adaptive => AdaptiveConfig::new(),
+            ConcurrencyPreset::Aggressive => AdaptiveConfig::aggressive(),
+            ConcurrencyPreset::Conservative => AdaptiveConfig::conservative(),
+        }));
+        self
+    }
+
+    /// Activa el modo tolerante a fallos: las descargas fallidas se acumulan y se
+    /// devuelven al final como `ProtonError::PartialFailure` en lugar de abortar
+    pub fn tolerate_failures(mut self, enabled: bool) -> Self {
+        self.tolerate_failures = enabled;
+        self
+    }
+
+    /// Incluye el server jar (`versions/<id>/server.jar`) en `download_all`
+    pub fn include_server(mut self, enabled: bool) -> Self {
+        self.include_server = enabled;
+        self
+    }
+
+    /// Orden de las descargas en `download_all`
+    pub fn priority(mut self, priority: DownloadPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Pasa a `true` cuando el cliente, los nativos y las librerías están
+    /// instalados. Con `DownloadPriority::CriticalFirst` ocurre antes de los
+    /// assets, así que el juego se puede lanzar mientras se descargan.
+    pub fn playable(&self) -> watch::Receiver<bool> {
+        self.playable.subscribe()
+    }
+
+    /// Plazo máximo de `download_all`; al vencer se cancelan las descargas en
+    /// curso y devuelve `ProtonError::Timeout` con lo que se llegó a completar
+    pub fn deadline(mut self, limit: Duration) -> Self {
+        self.deadline = Some(limit);
+        self
+    }
+
+    /// Sistema de archivos de este descargador en lugar del global de
+    /// `set_file_system`. Se aplica a `download_all`, `verify` y demás
+    /// operaciones, incluidas las tareas que lanzan, así que varios
+    /// descargadores con sistemas distintos pueden trabajar a la vez.
+    pub fn file_system(mut self, fs: Arc<dyn FileSystem>) -> Self {
+        self.file_system = Some(fs);
+        self
+    }
+
+    /// Token para cancelar `download_all` desde otra tarea (p. ej. con Ctrl+C):
+    /// se abortan las descargas, se borran los temporales, se guarda el
+    /// `ResumeState` y devuelve `ProtonError::Cancelled`. Tras cancelar, el
+    /// descargador usa un token nuevo.
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Limita `download_all` a estos archivos del plan, p. ej. `VersionDelta::added`
+    /// de `diff_versions`; el resto se da por instalado y no se comprueba
+    pub fn only(mut self, files: &[PlannedDownload]) -> Self {
+        let hashes = files.iter().map(|file| file.sha1.to_lowercase()).collect();
+        self.only = Some(Arc::new(hashes));
+        self
+    }
+
+    /// Orden en que se descargan los assets
+    pub fn asset_order(mut self, order: AssetOrder) -> Self {
+        self.asset_order = order;
+        self
+    }
+
+    fn set_playable(&self, playable: bool) {
+        // Solo se notifica si cambia, para no anunciar dos veces la misma versión
+        self.playable
+            .send_if_modified(|current| std::mem::replace(current, playable) != playable);
+    }
+
+    /// Añade un filtro de assets; solo se descargan los assets que pasan todos los filtros
+    pub fn asset_filter(mut self, filter: AssetFilter) -> Self {
+        self.asset_filters.push(filter);
+        self
+    }
+
     /// Método principal con descarga adaptativa
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(version = %self.game_version.id))
+    )]
     pub async fn download_all(
         &mut self,
         progress_tx: Option<Sender<DownloadProgress>>,
     ) -> Result<(), ProtonError> {
-        println!(
+        let fs = self.file_system.clone();
+        // El futuro de la instalación ocupa decenas de KiB; en el heap no
+        // desborda la pila de quien lo espera, sobre todo en debug
+        Box::pin(scoped_file_system(fs, async {
+            let lock = DirLock::acquire(&self.game_path).await?;
+            let result = self.download_all_locked(progress_tx).await;
+            save_hash_cache().await;
+            lock.release().await;
+            result
+        }))
+        .await
+    }
+
+    async fn download_all_locked(
+        &mut self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<(), ProtonError> {
+        self.game_version.check_paths()?;
+
+        // Los eventos llevan además el progreso conjunto de la instalación
+        let totals = Arc::new(OverallTotals::default());
+        let plan = DownloadPlan::new(&self.game_version, None);
+        // El JSON de la versión no está en el plan
+        totals.add(plan.files.len() + 1, plan.total_size());
+        if self.include_server
+            && let Some(server_jar) = &self.game_version.server_jar
+        {
+            totals.add(1, server_jar.size);
+        }
+
+        if let Some(state) = ResumeState::load(&self.game_path, &self.game_version.id).await {
+            info!(
+                "Resuming install of {} interrupted by: {} ({} file(s) pending)",
+                state.version,
+                state.reason,
+                state.pending.len()
+            );
+        }
+
+        let (tx, forwarder) = forward_overall(progress_tx, Arc::clone(&totals));
+        let deadline = self.deadline;
+        let cancel = self.cancel.clone();
+        let install = self.install(Some(tx), Arc::clone(&totals));
+        // Al vencer el plazo o cancelar se suelta la instalación, que aborta sus tareas
+        let bounded = async {
+            match deadline {
+                Some(limit) => tokio::time::timeout(limit, install)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(ProtonError::Timeout {
+                            limit,
+                            progress: totals.snapshot(),
+                        })
+                    }),
+                None => install.await,
+            }
+        };
+        let result = tokio::select! {
+            result = bounded => result,
+            () = cancel.cancelled() => Err(ProtonError::Cancelled),
+        };
+        // Termina cuando ya no queda ninguna tarea (ni sus temporales)
+        let _ = forwarder.await;
+
+        match &result {
+            Ok(()) => self.clear_resume_state().await,
+            Err(e) => {
+                if cancel.is_cancelled() {
+                    self.cancel = CancellationToken::new();
+                }
+                if let Err(write_error) = self.write_resume_state(e).await {
+                    warn!(
+                        "Failed to save resume state for {}: {write_error}",
+                        self.game_version.id
+                    );
+                }
+            }
+        }
+        result
+    }
+
+    async fn install(
+        &mut self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+        totals: Arc<OverallTotals>,
+    ) -> Result<(), ProtonError> {
+        self.set_playable(false);
+        self.remove_orphan_temp_files().await;
+        info!(
             "Starting adaptive downloads with initial concurrency: {}",
             self.adaptive_config.lock().await.current_concurrent
         );
 
         let (natives_tx, libraries_tx, assets_tx, client_manifest_tx, asset_index_tx) =
-            if progress_tx.is_some() {
-                let tx = progress_tx.as_ref().unwrap();
-                (
+            match progress_tx.as_ref() {
+                Some(tx) => (
                     Some(tx.clone()),
                     Some(tx.clone()),
                     Some(tx.clone()),
                     Some(tx.clone()),
                     Some(tx.clone()),
-                )
-            } else {
-                (None, None, None, None, None)
+                ),
+                None => (None, None, None, None, None),
             };
 
-        // Clonar configuración para cada hilo
-        let natives_config = Arc::clone(&self.adaptive_config);
-        let libraries_config = Arc::clone(&self.adaptive_config);
-        let assets_config = Arc::clone(&self.adaptive_config);
-        let client_manifest_config = Arc::clone(&self.adaptive_config);
-        let asset_index_config = Arc::clone(&self.adaptive_config);
-
         // Primero descargar el asset index antes que los assets
         let asset_index_handle = {
-            let mut downloader = self.clone_for_asset_index();
-            downloader.adaptive_config = asset_index_config;
-            tokio::spawn(async move {
+            let downloader = self.clone_for_asset_index();
+            spawn_scoped(async move {
                 downloader
                     .download_asset_index(&downloader.game_version.id.clone(), asset_index_tx)
                     .await
@@ -294,185 +824,421 @@ impl MinecraftDownloader {
 
         let natives_handle = {
             let mut downloader = self.clone_for_natives();
-            downloader.adaptive_config = natives_config;
-            tokio::spawn(async move { downloader.download_natives_internal(natives_tx).await })
+            let totals = Arc::clone(&totals);
+            spawn_scoped(async move {
+                downloader
+                    .download_natives_internal(natives_tx, &totals)
+                    .await
+            })
         };
 
         let libraries_handle = {
             let mut downloader = self.clone_for_libraries();
-            downloader.adaptive_config = libraries_config;
-            tokio::spawn(async move { downloader.download_libraries_internal(libraries_tx).await })
+            spawn_scoped(async move { downloader.download_libraries_internal(libraries_tx).await })
         };
 
         // Cliente y manifest en el mismo hilo
         let client_manifest_handle = {
-            let mut downloader = self.clone_for_client();
-            downloader.adaptive_config = client_manifest_config;
-            tokio::spawn(async move {
+            let downloader = self.clone_for_client();
+            spawn_scoped(async move {
                 downloader
                     .download_client_and_manifest_internal(client_manifest_tx)
                     .await
             })
         };
 
+        // Server jar opcional, en su propio hilo
+        let server_handle = if !self.include_server {
+            None
+        } else if self.game_version.server_jar.is_none() {
+            warn!(
+                "Version {} has no server jar, skipping it",
+                self.game_version.id
+            );
+            None
+        } else {
+            let downloader = self.clone_base();
+            let server_tx = progress_tx.clone();
+            Some(spawn_scoped(async move {
+                downloader.download_server_internal(server_tx).await
+            }))
+        };
+
         // Esperar a que se descargue el asset index primero
         let asset_index_result = asset_index_handle.await;
         asset_index_result??;
 
-        // Ahora descargar los assets
-        let assets_handle = {
-            let mut downloader = self.clone_for_assets();
-            downloader.adaptive_config = assets_config;
-            tokio::spawn(async move { downloader.download_assets_internal(assets_tx).await })
+        let critical = async {
+            let (natives_result, libraries_result, client_manifest_result) =
+                tokio::join!(natives_handle, libraries_handle, client_manifest_handle);
+            let mut failures = natives_result??;
+            failures.extend(libraries_result??);
+            failures.extend(client_manifest_result??);
+            Ok::<_, ProtonError>(failures)
         };
 
-        let (natives_result, libraries_result, assets_result, client_manifest_result) = tokio::join!(
-            natives_handle,
-            libraries_handle,
-            assets_handle,
-            client_manifest_handle
-        );
+        let assets_downloader = self.clone_for_assets();
+        let spawn_assets = move || {
+            spawn_scoped(async move {
+                assets_downloader
+                    .download_assets_internal(assets_tx, &totals)
+                    .await
+            })
+        };
 
-        natives_result??;
-        libraries_result??;
-        assets_result??;
-        client_manifest_result??;
+        let (mut failures, asset_failures) = match self.priority {
+            // Si falla lo crítico, soltar la tarea de assets la cancela
+            DownloadPriority::Concurrent => {
+                tokio::try_join!(critical, async { spawn_assets().await? })?
+            }
+            DownloadPriority::CriticalFirst => {
+                let failures = critical.await?;
+                if failures.is_empty() {
+                    let id = &self.game_version.id;
+                    self.promote(&[&format!("{id}.jar"), &format!("{id}.json")])
+                        .await?;
+                    self.set_playable(true);
+                    info!("Version {id} is playable, downloading assets");
+                }
+                (failures, spawn_assets().await??)
+            }
+        };
+        failures.extend(asset_failures);
+        if let Some(handle) = server_handle {
+            failures.extend(handle.await??);
+        }
 
         let final_config = self.adaptive_config.lock().await;
-        println!(
+        info!(
             "Downloads completed with final concurrency: {}",
             final_config.current_concurrent
         );
 
+        if !failures.is_empty() {
+            return Err(ProtonError::PartialFailure(failures));
+        }
+
+        self.promote_staged().await?;
+        self.set_playable(true);
+        if let Err(e) = self.write_install_manifest().await {
+            warn!(
+                "Failed to write install manifest for {}: {e}",
+                self.game_version.id
+            );
+        }
         Ok(())
     }
 
-    pub async fn download_version_manifest(
+    /// Descarga y verifica el server jar de la versión en `versions/<id>/server.jar`
+    pub async fn download_server(
+        &self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<(), ProtonError> {
+        let fs = self.file_system.clone();
+        scoped_file_system(fs, async {
+            let lock = DirLock::acquire(&self.game_path).await?;
+            let result = self.download_server_locked(progress_tx).await;
+            lock.release().await;
+            result
+        })
+        .await
+    }
+
+    async fn download_server_locked(
         &self,
-        version_id: &str,
         progress_tx: Option<Sender<DownloadProgress>>,
     ) -> Result<(), ProtonError> {
-        let version = resolve_version_in_manifest(version_id).await?;
+        self.game_version.check_paths()?;
+        let failures = self.download_server_internal(progress_tx).await?;
 
-        let version_dir = self.game_path.join("versions").join(version_id);
-        tokio::fs::create_dir_all(&version_dir).await?;
+        if !failures.is_empty() {
+            return Err(ProtonError::PartialFailure(failures));
+        }
 
-        let manifest_path = version_dir.join(format!("{version_id}.json"));
+        self.promote(&["server.jar"]).await
+    }
 
-        if let Some(ref tx) = progress_tx {
-            let info = DownloadProgressInfo {
-                name: format!("manifest-{version_id}"),
-                version: Arc::new(version_id.to_string()),
-            };
+    /// Reintenta únicamente los archivos devueltos en `ProtonError::PartialFailure`
+    pub async fn retry_failed(&self, failed: Vec<FailedFile>) -> Result<(), ProtonError> {
+        let fs = self.file_system.clone();
+        scoped_file_system(fs, async {
+            let lock = DirLock::acquire(&self.game_path).await?;
+            let result = self.retry_failed_locked(failed).await;
+            lock.release().await;
+            result
+        })
+        .await
+    }
 
-            let _ = tx
-                .send(DownloadProgress {
-                    current: 0,
-                    total: 1,
-                    info: info.clone(),
-                    download_type: DownloadProgressType::Manifest,
-                })
-                .await;
-        }
+    async fn retry_failed_locked(&self, failed: Vec<FailedFile>) -> Result<(), ProtonError> {
+        self.remove_orphan_temp_files().await;
+        let concurrency = self.adaptive_config.lock().await.current_concurrent;
+        let has_natives = failed
+            .iter()
+            .any(|file| matches!(file.download_type, DownloadProgressType::Native));
+        let natives_state = Mutex::new(NativesState::load(&self.natives_dir).await);
+        let natives_state = &natives_state;
+
+        let results: Vec<(FailedFile, Result<(), ProtonError>)> = futures::stream::iter(failed)
+            .map(|file| async move {
+                // Sin hash solo pueden fallar librerías que no lo publican
+                let sha1 = (!file.sha1.is_empty()).then_some(file.sha1.as_str());
+                let result =
+                    match download_with_fallback(file.url.clone(), &file.path, sha1, 0).await {
+                        Ok(()) if matches!(file.download_type, DownloadProgressType::Native) => {
+                            let exclude = self
+                                .game_version
+                                .natives
+                                .iter()
+                                .find(|native| native.name == file.name)
+                                .map(|native| native.exclude.clone())
+                                .unwrap_or_default();
+                            let extracted =
+                                extract_native(&file.path, &self.natives_dir, &exclude).await;
+                            let _ = file_system().remove_file(&file.path).await;
+                            match extracted {
+                                Ok(files) => {
+                                    natives_state
+                                        .lock()
+                                        .await
+                                        .extracted
+                                        .insert(file.sha1.clone(), files);
+                                    Ok(())
+                                }
+                                Err(e) => Err(e),
+                            }
+                        }
+                        other => other,
+                    };
+                (file, result)
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
 
-        download_file(version.url, &manifest_path, version.sha1).await?;
+        if has_natives && let Err(e) = natives_state.lock().await.save(&self.natives_dir).await {
+            warn!("Failed to save natives state: {e}");
+        }
 
-        if let Some(ref tx) = progress_tx {
-            let info = DownloadProgressInfo {
-                name: format!("manifest-{version_id}"),
-                version: Arc::new(version_id.to_string()),
-            };
+        let mut still_failing = Vec::new();
+        for (file, result) in results {
+            if let Err(error) = result {
+                let failure = TaskFailure::new(file, error);
+                if !self.tolerate_failures {
+                    return Err(failure.into_error());
+                }
+                still_failing.push(failure.file);
+            }
+        }
 
-            let _ = tx
-                .send(DownloadProgress {
-                    current: 1,
-                    total: 1,
-                    info,
-                    download_type: DownloadProgressType::Manifest,
-                })
-                .await;
+        if !still_failing.is_empty() {
+            return Err(ProtonError::PartialFailure(still_failing));
         }
 
-        Ok(())
+        self.promote_staged().await
     }
 
-    pub async fn download_asset_index(
+    pub async fn download_version_manifest(
         &self,
         version_id: &str,
         progress_tx: Option<Sender<DownloadProgress>>,
     ) -> Result<(), ProtonError> {
-        let version = resolve_version_data(version_id).await?;
+        let fs = self.file_system.clone();
+        scoped_file_system(fs, async {
+            let version = resolve_version_in_manifest(version_id).await?;
 
-        tokio::fs::create_dir_all(&self.asset_index_dir).await?;
+            let version_dir = self.game_path.join("versions").join(version_id);
+            file_system().create_dir_all(&version_dir).await?;
 
-        let asset_index_path = self
-            .asset_index_dir
-            .join(format!("{}.json", version.asset_index.id));
+            let manifest_path = version_dir.join(format!("{version_id}.json"));
 
-        if let Some(ref tx) = progress_tx {
-            let info = DownloadProgressInfo {
-                name: format!("asset-index-{}", version.asset_index.id),
-                version: Arc::new(version_id.to_string()),
-            };
+            if let Some(ref tx) = progress_tx {
+                let info = DownloadProgressInfo::new(
+                    format!("manifest-{version_id}"),
+                    Arc::new(version_id.to_string()),
+                );
 
-            let _ = tx
-                .send(DownloadProgress {
-                    current: 0,
-                    total: 1,
-                    info: info.clone(),
-                    download_type: DownloadProgressType::Manifest,
-                })
-                .await;
-        }
+                let _ = tx
+                    .send(DownloadProgress::new(
+                        info,
+                        DownloadProgressType::Manifest,
+                        0,
+                        1,
+                        0,
+                    ))
+                    .await;
+            }
 
-        download_file(
-            version.asset_index.url,
-            &asset_index_path,
-            version.asset_index.sha1,
-        )
-        .await?;
+            download_file(version.url.clone(), &manifest_path, Some(&version.sha1), 0)
+                .await
+                .map_err(|e| {
+                    ProtonError::download(
+                        format!("manifest-{version_id}"),
+                        version.url,
+                        &manifest_path,
+                        DownloadProgressType::Manifest,
+                        e,
+                    )
+                })?;
+
+            if let Some(ref tx) = progress_tx {
+                let info = DownloadProgressInfo::new(
+                    format!("manifest-{version_id}"),
+                    Arc::new(version_id.to_string()),
+                );
+
+                let _ = tx
+                    .send(DownloadProgress::new(
+                        info,
+                        DownloadProgressType::Manifest,
+                        1,
+                        1,
+                        file_system()
+                            .metadata(&manifest_path)
+                            .await
+                            .map_or(0, |m| m.len),
+                    ))
+                    .await;
+            }
+
+            Ok(())
+        })
+        .await
+    }
 
-        if let Some(ref tx) = progress_tx {
-            let info = DownloadProgressInfo {
-                name: format!("asset-index-{}", version.asset_index.id),
-                version: Arc::new(version_id.to_string()),
+    pub async fn download_asset_index(
+        &self,
+        version_id: &str,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<(), ProtonError> {
+        let fs = self.file_system.clone();
+        scoped_file_system(fs, async {
+            // La versión propia ya está resuelta (y puede no existir en el manifest)
+            let asset_index = if version_id == self.game_version.id {
+                self.game_version.asset_index.clone()
+            } else {
+                resolve_version_data(version_id).await?.asset_index
             };
 
-            let _ = tx
-                .send(DownloadProgress {
-                    current: 1,
-                    total: 1,
-                    info,
-                    download_type: DownloadProgressType::Manifest,
-                })
-                .await;
-        }
+            file_system().create_dir_all(&self.asset_index_dir).await?;
 
-        Ok(())
+            let asset_index_path = self.asset_index_path(&asset_index.id);
+
+            if let Some(ref tx) = progress_tx {
+                let info = DownloadProgressInfo::new(
+                    format!("asset-index-{}", asset_index.id),
+                    Arc::new(version_id.to_string()),
+                );
+
+                let _ = tx
+                    .send(DownloadProgress::new(
+                        info,
+                        DownloadProgressType::Manifest,
+                        0,
+                        1,
+                        0,
+                    ))
+                    .await;
+            }
+
+            fetch(
+                self.shared.as_deref(),
+                asset_index.url.clone(),
+                &asset_index_path,
+                Some(&asset_index.sha1),
+                asset_index.size,
+            )
+            .await
+            .map_err(|e| {
+                ProtonError::download(
+                    format!("asset-index-{}", asset_index.id),
+                    &asset_index.url,
+                    &asset_index_path,
+                    DownloadProgressType::Manifest,
+                    e,
+                )
+            })?;
+
+            if let Some(ref tx) = progress_tx {
+                let info = DownloadProgressInfo::new(
+                    format!("asset-index-{}", asset_index.id),
+                    Arc::new(version_id.to_string()),
+                );
+
+                let _ = tx
+                    .send(DownloadProgress::new(
+                        info,
+                        DownloadProgressType::Manifest,
+                        1,
+                        1,
+                        file_system()
+                            .metadata(&asset_index_path)
+                            .await
+                            .map_or(0, |m| m.len),
+                    ))
+                    .await;
+            }
+
+            Ok(())
+        })
+        .await
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     async fn download_natives_internal(
         &mut self,
         progress_tx: Option<Sender<DownloadProgress>>,
-    ) -> Result<(), ProtonError> {
-        let natives = std::mem::take(&mut self.game_version.natives);
+        totals: &OverallTotals,
+    ) -> Result<Vec<FailedFile>, ProtonError> {
+        let all_natives = std::mem::take(&mut self.game_version.natives);
+
+        // Omitir los jars ya extraídos cuyo contenido sigue intacto
+        let state = NativesState::load(&self.natives_dir).await;
+        let mut natives = Vec::with_capacity(all_natives.len());
+        for native in all_natives {
+            if state.is_up_to_date(&self.natives_dir, &native.sha1).await {
+                info!("Natives {} already extracted, skipping", native.name);
+                totals.skip(native.size);
+            } else {
+                natives.push(native);
+            }
+        }
+        if natives.is_empty() {
+            return Ok(Vec::new());
+        }
+        let state = Arc::new(Mutex::new(state));
+
         let total = natives.len();
-        let (semaphore, completed, mut tasks, game_version_arc, _) =
+        let (semaphore, completed, tasks, game_version_arc, _) =
             create_adaptive_infrastructure!(total, self.game_version.id, self.adaptive_config);
 
         let natives_dir = Arc::new(self.natives_dir.clone());
-        let temp_dir = self
-            .game_path
-            .join("temp")
-            .join("natives")
-            .join(format!("native_temp_{}", std::process::id()));
+        let temp_dir = self.game_path.join("temp").join("natives").join(format!(
+            "native_temp_{}_{}",
+            std::process::id(),
+            self.game_version.id
+        ));
 
-        tokio::fs::create_dir_all(&temp_dir).await?;
+        file_system().create_dir_all(&temp_dir).await?;
+        // Se borra aunque la descarga falle o se cancele
+        let _temp_guard = TempGuard::dir(temp_dir.clone());
+        let endpoints = endpoints();
 
         for native in natives {
-            let temp_native_path = temp_dir.join(&native.path);
+            // Un jar ya colocado en `natives/<id>` (importado o bajado con otra
+            // herramienta) se extrae sin descargarlo y después se borra
+            let placed_path = self.natives_dir.join(&native.path);
+            let placed = file_system().exists(&placed_path).await;
+            let temp_native_path = if placed {
+                placed_path
+            } else {
+                temp_dir.join(&native.path)
+            };
             let natives_dir_clone = Arc::clone(&natives_dir);
             let temp_path_for_task = temp_native_path.clone();
+            let exclude = native.exclude;
+            let state_for_task = Arc::clone(&state);
+            let sha1_for_task = native.sha1.clone();
 
             create_monitored_task!(
                 tasks,
@@ -481,35 +1247,57 @@ impl MinecraftDownloader {
                 progress_tx,
                 game_version_arc,
                 self.adaptive_config,
+                self.shared,
+                self.only,
                 total,
                 DownloadProgressType::Native,
                 native.name,
-                native.url,
+                endpoints.library_url(&native.url),
                 temp_native_path,
-                native.sha1,
-                extract_native(&temp_path_for_task, natives_dir_clone.as_ref()).await
+                Some(native.sha1),
+                native.size,
+                async {
+                    let files =
+                        extract_native(&temp_path_for_task, natives_dir_clone.as_ref(), &exclude)
+                            .await?;
+                    if placed {
+                        file_system().remove_file(&temp_path_for_task).await?;
+                    }
+                    state_for_task
+                        .lock()
+                        .await
+                        .extracted
+                        .insert(sha1_for_task, files);
+                    Ok::<(), ProtonError>(())
+                }
+                .await
             );
         }
 
-        while let Some(res) = tasks.next().await {
-            res??;
+        let failures = drain_tasks(tasks, self.tolerate_failures).await?;
+
+        if let Err(e) = state.lock().await.save(&self.natives_dir).await {
+            warn!("Failed to save natives state: {e}");
         }
 
-        tokio::fs::remove_dir_all(temp_dir).await?;
-        Ok(())
+        Ok(failures)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     async fn download_libraries_internal(
         &mut self,
         progress_tx: Option<Sender<DownloadProgress>>,
-    ) -> Result<(), ProtonError> {
+    ) -> Result<Vec<FailedFile>, ProtonError> {
         let libraries = std::mem::take(&mut self.game_version.libraries);
         let total = libraries.len();
-        let (semaphore, completed, mut tasks, game_version_arc, _) =
+        let (semaphore, completed, tasks, game_version_arc, _) =
             create_adaptive_infrastructure!(total, self.game_version.id, self.adaptive_config);
+        let endpoints = endpoints();
 
         for library in libraries {
             let library_path = self.libraries_dir.join(&library.path);
+            // Algunos perfiles modded no publican el hash de sus librerías
+            let sha1 = (!library.sha1.is_empty()).then_some(library.sha1);
 
             create_monitored_task!(
                 tasks,
@@ -518,36 +1306,71 @@ impl MinecraftDownloader {
                 progress_tx,
                 game_version_arc,
                 self.adaptive_config,
+                self.shared,
+                self.only,
                 total,
                 DownloadProgressType::Library,
                 library.name,
-                library.url,
+                endpoints.library_url(&library.url),
                 library_path,
-                library.sha1,
+                sha1,
+                library.size,
                 Ok::<(), ProtonError>(())
             );
         }
 
-        while let Some(res) = tasks.next().await {
-            res??;
-        }
-        Ok(())
+        drain_tasks(tasks, self.tolerate_failures).await
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     async fn download_assets_internal(
         &self,
         progress_tx: Option<Sender<DownloadProgress>>,
-    ) -> Result<(), ProtonError> {
-        let asset_index = resolve_asset_index(&self.game_version).await?;
-        let total = asset_index.len();
-        let (semaphore, completed, mut tasks, game_version_arc, _) =
+        totals: &OverallTotals,
+    ) -> Result<Vec<FailedFile>, ProtonError> {
+        let asset_index = self.load_asset_index().await?;
+        let is_virtual = asset_index.is_virtual;
+        let map_to_resources = asset_index.map_to_resources;
+        let mut entries: Vec<_> = asset_index
+            .into_vec()
+            .into_iter()
+            .filter(|(name, asset)| self.asset_filters.iter().all(|f| f.keeps(name, asset)))
+            .collect();
+        // Las tareas se lanzan en este orden y el semáforo atiende por orden de llegada
+        self.asset_order.sort(&mut entries);
+        let total = entries.len();
+        totals.add(
+            total,
+            entries.iter().map(|(_, asset)| asset.size as u64).sum(),
+        );
+        let (semaphore, completed, tasks, game_version_arc, _) =
             create_adaptive_infrastructure!(total, self.game_version.id, self.adaptive_config);
+        let endpoints = endpoints();
+
+        // Destino adicional para índices legacy/pre-1.6
+        let legacy_dir = if map_to_resources {
+            Some(self.game_path.join("resources"))
+        } else if is_virtual {
+            Some(
+                self.game_path
+                    .join("assets")
+                    .join("virtual")
+                    .join(&self.game_version.asset_index.id),
+            )
+        } else {
+            None
+        };
+        let mut legacy_entries = Vec::new();
+
+        for (name, asset) in entries {
+            if legacy_dir.is_some() {
+                legacy_entries.push((name.clone(), asset.hash.clone()));
+            }
 
-        for (name, asset) in asset_index.into_vec() {
             let hash = &asset.hash;
-            let subhash: String = hash.chars().take(2).collect();
-            let url = format!("{RESOURCES_BASE_URL}/{subhash}/{hash}");
-            let path = self.objects_dir.join(&subhash).join(hash);
+            let url = endpoints.asset_url(hash)?;
+            let subhash = &hash[..2];
+            let path = self.objects_dir.join(subhash).join(hash);
             let hash_string = hash.to_string();
 
             create_monitored_task!(
@@ -557,39 +1380,149 @@ impl MinecraftDownloader {
                 progress_tx,
                 game_version_arc,
                 self.adaptive_config,
+                self.shared,
+                self.only,
                 total,
                 DownloadProgressType::Asset,
                 name,
                 url,
                 path,
-                hash_string,
+                Some(hash_string),
+                asset.size as u64,
                 Ok::<(), ProtonError>(())
             );
         }
 
-        while let Some(res) = tasks.next().await {
-            res??;
+        let failures = drain_tasks(tasks, self.tolerate_failures).await?;
+
+        if let Some(legacy_dir) = legacy_dir {
+            self.install_legacy_assets(&legacy_dir, legacy_entries)
+                .await?;
+        }
+
+        Ok(failures)
+    }
+
+    /// Borra los temporales que dejaron descargas de procesos ya terminados.
+    /// Se llama con el directorio bloqueado, así que nadie más los está usando.
+    async fn remove_orphan_temp_files(&self) {
+        for dir in [
+            self.game_path.join("versions").join(&self.game_version.id),
+            self.libraries_dir.clone(),
+            self.game_path.join("assets"),
+            self.natives_dir.clone(),
+            self.game_path.join("temp").join("staging"),
+        ] {
+            remove_orphan_temp_files(&dir).await;
+        }
+
+        // Directorios de extracción de nativos (`native_temp_<pid>_<versión>`)
+        let fs = file_system();
+        let natives_temp = self.game_path.join("temp").join("natives");
+        for entry in fs.read_dir(&natives_temp).await.unwrap_or_default() {
+            let pid = entry.file_name().and_then(|name| {
+                let rest = name.to_str()?.strip_prefix("native_temp_")?;
+                rest.split('_').next()?.parse().ok()
+            });
+            if let Some(pid) = pid
+                && !process_running(pid)
+                && let Err(e) = fs.remove_dir_all(&entry).await
+            {
+                warn!("Failed to remove orphaned directory {entry:?}: {e}");
+            }
+        }
+    }
+
+    fn asset_index_path(&self, index_id: &str) -> PathBuf {
+        self.asset_index_dir.join(format!("{index_id}.json"))
+    }
+
+    /// Lee el índice ya instalado y verificado; si no está, lo pide a la red
+    async fn load_asset_index(&self) -> Result<VersionAssets, ProtonError> {
+        let path = self.asset_index_path(&self.game_version.asset_index.id);
+
+        let index: VersionAssets = match file_system().read(&path).await {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|e| ProtonError::Other(format!("Invalid asset index {path:?}: {e}")))?,
+            Err(_) => resolve_asset_index(&self.game_version).await?,
+        };
+        index.check_hashes()?;
+        Ok(index)
+    }
+
+    /// Copia los objetos descargados con su nombre original para versiones
+    /// que leen `assets/virtual/<índice>` o `resources/` en lugar del almacén por hash
+    async fn install_legacy_assets(
+        &self,
+        target_dir: &Path,
+        entries: Vec<(String, String)>,
+    ) -> Result<(), ProtonError> {
+        let fs = file_system();
+        for (name, hash) in entries {
+            let source = self.objects_dir.join(&hash[..2]).join(&hash);
+            let target = safe_join(target_dir, &name)?;
+
+            // Si el objeto falló en modo tolerante no hay nada que copiar
+            let Ok(source_meta) = fs.metadata(&source).await else {
+                continue;
+            };
+            if let Ok(target_meta) = fs.metadata(&target).await
+                && target_meta.len == source_meta.len
+            {
+                continue;
+            }
+
+            if let Some(parent) = target.parent() {
+                fs.create_dir_all(parent).await?;
+            }
+            let _ = fs.remove_file(&target).await;
+
+            // Enlace duro cuando es posible, copia si no (p. ej. otro sistema de archivos)
+            if fs.hard_link(&source, &target).await.is_err() {
+                fs.copy(&source, &target).await?;
+            }
         }
+
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     async fn download_client_and_manifest_internal(
         &self,
         progress_tx: Option<Sender<DownloadProgress>>,
-    ) -> Result<(), ProtonError> {
+    ) -> Result<Vec<FailedFile>, ProtonError> {
         let client_info = self.game_version.client_jar.clone();
+        let logging = self.game_version.logging.clone();
         let version_id = &self.game_version.id;
-        let total = 2; // client + manifest
 
-        let (semaphore, completed, mut tasks, game_version_arc, _) =
-            create_adaptive_infrastructure!(total, self.game_version.id, self.adaptive_config);
+        // Las versiones locales (propias o modded) no están en el manifest: su
+        // JSON ya está en `versions/<id>`
+        let version_info = match resolve_version_in_manifest(version_id).await {
+            Ok(info) => Some(info),
+            Err(ProtonError::VersionNotFound(_))
+                if file_system()
+                    .exists(&self.version_dir().join(format!("{version_id}.json")))
+                    .await =>
+            {
+                info!("{version_id} is not in the manifest, using its local JSON");
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        // client + manifest + log4j
+        let total = 1 + usize::from(version_info.is_some()) + usize::from(logging.is_some());
 
-        // Crear directorios necesarios
-        let version_dir = self.game_path.join("versions").join(version_id);
-        tokio::fs::create_dir_all(&version_dir).await?;
+        let (semaphore, completed, tasks, game_version_arc, _) =
+            create_adaptive_infrastructure!(total, self.game_version.id, self.adaptive_config);
 
         // 1. Tarea para descargar el client jar
-        let client_path = version_dir.join(format!("{version_id}.jar"));
+        let client_path = self
+            .staged_path(
+                &format!("{version_id}.jar"),
+                &client_info.sha1,
+                client_info.size,
+            )
+            .await;
 
         create_monitored_task!(
             tasks,
@@ -598,20 +1531,91 @@ impl MinecraftDownloader {
             progress_tx,
             game_version_arc,
             self.adaptive_config,
+            self.shared,
+            self.only,
             total,
             DownloadProgressType::Client,
             format!("minecraft-{}", version_id),
             client_info.url,
             client_path,
-            client_info.sha1,
+            Some(client_info.sha1),
+            client_info.size,
             Ok::<(), ProtonError>(())
         );
 
         // 2. Tarea para descargar el manifest de la versión específica
-        let manifest_path = version_dir.join(format!("{version_id}.json"));
+        if let Some(version_info) = version_info {
+            let manifest_path = self
+                .staged_path(&format!("{version_id}.json"), &version_info.sha1, 0)
+                .await;
+
+            create_monitored_task!(
+                tasks,
+                semaphore,
+                completed,
+                progress_tx,
+                game_version_arc,
+                self.adaptive_config,
+                self.shared,
+                self.only,
+                total,
+                DownloadProgressType::Manifest,
+                format!("manifest-{}", version_id),
+                version_info.url,
+                manifest_path,
+                Some(version_info.sha1),
+                0,
+                Ok::<(), ProtonError>(())
+            );
+        }
+
+        // 3. Configuración de log4j declarada por la versión
+        if let Some(logging) = logging {
+            let logging_path = logging.path(&self.game_path);
 
-        // Resolver la información del manifest de la versión específica
-        let version_info = resolve_version_in_manifest(version_id).await?;
+            create_monitored_task!(
+                tasks,
+                semaphore,
+                completed,
+                progress_tx,
+                game_version_arc,
+                self.adaptive_config,
+                self.shared,
+                self.only,
+                total,
+                DownloadProgressType::Manifest,
+                format!("log-config-{}", logging.id),
+                logging.url,
+                logging_path,
+                Some(logging.sha1),
+                logging.size,
+                Ok::<(), ProtonError>(())
+            );
+        }
+
+        // Ejecutar las tareas concurrentemente
+        drain_tasks(tasks, self.tolerate_failures).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    async fn download_server_internal(
+        &self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<Vec<FailedFile>, ProtonError> {
+        let version_id = &self.game_version.id;
+        let server_info = self
+            .game_version
+            .server_jar
+            .clone()
+            .ok_or_else(|| ProtonError::ServerJarUnavailable(version_id.clone()))?;
+        let total = 1;
+
+        let (semaphore, completed, tasks, game_version_arc, _) =
+            create_adaptive_infrastructure!(total, self.game_version.id, self.adaptive_config);
+
+        let server_path = self
+            .staged_path("server.jar", &server_info.sha1, server_info.size)
+            .await;
 
         create_monitored_task!(
             tasks,
@@ -620,48 +1624,62 @@ impl MinecraftDownloader {
             progress_tx,
             game_version_arc,
             self.adaptive_config,
+            self.shared,
+            self.only,
             total,
-            DownloadProgressType::Manifest,
-            format!("manifest-{}", version_id),
-            version_info.url,
-            manifest_path,
-            version_info.sha1,
+            DownloadProgressType::Server,
+            format!("server-{}", version_id),
+            server_info.url,
+            server_path,
+            Some(server_info.sha1),
+            server_info.size,
             Ok::<(), ProtonError>(())
         );
 
-        // Ejecutar ambas tareas concurrentemente
-        while let Some(res) = tasks.next().await {
-            res??;
-        }
-
-        Ok(())
+        drain_tasks(tasks, self.tolerate_failures).await
     }
 
     // Métodos de clonación
-    fn clone_for_natives(&self) -> MinecraftDownloader {
+    fn clone_base(&self) -> MinecraftDownloader {
         let mut cloned =
             MinecraftDownloader::new(self.game_path.clone(), self.game_version.clone());
+        cloned.tolerate_failures = self.tolerate_failures;
+        cloned.include_server = self.include_server;
+        cloned.asset_filters = self.asset_filters.clone();
+        cloned.shared = self.shared.clone();
+        cloned.only = self.only.clone();
+        cloned.asset_order = self.asset_order;
+        // Todas las tareas de una instalación ajustan la misma concurrencia
+        cloned.adaptive_config = Arc::clone(&self.adaptive_config);
+        cloned.priority = self.priority;
+        cloned.deadline = self.deadline;
+        cloned.cancel = self.cancel.clone();
+        cloned.file_system = self.file_system.clone();
+        cloned
+    }
+
+    fn clone_for_natives(&self) -> MinecraftDownloader {
+        let mut cloned = self.clone_base();
         cloned.game_version.natives = self.game_version.natives.clone();
         cloned
     }
 
     fn clone_for_libraries(&self) -> MinecraftDownloader {
-        let mut cloned =
-            MinecraftDownloader::new(self.game_path.clone(), self.game_version.clone());
+        let mut cloned = self.clone_base();
         cloned.game_version.libraries = self.game_version.libraries.clone();
         cloned
     }
 
     fn clone_for_assets(&self) -> MinecraftDownloader {
-        MinecraftDownloader::new(self.game_path.clone(), self.game_version.clone())
+        self.clone_base()
     }
 
     fn clone_for_client(&self) -> MinecraftDownloader {
-        MinecraftDownloader::new(self.game_path.clone(), self.game_version.clone())
+        self.clone_base()
     }
 
     fn clone_for_asset_index(&self) -> MinecraftDownloader {
-        MinecraftDownloader::new(self.game_path.clone(), self.game_version.clone())
+        self.clone_base()
     }
 
     /// Obtiene estadísticas actuales de la configuración adaptativa
@@ -674,3 +1692,763 @@ impl MinecraftDownloader {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFs;
+    use crate::launcher::{LaunchOptions, build_launch_command};
+    use crate::manifest::normalize_version_json;
+    use crate::testing::{
+        FIXTURE_VERSION, MockEnvironment, MockMinecraftServer, fixture_asset_index,
+        fixture_version_details, sha1,
+    };
+    use crate::types::{AssetIndex, Downloadable};
+    use serde_json::json;
+    use tokio::sync::mpsc::channel;
+    use wiremock::matchers::{method, path, path_regex};
+    use wiremock::{Mock, ResponseTemplate};
+
+    async fn serve(server: &MockMinecraftServer, file_path: &str, content: Vec<u8>) {
+        Mock::given(method("GET"))
+            .and(path(file_path))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+            .mount(server.server())
+            .await;
+    }
+
+    #[tokio::test]
+    async fn download_all_installs_the_fixture_version() {
+        let env = MockEnvironment::start().await;
+        let (fs, game_path) = (&env.fs, &env.game_dir);
+        let mut downloader = env.downloader();
+
+        let (tx, mut rx) = channel(1024);
+        downloader.download_all(Some(tx)).await.unwrap();
+
+        let version_dir = game_path.join("versions").join(FIXTURE_VERSION);
+        assert_eq!(
+            fs.get(version_dir.join(format!("{FIXTURE_VERSION}.jar")))
+                .as_deref(),
+            Some(&b"proton fixture client jar\n"[..])
+        );
+        assert!(
+            fs.get(game_path.join("libraries/org/proton/library/1.0/library-1.0.jar"))
+                .is_some()
+        );
+        for asset in fixture_asset_index().objects.values() {
+            let object = game_path
+                .join("assets/objects")
+                .join(&asset.hash[..2])
+                .join(&asset.hash);
+            assert!(fs.get(&object).is_some(), "missing asset {object:?}");
+        }
+        let natives = fs
+            .read_dir(&game_path.join("natives").join(FIXTURE_VERSION))
+            .await
+            .unwrap();
+        assert!(natives.iter().any(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().contains("proton-test"))
+        }));
+        assert!(!natives.iter().any(|path| path.ends_with("META-INF")));
+        // Nada se escribió fuera del sistema del descargador
+        assert!(!game_path.exists());
+
+        let mut last = None;
+        while let Ok(progress) = rx.try_recv() {
+            last = progress.overall;
+        }
+        let last = last.unwrap();
+        assert_eq!(last.completed_files, last.total_files);
+
+        assert!(downloader.verify().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn tolerant_mode_collects_failures_and_finishes_the_rest() {
+        let env = MockEnvironment::start().await;
+        let (server, game_path) = (&env.server, &env.game_dir);
+        let mut version = server.version();
+        let library_url = std::mem::replace(
+            &mut version.libraries[0].url,
+            format!("{}/libraries/missing.jar", server.uri()),
+        );
+
+        // Sin el modo tolerante, el primer fallo aborta la instalación
+        let fs = MemoryFs::new();
+        let mut strict = MinecraftDownloader::new(game_path.clone(), version.clone())
+            .file_system(Arc::new(fs.clone()));
+        let error = strict.download_all(None).await.unwrap_err();
+        assert!(!matches!(error, ProtonError::PartialFailure(_)));
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+
+        let fs = MemoryFs::new();
+        let mut tolerant = MinecraftDownloader::new(game_path.clone(), version)
+            .tolerate_failures(true)
+            .file_system(Arc::new(fs.clone()));
+        let Err(ProtonError::PartialFailure(mut failed)) = tolerant.download_all(None).await else {
+            panic!("expected a partial failure");
+        };
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].download_type, DownloadProgressType::Library);
+        assert_eq!(failed[0].kind, ErrorKind::NotFound);
+        // El resto de descargas siguió adelante
+        for asset in fixture_asset_index().objects.values() {
+            let object = game_path
+                .join("assets/objects")
+                .join(&asset.hash[..2])
+                .join(&asset.hash);
+            assert!(fs.get(&object).is_some(), "missing asset {object:?}");
+        }
+
+        // Solo se reintenta lo que falló
+        failed[0].url = library_url;
+        tolerant.retry_failed(failed).await.unwrap();
+        assert!(
+            fs.get(game_path.join("libraries/org/proton/library/1.0/library-1.0.jar"))
+                .is_some()
+        );
+        assert!(
+            fs.get(
+                game_path
+                    .join("versions")
+                    .join(FIXTURE_VERSION)
+                    .join(format!("{FIXTURE_VERSION}.jar"))
+            )
+            .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn legacy_indexes_copy_assets_with_their_names() {
+        let env = MockEnvironment::start().await;
+        let (server, game_path) = (&env.server, &env.game_dir);
+
+        for (id, flag, target_dir) in [
+            ("legacy", "virtual", game_path.join("assets/virtual/legacy")),
+            ("pre-1.6", "map_to_resources", game_path.join("resources")),
+        ] {
+            let mut index = serde_json::to_value(fixture_asset_index()).unwrap();
+            index[flag] = json!(true);
+            let index = serde_json::to_vec(&index).unwrap();
+            let mut version = server.version();
+            version.asset_index = AssetIndex {
+                id: id.to_string(),
+                url: format!("{}/indexes/{id}.json", server.uri()),
+                sha1: sha1(&index),
+                size: index.len() as u64,
+            };
+            serve(server, &format!("/indexes/{id}.json"), index).await;
+
+            let fs = MemoryFs::new();
+            let mut downloader = MinecraftDownloader::new(game_path.clone(), version)
+                .file_system(Arc::new(fs.clone()));
+            downloader.download_all(None).await.unwrap();
+
+            assert_eq!(
+                fs.get(target_dir.join("minecraft/sounds/fixture.ogg"))
+                    .as_deref(),
+                Some(&b"OggS proton fixture\n"[..])
+            );
+            assert!(
+                fs.get(target_dir.join("minecraft/lang/en_us.json"))
+                    .is_some()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn asset_filters_skip_unwanted_assets() {
+        let env = MockEnvironment::start().await;
+        let (server, fs, game_path) = (&env.server, &env.fs, &env.game_dir);
+        let mut downloader = MinecraftDownloader::new(game_path.clone(), server.version())
+            .asset_filter(AssetFilter::Languages(Vec::new()))
+            .asset_filter(AssetFilter::Custom(Arc::new(|name, _| {
+                !name.starts_with("minecraft/sounds/")
+            })))
+            .file_system(Arc::new(fs.clone()));
+        downloader.download_all(None).await.unwrap();
+
+        let index = fixture_asset_index();
+        let object = |name: &str| {
+            let hash = &index.objects[name].hash;
+            game_path.join("assets/objects").join(&hash[..2]).join(hash)
+        };
+        // `en_us` se conserva siempre
+        assert!(fs.get(object("minecraft/lang/en_us.json")).is_some());
+        assert!(fs.get(object("minecraft/sounds/fixture.ogg")).is_none());
+        // Lo filtrado tampoco cuenta como ausente al verificar
+        assert!(downloader.verify().await.unwrap().is_empty());
+
+        let keeps = |filter: AssetFilter, name: &str| {
+            filter.keeps(
+                name,
+                &Asset {
+                    hash: String::new(),
+                    size: 0,
+                },
+            )
+        };
+        assert!(keeps(
+            AssetFilter::Languages(vec!["ES_ES".into()]),
+            "minecraft/lang/es_es.json"
+        ));
+        assert!(!keeps(
+            AssetFilter::Languages(vec!["es_es".into()]),
+            "minecraft/lang/de_de.json"
+        ));
+        assert!(!keeps(
+            AssetFilter::SkipMusic,
+            "minecraft/sounds/music/game/calm1.ogg"
+        ));
+        assert!(!keeps(
+            AssetFilter::SkipMusic,
+            "minecraft/sounds/records/cat.ogg"
+        ));
+        assert!(keeps(
+            AssetFilter::SkipMusic,
+            "minecraft/sounds/ambient/cave/cave1.ogg"
+        ));
+    }
+
+    #[tokio::test]
+    async fn extracted_natives_are_not_downloaded_again() {
+        let env = MockEnvironment::start().await;
+        let (server, fs, game_path) = (&env.server, &env.fs, &env.game_dir);
+        let natives_dir = game_path.join("natives").join(FIXTURE_VERSION);
+        let mut downloader = env.downloader();
+        let natives_requests = || async {
+            let requests = server.server().received_requests().await.unwrap();
+            requests
+                .iter()
+                .filter(|request| request.url.path().ends_with("natives-1.0-natives.jar"))
+                .count()
+        };
+
+        downloader.download_all(None).await.unwrap();
+        assert_eq!(natives_requests().await, 1);
+        assert!(fs.get(natives_dir.join(".proton")).is_some());
+
+        downloader.download_all(None).await.unwrap();
+        assert_eq!(natives_requests().await, 1);
+
+        // Si falta un archivo extraído, el jar se vuelve a bajar y extraer
+        let extracted = fs
+            .files()
+            .into_iter()
+            .find(|path| {
+                path.starts_with(&natives_dir)
+                    && path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().contains("proton-test"))
+            })
+            .unwrap();
+        fs.remove_file(&extracted).await.unwrap();
+        downloader.download_all(None).await.unwrap();
+        assert_eq!(natives_requests().await, 2);
+        assert!(fs.get(&extracted).is_some());
+    }
+
+    #[test]
+    fn one_task_per_turn_samples_the_load() {
+        let mut config = AdaptiveConfig::new();
+        config.last_adjustment = Instant::now() - Duration::from_secs(10);
+        let fast = Duration::from_millis(10);
+
+        for _ in 1..config.sample_size / 2 {
+            assert!(config.record(fast).is_none());
+        }
+        assert!(config.record(fast).is_some());
+        // El turno ya está tomado: el resto de tareas no esperan a la muestra
+        assert!(config.record(fast).is_none());
+
+        let before = config.current_concurrent;
+        config.adjust_concurrency(SystemLoad {
+            cpu_percent: 100,
+            io_percent: None,
+        });
+        assert_eq!(
+            config.current_concurrent,
+            (before / 2).max(config.min_concurrent)
+        );
+        assert!(config.performance_samples.is_empty());
+    }
+
+    #[tokio::test]
+    async fn concurrent_installs_stop_assets_when_critical_files_fail() {
+        let env = MockEnvironment::start().await;
+        let server = &env.server;
+        stall_assets(server).await;
+        let mut version = server.version();
+        version.libraries[0].url = format!("{}/libraries/missing.jar", server.uri());
+
+        let mut downloader = MinecraftDownloader::new(env.game_dir.clone(), version)
+            .priority(DownloadPriority::Concurrent)
+            .file_system(Arc::new(env.fs.clone()));
+        let started = Instant::now();
+        let error = downloader.download_all(None).await.unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn critical_first_requests_assets_after_the_game_files() {
+        let env = MockEnvironment::start().await;
+        let server = &env.server;
+        let mut downloader = env.downloader().priority(DownloadPriority::CriticalFirst);
+        let playable = downloader.playable();
+
+        downloader.download_all(None).await.unwrap();
+
+        assert!(*playable.borrow());
+        let requests: Vec<String> = server
+            .server()
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| request.url.path().to_string())
+            .collect();
+        let first_asset = requests
+            .iter()
+            .position(|path| path.starts_with("/resources/"))
+            .unwrap();
+        let last_critical = requests
+            .iter()
+            .rposition(|path| path.starts_with("/libraries/") || path == "/client.jar")
+            .unwrap();
+        assert!(last_critical < first_asset, "{requests:?}");
+    }
+
+    #[test]
+    fn install_tasks_share_the_downloader_settings() {
+        let version = crate::testing::fixture_version("http://localhost");
+        let cancel = CancellationToken::new();
+        let mut downloader = MinecraftDownloader::new(PathBuf::from("/game"), version)
+            .priority(DownloadPriority::Concurrent)
+            .deadline(Duration::from_secs(5))
+            .file_system(Arc::new(MemoryFs::new()));
+        downloader.cancel = cancel.clone();
+
+        let task = downloader.clone_for_assets();
+        assert!(Arc::ptr_eq(
+            &task.adaptive_config,
+            &downloader.adaptive_config
+        ));
+        assert_eq!(task.priority, DownloadPriority::Concurrent);
+        assert_eq!(task.deadline, Some(Duration::from_secs(5)));
+        assert!(task.file_system.is_some());
+        cancel.cancel();
+        assert!(task.cancel.is_cancelled());
+    }
+
+    #[test]
+    fn asset_orders_sort_by_size() {
+        let sizes = |order: AssetOrder| {
+            let mut entries: Vec<(String, Asset)> = [3, 1, 4, 2, 5]
+                .into_iter()
+                .map(|size| {
+                    let asset = Asset {
+                        hash: format!("{size:040}"),
+                        size,
+                    };
+                    (format!("asset-{size}"), asset)
+                })
+                .collect();
+            order.sort(&mut entries);
+            entries
+                .into_iter()
+                .map(|(_, asset)| asset.size)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sizes(AssetOrder::Unordered), [3, 1, 4, 2, 5]);
+        assert_eq!(sizes(AssetOrder::SmallestFirst), [1, 2, 3, 4, 5]);
+        assert_eq!(sizes(AssetOrder::LargestFirst), [5, 4, 3, 2, 1]);
+        assert_eq!(sizes(AssetOrder::Interleaved), [5, 1, 4, 2, 3]);
+        assert_eq!(
+            "largest-first".parse::<AssetOrder>().unwrap(),
+            AssetOrder::LargestFirst
+        );
+        assert!("biggest".parse::<AssetOrder>().is_err());
+    }
+
+    async fn stall_assets(server: &MockMinecraftServer) {
+        Mock::given(method("GET"))
+            .and(path_regex("^/resources/"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+    }
+
+    #[tokio::test]
+    async fn deadlines_stop_the_install_with_its_progress() {
+        let env = MockEnvironment::start().await;
+        let server = &env.server;
+        stall_assets(server).await;
+        let limit = Duration::from_secs(1);
+        let mut downloader = env
+            .downloader()
+            .priority(DownloadPriority::CriticalFirst)
+            .deadline(limit);
+
+        let started = Instant::now();
+        let error = downloader.download_all(None).await.unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let ProtonError::Timeout {
+            limit: reported,
+            progress,
+        } = error
+        else {
+            panic!("expected a timeout, got {error:?}");
+        };
+        assert_eq!(reported, limit);
+        assert!(progress.completed_files > 0);
+        assert!(progress.completed_files < progress.total_files);
+    }
+
+    #[tokio::test]
+    async fn cancelled_installs_leave_no_temp_files_and_can_resume() {
+        let env = MockEnvironment::start().await;
+        let (server, fs, game_path) = (&env.server, &env.fs, &env.game_dir);
+        stall_assets(server).await;
+        let mut downloader = env.downloader().priority(DownloadPriority::CriticalFirst);
+        let cancel = downloader.cancel_token();
+        let mut playable = downloader.playable();
+        let canceller = tokio::spawn(async move {
+            let _ = playable.wait_for(|playable| *playable).await;
+            cancel.cancel();
+        });
+
+        let error = downloader.download_all(None).await.unwrap_err();
+        canceller.await.unwrap();
+
+        assert!(matches!(error, ProtonError::Cancelled));
+        let leftovers: Vec<_> = fs
+            .files()
+            .into_iter()
+            .filter(|path| {
+                let path = path.to_string_lossy();
+                path.ends_with(".proton-tmp") || path.contains("native_temp")
+            })
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+
+        let state = fs
+            .get(
+                game_path
+                    .join("temp/resume")
+                    .join(format!("{FIXTURE_VERSION}.json")),
+            )
+            .unwrap();
+        let state: ResumeState = serde_json::from_slice(&state).unwrap();
+        assert!(!state.pending.is_empty());
+        assert!(
+            state
+                .pending
+                .iter()
+                .all(|file| file.download_type == DownloadProgressType::Asset)
+        );
+        // Un token nuevo para el siguiente intento
+        assert!(!downloader.cancel_token().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn only_downloads_the_listed_files() {
+        let env = MockEnvironment::start().await;
+        let server = &env.server;
+        let version = server.version();
+        let library = DownloadPlan::new(&version, None)
+            .files
+            .into_iter()
+            .filter(|file| file.download_type == DownloadProgressType::Library)
+            .collect::<Vec<_>>();
+        let (fs, game_path) = (&env.fs, &env.game_dir);
+        let mut downloader = MinecraftDownloader::new(game_path.clone(), version)
+            .only(&library)
+            .file_system(Arc::new(fs.clone()));
+
+        let (tx, mut rx) = channel(1024);
+        downloader.download_all(Some(tx)).await.unwrap();
+
+        assert!(fs.get(game_path.join(&library[0].path)).is_some());
+        let client = game_path
+            .join("versions")
+            .join(FIXTURE_VERSION)
+            .join(format!("{FIXTURE_VERSION}.jar"));
+        assert!(fs.get(client).is_none());
+        for asset in fixture_asset_index().objects.values() {
+            let object = game_path
+                .join("assets/objects")
+                .join(&asset.hash[..2])
+                .join(&asset.hash);
+            assert!(fs.get(&object).is_none());
+        }
+        // Lo omitido cuenta como hecho en el progreso
+        let mut last = None;
+        while let Ok(progress) = rx.try_recv() {
+            last = progress.overall;
+        }
+        let last = last.unwrap();
+        assert_eq!(last.completed_files, last.total_files);
+    }
+
+    #[tokio::test]
+    async fn the_server_jar_is_only_downloaded_on_request() {
+        const SERVER_JAR: &[u8] = b"proton fixture server jar";
+        let env = MockEnvironment::start().await;
+        serve(&env.server, "/server.jar", SERVER_JAR.to_vec()).await;
+        let mut version = env.server.version();
+        version.server_jar = Some(Downloadable {
+            url: format!("{}/server.jar", env.server.uri()),
+            sha1: sha1(SERVER_JAR),
+            size: SERVER_JAR.len() as u64,
+        });
+        let downloader = |version: NormalizedVersion| {
+            MinecraftDownloader::new(env.game_dir.clone(), version)
+                .file_system(Arc::new(env.fs.clone()))
+        };
+        let server_jar = env
+            .game_dir
+            .join("versions")
+            .join(FIXTURE_VERSION)
+            .join("server.jar");
+
+        downloader(version.clone())
+            .download_all(None)
+            .await
+            .unwrap();
+        assert!(env.fs.get(&server_jar).is_none());
+
+        let (tx, mut rx) = channel(1024);
+        downloader(version.clone())
+            .include_server(true)
+            .download_all(Some(tx))
+            .await
+            .unwrap();
+        assert_eq!(env.fs.get(&server_jar).as_deref(), Some(SERVER_JAR));
+        let mut server_events = 0;
+        while let Ok(progress) = rx.try_recv() {
+            server_events += usize::from(progress.download_type == DownloadProgressType::Server);
+        }
+        assert!(server_events > 0);
+
+        // Un jar que no coincide con su hash no llega a `versions/<id>`
+        env.fs.remove_file(&server_jar).await.unwrap();
+        let mut corrupted = version.clone();
+        if let Some(jar) = &mut corrupted.server_jar {
+            jar.sha1 = sha1(b"another jar");
+        }
+        assert!(downloader(corrupted).download_server(None).await.is_err());
+        assert!(env.fs.get(&server_jar).is_none());
+
+        version.server_jar = None;
+        assert!(matches!(
+            downloader(version).download_server(None).await,
+            Err(ProtonError::ServerJarUnavailable(id)) if id == FIXTURE_VERSION
+        ));
+    }
+
+    #[tokio::test]
+    async fn the_log4j_config_is_installed_and_passed_to_the_jvm() {
+        const LOG_CONFIG: &[u8] = b"<Configuration status=\"WARN\"></Configuration>\n";
+        let env = MockEnvironment::start().await;
+        serve(
+            &env.server,
+            "/log_configs/client-1.12.xml",
+            LOG_CONFIG.to_vec(),
+        )
+        .await;
+        let mut details = serde_json::to_value(fixture_version_details(&env.server.uri())).unwrap();
+        details["logging"] = json!({
+            "client": {
+                "argument": "-Dlog4j.configurationFile=${path}",
+                "type": "log4j2-xml",
+                "file": {
+                    "id": "client-1.12.xml",
+                    "sha1": sha1(LOG_CONFIG),
+                    "size": LOG_CONFIG.len(),
+                    "url": format!("{}/log_configs/client-1.12.xml", env.server.uri()),
+                },
+            },
+        });
+        let version = normalize_version_json(&details.to_string()).unwrap();
+
+        MinecraftDownloader::new(env.game_dir.clone(), version.clone())
+            .file_system(Arc::new(env.fs.clone()))
+            .download_all(None)
+            .await
+            .unwrap();
+
+        let config_path = env.game_dir.join("assets/log_configs/client-1.12.xml");
+        assert_eq!(env.fs.get(&config_path).as_deref(), Some(LOG_CONFIG));
+        let options = LaunchOptions::new(&env.game_dir, "Steve");
+        let command = build_launch_command(&version, &options);
+        assert!(command.jvm_args.contains(&format!(
+            "-Dlog4j.configurationFile={}",
+            config_path.display()
+        )));
+    }
+
+    #[tokio::test]
+    async fn the_asset_index_is_installed_once_and_verified() {
+        let env = MockEnvironment::start().await;
+        let version = env.server.version();
+        let index_path = env.game_dir.join("assets/indexes/fixture.json");
+
+        env.downloader().download_all(None).await.unwrap();
+
+        let installed = env.fs.get(&index_path).unwrap();
+        assert_eq!(sha1(&installed), version.asset_index.sha1);
+        // Los assets se leen del índice instalado, sin pedirlo otra vez
+        let requests = env.server.server().received_requests().await.unwrap();
+        let index_requests = requests
+            .iter()
+            .filter(|request| request.url.path() == "/indexes/fixture.json")
+            .count();
+        assert_eq!(index_requests, 1);
+
+        // Un índice que no coincide con su hash no se instala
+        let fs = MemoryFs::new();
+        let mut corrupted = version;
+        corrupted.asset_index.sha1 = sha1(b"another index");
+        let result = MinecraftDownloader::new(env.game_dir.clone(), corrupted)
+            .file_system(Arc::new(fs.clone()))
+            .download_all(None)
+            .await;
+        assert!(result.is_err());
+        assert!(fs.get(&index_path).is_none());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn downloads_open_a_span_per_phase_and_file() {
+        use std::sync::{Arc, Mutex};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// Suscriptor que solo anota el nombre de cada span creado
+        #[derive(Clone, Default)]
+        struct Spans(Arc<Mutex<Vec<&'static str>>>);
+
+        impl Subscriber for Spans {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut names = self.0.lock().unwrap();
+                names.push(span.metadata().name());
+                Id::from_u64(names.len() as u64)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let env = MockEnvironment::start().await;
+        let spans = Spans::default();
+        let _subscriber = tracing::subscriber::set_default(spans.clone());
+
+        env.downloader().download_all(None).await.unwrap();
+
+        let names = spans.0.lock().unwrap().clone();
+        for phase in [
+            "download_all",
+            "download_client_and_manifest_internal",
+            "download_libraries_internal",
+            "download_natives_internal",
+            "download_assets_internal",
+            "download_file",
+            "extract_native",
+        ] {
+            assert!(names.contains(&phase), "missing {phase} span: {names:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn download_errors_name_the_failing_file() {
+        let env = MockEnvironment::start().await;
+        Mock::given(method("GET"))
+            .and(path("/client.jar"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"tampered".to_vec()))
+            .with_priority(1)
+            .mount(env.server.server())
+            .await;
+
+        let error = env.downloader().download_all(None).await.unwrap_err();
+
+        let ProtonError::Download {
+            name,
+            url,
+            path,
+            download_type,
+            source,
+        } = &error
+        else {
+            panic!("expected download context, got {error:?}");
+        };
+        assert_eq!(*name, format!("minecraft-{FIXTURE_VERSION}"));
+        assert_eq!(*url, format!("{}/client.jar", env.server.uri()));
+        assert!(path.ends_with(format!("{FIXTURE_VERSION}.jar")), "{path:?}");
+        assert_eq!(*download_type, DownloadProgressType::Client);
+        assert!(matches!(**source, ProtonError::HashMismatch { .. }));
+        assert_eq!(error.code(), source.code());
+        assert!(error.to_string().contains("/client.jar"));
+    }
+
+    #[tokio::test]
+    async fn progress_events_count_the_finished_files_of_each_phase() {
+        let env = MockEnvironment::start().await;
+        let (tx, mut rx) = channel(1024);
+
+        env.downloader().download_all(Some(tx)).await.unwrap();
+
+        let mut events = Vec::new();
+        while let Some(progress) = rx.recv().await {
+            events.push(progress);
+        }
+        let (asset_index, events): (Vec<_>, Vec<_>) = events
+            .into_iter()
+            .partition(|progress| progress.info.name.starts_with("asset-index-"));
+        assert_eq!(asset_index.len(), 2);
+        assert!(asset_index[0].is_start() && asset_index[1].is_finished());
+
+        // El JSON de la versión y log4j cuentan en la fase del cliente
+        let phases = [
+            vec![DownloadProgressType::Client, DownloadProgressType::Manifest],
+            vec![DownloadProgressType::Library],
+            vec![DownloadProgressType::Native],
+            vec![DownloadProgressType::Asset],
+        ];
+        for kinds in phases {
+            let phase: Vec<_> = events
+                .iter()
+                .filter(|progress| kinds.contains(&progress.download_type))
+                .collect();
+            assert!(!phase.is_empty(), "{kinds:?}");
+            let total = phase[0].total;
+            let mut counts: Vec<_> = phase.iter().map(|progress| progress.current).collect();
+            counts.sort_unstable();
+            assert_eq!(counts, (1..=total).collect::<Vec<_>>(), "{kinds:?}");
+            assert_eq!(
+                phase
+                    .iter()
+                    .filter(|progress| progress.is_finished())
+                    .count(),
+                1,
+                "{kinds:?}"
+            );
+        }
+        assert!(
+            events
+                .iter()
+                .all(|progress| progress.info.version.as_str() == FIXTURE_VERSION)
+        );
+    }
+}
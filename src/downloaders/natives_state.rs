@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::filesystem::file_system;
+use crate::types::ExtractedFile;
+
+// Archivo de estado dentro de `natives/<versión>/`
+const STATE_FILE: &str = ".proton";
+
+/// Registro de los jars de nativos ya extraídos, indexado por su SHA1
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct NativesState {
+    #[serde(default)]
+    pub extracted: HashMap<String, Vec<ExtractedFile>>,
+}
+
+impl NativesState {
+    fn path(natives_dir: &Path) -> PathBuf {
+        natives_dir.join(STATE_FILE)
+    }
+
+    /// Carga el estado; si no existe o está corrupto se parte de cero
+    pub async fn load(natives_dir: &Path) -> Self {
+        match file_system().read(&Self::path(natives_dir)).await {
+            Ok(content) => serde_json::from_slice(&content).unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub async fn save(&self, natives_dir: &Path) -> std::io::Result<()> {
+        let fs = file_system();
+        fs.create_dir_all(natives_dir).await?;
+        let json = serde_json::to_vec(self).map_err(std::io::Error::other)?;
+        fs.write(&Self::path(natives_dir), &json).await
+    }
+
+    /// El jar con este hash ya se extrajo y sus archivos siguen intactos
+    pub async fn is_up_to_date(&self, natives_dir: &Path, sha1: &str) -> bool {
+        let Some(files) = self.extracted.get(sha1) else {
+            return false;
+        };
+
+        let fs = file_system();
+        for file in files {
+            match fs.metadata(&natives_dir.join(&file.path)).await {
+                Ok(meta) if meta.len == file.size => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
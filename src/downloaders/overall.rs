@@ -0,0 +1,124 @@
+use crate::types::{DownloadProgress, OverallProgress};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tokio::sync::mpsc::{Sender, channel};
+use tokio::task::JoinHandle;
+
+// Eventos en cola hacia el seguimiento cuando no hay canal de progreso
+const EVENT_BUFFER: usize = 256;
+
+/// Progreso conjunto de una instalación; los totales crecen cuando se conoce
+/// el índice de assets
+#[derive(Debug, Default)]
+pub(super) struct OverallTotals {
+    files: AtomicUsize,
+    bytes: AtomicU64,
+    completed_files: AtomicUsize,
+    completed_bytes: AtomicU64,
+}
+
+impl OverallTotals {
+    pub(super) fn add(&self, files: usize, bytes: u64) {
+        self.files.fetch_add(files, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Descuenta un archivo que se omite sin emitir eventos
+    pub(super) fn skip(&self, bytes: u64) {
+        self.files.fetch_sub(1, Ordering::Relaxed);
+        self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    fn complete(&self, bytes: u64) {
+        self.completed_files.fetch_add(1, Ordering::Relaxed);
+        self.completed_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(super) fn snapshot(&self) -> OverallProgress {
+        let completed_files = self.completed_files.load(Ordering::Relaxed);
+        let completed_bytes = self.completed_bytes.load(Ordering::Relaxed);
+        // El JSON de la versión no declara su tamaño
+        OverallProgress {
+            completed_files,
+            total_files: self.files.load(Ordering::Relaxed).max(completed_files),
+            completed_bytes,
+            total_bytes: self.bytes.load(Ordering::Relaxed).max(completed_bytes),
+        }
+    }
+}
+
+/// Canal que lleva la cuenta de los archivos terminados y, si hay `tx`, le
+/// reenvía cada evento con el progreso conjunto añadido. La tarea termina
+/// cuando se sueltan todos los emisores del canal devuelto.
+pub(super) fn forward_overall(
+    tx: Option<Sender<DownloadProgress>>,
+    totals: Arc<OverallTotals>,
+) -> (Sender<DownloadProgress>, JoinHandle<()>) {
+    let capacity = tx.as_ref().map_or(EVENT_BUFFER, Sender::max_capacity);
+    let (inner_tx, mut inner_rx) = channel::<DownloadProgress>(capacity);
+    let handle = tokio::spawn(async move {
+        let mut tx = tx;
+        while let Some(mut progress) = inner_rx.recv().await {
+            // Los metadatos avisan también al empezar, con `current` a 0
+            if progress.current > 0 {
+                totals.complete(progress.bytes);
+            }
+            if let Some(sender) = &tx {
+                progress.overall = Some(totals.snapshot());
+                if sender.send(progress).await.is_err() {
+                    // Sin receptor se sigue llevando la cuenta
+                    tx = None;
+                }
+            }
+        }
+    });
+    (inner_tx, handle)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::plan::DownloadPlan;
+    use crate::testing::{MockEnvironment, fixture_asset_index};
+    use crate::types::DownloadProgressType;
+    use std::collections::HashSet;
+
+    use tokio::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn every_event_carries_the_install_totals() {
+        let env = MockEnvironment::start().await;
+        let plan = DownloadPlan::new(&env.server.version(), None);
+        let mut downloader = env.downloader();
+
+        let (tx, mut rx) = channel(1024);
+        downloader.download_all(Some(tx)).await.unwrap();
+
+        let mut categories = HashSet::new();
+        let mut completed = 0;
+        let mut last = None;
+        while let Ok(progress) = rx.try_recv() {
+            let overall = progress.overall.unwrap();
+            assert!(overall.completed_files >= completed);
+            completed = overall.completed_files;
+            categories.insert(progress.download_type);
+            last = Some(overall);
+        }
+
+        // El plan, el JSON de la versión y los assets del índice, en todas
+        // las categorías
+        let last = last.unwrap();
+        let assets = fixture_asset_index().objects.len();
+        assert_eq!(last.total_files, plan.files.len() + 1 + assets);
+        assert_eq!(last.completed_files, last.total_files);
+        assert_eq!(last.completed_bytes, last.total_bytes);
+        for category in [
+            DownloadProgressType::Library,
+            DownloadProgressType::Native,
+            DownloadProgressType::Asset,
+            DownloadProgressType::Client,
+        ] {
+            assert!(categories.contains(&category), "{category:?}");
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use super::MinecraftDownloader;
+use super::natives_state::NativesState;
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::plan::{DownloadPlan, PlannedDownload};
+use crate::types::DownloadProgressType;
+use crate::utilities::{move_file, temp_path};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Estado de una instalación que no terminó, en `temp/resume/<id>.json`.
+/// `download_all` lo escribe al fallar, cancelarse o vencer su plazo y lo
+/// borra al terminar bien; al repetirla solo se descarga lo que falta.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeState {
+    pub version: String,
+    /// Segundos desde la época Unix
+    pub interrupted: u64,
+    /// Error con el que se interrumpió
+    pub reason: String,
+    /// Archivos que faltan o están incompletos
+    pub pending: Vec<PlannedDownload>,
+}
+
+impl ResumeState {
+    fn path(game_dir: &Path, version_id: &str) -> PathBuf {
+        game_dir
+            .join("temp")
+            .join("resume")
+            .join(format!("{version_id}.json"))
+    }
+
+    /// Estado de la última instalación de una versión, si quedó a medias
+    pub async fn load(game_dir: impl AsRef<Path>, version_id: &str) -> Option<Self> {
+        let content = file_system()
+            .read(&Self::path(game_dir.as_ref(), version_id))
+            .await
+            .ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+}
+
+impl MinecraftDownloader {
+    /// Apunta lo que falta tras una instalación interrumpida. No usa la red:
+    /// sin índice de assets en disco los assets no se listan.
+    pub(super) async fn write_resume_state(&self, reason: &ProtonError) -> Result<(), ProtonError> {
+        let fs = file_system();
+        let index_path = self.asset_index_path(&self.game_version.asset_index.id);
+        let plan = if fs.exists(&index_path).await {
+            self.download_plan().await?
+        } else {
+            DownloadPlan::new(&self.game_version, None)
+        };
+
+        // Basta con el tamaño: los hashes se comprueban al reanudar
+        let natives = NativesState::load(&self.natives_dir).await;
+        let mut pending = Vec::new();
+        for file in plan.files {
+            let present = if file.download_type == DownloadProgressType::Native {
+                natives.is_up_to_date(&self.natives_dir, &file.sha1).await
+            } else {
+                fs.metadata(&self.game_path.join(&file.path))
+                    .await
+                    .is_ok_and(|meta| file.size == 0 || meta.len == file.size)
+            };
+            if !present {
+                pending.push(file);
+            }
+        }
+
+        let state = ResumeState {
+            version: self.game_version.id.clone(),
+            interrupted: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            reason: reason.to_string(),
+            pending,
+        };
+
+        let path = ResumeState::path(&self.game_path, &self.game_version.id);
+        if let Some(parent) = path.parent() {
+            fs.create_dir_all(parent).await?;
+        }
+        let temp = temp_path(&path);
+        let json = serde_json::to_vec_pretty(&state).map_err(std::io::Error::other)?;
+        fs.write(&temp, &json).await?;
+        move_file(&temp, &path).await?;
+        Ok(())
+    }
+
+    pub(super) async fn clear_resume_state(&self) {
+        let fs = file_system();
+        let path = ResumeState::path(&self.game_path, &self.game_version.id);
+        if fs.exists(&path).await {
+            let _ = fs.remove_file(&path).await;
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use super::MinecraftDownloader;
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::utilities::{move_file, verify_file_hash};
+use std::path::PathBuf;
+
+// `versions/<id>` solo se rellena cuando todo lo demás está verificado: hasta
+// entonces el jar y el JSON de la versión esperan en `temp/staging/<id>`, así
+// una instalación a medias nunca parece completa
+impl MinecraftDownloader {
+    pub(super) fn version_dir(&self) -> PathBuf {
+        self.game_path.join("versions").join(&self.game_version.id)
+    }
+
+    fn staging_dir(&self) -> PathBuf {
+        self.game_path
+            .join("temp")
+            .join("staging")
+            .join(&self.game_version.id)
+    }
+
+    /// Dónde descargar `file_name` de `versions/<id>`: el destino final si ya
+    /// está instalado, o su copia en staging
+    pub(super) async fn staged_path(&self, file_name: &str, sha1: &str, size: u64) -> PathBuf {
+        let final_path = self.version_dir().join(file_name);
+        let installed = if sha1.is_empty() {
+            file_system().exists(&final_path).await
+        } else {
+            verify_file_hash(&final_path, sha1, size)
+                .await
+                .unwrap_or(false)
+        };
+
+        if installed {
+            final_path
+        } else {
+            self.staging_dir().join(file_name)
+        }
+    }
+
+    /// Mueve a `versions/<id>` todo lo descargado en staging. El JSON va el
+    /// último: es lo que los launchers miran para saber si una versión existe.
+    pub(super) async fn promote_staged(&self) -> Result<(), ProtonError> {
+        let id = &self.game_version.id;
+        self.promote(&[&format!("{id}.jar"), "server.jar", &format!("{id}.json")])
+            .await
+    }
+
+    /// Mueve a `versions/<id>` los archivos indicados que estén en staging
+    pub(super) async fn promote(&self, file_names: &[&str]) -> Result<(), ProtonError> {
+        let fs = file_system();
+        let staging_dir = self.staging_dir();
+        if !fs.exists(&staging_dir).await {
+            return Ok(());
+        }
+
+        let version_dir = self.version_dir();
+        fs.create_dir_all(&version_dir).await?;
+        for file_name in file_names {
+            let staged = staging_dir.join(file_name);
+            if fs.exists(&staged).await {
+                move_file(&staged, &version_dir.join(file_name)).await?;
+            }
+        }
+
+        if fs.read_dir(&staging_dir).await?.is_empty() {
+            fs.remove_dir_all(&staging_dir).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn failed_installs_never_fill_the_version_dir() {
+        let env = MockEnvironment::start().await;
+        let (server, fs, game_path) = (&env.server, &env.fs, &env.game_dir);
+        let jar = format!("{FIXTURE_VERSION}.jar");
+        let version_dir = game_path.join("versions").join(FIXTURE_VERSION);
+        let staging_dir = game_path.join("temp/staging").join(FIXTURE_VERSION);
+
+        let mut broken = server.version();
+        broken.libraries[0].url = format!("{}/libraries/missing.jar", server.uri());
+        let mut downloader =
+            MinecraftDownloader::new(game_path.clone(), broken).file_system(Arc::new(fs.clone()));
+        assert!(downloader.download_all(None).await.is_err());
+        assert!(
+            fs.files()
+                .iter()
+                .all(|path| !path.starts_with(&version_dir))
+        );
+
+        let client_requests = || async {
+            let requests = server.server().received_requests().await.unwrap();
+            requests
+                .iter()
+                .filter(|request| request.url.path() == "/client.jar")
+                .count()
+        };
+        assert!(fs.get(staging_dir.join(&jar)).is_some());
+        let before = client_requests().await;
+
+        let mut downloader = env.downloader();
+        downloader.download_all(None).await.unwrap();
+        assert!(fs.get(version_dir.join(&jar)).is_some());
+        assert!(
+            fs.get(version_dir.join(format!("{FIXTURE_VERSION}.json")))
+                .is_some()
+        );
+        assert!(
+            fs.files()
+                .iter()
+                .all(|path| !path.starts_with(&staging_dir))
+        );
+        // Lo que ya estaba verificado en staging no se vuelve a descargar
+        assert_eq!(client_requests().await, before);
+    }
+}
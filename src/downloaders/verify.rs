@@ -0,0 +1,314 @@
+use super::MinecraftDownloader;
+use super::natives_state::NativesState;
+use crate::endpoints::endpoints;
+use crate::errors::{ErrorKind, ProtonError};
+use crate::filesystem::{file_system, scoped_file_system};
+use crate::types::{DownloadProgress, DownloadProgressInfo, DownloadProgressType, FailedFile};
+use crate::utilities::{save_hash_cache, verify_file_hash};
+use futures::stream::{self, StreamExt};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::Sender;
+
+impl MinecraftDownloader {
+    /// Comprueba los archivos instalados sin descargar nada; devuelve los que
+    /// faltan o están corruptos, listos para pasarlos a `retry_failed`
+    pub async fn verify(&self) -> Result<Vec<FailedFile>, ProtonError> {
+        self.verify_with_progress(None).await
+    }
+
+    /// Como `verify`, informando de cada archivo comprobado. Los hashes se
+    /// calculan en el pool de hilos bloqueantes, tantos a la vez como núcleos.
+    pub async fn verify_with_progress(
+        &self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<Vec<FailedFile>, ProtonError> {
+        let fs = self.file_system.clone();
+        scoped_file_system(fs, async {
+            let endpoints = endpoints();
+            let version_id = &self.game_version.id;
+            let version_dir = self.game_path.join("versions").join(version_id);
+            let mut broken = Vec::new();
+            let mut checks = Vec::new();
+
+            // Sin índice válido no se sabe qué assets comprobar
+            let index = &self.game_version.asset_index;
+            let index_check = FileCheck {
+                name: format!("asset-index-{}", index.id),
+                url: index.url.clone(),
+                path: self.asset_index_path(&index.id),
+                sha1: index.sha1.clone(),
+                size: index.size,
+                download_type: DownloadProgressType::Manifest,
+            };
+            let index_ok = match index_check.run().await {
+                None => true,
+                Some(failed) => {
+                    broken.push(failed);
+                    false
+                }
+            };
+
+            let client = &self.game_version.client_jar;
+            checks.push(FileCheck {
+                name: format!("minecraft-{version_id}"),
+                url: client.url.clone(),
+                path: version_dir.join(format!("{version_id}.jar")),
+                sha1: client.sha1.clone(),
+                size: client.size,
+                download_type: DownloadProgressType::Client,
+            });
+
+            if let Some(logging) = &self.game_version.logging {
+                checks.push(FileCheck {
+                    name: format!("log-config-{}", logging.id),
+                    url: logging.url.clone(),
+                    path: logging.path(&self.game_path),
+                    sha1: logging.sha1.clone(),
+                    size: logging.size,
+                    download_type: DownloadProgressType::Manifest,
+                });
+            }
+
+            for library in &self.game_version.libraries {
+                checks.push(FileCheck {
+                    name: library.name.clone(),
+                    url: endpoints.library_url(&library.url),
+                    path: self.libraries_dir.join(&library.path),
+                    sha1: library.sha1.clone(),
+                    size: library.size,
+                    download_type: DownloadProgressType::Library,
+                });
+            }
+
+            // Los jars de nativos no se conservan: se comprueba lo extraído
+            let state = NativesState::load(&self.natives_dir).await;
+            for native in &self.game_version.natives {
+                if !state.is_up_to_date(&self.natives_dir, &native.sha1).await {
+                    broken.push(FailedFile {
+                        name: native.name.clone(),
+                        url: endpoints.library_url(&native.url),
+                        path: self.natives_dir.join(&native.path),
+                        sha1: native.sha1.clone(),
+                        download_type: DownloadProgressType::Native,
+                        error: "natives not extracted".to_string(),
+                        kind: ErrorKind::Integrity,
+                    });
+                }
+            }
+
+            if index_ok {
+                let asset_index = self.load_asset_index().await?;
+                for (name, asset) in asset_index.into_vec() {
+                    if !self.asset_filters.iter().all(|f| f.keeps(&name, &asset)) {
+                        continue;
+                    }
+                    let hash = &asset.hash;
+                    checks.push(FileCheck {
+                        name,
+                        url: endpoints.asset_url(hash)?,
+                        path: self.objects_dir.join(&hash[..2]).join(hash),
+                        sha1: hash.clone(),
+                        size: asset.size as u64,
+                        download_type: DownloadProgressType::Asset,
+                    });
+                }
+            }
+
+            let progress = progress_tx.map(|tx| VerifyProgress::new(tx, version_id, &checks));
+            broken.extend(run_checks(checks, progress.as_ref()).await);
+            save_hash_cache().await;
+            Ok(broken)
+        })
+        .await
+    }
+}
+
+/// Comprueba los archivos en paralelo y devuelve los que fallan
+pub(super) async fn run_checks(
+    checks: Vec<FileCheck>,
+    progress: Option<&VerifyProgress>,
+) -> Vec<FailedFile> {
+    let results: Vec<Option<FailedFile>> = stream::iter(checks)
+        .map(|check| async move {
+            let name = check.name.clone();
+            let download_type = check.download_type;
+            let size = check.size;
+            let failed = check.run().await;
+            if let Some(progress) = progress {
+                let bytes = if failed.is_none() { size } else { 0 };
+                progress.report(name, download_type, bytes).await;
+            }
+            failed
+        })
+        .buffered(hash_workers())
+        .collect()
+        .await;
+
+    results.into_iter().flatten().collect()
+}
+
+/// Hashes calculados a la vez: uno por núcleo
+fn hash_workers() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+/// Archivo instalado que `verify` debe comprobar
+pub(super) struct FileCheck {
+    pub name: String,
+    pub url: String,
+    pub path: PathBuf,
+    pub sha1: String,
+    pub size: u64,
+    pub download_type: DownloadProgressType,
+}
+
+impl FileCheck {
+    /// `None` si el archivo está bien o el fallo si falta o su hash no
+    /// coincide. El tamaño se compara primero para no leer archivos que ya se
+    /// sabe que están mal.
+    async fn run(self) -> Option<FailedFile> {
+        let error = match file_system().metadata(&self.path).await {
+            Err(_) => "missing",
+            Ok(metadata) if self.size > 0 && metadata.len != self.size => "size mismatch",
+            Ok(_) => match verify_file_hash(&self.path, &self.sha1, 0).await {
+                Ok(true) => return None,
+                Ok(false) => "hash mismatch",
+                Err(_) => "unreadable",
+            },
+        };
+
+        Some(FailedFile {
+            name: self.name,
+            url: self.url,
+            path: self.path,
+            sha1: self.sha1,
+            download_type: self.download_type,
+            error: error.to_string(),
+            kind: ErrorKind::Integrity,
+        })
+    }
+}
+
+/// Progreso de `verify_with_progress`, contado por tipo de archivo como en
+/// las fases de `download_all`
+pub(super) struct VerifyProgress {
+    tx: Sender<DownloadProgress>,
+    version: Arc<String>,
+    /// Tipo, archivos a comprobar y comprobados
+    kinds: Mutex<Vec<(DownloadProgressType, usize, usize)>>,
+}
+
+impl VerifyProgress {
+    fn new(tx: Sender<DownloadProgress>, version_id: &str, checks: &[FileCheck]) -> Self {
+        let mut kinds: Vec<(DownloadProgressType, usize, usize)> = Vec::new();
+        for check in checks {
+            match kinds
+                .iter_mut()
+                .find(|(kind, ..)| *kind == check.download_type)
+            {
+                Some((_, total, _)) => *total += 1,
+                None => kinds.push((check.download_type, 1, 0)),
+            }
+        }
+
+        Self {
+            tx,
+            version: Arc::new(version_id.to_string()),
+            kinds: Mutex::new(kinds),
+        }
+    }
+
+    async fn report(&self, name: String, download_type: DownloadProgressType, bytes: u64) {
+        let (current, total) = {
+            let mut kinds = self.kinds.lock().unwrap_or_else(|e| e.into_inner());
+            let Some((_, total, done)) = kinds.iter_mut().find(|(kind, ..)| *kind == download_type)
+            else {
+                return;
+            };
+            *done += 1;
+            (*done, *total)
+        };
+
+        let _ = self
+            .tx
+            .send(DownloadProgress::new(
+                DownloadProgressInfo::new(name, Arc::clone(&self.version)),
+                download_type,
+                current,
+                total,
+                bytes,
+            ))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filesystem::FileSystem;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+    use crate::types::DownloadProgressType;
+    use tokio::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn verification_reports_each_file_and_why_it_failed() {
+        let env = MockEnvironment::start().await;
+        let mut downloader = env.downloader();
+        downloader.download_all(None).await.unwrap();
+
+        let library = env
+            .game_dir
+            .join("libraries/org/proton/library/1.0/library-1.0.jar");
+        let mut content = env.fs.read(&library).await.unwrap();
+        content.iter_mut().for_each(|byte| *byte = !*byte);
+        env.fs.write(&library, &content).await.unwrap();
+        let client = env
+            .game_dir
+            .join(format!("versions/{FIXTURE_VERSION}/{FIXTURE_VERSION}.jar"));
+        env.fs.write(&client, b"short").await.unwrap();
+
+        let (tx, mut rx) = channel(1024);
+        let broken = downloader.verify_with_progress(Some(tx)).await.unwrap();
+
+        let mut errors: Vec<_> = broken
+            .iter()
+            .map(|file| (file.download_type, file.error.as_str()))
+            .collect();
+        errors.sort_by_key(|(kind, _)| format!("{kind:?}"));
+        assert_eq!(
+            errors,
+            [
+                (DownloadProgressType::Client, "size mismatch"),
+                (DownloadProgressType::Library, "hash mismatch"),
+            ]
+        );
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        assert!(!events.is_empty());
+        // Cada tipo llega a su total y solo cuentan los bytes de lo que está bien
+        for kind in [
+            DownloadProgressType::Client,
+            DownloadProgressType::Library,
+            DownloadProgressType::Asset,
+        ] {
+            let of_kind: Vec<_> = events
+                .iter()
+                .filter(|event| event.download_type == kind)
+                .collect();
+            let last = of_kind.last().unwrap();
+            assert_eq!(last.current, last.total, "{kind:?}");
+            assert_eq!(of_kind.len(), last.total, "{kind:?}");
+        }
+        let client_bytes: u64 = events
+            .iter()
+            .filter(|event| event.download_type == DownloadProgressType::Client)
+            .map(|event| event.bytes)
+            .sum();
+        assert_eq!(client_bytes, 0);
+    }
+}
@@ -0,0 +1,238 @@
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+use crate::errors::ProtonError;
+use crate::types::{LIBRARIES_BASE_URL, MOJANG_MANIFEST_URL, RESOURCES_BASE_URL};
+
+/// Maven de Forge, de donde se descargan sus instaladores
+pub const FORGE_MAVEN_URL: &str = "https://maven.minecraftforge.net/";
+
+/// Repositorios maven en los que se busca una librería cuya URL da 404, en orden
+pub const DEFAULT_MAVEN_REPOSITORIES: [&str; 4] = [
+    LIBRARIES_BASE_URL,
+    "https://maven.fabricmc.net/",
+    FORGE_MAVEN_URL,
+    "https://repo1.maven.org/maven2/",
+];
+
+/// URLs base consultadas por `manifest` y por el descargador. Permite usar
+/// servidores de metadatos propios o mirrors corporativos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointConfig {
+    pub manifest_url: String,
+    pub resources_base_url: String,
+    pub libraries_base_url: String,
+    pub forge_maven_url: String,
+    /// Repositorios alternativos para librerías que no se encuentran
+    pub maven_repositories: Vec<String>,
+}
+
+impl Default for EndpointConfig {
+    fn default() -> Self {
+        Self {
+            manifest_url: MOJANG_MANIFEST_URL.to_string(),
+            resources_base_url: RESOURCES_BASE_URL.to_string(),
+            libraries_base_url: LIBRARIES_BASE_URL.to_string(),
+            forge_maven_url: FORGE_MAVEN_URL.to_string(),
+            maven_repositories: DEFAULT_MAVEN_REPOSITORIES.map(String::from).to_vec(),
+        }
+    }
+}
+
+impl EndpointConfig {
+    /// Configuración por defecto sobrescrita con `PROTON_MANIFEST_URL`,
+    /// `PROTON_RESOURCES_URL` y `PROTON_LIBRARIES_URL` si están definidas y
+    /// no vacías
+    pub fn from_env() -> Self {
+        Self::from_vars(env_var)
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let mut config = Self::default();
+        if let Some(url) = var("PROTON_MANIFEST_URL") {
+            config.manifest_url = url;
+        }
+        if let Some(url) = var("PROTON_RESOURCES_URL") {
+            config.resources_base_url = url;
+        }
+        if let Some(url) = var("PROTON_LIBRARIES_URL") {
+            config.libraries_base_url = url;
+        }
+        config
+    }
+
+    pub fn manifest_url(mut self, url: impl Into<String>) -> Self {
+        self.manifest_url = url.into();
+        self
+    }
+
+    pub fn resources_base_url(mut self, url: impl Into<String>) -> Self {
+        self.resources_base_url = url.into();
+        self
+    }
+
+    pub fn libraries_base_url(mut self, url: impl Into<String>) -> Self {
+        self.libraries_base_url = url.into();
+        self
+    }
+
+    pub fn forge_maven_url(mut self, url: impl Into<String>) -> Self {
+        self.forge_maven_url = url.into();
+        self
+    }
+
+    /// Sustituye la lista de repositorios alternativos; vacía desactiva la búsqueda
+    pub fn maven_repositories(mut self, repositories: Vec<String>) -> Self {
+        self.maven_repositories = repositories;
+        self
+    }
+
+    /// URL de un objeto de assets a partir de su hash. El hash también da la
+    /// ruta en disco, así que solo se aceptan hashes hexadecimales.
+    pub fn asset_url(&self, hash: &str) -> Result<String, ProtonError> {
+        let subhash = hash
+            .get(..2)
+            .filter(|_| hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| ProtonError::UnsafePath(hash.to_string()))?;
+        Ok(format!(
+            "{}/{subhash}/{hash}",
+            self.resources_base_url.trim_end_matches('/')
+        ))
+    }
+
+    /// Redirige una URL de librería de Mojang al mirror configurado
+    pub fn library_url(&self, url: &str) -> String {
+        match url.strip_prefix(LIBRARIES_BASE_URL) {
+            Some(rest) if self.libraries_base_url != LIBRARIES_BASE_URL => {
+                format!("{}/{rest}", self.libraries_base_url.trim_end_matches('/'))
+            }
+            _ => url.to_string(),
+        }
+    }
+
+    /// La misma ruta maven en los demás repositorios, por orden, si `url`
+    /// pertenece al mirror de librerías o a uno de los repositorios conocidos
+    pub fn library_fallback_urls(&self, url: &str) -> Vec<String> {
+        let mut repositories: Vec<&str> = Vec::new();
+        for repository in std::iter::once(&self.libraries_base_url).chain(&self.maven_repositories)
+        {
+            let repository = repository.trim_end_matches('/');
+            if !repositories.contains(&repository) {
+                repositories.push(repository);
+            }
+        }
+
+        let Some((origin, path)) = repositories.iter().find_map(|repository| {
+            let path = url.strip_prefix(repository)?.strip_prefix('/')?;
+            Some((*repository, path))
+        }) else {
+            return Vec::new();
+        };
+
+        repositories
+            .into_iter()
+            .filter(|repository| *repository != origin)
+            .map(|repository| format!("{repository}/{path}"))
+            .collect()
+    }
+}
+
+/// Variable de entorno con valor; vacía cuenta como no definida, para poder
+/// anularla con `PROTON_X=`
+pub(crate) fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+static ENDPOINTS: Lazy<RwLock<EndpointConfig>> =
+    Lazy::new(|| RwLock::new(EndpointConfig::from_env()));
+
+/// Reemplaza la configuración de endpoints global
+pub fn set_endpoints(config: EndpointConfig) {
+    *ENDPOINTS.write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+/// Configuración de endpoints global actual
+pub fn endpoints() -> EndpointConfig {
+    ENDPOINTS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const HASH: &str = "bdf48ef6b5d0d23bbb02e17d04865216179f510a";
+
+    #[test]
+    fn environment_overrides_only_the_given_urls() {
+        let vars = HashMap::from([("PROTON_RESOURCES_URL", "https://mirror.local/resources")]);
+        let config = EndpointConfig::from_vars(|name| vars.get(name).map(|url| url.to_string()));
+
+        assert_eq!(config.resources_base_url, "https://mirror.local/resources");
+        assert_eq!(config.manifest_url, MOJANG_MANIFEST_URL);
+        assert_eq!(config.libraries_base_url, LIBRARIES_BASE_URL);
+    }
+
+    #[test]
+    fn asset_urls_use_the_hash_prefix() {
+        let config = EndpointConfig::default().resources_base_url("https://mirror.local/objects/");
+
+        assert_eq!(
+            config.asset_url(HASH).unwrap(),
+            format!("https://mirror.local/objects/bd/{HASH}")
+        );
+    }
+
+    #[test]
+    fn malformed_asset_hashes_are_errors() {
+        let config = EndpointConfig::default();
+
+        for hash in ["", "b", "../../etc/passwd", "bdé", "zz"] {
+            assert!(
+                matches!(config.asset_url(hash), Err(ProtonError::UnsafePath(_))),
+                "{hash}"
+            );
+        }
+    }
+
+    #[test]
+    fn library_urls_follow_the_mirror() {
+        let url = format!("{LIBRARIES_BASE_URL}org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3.jar");
+
+        assert_eq!(EndpointConfig::default().library_url(&url), url);
+        let mirrored =
+            EndpointConfig::default().libraries_base_url("https://mirror.local/libraries/");
+        assert_eq!(
+            mirrored.library_url(&url),
+            "https://mirror.local/libraries/org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3.jar"
+        );
+        // Las URLs de otros repositorios no se tocan
+        let fabric = "https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.15.11/fabric-loader-0.15.11.jar";
+        assert_eq!(mirrored.library_url(fabric), fabric);
+    }
+
+    #[test]
+    fn fallbacks_are_the_other_repositories_in_order() {
+        let config = EndpointConfig::default().maven_repositories(vec![
+            "https://a.local/".to_string(),
+            LIBRARIES_BASE_URL.to_string(),
+            "https://b.local".to_string(),
+        ]);
+
+        assert_eq!(
+            config.library_fallback_urls("https://a.local/com/example/lib/1.0/lib-1.0.jar"),
+            [
+                format!(
+                    "{}/com/example/lib/1.0/lib-1.0.jar",
+                    LIBRARIES_BASE_URL.trim_end_matches('/')
+                ),
+                "https://b.local/com/example/lib/1.0/lib-1.0.jar".to_string(),
+            ]
+        );
+        assert!(
+            config
+                .library_fallback_urls("https://elsewhere.local/lib.jar")
+                .is_empty()
+        );
+    }
+}
@@ -1,5 +1,10 @@
+use crate::types::{DownloadProgressType, FailedFile, OverallProgress};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -10,8 +15,16 @@ pub enum ProtonError {
     VersionNotFound(String),
     #[error("Filesystem error {0}")]
     IoError(#[from] io::Error),
-    #[error("Hash mismatch")]
-    HashMismatch,
+    #[error("Hash mismatch: expected {expected}, got {actual} after {bytes} bytes")]
+    HashMismatch {
+        expected: String,
+        actual: String,
+        /// Bytes recibidos
+        bytes: u64,
+        /// Copia del archivo recibido si hay carpeta de cuarentena
+        quarantined: Option<PathBuf>,
+    },
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("Concurrency Error")]
     JoinError(#[from] tokio::task::JoinError),
     #[error("Invalid library name: '{0}'")]
@@ -20,18 +33,421 @@ pub enum ProtonError {
     LibraryNotFound(PathBuf),
     #[error("Invalid Maven coordinate: '{0}'")]
     InvalidMavenCoordinate(String),
+    #[error("Invalid version pattern: '{0}'")]
+    InvalidVersionPattern(String),
+    #[error("Version {0} has no server jar")]
+    ServerJarUnavailable(String),
+    #[error("Failed to download {download_type:?} {name} from {url} to {path:?}: {source}")]
+    Download {
+        name: String,
+        url: String,
+        path: PathBuf,
+        download_type: DownloadProgressType,
+        source: Box<ProtonError>,
+    },
+    #[error("{} files failed to download", .0.len())]
+    PartialFailure(Vec<FailedFile>),
+    #[error("Archive entry '{0}' escapes the destination directory")]
+    UnsafeArchivePath(String),
+    #[error("Unsafe path '{0}' in version metadata")]
+    UnsafePath(String),
+    #[error("Invalid instance: {0}")]
+    InvalidInstance(String),
+    #[error("Artifact not found: {0}")]
+    ArtifactNotFound(String),
+    #[error("Game directory {path:?} is in use by process {pid}")]
+    AlreadyLocked { path: PathBuf, pid: u32 },
+    #[error("Invalid install manifest {0}")]
+    InvalidInstallManifest(String),
+    #[error(
+        "Timed out after {limit:?} with {}/{} files done",
+        .progress.completed_files,
+        .progress.total_files
+    )]
+    Timeout {
+        limit: Duration,
+        progress: OverallProgress,
+    },
+    #[error("Operation cancelled")]
+    Cancelled,
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid binary patch: {0}")]
+    InvalidBinaryPatch(String),
+    #[error("Forge installation failed: {0}")]
+    ForgeInstall(String),
+    /// Zip dañado o incompleto: CRC incorrecto, archivo truncado...
+    #[error("Corrupt archive: {0}")]
+    CorruptArchive(String),
+    /// Un mismo error entregado a varias operaciones (p. ej. las versiones de
+    /// un lote que comparten un archivo); conserva el tipo y el código
+    #[error(transparent)]
+    Shared(Arc<ProtonError>),
     #[error("Other error: {0}")]
     Other(String),
 }
 
+/// Qué tipo de fallo es un error, para decidir si reintentar, reparar o
+/// mostrárselo al usuario
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    /// Conexión caída, tiempo de espera o error del servidor
+    Network,
+    /// El archivo llegó o está corrupto; se arregla descargándolo de nuevo
+    Integrity,
+    /// No queda espacio en disco
+    DiskFull,
+    /// La versión o el archivo no existen en el servidor ni en disco
+    NotFound,
+    /// Cancelado por quien llamó o por vencer el plazo
+    Cancelled,
+    /// Cualquier otro fallo, que reintentar no arregla
+    #[default]
+    Other,
+}
+
+impl ErrorKind {
+    /// Si repetir la operación puede salir bien
+    pub fn is_retryable(self) -> bool {
+        matches!(self, ErrorKind::Network | ErrorKind::Integrity)
+    }
+}
+
+impl ProtonError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ProtonError::RequestError(e) => match e.status() {
+                Some(status) if status.as_u16() == 404 || status.as_u16() == 410 => {
+                    ErrorKind::NotFound
+                }
+                // 408 y 429 piden volver a intentarlo más tarde
+                Some(status)
+                    if status.is_client_error()
+                        && status.as_u16() != 408
+                        && status.as_u16() != 429 =>
+                {
+                    ErrorKind::Other
+                }
+                _ if e.is_decode() => ErrorKind::Integrity,
+                _ => ErrorKind::Network,
+            },
+            ProtonError::IoError(e) => io_error_kind(e),
+            ProtonError::HashMismatch { .. }
+            | ProtonError::InvalidBinaryPatch(_)
+            | ProtonError::CorruptArchive(_) => ErrorKind::Integrity,
+            #[cfg(not(target_arch = "wasm32"))]
+            ProtonError::JoinError(e) if e.is_cancelled() => ErrorKind::Cancelled,
+            ProtonError::VersionNotFound(_)
+            | ProtonError::LibraryNotFound(_)
+            | ProtonError::ArtifactNotFound(_)
+            | ProtonError::ServerJarUnavailable(_) => ErrorKind::NotFound,
+            ProtonError::Download { source, .. } => source.kind(),
+            ProtonError::Shared(error) => error.kind(),
+            // El primer fallo que no se arregla reintentando manda
+            ProtonError::PartialFailure(files) => files
+                .iter()
+                .map(|file| file.kind)
+                .find(|kind| !kind.is_retryable())
+                .or_else(|| files.first().map(|file| file.kind))
+                .unwrap_or_default(),
+            ProtonError::Timeout { .. } | ProtonError::Cancelled => ErrorKind::Cancelled,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Si reintentar la operación puede arreglar el error
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    /// Código estable del error (`E_HASH_MISMATCH`, ...) para traducir el
+    /// mensaje en la interfaz. `Download` y `Shared` devuelven el de su causa.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProtonError::RequestError(_) => "E_REQUEST",
+            ProtonError::VersionNotFound(_) => "E_VERSION_NOT_FOUND",
+            ProtonError::IoError(_) => "E_IO",
+            ProtonError::HashMismatch { .. } => "E_HASH_MISMATCH",
+            #[cfg(not(target_arch = "wasm32"))]
+            ProtonError::JoinError(_) => "E_TASK_FAILED",
+            ProtonError::InvalidLibraryName(_) => "E_INVALID_LIBRARY_NAME",
+            ProtonError::LibraryNotFound(_) => "E_LIBRARY_NOT_FOUND",
+            ProtonError::InvalidMavenCoordinate(_) => "E_INVALID_MAVEN_COORDINATE",
+            ProtonError::InvalidVersionPattern(_) => "E_INVALID_VERSION_PATTERN",
+            ProtonError::ServerJarUnavailable(_) => "E_SERVER_JAR_UNAVAILABLE",
+            ProtonError::Download { source, .. } => source.code(),
+            ProtonError::PartialFailure(_) => "E_PARTIAL_FAILURE",
+            ProtonError::UnsafeArchivePath(_) => "E_UNSAFE_ARCHIVE_PATH",
+            ProtonError::UnsafePath(_) => "E_UNSAFE_PATH",
+            ProtonError::InvalidInstance(_) => "E_INVALID_INSTANCE",
+            ProtonError::ArtifactNotFound(_) => "E_ARTIFACT_NOT_FOUND",
+            ProtonError::AlreadyLocked { .. } => "E_ALREADY_LOCKED",
+            ProtonError::InvalidInstallManifest(_) => "E_INVALID_INSTALL_MANIFEST",
+            ProtonError::Timeout { .. } => "E_TIMEOUT",
+            ProtonError::Cancelled => "E_CANCELLED",
+            ProtonError::InvalidConfig(_) => "E_INVALID_CONFIG",
+            ProtonError::InvalidBinaryPatch(_) => "E_INVALID_BINARY_PATCH",
+            ProtonError::ForgeInstall(_) => "E_FORGE_INSTALL",
+            ProtonError::CorruptArchive(_) => "E_CORRUPT_ARCHIVE",
+            ProtonError::Shared(error) => error.code(),
+            ProtonError::Other(_) => "E_OTHER",
+        }
+    }
+
+    /// El error original si es `Shared`
+    fn unshared(&self) -> &ProtonError {
+        match self {
+            ProtonError::Shared(error) => error.unshared(),
+            error => error,
+        }
+    }
+
+    /// El error como `ErrorReport`, listo para serializar
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport::from(self)
+    }
+
+    /// Añade al error el archivo con el que ocurrió
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn download(
+        name: impl Into<String>,
+        url: impl Into<String>,
+        path: impl Into<PathBuf>,
+        download_type: DownloadProgressType,
+        source: ProtonError,
+    ) -> Self {
+        if matches!(source, ProtonError::Download { .. }) {
+            return source;
+        }
+        ProtonError::Download {
+            name: name.into(),
+            url: url.into(),
+            path: path.into(),
+            download_type,
+            source: Box::new(source),
+        }
+    }
+}
+
+/// Error serializable para enviarlo a una interfaz por IPC. `code` no cambia
+/// entre versiones; `message` es el texto en inglés para los registros y
+/// `details`, los valores con los que componer un mensaje traducido.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorReport {
+    pub code: String,
+    pub kind: ErrorKind,
+    pub retryable: bool,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+    /// Archivos de `PartialFailure`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<FailedFile>,
+}
+
+impl From<&ProtonError> for ErrorReport {
+    fn from(error: &ProtonError) -> Self {
+        let mut details = BTreeMap::new();
+        let mut detail = |key: &str, value: String| {
+            details.insert(key.to_string(), value);
+        };
+
+        // El contexto de `Download` se suma a los detalles de su causa
+        let mut cause = error.unshared();
+        if let ProtonError::Download {
+            name,
+            url,
+            path,
+            download_type,
+            source,
+        } = error
+        {
+            detail("name", name.clone());
+            detail("url", url.clone());
+            detail("path", path.display().to_string());
+            detail("downloadType", format!("{download_type:?}").to_lowercase());
+            cause = source.unshared();
+        }
+
+        match cause {
+            ProtonError::VersionNotFound(version) | ProtonError::ServerJarUnavailable(version) => {
+                detail("version", version.clone())
+            }
+            ProtonError::HashMismatch {
+                expected,
+                actual,
+                bytes,
+                quarantined,
+            } => {
+                detail("expected", expected.clone());
+                detail("actual", actual.clone());
+                detail("bytes", bytes.to_string());
+                if let Some(quarantined) = quarantined {
+                    detail("quarantined", quarantined.display().to_string());
+                }
+            }
+            ProtonError::InvalidLibraryName(value)
+            | ProtonError::InvalidMavenCoordinate(value)
+            | ProtonError::InvalidVersionPattern(value) => detail("value", value.clone()),
+            ProtonError::LibraryNotFound(path) => detail("path", path.display().to_string()),
+            ProtonError::UnsafeArchivePath(entry) => detail("entry", entry.clone()),
+            ProtonError::UnsafePath(path) => detail("path", path.clone()),
+            ProtonError::ArtifactNotFound(url) => detail("url", url.clone()),
+            ProtonError::AlreadyLocked { path, pid } => {
+                detail("path", path.display().to_string());
+                detail("pid", pid.to_string());
+            }
+            ProtonError::Timeout { limit, progress } => {
+                detail("limitSecs", limit.as_secs().to_string());
+                detail("completedFiles", progress.completed_files.to_string());
+                detail("totalFiles", progress.total_files.to_string());
+            }
+            _ => {}
+        }
+
+        let failed = match error.unshared() {
+            ProtonError::PartialFailure(files) => files.clone(),
+            _ => Vec::new(),
+        };
+        let kind = error.kind();
+        ErrorReport {
+            code: error.code().to_string(),
+            kind,
+            retryable: kind.is_retryable(),
+            message: error.to_string(),
+            details,
+            failed,
+        }
+    }
+}
+
+fn io_error_kind(error: &io::Error) -> ErrorKind {
+    use io::ErrorKind as Io;
+
+    match error.kind() {
+        Io::StorageFull | Io::QuotaExceeded => ErrorKind::DiskFull,
+        Io::NotFound => ErrorKind::NotFound,
+        Io::InvalidData => ErrorKind::Integrity,
+        Io::TimedOut
+        | Io::Interrupted
+        | Io::UnexpectedEof
+        | Io::ConnectionRefused
+        | Io::ConnectionReset
+        | Io::ConnectionAborted
+        | Io::NotConnected
+        | Io::BrokenPipe
+        | Io::HostUnreachable
+        | Io::NetworkUnreachable
+        | Io::NetworkDown => ErrorKind::Network,
+        _ => ErrorKind::Other,
+    }
+}
+
 impl From<Box<dyn std::error::Error + Send + Sync>> for ProtonError {
     fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
         ProtonError::Other(err.to_string())
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<async_zip::error::ZipError> for ProtonError {
     fn from(err: async_zip::error::ZipError) -> Self {
-        ProtonError::Other(format!("Zip extraction error: {err}"))
+        use async_zip::error::ZipError;
+
+        match err {
+            ZipError::UpstreamReadError(e) => ProtonError::IoError(e),
+            // Se arregla volviendo a descargar el archivo
+            ZipError::CRC32CheckError
+            | ZipError::EOFNotReached
+            | ZipError::UnableToLocateEOCDR
+            | ZipError::UnexpectedHeaderError(..)
+            | ZipError::InvalidExtraFieldHeader(..)
+            | ZipError::Zip64ExtendedFieldIncomplete => {
+                ProtonError::CorruptArchive(err.to_string())
+            }
+            err => ProtonError::Other(format!("Zip extraction error: {err}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_errors_report_like_the_original() {
+        let original = ProtonError::HashMismatch {
+            expected: "aa".to_string(),
+            actual: "bb".to_string(),
+            bytes: 3,
+            quarantined: None,
+        };
+        let shared = ProtonError::Shared(Arc::new(ProtonError::HashMismatch {
+            expected: "aa".to_string(),
+            actual: "bb".to_string(),
+            bytes: 3,
+            quarantined: None,
+        }));
+
+        assert_eq!(shared.to_string(), original.to_string());
+        assert_eq!(shared.kind(), ErrorKind::Integrity);
+        assert_eq!(shared.code(), original.code());
+        let report = ErrorReport::from(&shared);
+        assert_eq!(report.details, ErrorReport::from(&original).details);
+        assert!(report.retryable);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn truncated_zips_are_integrity_errors() {
+        use async_zip::error::ZipError;
+
+        for error in [ZipError::CRC32CheckError, ZipError::UnableToLocateEOCDR] {
+            let error = ProtonError::from(error);
+            assert_eq!(error.code(), "E_CORRUPT_ARCHIVE");
+            assert_eq!(error.kind(), ErrorKind::Integrity);
+        }
+        assert!(matches!(
+            ProtonError::from(ZipError::UpstreamReadError(io::ErrorKind::NotFound.into())),
+            ProtonError::IoError(_)
+        ));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn reports_serialize_with_stable_codes_and_details() {
+        let error = ProtonError::download(
+            "minecraft-1.21.8",
+            "https://example.invalid/client.jar",
+            "versions/1.21.8/1.21.8.jar",
+            DownloadProgressType::Client,
+            ProtonError::VersionNotFound("1.21.8".to_string()),
+        );
+
+        let json = serde_json::to_value(error.report()).unwrap();
+        assert_eq!(json["code"], "E_VERSION_NOT_FOUND");
+        assert_eq!(json["kind"], "not-found");
+        assert_eq!(json["retryable"], false);
+        assert_eq!(json["message"], error.to_string());
+        assert_eq!(
+            json["details"],
+            serde_json::json!({
+                "name": "minecraft-1.21.8",
+                "url": "https://example.invalid/client.jar",
+                "path": "versions/1.21.8/1.21.8.jar",
+                "downloadType": "client",
+                "version": "1.21.8",
+            })
+        );
+        // Sin detalles ni archivos fallidos los campos se omiten
+        let json = serde_json::to_value(ProtonError::Cancelled.report()).unwrap();
+        assert_eq!(json["code"], "E_CANCELLED");
+        assert!(json.get("details").is_none() && json.get("failed").is_none());
+
+        let report: ErrorReport =
+            serde_json::from_value(serde_json::to_value(error.report()).unwrap()).unwrap();
+        assert_eq!(report.code, error.code());
     }
 }
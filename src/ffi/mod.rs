@@ -0,0 +1,415 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::blocking::{RUNTIME, block_on, check_blocking_allowed};
+use crate::downloaders::MinecraftDownloader;
+use crate::errors::ProtonError;
+use crate::manifest;
+use crate::types::{DownloadProgress, DownloadProgressType};
+
+/// Operación correcta
+pub const PROTON_OK: c_int = 0;
+/// Error; el detalle está en `proton_last_error`
+pub const PROTON_ERROR: c_int = -1;
+/// La descarga se canceló con `proton_downloader_cancel`
+pub const PROTON_CANCELLED: c_int = -2;
+
+/// `user_data`, tipo (`DownloadProgressType` en orden de declaración),
+/// completados, total y bytes del archivo terminado
+pub type ProtonProgressCallback =
+    extern "C" fn(user_data: *mut c_void, kind: c_int, current: usize, total: usize, bytes: u64);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Into<String>) {
+    // Un mensaje con NUL interior no se puede representar en C
+    let message = CString::new(message.into().replace('\0', " ")).ok();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+struct ProgressCallback {
+    func: ProtonProgressCallback,
+    user_data: *mut c_void,
+}
+
+// El llamador garantiza que `user_data` se puede usar desde otros hilos
+unsafe impl Send for ProgressCallback {}
+
+impl ProgressCallback {
+    fn call(&self, progress: &DownloadProgress) {
+        let kind = match progress.download_type {
+            DownloadProgressType::Library => 0,
+            DownloadProgressType::Asset => 1,
+            DownloadProgressType::Native => 2,
+            DownloadProgressType::Client => 3,
+            DownloadProgressType::Server => 4,
+            DownloadProgressType::Manifest => 5,
+        };
+        (self.func)(
+            self.user_data,
+            kind,
+            progress.current,
+            progress.total,
+            progress.bytes,
+        );
+    }
+}
+
+/// Tarea que entrega el progreso al callback de C
+struct ProgressForwarder {
+    task: JoinHandle<()>,
+    // Al cancelarse se entrega lo que ya está en el canal y se termina
+    finished: CancellationToken,
+    // Se toma durante cada llamada: vaciarlo espera a la que esté en curso
+    callback: Arc<Mutex<Option<ProgressCallback>>>,
+}
+
+impl ProgressForwarder {
+    fn spawn(callback: ProgressCallback) -> (Self, mpsc::Sender<DownloadProgress>) {
+        let (tx, mut rx) = mpsc::channel::<DownloadProgress>(100);
+        let callback = Arc::new(Mutex::new(Some(callback)));
+        let finished = CancellationToken::new();
+        let task = RUNTIME.spawn({
+            let callback = Arc::clone(&callback);
+            let finished = finished.clone();
+            async move {
+                loop {
+                    let progress = tokio::select! {
+                        biased;
+                        progress = rx.recv() => progress,
+                        // Lo que llegue después de terminar se descarta
+                        () = finished.cancelled() => rx.try_recv().ok(),
+                    };
+                    let Some(progress) = progress else { break };
+                    let callback = callback.lock().unwrap_or_else(|e| e.into_inner());
+                    let Some(callback) = callback.as_ref() else {
+                        break;
+                    };
+                    callback.call(&progress);
+                }
+            }
+        });
+        let forwarder = Self {
+            task,
+            finished,
+            callback,
+        };
+        (forwarder, tx)
+    }
+
+    /// Entrega el progreso pendiente y espera a que la tarea termine
+    fn finish(mut self) {
+        self.finished.cancel();
+        let _ = block_on(async { Ok((&mut self.task).await?) });
+        self.disable();
+    }
+
+    /// Descarta el progreso pendiente. Al volver no hay ninguna llamada en
+    /// curso ni habrá más.
+    fn stop(self) {
+        self.task.abort();
+        self.disable();
+    }
+
+    fn disable(&self) {
+        self.callback
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+    }
+}
+
+/// Descargador opaco para C. Solo se accede a él por referencias compartidas:
+/// `cancel` puede llegar desde otro hilo mientras `wait` está bloqueado.
+pub struct ProtonDownloader {
+    state: Mutex<DownloaderState>,
+    // Aparte de `state` para cancelar sin esperar a que lo suelte otro hilo
+    cancel: Mutex<Option<CancellationToken>>,
+}
+
+struct DownloaderState {
+    downloader: Option<MinecraftDownloader>,
+    callback: Option<ProgressCallback>,
+    task: Option<JoinHandle<Result<(), ProtonError>>>,
+    forwarder: Option<ProgressForwarder>,
+}
+
+impl ProtonDownloader {
+    fn state(&self) -> MutexGuard<'_, DownloaderState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cancel_token(&self) -> MutexGuard<'_, Option<CancellationToken>> {
+        self.cancel.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Lee una cadena C como UTF-8
+unsafe fn read_str<'a>(value: *const c_char, name: &str) -> Option<&'a str> {
+    if value.is_null() {
+        set_last_error(format!("{name} is null"));
+        return None;
+    }
+    match unsafe { CStr::from_ptr(value) }.to_str() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            set_last_error(format!("{name} is not valid UTF-8"));
+            None
+        }
+    }
+}
+
+/// Resuelve la versión y crea un descargador; devuelve NULL si falla.
+///
+/// # Safety
+///
+/// `game_path` y `version_id` deben ser cadenas C válidas terminadas en NUL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn proton_downloader_new(
+    game_path: *const c_char,
+    version_id: *const c_char,
+) -> *mut ProtonDownloader {
+    let (Some(game_path), Some(version_id)) =
+        (unsafe { read_str(game_path, "game_path") }, unsafe {
+            read_str(version_id, "version_id")
+        })
+    else {
+        return ptr::null_mut();
+    };
+
+    match block_on(manifest::resolve_version_data(version_id)) {
+        Ok(version) => Box::into_raw(Box::new(ProtonDownloader {
+            state: Mutex::new(DownloaderState {
+                downloader: Some(MinecraftDownloader::new(PathBuf::from(game_path), version)),
+                callback: None,
+                task: None,
+                forwarder: None,
+            }),
+            cancel: Mutex::new(None),
+        })),
+        Err(error) => {
+            set_last_error(error.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Registra el callback de progreso; debe llamarse antes de `proton_downloader_start`.
+/// El callback se invoca desde hilos internos de proton.
+///
+/// # Safety
+///
+/// `downloader` debe venir de `proton_downloader_new` y `user_data` debe poder
+/// usarse desde otros hilos mientras dure la descarga.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn proton_downloader_set_progress_callback(
+    downloader: *mut ProtonDownloader,
+    callback: Option<ProtonProgressCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    let Some(downloader) = (unsafe { downloader.as_ref() }) else {
+        set_last_error("downloader is null");
+        return PROTON_ERROR;
+    };
+    downloader.state().callback = callback.map(|func| ProgressCallback { func, user_data });
+    PROTON_OK
+}
+
+/// Empieza la descarga en segundo plano y vuelve inmediatamente.
+///
+/// # Safety
+///
+/// `downloader` debe venir de `proton_downloader_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn proton_downloader_start(downloader: *mut ProtonDownloader) -> c_int {
+    let Some(handle) = (unsafe { downloader.as_ref() }) else {
+        set_last_error("downloader is null");
+        return PROTON_ERROR;
+    };
+    let mut state = handle.state();
+    let Some(mut downloader) = state.downloader.take() else {
+        set_last_error("download already started");
+        return PROTON_ERROR;
+    };
+
+    let progress_tx = state.callback.take().map(|callback| {
+        let (forwarder, tx) = ProgressForwarder::spawn(callback);
+        state.forwarder = Some(forwarder);
+        tx
+    });
+
+    *handle.cancel_token() = Some(downloader.cancel_token());
+    let task = RUNTIME.spawn(async move { downloader.download_all(progress_tx).await });
+    state.task = Some(task);
+    PROTON_OK
+}
+
+/// Espera a que termine la descarga iniciada con `proton_downloader_start`.
+/// Al volver ya se entregó todo el progreso y el callback no se vuelve a llamar.
+///
+/// # Safety
+///
+/// `downloader` debe venir de `proton_downloader_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn proton_downloader_wait(downloader: *mut ProtonDownloader) -> c_int {
+    let Some(handle) = (unsafe { downloader.as_ref() }) else {
+        set_last_error("downloader is null");
+        return PROTON_ERROR;
+    };
+    if let Err(error) = check_blocking_allowed() {
+        set_last_error(error.to_string());
+        return PROTON_ERROR;
+    }
+    // El bloqueo se suelta antes de esperar
+    let (task, forwarder) = {
+        let mut state = handle.state();
+        (state.task.take(), state.forwarder.take())
+    };
+    let Some(task) = task else {
+        set_last_error("download not started");
+        return PROTON_ERROR;
+    };
+
+    let result = block_on(async move { task.await? });
+    if let Some(forwarder) = forwarder {
+        forwarder.finish();
+    }
+    match result {
+        Ok(()) => PROTON_OK,
+        Err(ProtonError::Cancelled) => {
+            set_last_error("download cancelled");
+            PROTON_CANCELLED
+        }
+        Err(ProtonError::JoinError(error)) if error.is_cancelled() => {
+            set_last_error("download cancelled");
+            PROTON_CANCELLED
+        }
+        Err(error) => {
+            set_last_error(error.to_string());
+            PROTON_ERROR
+        }
+    }
+}
+
+/// Cancela la descarga en curso; `proton_downloader_wait` devolverá `PROTON_CANCELLED`.
+/// Puede llamarse desde otro hilo mientras `proton_downloader_wait` está bloqueado,
+/// pero no a la vez que `proton_downloader_free`.
+///
+/// # Safety
+///
+/// `downloader` debe venir de `proton_downloader_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn proton_downloader_cancel(downloader: *const ProtonDownloader) -> c_int {
+    let Some(handle) = (unsafe { downloader.as_ref() }) else {
+        set_last_error("downloader is null");
+        return PROTON_ERROR;
+    };
+    match handle.cancel_token().as_ref() {
+        Some(cancel) => {
+            cancel.cancel();
+            PROTON_OK
+        }
+        None => {
+            set_last_error("download not started");
+            PROTON_ERROR
+        }
+    }
+}
+
+/// Libera el descargador, cancelando la descarga si sigue en curso. Al
+/// volver el callback no se vuelve a llamar.
+///
+/// # Safety
+///
+/// `downloader` debe venir de `proton_downloader_new` (o ser NULL) y no usarse después.
+/// No puede llamarse mientras otro hilo está en otra función con el mismo
+/// descargador, en particular bloqueado en `proton_downloader_wait`: hay que
+/// cancelar y esperar a que `wait` vuelva antes de liberarlo.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn proton_downloader_free(downloader: *mut ProtonDownloader) {
+    if downloader.is_null() {
+        return;
+    }
+    let handle = unsafe { Box::from_raw(downloader) };
+    // La tarea sigue hasta dejar limpio el directorio, pero ya sin callback
+    if let Some(cancel) = handle.cancel_token().as_ref() {
+        cancel.cancel();
+    }
+    if let Some(forwarder) = handle.state().forwarder.take() {
+        forwarder.stop();
+    }
+}
+
+/// Último error del hilo actual, o NULL. El puntero es válido hasta la
+/// siguiente llamada a proton desde el mismo hilo.
+#[unsafe(no_mangle)]
+pub extern "C" fn proton_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    extern "C" fn count(user_data: *mut c_void, _: c_int, _: usize, _: usize, _: u64) {
+        let calls = unsafe { &*(user_data as *const AtomicUsize) };
+        calls.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn no_callbacks_after_wait_returns() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _env = runtime.block_on(MockEnvironment::start());
+        let dir = std::env::temp_dir().join(format!("proton-ffi-{}", uuid::Uuid::new_v4()));
+        let game_path = CString::new(dir.to_string_lossy().as_ref()).unwrap();
+        let version = CString::new(FIXTURE_VERSION).unwrap();
+        let calls = AtomicUsize::new(0);
+
+        unsafe {
+            let downloader = proton_downloader_new(game_path.as_ptr(), version.as_ptr());
+            assert!(!downloader.is_null());
+            let user_data = &calls as *const AtomicUsize as *mut c_void;
+            assert_eq!(
+                proton_downloader_set_progress_callback(downloader, Some(count), user_data),
+                PROTON_OK
+            );
+            assert_eq!(proton_downloader_start(downloader), PROTON_OK);
+            assert_eq!(proton_downloader_wait(downloader), PROTON_OK);
+
+            let delivered = calls.load(Ordering::SeqCst);
+            assert!(delivered > 0);
+            std::thread::sleep(Duration::from_millis(200));
+            assert_eq!(calls.load(Ordering::SeqCst), delivered);
+            proton_downloader_free(downloader);
+            assert_eq!(calls.load(Ordering::SeqCst), delivered);
+        }
+
+        let client = dir.join(format!("versions/{FIXTURE_VERSION}/{FIXTURE_VERSION}.jar"));
+        assert!(client.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn errors_are_reported_through_last_error() {
+        unsafe {
+            assert_eq!(proton_downloader_wait(ptr::null_mut()), PROTON_ERROR);
+            let message = CStr::from_ptr(proton_last_error()).to_str().unwrap();
+            assert_eq!(message, "downloader is null");
+        }
+    }
+}
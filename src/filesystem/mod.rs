@@ -0,0 +1,758 @@
+use futures::future::BoxFuture;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Cursor};
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncWrite, AsyncWriteExt};
+
+/// Lectura con posicionamiento, necesaria para abrir zips
+pub trait FileRead: AsyncRead + AsyncSeek + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncSeek + Send + Unpin> FileRead for T {}
+
+pub type FileReader = Box<dyn FileRead>;
+pub type FileWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Datos de un archivo o directorio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub len: u64,
+    pub is_dir: bool,
+    /// Última modificación, si el sistema de archivos la conoce
+    pub modified: Option<SystemTime>,
+}
+
+/// Sistema de archivos usado por la descarga y extracción. Por defecto es
+/// el disco local; se puede sustituir por uno en memoria para pruebas.
+pub trait FileSystem: Send + Sync {
+    fn create_dir_all<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>>;
+
+    /// Crea (o trunca) un archivo para escribirlo en streaming
+    fn create<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileWriter>>;
+
+    fn open<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileReader>>;
+
+    fn write<'a>(&'a self, path: &'a Path, contents: &'a [u8]) -> BoxFuture<'a, io::Result<()>>;
+
+    /// Como `write`, pero falla con `AlreadyExists` si el archivo ya existe.
+    /// La implementación por defecto no es atómica.
+    fn write_new<'a>(
+        &'a self,
+        path: &'a Path,
+        contents: &'a [u8],
+    ) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            if self.exists(path).await {
+                return Err(io::ErrorKind::AlreadyExists.into());
+            }
+            self.write(path, contents).await
+        })
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, io::Result<()>>;
+
+    fn remove_file<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>>;
+
+    fn remove_dir_all<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>>;
+
+    fn metadata<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileMetadata>>;
+
+    fn read<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<Vec<u8>>> {
+        Box::pin(async move {
+            let mut content = Vec::new();
+            self.open(path).await?.read_to_end(&mut content).await?;
+            Ok(content)
+        })
+    }
+
+    fn exists<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, bool> {
+        Box::pin(async move { self.metadata(path).await.is_ok() })
+    }
+
+    /// Rutas de las entradas directas de un directorio; los sistemas que no
+    /// lo admiten devuelven `Unsupported`
+    fn read_dir<'a>(&'a self, _path: &'a Path) -> BoxFuture<'a, io::Result<Vec<PathBuf>>> {
+        Box::pin(async { Err(io::ErrorKind::Unsupported.into()) })
+    }
+
+    fn copy<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let content = self.read(from).await?;
+            self.write(to, &content).await
+        })
+    }
+
+    /// Vuelca al disco el contenido de un archivo o directorio; no hace nada
+    /// donde no aplica
+    fn sync<'a>(&'a self, _path: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Las rutas son del disco local y se pueden leer con `std::fs` desde el
+    /// pool de hilos bloqueantes
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// Fija la longitud de un archivo, reservando el espacio en disco cuando
+    /// crece; no hace nada donde no aplica
+    fn allocate<'a>(&'a self, _path: &'a Path, _len: u64) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Enlace duro; los sistemas que no lo admiten devuelven `Unsupported`
+    fn hard_link<'a>(&'a self, _from: &'a Path, _to: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async { Err(io::ErrorKind::Unsupported.into()) })
+    }
+
+    /// Ruta con los enlaces simbólicos resueltos
+    fn canonicalize<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<PathBuf>> {
+        Box::pin(async move { Ok(path.to_path_buf()) })
+    }
+
+    /// Bits de permisos Unix; se ignoran donde no aplican
+    fn set_permissions<'a>(&'a self, _path: &'a Path, _mode: u32) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Reserva los bloques de verdad (`set_len` solo crea un archivo disperso), así
+/// la falta de espacio aparece aquí y no a mitad de la escritura
+#[cfg(target_os = "linux")]
+fn fallocate(file: &std::fs::File, len: u64) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let len = libc::off_t::try_from(len).map_err(io::Error::other)?;
+    match unsafe { libc::posix_fallocate(file.as_raw_fd(), 0, len) } {
+        0 => Ok(()),
+        errno => Err(io::Error::from_raw_os_error(errno)),
+    }
+}
+
+/// Disco local mediante `tokio::fs`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalFs;
+
+impl FileSystem for LocalFs {
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    fn create_dir_all<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(tokio::fs::create_dir_all(path))
+    }
+
+    fn create<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileWriter>> {
+        Box::pin(async move {
+            let file = tokio::fs::File::create(path).await?;
+            Ok(Box::new(file) as FileWriter)
+        })
+    }
+
+    fn open<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileReader>> {
+        Box::pin(async move {
+            let file = tokio::fs::File::open(path).await?;
+            Ok(Box::new(file) as FileReader)
+        })
+    }
+
+    fn write<'a>(&'a self, path: &'a Path, contents: &'a [u8]) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(tokio::fs::write(path, contents))
+    }
+
+    fn write_new<'a>(
+        &'a self,
+        path: &'a Path,
+        contents: &'a [u8],
+    ) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let mut file = tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .await?;
+            file.write_all(contents).await?;
+            file.flush().await
+        })
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(tokio::fs::rename(from, to))
+    }
+
+    fn remove_file<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(tokio::fs::remove_file(path))
+    }
+
+    fn remove_dir_all<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(tokio::fs::remove_dir_all(path))
+    }
+
+    fn metadata<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileMetadata>> {
+        Box::pin(async move {
+            let meta = tokio::fs::metadata(path).await?;
+            Ok(FileMetadata {
+                len: meta.len(),
+                is_dir: meta.is_dir(),
+                modified: meta.modified().ok(),
+            })
+        })
+    }
+
+    fn read<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<Vec<u8>>> {
+        Box::pin(tokio::fs::read(path))
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<Vec<PathBuf>>> {
+        Box::pin(async move {
+            let mut entries = tokio::fs::read_dir(path).await?;
+            let mut paths = Vec::new();
+            while let Some(entry) = entries.next_entry().await? {
+                paths.push(entry.path());
+            }
+            Ok(paths)
+        })
+    }
+
+    fn copy<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move { tokio::fs::copy(from, to).await.map(|_| ()) })
+    }
+
+    fn sync<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            // Windows no permite abrir un directorio para sincronizarlo
+            if cfg!(windows) && tokio::fs::metadata(path).await?.is_dir() {
+                return Ok(());
+            }
+            tokio::fs::File::open(path).await?.sync_all().await
+        })
+    }
+
+    fn allocate<'a>(&'a self, path: &'a Path, len: u64) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+            #[cfg(target_os = "linux")]
+            if file.metadata().await?.len() < len {
+                let file = file.into_std().await;
+                return tokio::task::spawn_blocking(move || fallocate(&file, len))
+                    .await
+                    .map_err(io::Error::other)?;
+            }
+            file.set_len(len).await
+        })
+    }
+
+    fn hard_link<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(tokio::fs::hard_link(from, to))
+    }
+
+    fn canonicalize<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<PathBuf>> {
+        Box::pin(tokio::fs::canonicalize(path))
+    }
+
+    #[cfg(unix)]
+    fn set_permissions<'a>(&'a self, path: &'a Path, mode: u32) -> BoxFuture<'a, io::Result<()>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        Box::pin(tokio::fs::set_permissions(
+            path,
+            std::fs::Permissions::from_mode(mode),
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct MemoryFile {
+    content: Vec<u8>,
+    modified: SystemTime,
+}
+
+impl MemoryFile {
+    fn new(content: Vec<u8>) -> Self {
+        Self {
+            content,
+            modified: SystemTime::now(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MemoryTree {
+    files: HashMap<PathBuf, MemoryFile>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl MemoryTree {
+    /// Mueve un directorio con todo su contenido. Como `rename` en Unix, el
+    /// destino puede existir solo si es un directorio vacío.
+    fn rename_dir(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        if to.starts_with(from) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot move {from:?} inside itself"),
+            ));
+        }
+        if self.files.contains_key(to) {
+            return Err(io::ErrorKind::NotADirectory.into());
+        }
+        let to_has_entries = self
+            .files
+            .keys()
+            .chain(self.dirs.iter())
+            .any(|entry| entry.parent() == Some(to));
+        if to_has_entries {
+            return Err(io::ErrorKind::DirectoryNotEmpty.into());
+        }
+
+        let moved = |path: PathBuf| match path.strip_prefix(from) {
+            Ok(rest) => to.join(rest),
+            Err(_) => path,
+        };
+        self.files = std::mem::take(&mut self.files)
+            .into_iter()
+            .map(|(path, file)| (moved(path), file))
+            .collect();
+        self.dirs = std::mem::take(&mut self.dirs)
+            .into_iter()
+            .map(moved)
+            .collect();
+        Ok(())
+    }
+
+    fn has_parent(&self, path: &Path) -> bool {
+        match path.parent() {
+            Some(parent) if !is_root(parent) => self.dirs.contains(parent),
+            _ => true,
+        }
+    }
+}
+
+/// `/`, `C:\` o la ruta vacía: siempre existen
+fn is_root(path: &Path) -> bool {
+    path.components()
+        .all(|c| matches!(c, Component::RootDir | Component::Prefix(_)))
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{path:?} not found"))
+}
+
+/// Sistema de archivos en memoria. Los clones comparten el mismo contenido,
+/// así que se puede instalar globalmente y seguir inspeccionándolo.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    tree: Arc<Mutex<MemoryTree>>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tree(&self) -> std::sync::MutexGuard<'_, MemoryTree> {
+        self.tree.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Contenido de un archivo, si existe
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.tree()
+            .files
+            .get(path.as_ref())
+            .map(|file| file.content.clone())
+    }
+
+    /// Cambia la fecha de modificación de un archivo, como `File::set_modified`
+    pub fn set_modified(&self, path: impl AsRef<Path>, modified: SystemTime) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tree = self.tree();
+        let file = tree.files.get_mut(path).ok_or_else(|| not_found(path))?;
+        file.modified = modified;
+        Ok(())
+    }
+
+    /// Rutas de todos los archivos guardados
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.tree().files.keys().cloned().collect();
+        files.sort();
+        files
+    }
+}
+
+/// Escritura en streaming sobre un archivo de `MemoryFs`
+struct MemoryWriter {
+    tree: Arc<Mutex<MemoryTree>>,
+    path: PathBuf,
+}
+
+impl AsyncWrite for MemoryWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut tree = self.tree.lock().unwrap_or_else(|e| e.into_inner());
+        match tree.files.get_mut(&self.path) {
+            Some(file) => {
+                file.content.extend_from_slice(buf);
+                file.modified = SystemTime::now();
+                Poll::Ready(Ok(buf.len()))
+            }
+            // El archivo se borró o movió mientras estaba abierto
+            None => Poll::Ready(Err(not_found(&self.path))),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn create_dir_all<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let mut tree = self.tree();
+            for ancestor in path.ancestors().filter(|p| !is_root(p)) {
+                if tree.files.contains_key(ancestor) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{ancestor:?} is a file"),
+                    ));
+                }
+                tree.dirs.insert(ancestor.to_path_buf());
+            }
+            Ok(())
+        })
+    }
+
+    fn create<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileWriter>> {
+        Box::pin(async move {
+            self.write(path, &[]).await?;
+            Ok(Box::new(MemoryWriter {
+                tree: Arc::clone(&self.tree),
+                path: path.to_path_buf(),
+            }) as FileWriter)
+        })
+    }
+
+    fn open<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileReader>> {
+        Box::pin(async move {
+            let content = self.get(path).ok_or_else(|| not_found(path))?;
+            Ok(Box::new(Cursor::new(content)) as FileReader)
+        })
+    }
+
+    fn write<'a>(&'a self, path: &'a Path, contents: &'a [u8]) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let mut tree = self.tree();
+            if !tree.has_parent(path) {
+                return Err(not_found(path.parent().unwrap_or(path)));
+            }
+            tree.files
+                .insert(path.to_path_buf(), MemoryFile::new(contents.to_vec()));
+            Ok(())
+        })
+    }
+
+    fn write_new<'a>(
+        &'a self,
+        path: &'a Path,
+        contents: &'a [u8],
+    ) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let mut tree = self.tree();
+            if !tree.has_parent(path) {
+                return Err(not_found(path.parent().unwrap_or(path)));
+            }
+            if tree.files.contains_key(path) || tree.dirs.contains(path) {
+                return Err(io::ErrorKind::AlreadyExists.into());
+            }
+            tree.files
+                .insert(path.to_path_buf(), MemoryFile::new(contents.to_vec()));
+            Ok(())
+        })
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let mut tree = self.tree();
+            if !tree.has_parent(to) {
+                return Err(not_found(to.parent().unwrap_or(to)));
+            }
+            if tree.dirs.contains(from) {
+                return tree.rename_dir(from, to);
+            }
+            if tree.dirs.contains(to) {
+                return Err(io::ErrorKind::IsADirectory.into());
+            }
+            let file = tree.files.remove(from).ok_or_else(|| not_found(from))?;
+            tree.files.insert(to.to_path_buf(), file);
+            Ok(())
+        })
+    }
+
+    fn remove_file<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            self.tree()
+                .files
+                .remove(path)
+                .map(|_| ())
+                .ok_or_else(|| not_found(path))
+        })
+    }
+
+    fn remove_dir_all<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let mut tree = self.tree();
+            if !tree.dirs.contains(path) {
+                return Err(not_found(path));
+            }
+            tree.files.retain(|file, _| !file.starts_with(path));
+            tree.dirs.retain(|dir| !dir.starts_with(path));
+            Ok(())
+        })
+    }
+
+    fn metadata<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<FileMetadata>> {
+        Box::pin(async move {
+            let tree = self.tree();
+            if let Some(file) = tree.files.get(path) {
+                Ok(FileMetadata {
+                    len: file.content.len() as u64,
+                    is_dir: false,
+                    modified: Some(file.modified),
+                })
+            } else if tree.dirs.contains(path) || is_root(path) {
+                Ok(FileMetadata {
+                    len: 0,
+                    is_dir: true,
+                    modified: None,
+                })
+            } else {
+                Err(not_found(path))
+            }
+        })
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, io::Result<Vec<PathBuf>>> {
+        Box::pin(async move {
+            let tree = self.tree();
+            if !tree.dirs.contains(path) && !is_root(path) {
+                return Err(not_found(path));
+            }
+            let mut paths: Vec<PathBuf> = tree
+                .files
+                .keys()
+                .chain(tree.dirs.iter())
+                .filter(|entry| entry.parent() == Some(path))
+                .cloned()
+                .collect();
+            paths.sort();
+            Ok(paths)
+        })
+    }
+}
+
+static FILE_SYSTEM: Lazy<RwLock<Option<Arc<dyn FileSystem>>>> = Lazy::new(|| RwLock::new(None));
+
+tokio::task_local! {
+    // Sistema de archivos fijado con `with_file_system` para la tarea actual
+    static TASK_FILE_SYSTEM: Arc<dyn FileSystem>;
+}
+
+/// Sustituye el sistema de archivos global; `None` vuelve al disco local
+pub fn set_file_system(fs: Option<Arc<dyn FileSystem>>) {
+    *FILE_SYSTEM.write().unwrap_or_else(|e| e.into_inner()) = fs;
+}
+
+/// Ejecuta `future` con `fs` como sistema de archivos, sin tocar el global.
+/// Las tareas que lance proton por dentro lo heredan, así que dos instalaciones
+/// con sistemas distintos pueden correr a la vez.
+pub async fn with_file_system<F: Future>(fs: Arc<dyn FileSystem>, future: F) -> F::Output {
+    TASK_FILE_SYSTEM.scope(fs, future).await
+}
+
+/// Como `with_file_system`, pero `None` deja el sistema que ya hubiera
+pub(crate) async fn scoped_file_system<F: Future>(
+    fs: Option<Arc<dyn FileSystem>>,
+    future: F,
+) -> F::Output {
+    match fs {
+        Some(fs) => with_file_system(fs, future).await,
+        None => future.await,
+    }
+}
+
+/// Sistema fijado para la tarea actual, para propagarlo a las que se lancen
+pub(crate) fn task_file_system() -> Option<Arc<dyn FileSystem>> {
+    TASK_FILE_SYSTEM.try_with(Arc::clone).ok()
+}
+
+/// Sistema de archivos actual: el de la tarea si se fijó con
+/// `with_file_system` (o `MinecraftDownloader::file_system`), si no el global
+pub fn file_system() -> Arc<dyn FileSystem> {
+    if let Some(fs) = task_file_system() {
+        return fs;
+    }
+    FILE_SYSTEM
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| Arc::new(LocalFs))
+}
+
+static DURABLE_WRITES: AtomicBool = AtomicBool::new(false);
+
+/// Sincroniza con el disco cada archivo descargado o extraído y el directorio
+/// que lo contiene. Más lento, pero un corte de luz no deja librerías corruptas.
+pub fn set_durable_writes(enabled: bool) {
+    DURABLE_WRITES.store(enabled, Ordering::Relaxed);
+}
+
+pub fn durable_writes() -> bool {
+    DURABLE_WRITES.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scoped_file_systems_do_not_share_files() {
+        let (first, second) = (MemoryFs::new(), MemoryFs::new());
+        let path = Path::new("/game/options.txt");
+        let write = |content: &'static [u8]| async move {
+            file_system().create_dir_all(Path::new("/game")).await?;
+            tokio::task::yield_now().await;
+            file_system().write(path, content).await
+        };
+
+        let (a, b) = tokio::join!(
+            with_file_system(Arc::new(first.clone()), write(b"first")),
+            with_file_system(Arc::new(second.clone()), write(b"second")),
+        );
+        a.unwrap();
+        b.unwrap();
+
+        assert_eq!(first.get(path).as_deref(), Some(&b"first"[..]));
+        assert_eq!(second.get(path).as_deref(), Some(&b"second"[..]));
+        assert!(file_system().is_local());
+    }
+
+    #[tokio::test]
+    async fn memory_directories_can_be_renamed() {
+        let fs = MemoryFs::new();
+        fs.create_dir_all(Path::new("/game/versions/old/natives"))
+            .await
+            .unwrap();
+        fs.write(Path::new("/game/versions/old/old.json"), b"{}")
+            .await
+            .unwrap();
+        fs.create_dir_all(Path::new("/game/versions/full"))
+            .await
+            .unwrap();
+        fs.write(Path::new("/game/versions/full/full.json"), b"{}")
+            .await
+            .unwrap();
+
+        let into_itself = fs
+            .rename(
+                Path::new("/game/versions/old"),
+                Path::new("/game/versions/old/inner"),
+            )
+            .await;
+        let onto_full = fs
+            .rename(
+                Path::new("/game/versions/old"),
+                Path::new("/game/versions/full"),
+            )
+            .await;
+        let file_onto_dir = fs
+            .rename(
+                Path::new("/game/versions/full/full.json"),
+                Path::new("/game/versions/old"),
+            )
+            .await;
+        fs.rename(
+            Path::new("/game/versions/old"),
+            Path::new("/game/versions/new"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(into_itself.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            onto_full.unwrap_err().kind(),
+            io::ErrorKind::DirectoryNotEmpty
+        );
+        assert_eq!(
+            file_onto_dir.unwrap_err().kind(),
+            io::ErrorKind::IsADirectory
+        );
+        assert_eq!(
+            fs.get("/game/versions/new/old.json").as_deref(),
+            Some(&b"{}"[..])
+        );
+        assert!(
+            fs.metadata(Path::new("/game/versions/new/natives"))
+                .await
+                .unwrap()
+                .is_dir
+        );
+        assert!(!fs.exists(Path::new("/game/versions/old")).await);
+        assert!(!fs.exists(Path::new("/game/versions/old/natives")).await);
+    }
+
+    #[tokio::test]
+    async fn memory_files_track_their_modification_time() {
+        let fs = MemoryFs::new();
+        let path = Path::new("/options.txt");
+        let before = SystemTime::now();
+        fs.write(path, b"fov:70").await.unwrap();
+        let written = fs.metadata(path).await.unwrap().modified.unwrap();
+        assert!(written >= before);
+
+        let old = before - std::time::Duration::from_secs(3600);
+        fs.set_modified(path, old).unwrap();
+        assert_eq!(fs.metadata(path).await.unwrap().modified, Some(old));
+
+        // Escribir en streaming también la actualiza, y moverlo la conserva
+        let mut writer = fs.create(path).await.unwrap();
+        writer.write_all(b"fov:90").await.unwrap();
+        let rewritten = fs.metadata(path).await.unwrap().modified.unwrap();
+        assert!(rewritten >= written);
+        fs.set_modified(path, old).unwrap();
+        fs.rename(path, Path::new("/options.bak")).await.unwrap();
+        assert_eq!(
+            fs.metadata(Path::new("/options.bak"))
+                .await
+                .unwrap()
+                .modified,
+            Some(old)
+        );
+        assert!(fs.set_modified(path, old).is_err());
+    }
+
+    #[tokio::test]
+    async fn local_files_can_grow_and_shrink() {
+        let path = std::env::temp_dir().join(format!("proton-allocate-{}", std::process::id()));
+        let fs = LocalFs;
+        fs.write(&path, b"natives").await.unwrap();
+
+        fs.allocate(&path, 4096).await.unwrap();
+        assert_eq!(fs.metadata(&path).await.unwrap().len, 4096);
+        fs.allocate(&path, 3).await.unwrap();
+        assert_eq!(fs.metadata(&path).await.unwrap().len, 3);
+        assert_eq!(fs.read(&path).await.unwrap(), b"nat");
+
+        fs.remove_file(&path).await.unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::downloaders::MinecraftDownloader;
+use crate::errors::ProtonError;
+use crate::manifest;
+use crate::types::{DownloadProgress, FailedFile, NormalizedVersion};
+
+/// Error expuesto a Kotlin/Swift
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum ProtonFfiError {
+    #[error("{message}")]
+    Failed { code: String, message: String },
+}
+
+impl From<ProtonError> for ProtonFfiError {
+    fn from(error: ProtonError) -> Self {
+        ProtonFfiError::Failed {
+            code: error.code().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Resumen de una versión resuelta
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct VersionSummary {
+    pub id: String,
+    pub version_type: Option<String>,
+    pub release_time: String,
+    pub java_version: u8,
+    pub main_class: String,
+    pub libraries: u64,
+    pub has_server: bool,
+}
+
+impl From<&NormalizedVersion> for VersionSummary {
+    fn from(version: &NormalizedVersion) -> Self {
+        Self {
+            id: version.id.clone(),
+            version_type: version
+                .version_type
+                .as_ref()
+                .map(|t| t.as_str().to_string()),
+            release_time: version.release_time.clone(),
+            java_version: version.java_version,
+            main_class: version.main_class.clone(),
+            libraries: version.libraries.len() as u64,
+            has_server: version.server_jar.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ProgressEvent {
+    pub kind: String,
+    pub name: String,
+    pub current: u64,
+    pub total: u64,
+    pub bytes: u64,
+}
+
+impl From<DownloadProgress> for ProgressEvent {
+    fn from(progress: DownloadProgress) -> Self {
+        Self {
+            kind: format!("{:?}", progress.download_type).to_lowercase(),
+            name: progress.info.name,
+            current: progress.current as u64,
+            total: progress.total as u64,
+            bytes: progress.bytes,
+        }
+    }
+}
+
+/// Archivo que falta o está corrupto
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct BrokenFile {
+    pub kind: String,
+    pub name: String,
+    pub path: String,
+    pub error: String,
+}
+
+impl From<FailedFile> for BrokenFile {
+    fn from(file: FailedFile) -> Self {
+        Self {
+            kind: format!("{:?}", file.download_type).to_lowercase(),
+            name: file.name,
+            path: file.path.to_string_lossy().into_owned(),
+            error: file.error,
+        }
+    }
+}
+
+/// Callback de progreso implementado en el lenguaje anfitrión
+#[uniffi::export(with_foreign)]
+pub trait ProgressListener: Send + Sync {
+    fn on_progress(&self, event: ProgressEvent);
+}
+
+async fn downloader(
+    game_path: String,
+    version_id: &str,
+) -> Result<MinecraftDownloader, ProtonError> {
+    let version = manifest::resolve_version_data(version_id).await?;
+    Ok(MinecraftDownloader::new(PathBuf::from(game_path), version))
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn resolve_version(version_id: String) -> Result<VersionSummary, ProtonFfiError> {
+    let version = manifest::resolve_version_data(&version_id).await?;
+    Ok(VersionSummary::from(&version))
+}
+
+/// Descarga la versión en `game_path` notificando el progreso a `listener`
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn download_version(
+    game_path: String,
+    version_id: String,
+    listener: Option<Arc<dyn ProgressListener>>,
+) -> Result<(), ProtonFfiError> {
+    let mut downloader = downloader(game_path, &version_id).await?;
+
+    let Some(listener) = listener else {
+        return Ok(downloader.download_all(None).await?);
+    };
+
+    let (tx, mut rx) = mpsc::channel::<DownloadProgress>(100);
+    let forwarder = tokio::spawn(async move {
+        while let Some(progress) = rx.recv().await {
+            listener.on_progress(progress.into());
+        }
+    });
+
+    let result = downloader.download_all(Some(tx)).await;
+    let _ = forwarder.await;
+    Ok(result?)
+}
+
+/// Comprueba los archivos instalados sin descargar nada
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn verify_version(
+    game_path: String,
+    version_id: String,
+) -> Result<Vec<BrokenFile>, ProtonFfiError> {
+    let broken = downloader(game_path, &version_id).await?.verify().await?;
+    Ok(broken.into_iter().map(BrokenFile::from).collect())
+}
+
+/// Vuelve a descargar los archivos que fallan la verificación; devuelve cuántos
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn repair_version(game_path: String, version_id: String) -> Result<u64, ProtonFfiError> {
+    let downloader = downloader(game_path, &version_id).await?;
+    let broken = downloader.verify().await?;
+    let repaired = broken.len() as u64;
+    if !broken.is_empty() {
+        downloader.retry_failed(broken).await?;
+    }
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::FileSystem;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+    use std::sync::Mutex;
+
+    /// Listener que guarda los eventos, como haría el lado Kotlin/Swift
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<ProgressEvent>>);
+
+    impl ProgressListener for Recorder {
+        fn on_progress(&self, event: ProgressEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn download_verify_and_repair_through_the_bindings() {
+        let env = MockEnvironment::start().await;
+        let game_path = env.game_dir.to_string_lossy().into_owned();
+        let recorder = Arc::new(Recorder::default());
+        let client = env
+            .game_dir
+            .join(format!("versions/{FIXTURE_VERSION}/{FIXTURE_VERSION}.jar"));
+        let id = || FIXTURE_VERSION.to_string();
+
+        let (summary, clean) = env
+            .run(async {
+                let summary = resolve_version(id()).await?;
+                let listener: Arc<dyn ProgressListener> = recorder.clone();
+                download_version(game_path.clone(), id(), Some(listener)).await?;
+                let clean = verify_version(game_path.clone(), id()).await?;
+                Ok::<_, ProtonFfiError>((summary, clean))
+            })
+            .await
+            .unwrap();
+        assert_eq!(summary.id, FIXTURE_VERSION);
+        assert!(clean.is_empty());
+        let events = recorder.0.lock().unwrap().clone();
+        assert!(events.iter().any(|event| event.kind == "client"));
+
+        env.fs.write(&client, b"corrupted").await.unwrap();
+        let (broken, repaired, after) = env
+            .run(async {
+                let broken = verify_version(game_path.clone(), id()).await?;
+                let repaired = repair_version(game_path.clone(), id()).await?;
+                let after = verify_version(game_path.clone(), id()).await?;
+                Ok::<_, ProtonFfiError>((broken, repaired, after))
+            })
+            .await
+            .unwrap();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].kind, "client");
+        assert_eq!(repaired, 1);
+        assert!(after.is_empty());
+    }
+
+    #[tokio::test]
+    async fn errors_carry_their_code() {
+        let env = MockEnvironment::start().await;
+
+        let error = env
+            .run(resolve_version("does-not-exist".to_string()))
+            .await
+            .unwrap_err();
+
+        let ProtonFfiError::Failed { code, message } = error;
+        assert!(code.starts_with("E_"), "{code}");
+        assert!(message.contains("does-not-exist"), "{message}");
+    }
+}
@@ -0,0 +1,377 @@
+use async_zip::base::read::mem::ZipFileReader as MemoryZipReader;
+use async_zip::tokio::read::seek::ZipFileReader;
+use async_zip::tokio::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use log::info;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::io::{AsyncWriteExt, BufReader};
+
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::utilities::{TempGuard, move_file, temp_path};
+
+// Cabecera de los deltas GDIFF (`com.nothome.delta`) que usa binarypatcher
+const GDIFF_MAGIC: [u8; 4] = [0xD1, 0xFF, 0xD1, 0xFF];
+const GDIFF_VERSION: u8 = 4;
+
+// Única versión del formato `.binpatch`
+const PATCH_VERSION: u8 = 1;
+
+fn invalid(message: impl Into<String>) -> ProtonError {
+    ProtonError::InvalidBinaryPatch(message.into())
+}
+
+/// Lectura big-endian, como `DataInputStream` de Java
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], ProtonError> {
+        let bytes = self
+            .position
+            .checked_add(length)
+            .and_then(|end| self.data.get(self.position..end))
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ProtonError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ProtonError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ProtonError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, ProtonError> {
+        Ok((u64::from(self.u32()?) << 32) | u64::from(self.u32()?))
+    }
+
+    /// Cadena de `writeUTF`: longitud de 2 bytes y UTF-8 modificado
+    fn utf(&mut self) -> Result<String, ProtonError> {
+        let length = usize::from(self.u16()?);
+        Ok(String::from_utf8_lossy(self.take(length)?).into_owned())
+    }
+}
+
+/// Parche de una clase dentro de `client.lzma`
+#[derive(Debug, Clone)]
+struct BinaryPatch {
+    /// Nombre de la clase en el jar limpio, sin `.class`
+    name: String,
+    /// Adler-32 de la clase original; `None` si el parche crea la clase
+    checksum: Option<u32>,
+    delta: Vec<u8>,
+}
+
+impl BinaryPatch {
+    fn parse(data: &[u8]) -> Result<Self, ProtonError> {
+        let mut reader = Reader::new(data);
+        let version = reader.u8()?;
+        if version != PATCH_VERSION {
+            return Err(invalid(format!("unsupported patch version {version}")));
+        }
+        let name = reader.utf()?;
+        let _deobfuscated_name = reader.utf()?;
+        let checksum = match reader.u8()? {
+            0 => None,
+            _ => Some(reader.u32()?),
+        };
+        let length = reader.u32()? as usize;
+        let delta = reader.take(length)?.to_vec();
+
+        Ok(Self {
+            name,
+            checksum,
+            delta,
+        })
+    }
+
+    fn apply(&self, base: &[u8]) -> Result<Vec<u8>, ProtonError> {
+        if let Some(expected) = self.checksum {
+            let actual = adler32(base);
+            if actual != expected {
+                return Err(invalid(format!(
+                    "{}: expected checksum {expected:08x}, got {actual:08x}",
+                    self.name
+                )));
+            }
+        }
+        apply_gdiff(base, &self.delta).map_err(|e| invalid(format!("{}: {e}", self.name)))
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes es lo máximo que se puede sumar sin desbordar u32
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Reconstruye un archivo a partir de `base` y un delta GDIFF
+fn apply_gdiff(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, ProtonError> {
+    let mut reader = Reader::new(delta);
+    if reader.take(4)? != GDIFF_MAGIC || reader.u8()? != GDIFF_VERSION {
+        return Err(invalid("not a GDIFF delta"));
+    }
+
+    let mut output = Vec::with_capacity(base.len());
+    loop {
+        let command = reader.u8()?;
+        // 1-248 añaden bytes del propio delta; 249-255 copian de `base`
+        let (offset, length) = match command {
+            0 => return Ok(output),
+            1..=246 => {
+                output.extend_from_slice(reader.take(usize::from(command))?);
+                continue;
+            }
+            247 => {
+                let length = usize::from(reader.u16()?);
+                output.extend_from_slice(reader.take(length)?);
+                continue;
+            }
+            248 => {
+                let length = reader.u32()? as usize;
+                output.extend_from_slice(reader.take(length)?);
+                continue;
+            }
+            249 => (u64::from(reader.u16()?), usize::from(reader.u8()?)),
+            250 => (u64::from(reader.u16()?), usize::from(reader.u16()?)),
+            251 => (u64::from(reader.u16()?), reader.u32()? as usize),
+            252 => (u64::from(reader.u32()?), usize::from(reader.u8()?)),
+            253 => (u64::from(reader.u32()?), usize::from(reader.u16()?)),
+            254 => (u64::from(reader.u32()?), reader.u32()? as usize),
+            255 => (reader.u64()?, reader.u32()? as usize),
+        };
+
+        let chunk = usize::try_from(offset)
+            .ok()
+            .and_then(|start| base.get(start..start.checked_add(length)?))
+            .ok_or_else(|| invalid(format!("copy of {length} bytes at {offset} out of range")))?;
+        output.extend_from_slice(chunk);
+    }
+}
+
+/// Lee los parches de un `client.lzma`: un jar comprimido con LZMA con un
+/// `.binpatch` por clase
+async fn load_patches(path: &Path) -> Result<BTreeMap<String, BinaryPatch>, ProtonError> {
+    let compressed = file_system().read(path).await?;
+    let jar = tokio::task::spawn_blocking(move || {
+        let mut jar = Vec::new();
+        lzma_rs::lzma_decompress(&mut compressed.as_slice(), &mut jar).map(|()| jar)
+    })
+    .await?
+    .map_err(|e| invalid(format!("{path:?}: {e}")))?;
+
+    let reader = MemoryZipReader::new(jar).await?;
+    let mut patches = BTreeMap::new();
+    for index in 0..reader.file().entries().len() {
+        let name = reader.file().entries()[index].filename().as_str()?;
+        if !name.ends_with(".binpatch") {
+            continue;
+        }
+        let name = name.to_string();
+        let mut data = Vec::new();
+        reader
+            .reader_with_entry(index)
+            .await?
+            .read_to_end_checked(&mut data)
+            .await?;
+
+        let patch = BinaryPatch::parse(&data).map_err(|e| invalid(format!("{name}: {e}")))?;
+        if patches.insert(patch.name.clone(), patch).is_some() {
+            return Err(invalid(format!("duplicate patch {name}")));
+        }
+    }
+    Ok(patches)
+}
+
+/// Qué más copiar del jar limpio además de las clases parcheadas
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PatchOutput {
+    /// Clases sin parche
+    pub unpatched: bool,
+    /// Recursos que no son clases
+    pub data: bool,
+}
+
+/// Aplica los parches de `patches` (`client.lzma`) a `clean` y escribe el
+/// resultado en `output`, como el procesador `binarypatcher` de Forge.
+/// Devuelve cuántas clases se parchearon o crearon.
+pub(crate) async fn apply_binpatches(
+    clean: &Path,
+    patches: &Path,
+    output: &Path,
+    include: PatchOutput,
+) -> Result<usize, ProtonError> {
+    let mut patches = load_patches(patches).await?;
+    let fs = file_system();
+    let mut reader = ZipFileReader::with_tokio(BufReader::new(fs.open(clean).await?)).await?;
+
+    if let Some(parent) = output.parent() {
+        fs.create_dir_all(parent).await?;
+    }
+    let temp = temp_path(output);
+    let mut guard = TempGuard::file(temp.clone());
+    let mut writer = ZipFileWriter::with_tokio(fs.create(&temp).await?);
+    let mut patched = 0;
+
+    for index in 0..reader.file().entries().len() {
+        let entry = &reader.file().entries()[index];
+        let name = entry.filename().as_str()?.to_string();
+        if name.ends_with('/') {
+            continue;
+        }
+        let class = name.strip_suffix(".class");
+        let patch = class.and_then(|class| patches.remove(class));
+        let copy = match class {
+            Some(_) => include.unpatched,
+            None => include.data,
+        };
+        if patch.is_none() && !copy {
+            continue;
+        }
+
+        let mut content = Vec::new();
+        reader
+            .reader_with_entry(index)
+            .await?
+            .read_to_end_checked(&mut content)
+            .await?;
+        if let Some(patch) = patch {
+            content = patch.apply(&content)?;
+            patched += 1;
+        }
+        writer
+            .write_entry_whole(
+                ZipEntryBuilder::new(name.into(), Compression::Deflate),
+                &content,
+            )
+            .await?;
+    }
+
+    // Lo que queda son clases que no existen en el jar limpio
+    for (class, patch) in patches {
+        let content = patch.apply(&[])?;
+        let name = format!("{class}.class");
+        writer
+            .write_entry_whole(
+                ZipEntryBuilder::new(name.into(), Compression::Deflate),
+                &content,
+            )
+            .await?;
+        patched += 1;
+    }
+
+    writer.close().await?.into_inner().shutdown().await?;
+    move_file(&temp, output).await?;
+    guard.disarm();
+    info!("Applied {patched} binary patches to {clean:?} into {output:?}");
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gdiff(commands: &[u8]) -> Vec<u8> {
+        let mut delta = GDIFF_MAGIC.to_vec();
+        delta.push(GDIFF_VERSION);
+        delta.extend_from_slice(commands);
+        delta
+    }
+
+    fn binpatch(name: &str, checksum: Option<u32>, delta: &[u8]) -> Vec<u8> {
+        let mut data = vec![PATCH_VERSION];
+        for utf in [name, name] {
+            data.extend_from_slice(&(utf.len() as u16).to_be_bytes());
+            data.extend_from_slice(utf.as_bytes());
+        }
+        match checksum {
+            Some(checksum) => {
+                data.push(1);
+                data.extend_from_slice(&checksum.to_be_bytes());
+            }
+            None => data.push(0),
+        }
+        data.extend_from_slice(&(delta.len() as u32).to_be_bytes());
+        data.extend_from_slice(delta);
+        data
+    }
+
+    #[test]
+    fn gdiff_mixes_copies_and_inline_data() {
+        let base = b"Hello, Minecraft!";
+        let delta = gdiff(&[
+            249, 0, 0, 7, // "Hello, "
+            5, b'F', b'o', b'r', b'g', b'e', // datos del delta
+            252, 0, 0, 0, 16, 1, // "!"
+            247, 0, 1, b'\n', // datos con longitud de 2 bytes
+            0,
+        ]);
+        assert_eq!(apply_gdiff(base, &delta).unwrap(), b"Hello, Forge!\n");
+    }
+
+    #[test]
+    fn gdiff_rejects_bad_headers_and_ranges() {
+        assert!(apply_gdiff(b"", b"\xd1\xff\xd1\xff\x03\x00").is_err());
+        // Copia que se sale de la base
+        assert!(apply_gdiff(b"abc", &gdiff(&[249, 0, 2, 5, 0])).is_err());
+        // Delta truncado: sin comando de fin
+        assert!(apply_gdiff(b"abc", &gdiff(&[3, b'x'])).is_err());
+    }
+
+    #[test]
+    fn parses_and_applies_binpatches() {
+        let base = b"Wikipedia";
+        assert_eq!(adler32(base), 0x11e6_0398);
+
+        let delta = gdiff(&[249, 0, 0, 4, 3, b'.', b'r', b's', 0]);
+        let patch =
+            BinaryPatch::parse(&binpatch("net/minecraft/Wiki", Some(0x11e6_0398), &delta)).unwrap();
+        assert_eq!(patch.name, "net/minecraft/Wiki");
+        assert_eq!(patch.checksum, Some(0x11e6_0398));
+        assert_eq!(patch.apply(base).unwrap(), b"Wiki.rs");
+
+        // Otra clase base que la esperada
+        assert!(matches!(
+            patch.apply(b"Wikipedio"),
+            Err(ProtonError::InvalidBinaryPatch(_))
+        ));
+    }
+
+    #[test]
+    fn new_classes_have_no_checksum() {
+        let delta = gdiff(&[2, b'o', b'k', 0]);
+        let patch = BinaryPatch::parse(&binpatch("New", None, &delta)).unwrap();
+        assert_eq!(patch.checksum, None);
+        assert_eq!(patch.apply(&[]).unwrap(), b"ok");
+
+        let mut unsupported = binpatch("New", None, &delta);
+        unsupported[0] = 2;
+        assert!(BinaryPatch::parse(&unsupported).is_err());
+        assert!(BinaryPatch::parse(&binpatch("New", None, &delta)[..10]).is_err());
+    }
+}
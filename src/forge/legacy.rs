@@ -0,0 +1,387 @@
+use async_zip::base::read::mem::ZipFileReader as MemoryZipReader;
+use async_zip::tokio::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use log::info;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::collections::HashSet;
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::Sender;
+
+use super::{ForgeInstaller, install_error, read_zip_entry};
+use crate::downloaders::MinecraftDownloader;
+use crate::endpoints::{EndpointConfig, endpoints};
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::manifest::{resolve_version_data, resolve_version_from_file};
+use crate::maven::MavenCoordinate;
+use crate::types::{DownloadProgress, LAUNCHWRAPPER_MAIN_CLASS, NormalizedVersion};
+use crate::utilities::{
+    TempGuard, file_sha1, is_safe_relative_path, move_file, safe_join, temp_path,
+};
+
+// Antiguo maven de Forge; se sustituye por `EndpointConfig::forge_maven_url`
+const LEGACY_FORGE_MAVEN: &str = "http://files.minecraftforge.net/maven";
+
+// Repositorios de los perfiles antiguos que ya no responden o exigen HTTPS
+const LEGACY_REPOSITORIES: [(&str, &str); 2] = [
+    (
+        "http://repo.maven.apache.org/maven2",
+        "https://repo1.maven.org/maven2",
+    ),
+    (
+        "http://repo1.maven.org/maven2",
+        "https://repo1.maven.org/maven2",
+    ),
+];
+
+/// `install_profile.json` de los instaladores anteriores a 1.13: el JSON de
+/// versión va dentro (`versionInfo`) y no hay procesadores
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct LegacyInstallProfile {
+    install: LegacyInstall,
+    version_info: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyInstall {
+    /// Id de la versión que se instala (`1.7.10-Forge10.13.4.1614-1.7.10`)
+    target: String,
+    /// Coordenada maven del jar universal
+    path: String,
+    /// Jar universal dentro del instalador
+    file_path: String,
+    minecraft: String,
+}
+
+impl ForgeInstaller {
+    /// Instala con un instalador antiguo: el jar universal va a `libraries`
+    /// y lo carga launchwrapper. Si la versión no arranca con launchwrapper
+    /// (Forge como mod del jar), se fusiona con el jar vanilla.
+    pub(super) async fn install_legacy(
+        &self,
+        installer: &Path,
+        profile: LegacyInstallProfile,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<NormalizedVersion, ProtonError> {
+        let LegacyInstallProfile {
+            install,
+            version_info,
+        } = profile;
+        let Value::Object(mut version) = version_info else {
+            return Err(install_error("versionInfo is not an object"));
+        };
+        let id = install.target.clone();
+        if id.contains('/') || !is_safe_relative_path(&id) {
+            return Err(ProtonError::UnsafePath(id));
+        }
+        info!("Installing {id} from legacy installer {installer:?}");
+
+        let universal =
+            read_zip_entry(installer, install.file_path.trim_start_matches('/')).await?;
+        let coordinate: MavenCoordinate = install.path.parse()?;
+        let version_dir = self.game_dir.join("versions").join(&id);
+        let fs = file_system();
+        fs.create_dir_all(&version_dir).await?;
+
+        let jar_mod =
+            version.get("mainClass").and_then(Value::as_str) != Some(LAUNCHWRAPPER_MAIN_CLASS);
+        if jar_mod {
+            let vanilla = resolve_version_data(&install.minecraft).await?;
+            MinecraftDownloader::new(self.game_dir.clone(), vanilla)
+                .download_all(progress_tx.clone())
+                .await?;
+            let vanilla_jar = self
+                .game_dir
+                .join("versions")
+                .join(&install.minecraft)
+                .join(format!("{}.jar", install.minecraft));
+            let jar = version_dir.join(format!("{id}.jar"));
+            merge_jars(&vanilla_jar, universal, &jar).await?;
+
+            // Como una versión propia: sin URL, solo se verifica
+            version.insert(
+                "downloads".to_string(),
+                json!({
+                    "client": {
+                        "sha1": file_sha1(&jar).await?,
+                        "size": fs.metadata(&jar).await?.len,
+                        "url": "",
+                    }
+                }),
+            );
+        } else {
+            let path = safe_join(&self.libraries_dir(), &coordinate.path())?;
+            if let Some(parent) = path.parent() {
+                fs.create_dir_all(parent).await?;
+            }
+            fs.write(&path, &universal).await?;
+        }
+
+        let endpoints = endpoints();
+        let universal_key = coordinate.key();
+        let libraries = match version.remove("libraries") {
+            Some(Value::Array(libraries)) => libraries,
+            _ => Vec::new(),
+        };
+        let libraries: Vec<Value> = libraries
+            .into_iter()
+            .filter_map(|library| client_library(library, &endpoints))
+            // Fusionado en el jar del cliente, el universal no va en el classpath
+            .filter(|library| {
+                !jar_mod
+                    || library
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .and_then(|name| name.parse::<MavenCoordinate>().ok())
+                        .is_none_or(|library| library.key() != universal_key)
+            })
+            .collect();
+        version.insert("libraries".to_string(), Value::Array(libraries));
+        version.insert("id".to_string(), Value::from(id.clone()));
+        // Los perfiles de 1.6 repiten la versión vanilla entera en lugar de heredar
+        version
+            .entry("inheritsFrom")
+            .or_insert_with(|| Value::from(install.minecraft.clone()));
+
+        let json_path = version_dir.join(format!("{id}.json"));
+        let content = serde_json::to_vec_pretty(&Value::Object(version))
+            .map_err(|e| ProtonError::Other(format!("Failed to serialize {id}: {e}")))?;
+        fs.write(&json_path, &content).await?;
+
+        let version = resolve_version_from_file(&json_path).await?;
+        MinecraftDownloader::new(self.game_dir.clone(), version.clone())
+            .download_all(progress_tx)
+            .await?;
+        info!("Installed {id}");
+        Ok(version)
+    }
+}
+
+/// Adapta una librería de `versionInfo` al formato que entiende el
+/// normalizador, o `None` si no hace falta en el cliente
+fn client_library(library: Value, endpoints: &EndpointConfig) -> Option<Value> {
+    let Value::Object(library) = library else {
+        return None;
+    };
+    if library.get("clientreq").and_then(Value::as_bool) == Some(false) {
+        return None;
+    }
+    // Sin `downloads` no se pueden resolver sus nativos: los aporta la versión vanilla
+    if library.contains_key("natives") && !library.contains_key("downloads") {
+        return None;
+    }
+
+    let repository = match library.get("url").and_then(Value::as_str) {
+        Some(url) => {
+            let url = url.trim_end_matches('/');
+            if url == LEGACY_FORGE_MAVEN {
+                return Some(with_repository(library, &endpoints.forge_maven_url));
+            }
+            LEGACY_REPOSITORIES
+                .iter()
+                .find(|(legacy, _)| *legacy == url)
+                .map_or(url, |(_, current)| current)
+                .to_string()
+        }
+        None if library.contains_key("downloads") => return Some(Value::Object(library)),
+        // Sin repositorio están en el de Mojang (launchwrapper, asm...)
+        None => endpoints.libraries_base_url.clone(),
+    };
+    Some(with_repository(library, &repository))
+}
+
+fn with_repository(mut library: Map<String, Value>, repository: &str) -> Value {
+    library.insert("url".to_string(), Value::from(repository));
+    for key in ["checksums", "clientreq", "serverreq"] {
+        library.remove(key);
+    }
+    Value::Object(library)
+}
+
+/// Escribe en `output` el jar vanilla con las clases de `overlay` encima.
+/// Se quita `META-INF` del vanilla: sus firmas ya no coincidirían.
+async fn merge_jars(base: &Path, overlay: Vec<u8>, output: &Path) -> Result<(), ProtonError> {
+    let fs = file_system();
+    let base = MemoryZipReader::new(fs.read(base).await?).await?;
+    let overlay = MemoryZipReader::new(overlay).await?;
+
+    let temp = temp_path(output);
+    let mut guard = TempGuard::file(temp.clone());
+    let mut writer = ZipFileWriter::with_tokio(fs.create(&temp).await?);
+    let mut written = HashSet::new();
+
+    for (reader, skip_meta_inf) in [(&overlay, false), (&base, true)] {
+        for index in 0..reader.file().entries().len() {
+            let name = reader.file().entries()[index]
+                .filename()
+                .as_str()?
+                .to_string();
+            if name.ends_with('/')
+                || (skip_meta_inf && name.starts_with("META-INF/"))
+                || !written.insert(name.clone())
+            {
+                continue;
+            }
+            let mut content = Vec::new();
+            reader
+                .reader_with_entry(index)
+                .await?
+                .read_to_end_checked(&mut content)
+                .await?;
+            writer
+                .write_entry_whole(
+                    ZipEntryBuilder::new(name.into(), Compression::Deflate),
+                    &content,
+                )
+                .await?;
+        }
+    }
+
+    writer.close().await?.into_inner().shutdown().await?;
+    move_file(&temp, output).await?;
+    guard.disarm();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{MemoryFs, with_file_system};
+    use crate::forge::read_zip_entry;
+    use crate::forge::tests::zip;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn universal_jar_goes_to_the_libraries() {
+        let env = MockEnvironment::start().await;
+        let (fs, game_dir) = (&env.fs, &env.game_dir);
+        let id = format!("{FIXTURE_VERSION}-Forge1.0");
+
+        let universal = zip(&[("net/minecraftforge/Forge.class", b"forge")]).await;
+        let profile = json!({
+            "install": {
+                "target": id,
+                "path": format!("net.minecraftforge:forge:{FIXTURE_VERSION}-1.0"),
+                "filePath": "forge-universal.jar",
+                "minecraft": FIXTURE_VERSION,
+            },
+            "versionInfo": {
+                "id": "ignored",
+                "mainClass": LAUNCHWRAPPER_MAIN_CLASS,
+                "minecraftArguments": "--tweakClass cpw.mods.fml.common.launcher.FMLTweaker",
+                "libraries": [
+                    {
+                        "name": format!("net.minecraftforge:forge:{FIXTURE_VERSION}-1.0"),
+                        "url": "http://files.minecraftforge.net/maven/",
+                    },
+                    { "name": "org.proton:library:1.0" },
+                    { "name": "org.proton:server-only:1.0", "clientreq": false },
+                ],
+            },
+        });
+        let installer = zip(&[
+            (
+                "install_profile.json",
+                &serde_json::to_vec(&profile).unwrap(),
+            ),
+            ("forge-universal.jar", &universal),
+        ])
+        .await;
+
+        let version = with_file_system(Arc::new(fs.clone()), async {
+            file_system()
+                .write(Path::new("/forge-installer.jar"), &installer)
+                .await?;
+            ForgeInstaller::new(game_dir, FIXTURE_VERSION, "1.0")
+                .installer_jar("/forge-installer.jar")
+                .install(None)
+                .await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(version.id, id);
+        assert_eq!(version.main_class, LAUNCHWRAPPER_MAIN_CLASS);
+        let universal_path = format!(
+            "libraries/net/minecraftforge/forge/{FIXTURE_VERSION}-1.0/forge-{FIXTURE_VERSION}-1.0.jar"
+        );
+        assert_eq!(fs.get(game_dir.join(&universal_path)), Some(universal));
+        let forge = version
+            .libraries
+            .iter()
+            .find(|library| library.path == universal_path.trim_start_matches("libraries/"))
+            .unwrap();
+        assert!(forge.url.starts_with(&endpoints().forge_maven_url));
+        assert!(
+            !version
+                .libraries
+                .iter()
+                .any(|library| library.path.contains("server-only"))
+        );
+        // Hereda el cliente y las librerías de la versión vanilla
+        assert!(
+            fs.get(game_dir.join(format!("versions/{id}/{id}.jar")))
+                .is_some()
+        );
+        assert!(
+            fs.get(game_dir.join("libraries/org/proton/library/1.0/library-1.0.jar"))
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn merged_jars_keep_the_overlay_and_drop_vanilla_signatures() {
+        let fs = MemoryFs::new();
+        let vanilla = zip(&[
+            ("net/minecraft/Client.class", b"vanilla"),
+            ("net/minecraft/World.class", b"world"),
+            ("META-INF/MOJANG_C.SF", b"signature"),
+        ])
+        .await;
+        let forge = zip(&[
+            ("net/minecraft/Client.class", b"patched"),
+            ("net/minecraftforge/Forge.class", b"forge"),
+            ("META-INF/fml_at.cfg", b"access"),
+        ])
+        .await;
+
+        let entries = with_file_system(Arc::new(fs.clone()), async {
+            let base = Path::new("/vanilla.jar");
+            let output = Path::new("/merged.jar");
+            file_system().write(base, &vanilla).await?;
+            merge_jars(base, forge, output).await?;
+            let mut entries = Vec::new();
+            for name in [
+                "net/minecraft/Client.class",
+                "net/minecraft/World.class",
+                "net/minecraftforge/Forge.class",
+                "META-INF/fml_at.cfg",
+            ] {
+                entries.push(read_zip_entry(output, name).await?);
+            }
+            assert!(
+                read_zip_entry(output, "META-INF/MOJANG_C.SF")
+                    .await
+                    .is_err()
+            );
+            Ok::<_, ProtonError>(entries)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            [&b"patched"[..], b"world", b"forge", b"access"].map(<[u8]>::to_vec)
+        );
+        assert_eq!(
+            fs.files(),
+            [PathBuf::from("/merged.jar"), PathBuf::from("/vanilla.jar")]
+        );
+    }
+}
@@ -0,0 +1,650 @@
+use async_zip::tokio::read::seek::ZipFileReader;
+use futures::future::try_join_all;
+use log::info;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::BufReader;
+use tokio::process::Command;
+use tokio::sync::mpsc::Sender;
+
+use crate::downloaders::{MinecraftDownloader, record_extra_files};
+use crate::endpoints::endpoints;
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::launcher::{CLASSPATH_SEPARATOR, resolve_java};
+use crate::manifest::{resolve_version_data, resolve_version_from_file};
+use crate::maven::MavenCoordinate;
+use crate::transport::artifact_source;
+use crate::types::{DownloadProgress, MojangLibrary, NormalizedVersion};
+use crate::utilities::{
+    TempGuard, download_file, extract_zip, file_sha1, is_safe_relative_path, safe_join,
+};
+
+mod binpatch;
+mod legacy;
+
+use binpatch::{PatchOutput, apply_binpatches};
+use legacy::LegacyInstallProfile;
+
+fn install_error(message: impl Into<String>) -> ProtonError {
+    ProtonError::ForgeInstall(message.into())
+}
+
+/// `install_profile.json` de los instaladores de Forge 1.13+
+#[derive(Debug, Deserialize)]
+struct InstallProfile {
+    /// Id de la versión que se instala (`1.20.1-forge-47.2.0`)
+    version: String,
+    /// Ruta del JSON de versión dentro del instalador
+    json: String,
+    minecraft: String,
+    #[serde(default)]
+    data: HashMap<String, SidedValue>,
+    #[serde(default)]
+    processors: Vec<Processor>,
+    /// Dependencias de los procesadores
+    #[serde(default)]
+    libraries: Vec<MojangLibrary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SidedValue {
+    client: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Processor {
+    /// Lados en los que se ejecuta; vacío si en ambos
+    #[serde(default)]
+    sides: Vec<String>,
+    jar: String,
+    #[serde(default)]
+    classpath: Vec<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    /// Archivos que genera y su SHA-1, ambos con marcadores
+    #[serde(default)]
+    outputs: HashMap<String, String>,
+}
+
+/// Instala Forge a partir de su instalador oficial sin abrir su interfaz:
+/// instala la versión vanilla, extrae las librerías incluidas, ejecuta los
+/// procesadores y escribe `versions/<id>/<id>.json`. El parcheo binario del
+/// cliente (`binarypatcher`) lo intenta proton y, si el jar no coincide con el
+/// SHA-1 del perfil, lo repite el procesador de Java; el resto de procesadores
+/// (división y remapeo del jar) necesitan Java. Los instaladores anteriores
+/// a 1.13 no tienen procesadores: se usa su jar universal.
+#[derive(Debug, Clone)]
+pub struct ForgeInstaller {
+    game_dir: PathBuf,
+    minecraft_version: String,
+    forge_version: String,
+    installer_jar: Option<PathBuf>,
+    java_path: Option<PathBuf>,
+}
+
+impl ForgeInstaller {
+    pub fn new(
+        game_dir: impl Into<PathBuf>,
+        minecraft_version: impl Into<String>,
+        forge_version: impl Into<String>,
+    ) -> Self {
+        Self {
+            game_dir: game_dir.into(),
+            minecraft_version: minecraft_version.into(),
+            forge_version: forge_version.into(),
+            installer_jar: None,
+            java_path: None,
+        }
+    }
+
+    /// Usa un instalador ya descargado en lugar de bajarlo del maven de Forge
+    /// (`EndpointConfig::forge_maven_url`)
+    pub fn installer_jar(mut self, path: impl Into<PathBuf>) -> Self {
+        self.installer_jar = Some(path.into());
+        self
+    }
+
+    /// Java con el que ejecutar los procesadores; por defecto el de
+    /// `JAVA_HOME` o el del `PATH`
+    pub fn java_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.java_path = Some(path.into());
+        self
+    }
+
+    fn libraries_dir(&self) -> PathBuf {
+        self.game_dir.join("libraries")
+    }
+
+    /// Coordenada del instalador en el maven de Forge
+    pub fn installer_coordinate(&self) -> MavenCoordinate {
+        MavenCoordinate::new(
+            "net.minecraftforge",
+            "forge",
+            format!("{}-{}", self.minecraft_version, self.forge_version),
+        )
+        .with_classifier("installer")
+    }
+
+    async fn fetch_installer(&self) -> Result<PathBuf, ProtonError> {
+        if let Some(path) = &self.installer_jar {
+            return Ok(path.clone());
+        }
+        let coordinate = self.installer_coordinate();
+        let path = self.libraries_dir().join(coordinate.path());
+        let url = coordinate.url(&endpoints().forge_maven_url);
+        // Su `install_profile.json` decide qué procesadores se ejecutan: sin
+        // el hash publicado junto al jar no se abre
+        let sha1 = published_sha1(&url).await?;
+        download_file(url, &path, Some(&sha1), 0).await?;
+        Ok(path)
+    }
+
+    /// Instala Forge y devuelve su versión normalizada, lista para lanzar
+    pub async fn install(
+        &self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<NormalizedVersion, ProtonError> {
+        let installer = self.fetch_installer().await?;
+        let profile: Value =
+            serde_json::from_slice(&read_zip_entry(&installer, "install_profile.json").await?)
+                .map_err(|e| install_error(format!("invalid install_profile.json: {e}")))?;
+        // Antes de 1.13 (y en las primeras builds de 1.12.2) el perfil es otro
+        if profile.get("versionInfo").is_some() {
+            let profile: LegacyInstallProfile = serde_json::from_value(profile)
+                .map_err(|e| install_error(format!("invalid install_profile.json: {e}")))?;
+            return self.install_legacy(&installer, profile, progress_tx).await;
+        }
+        let profile: InstallProfile = serde_json::from_value(profile)
+            .map_err(|e| install_error(format!("invalid install_profile.json: {e}")))?;
+        let version_json = read_zip_entry(&installer, profile.json.trim_start_matches('/')).await?;
+        let id = profile.version.clone();
+        if id.contains('/') || !is_safe_relative_path(&id) {
+            return Err(ProtonError::UnsafePath(id));
+        }
+        // Los procesadores son programas Java: solo ven el disco local
+        if !file_system().is_local() {
+            return Err(install_error(
+                "processors can only run on the local file system".to_string(),
+            ));
+        }
+        info!("Installing {id} from {installer:?}");
+
+        // Los procesadores parten del jar vanilla
+        let vanilla = resolve_version_data(&profile.minecraft).await?;
+        MinecraftDownloader::new(self.game_dir.clone(), vanilla)
+            .download_all(progress_tx.clone())
+            .await?;
+
+        // Librerías incluidas en el instalador (`maven/`) y datos de los procesadores
+        extract_zip(&installer, &self.libraries_dir(), |name| {
+            name.strip_prefix("maven/")
+                .filter(|path| !path.is_empty() && !path.ends_with('/'))
+                .map(str::to_string)
+        })
+        .await?;
+        let work_dir = self
+            .game_dir
+            .join("temp")
+            .join(format!("forge-{}", uuid::Uuid::new_v4().simple()));
+        let _work_guard = TempGuard::dir(work_dir.clone());
+        extract_zip(&installer, &work_dir, |name| {
+            (name.starts_with("data/") && !name.ends_with('/')).then(|| name.to_string())
+        })
+        .await?;
+
+        self.download_libraries(&profile.libraries).await?;
+
+        let data = self.processor_data(&profile, &installer, &work_dir)?;
+        for processor in &profile.processors {
+            if processor.sides.is_empty() || processor.sides.iter().any(|side| side == "client") {
+                self.run_processor(processor, &data).await?;
+            }
+        }
+
+        // El JSON va al final: es lo que indica que la versión está instalada
+        let version_dir = self.game_dir.join("versions").join(&id);
+        let fs = file_system();
+        fs.create_dir_all(&version_dir).await?;
+        let json_path = version_dir.join(format!("{id}.json"));
+        fs.write(&json_path, &version_json).await?;
+        // El juego encuentra estos archivos por `--fml.*`, no por `libraries`
+        record_extra_files(
+            &self.game_dir,
+            &id,
+            &self.processor_outputs(&profile, &data)?,
+        )
+        .await?;
+
+        let version = resolve_version_from_file(&json_path).await?;
+        MinecraftDownloader::new(self.game_dir.clone(), version.clone())
+            .download_all(progress_tx)
+            .await?;
+        info!("Installed {id}");
+        Ok(version)
+    }
+
+    async fn download_libraries(&self, libraries: &[MojangLibrary]) -> Result<(), ProtonError> {
+        let libraries_dir = self.libraries_dir();
+        try_join_all(libraries.iter().filter_map(|library| {
+            let artifact = library.downloads.artifact.clone()?;
+            let libraries_dir = libraries_dir.clone();
+            Some(async move {
+                // Sin URL vienen dentro del instalador y ya están extraídas
+                let path = safe_join(&libraries_dir, &artifact.path)?;
+                download_file(artifact.url, &path, Some(&artifact.sha1), artifact.size).await
+            })
+        }))
+        .await?;
+        Ok(())
+    }
+
+    /// Valores de los marcadores `{CLAVE}` de los argumentos
+    fn processor_data(
+        &self,
+        profile: &InstallProfile,
+        installer: &Path,
+        work_dir: &Path,
+    ) -> Result<HashMap<String, String>, ProtonError> {
+        let minecraft = &profile.minecraft;
+        let path = |path: PathBuf| path.to_string_lossy().into_owned();
+        let mut data = HashMap::from([
+            ("SIDE".to_string(), "client".to_string()),
+            ("MINECRAFT_VERSION".to_string(), minecraft.clone()),
+            (
+                "MINECRAFT_JAR".to_string(),
+                path(
+                    self.game_dir
+                        .join("versions")
+                        .join(minecraft)
+                        .join(format!("{minecraft}.jar")),
+                ),
+            ),
+            ("ROOT".to_string(), path(self.game_dir.clone())),
+            ("INSTALLER".to_string(), path(installer.to_path_buf())),
+            ("LIBRARY_DIR".to_string(), path(self.libraries_dir())),
+        ]);
+
+        for (key, value) in &profile.data {
+            let value = &value.client;
+            let resolved = if let Some(literal) = quoted(value) {
+                literal.to_string()
+            } else if let Some(coordinate) = bracketed(value) {
+                path(self.library_path(coordinate)?)
+            } else if let Some(entry) = value.strip_prefix('/') {
+                path(safe_join(work_dir, entry)?)
+            } else {
+                value.clone()
+            };
+            data.insert(key.clone(), resolved);
+        }
+        Ok(data)
+    }
+
+    /// Librerías que generan los procesadores: las de `data` y sus `outputs`
+    fn processor_outputs(
+        &self,
+        profile: &InstallProfile,
+        data: &HashMap<String, String>,
+    ) -> Result<Vec<PathBuf>, ProtonError> {
+        let mut outputs = Vec::new();
+        for value in profile.data.values() {
+            if let Some(coordinate) = bracketed(&value.client) {
+                outputs.push(self.library_path(coordinate)?);
+            }
+        }
+        for processor in &profile.processors {
+            for path in processor.outputs.keys() {
+                outputs.push(PathBuf::from(self.resolve_argument(path, data)?));
+            }
+        }
+        Ok(outputs)
+    }
+
+    fn library_path(&self, coordinate: &str) -> Result<PathBuf, ProtonError> {
+        let coordinate: MavenCoordinate = coordinate.parse()?;
+        safe_join(&self.libraries_dir(), &coordinate.path())
+    }
+
+    /// Sustituye `[coordenada]` por la ruta de la librería y `{CLAVE}` por su valor
+    fn resolve_argument(
+        &self,
+        argument: &str,
+        data: &HashMap<String, String>,
+    ) -> Result<String, ProtonError> {
+        if let Some(coordinate) = bracketed(argument) {
+            return Ok(self
+                .library_path(coordinate)?
+                .to_string_lossy()
+                .into_owned());
+        }
+        if let Some(literal) = quoted(argument) {
+            return Ok(literal.to_string());
+        }
+
+        let mut resolved = String::new();
+        let mut rest = argument;
+        while let Some(start) = rest.find('{') {
+            resolved.push_str(&rest[..start]);
+            let (key, after) = rest[start + 1..]
+                .split_once('}')
+                .ok_or_else(|| install_error(format!("unclosed token in '{argument}'")))?;
+            let value = data
+                .get(key)
+                .ok_or_else(|| install_error(format!("unknown token {{{key}}}")))?;
+            resolved.push_str(value);
+            rest = after;
+        }
+        resolved.push_str(rest);
+        Ok(resolved)
+    }
+
+    /// Si los archivos que genera el procesador ya existen con su hash
+    async fn outputs_ready(
+        &self,
+        processor: &Processor,
+        data: &HashMap<String, String>,
+    ) -> Result<bool, ProtonError> {
+        Ok(!processor.outputs.is_empty() && self.check_outputs(processor, data).await?.is_none())
+    }
+
+    /// Compara los archivos de `outputs` con su SHA-1; devuelve el primero
+    /// que falta o no coincide
+    async fn check_outputs(
+        &self,
+        processor: &Processor,
+        data: &HashMap<String, String>,
+    ) -> Result<Option<String>, ProtonError> {
+        for (path, sha1) in &processor.outputs {
+            let path = PathBuf::from(self.resolve_argument(path, data)?);
+            let expected = self.resolve_argument(sha1, data)?;
+            if !file_system().exists(&path).await {
+                return Ok(Some(format!("did not produce {path:?}")));
+            }
+            let actual = file_sha1(&path).await?;
+            if !actual.eq_ignore_ascii_case(&expected) {
+                return Ok(Some(format!(
+                    "produced {path:?} with SHA-1 {actual}, expected {expected}"
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn run_processor(
+        &self,
+        processor: &Processor,
+        data: &HashMap<String, String>,
+    ) -> Result<(), ProtonError> {
+        if self.outputs_ready(processor, data).await? {
+            info!(
+                "Skipping processor {}, outputs are up to date",
+                processor.jar
+            );
+            return Ok(());
+        }
+        let args = processor
+            .args
+            .iter()
+            .map(|arg| self.resolve_argument(arg, data))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let coordinate: MavenCoordinate = processor.jar.parse()?;
+        if coordinate.group == "net.minecraftforge"
+            && coordinate.artifact == "binarypatcher"
+            && let Some(patch) = BinpatchArgs::parse(&args)
+        {
+            apply_binpatches(&patch.clean, &patch.apply, &patch.output, patch.include).await?;
+            // El jar no siempre es idéntico byte a byte al del procesador de
+            // Java: si no coincide con `outputs` se ejecuta el de Java
+            match self.check_outputs(processor, data).await? {
+                None => return Ok(()),
+                Some(mismatch) => info!(
+                    "Built-in binpatcher {mismatch}, running {} instead",
+                    processor.jar
+                ),
+            }
+        }
+
+        let jar = self.library_path(&processor.jar)?;
+        let main_class = main_class(&jar).await?;
+        let mut classpath = vec![jar.to_string_lossy().into_owned()];
+        for library in &processor.classpath {
+            classpath.push(self.library_path(library)?.to_string_lossy().into_owned());
+        }
+
+        info!("Running processor {}", processor.jar);
+        let output = Command::new(resolve_java(self.java_path.as_deref()))
+            .arg("-cp")
+            .arg(classpath.join(CLASSPATH_SEPARATOR))
+            .arg(&main_class)
+            .args(&args)
+            .current_dir(&self.game_dir)
+            .output()
+            .await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let last_lines: Vec<&str> = stderr.lines().rev().take(5).collect();
+            return Err(install_error(format!(
+                "processor {} exited with {}: {}",
+                processor.jar,
+                output.status,
+                last_lines.into_iter().rev().collect::<Vec<_>>().join("\n")
+            )));
+        }
+
+        if let Some(mismatch) = self.check_outputs(processor, data).await? {
+            return Err(install_error(format!(
+                "processor {} {mismatch}",
+                processor.jar
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Argumentos del modo `--apply` de binarypatcher
+struct BinpatchArgs {
+    clean: PathBuf,
+    apply: PathBuf,
+    output: PathBuf,
+    include: PatchOutput,
+}
+
+impl BinpatchArgs {
+    /// `None` si usa opciones que solo entiende el procesador de Java
+    fn parse(args: &[String]) -> Option<Self> {
+        let (mut clean, mut apply, mut output) = (None, None, None);
+        let mut include = PatchOutput::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--clean" => clean = Some(PathBuf::from(args.next()?)),
+                "--apply" => apply = Some(PathBuf::from(args.next()?)),
+                "--output" => output = Some(PathBuf::from(args.next()?)),
+                "--unpatched" => include.unpatched = true,
+                "--data" => include.data = true,
+                _ => return None,
+            }
+        }
+        Some(Self {
+            clean: clean?,
+            apply: apply?,
+            output: output?,
+            include,
+        })
+    }
+}
+
+/// SHA-1 que el maven publica en `<url>.sha1`
+async fn published_sha1(url: &str) -> Result<String, ProtonError> {
+    let sha1_url = format!("{url}.sha1");
+    let content = artifact_source().fetch(&sha1_url).await?;
+    String::from_utf8_lossy(&content)
+        .split_whitespace()
+        .next()
+        .filter(|sha1| sha1.len() == 40 && sha1.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(str::to_ascii_lowercase)
+        .ok_or_else(|| install_error(format!("invalid SHA-1 at {sha1_url}")))
+}
+
+fn bracketed(value: &str) -> Option<&str> {
+    value.strip_prefix('[')?.strip_suffix(']')
+}
+
+fn quoted(value: &str) -> Option<&str> {
+    value.strip_prefix('\'')?.strip_suffix('\'')
+}
+
+/// Contenido de una entrada de un zip
+async fn read_zip_entry(zip_path: &Path, name: &str) -> Result<Vec<u8>, ProtonError> {
+    let file = file_system().open(zip_path).await?;
+    let mut reader = ZipFileReader::with_tokio(BufReader::new(file)).await?;
+    let index = reader
+        .file()
+        .entries()
+        .iter()
+        .position(|entry| entry.filename().as_str().is_ok_and(|entry| entry == name))
+        .ok_or_else(|| install_error(format!("{name} not found in {zip_path:?}")))?;
+
+    let mut content = Vec::new();
+    reader
+        .reader_with_entry(index)
+        .await?
+        .read_to_end_checked(&mut content)
+        .await?;
+    Ok(content)
+}
+
+/// `Main-Class` del manifiesto de un jar
+async fn main_class(jar: &Path) -> Result<String, ProtonError> {
+    let manifest = read_zip_entry(jar, "META-INF/MANIFEST.MF").await?;
+    String::from_utf8_lossy(&manifest)
+        .lines()
+        .find_map(|line| line.strip_prefix("Main-Class:"))
+        .map(|class| class.trim().to_string())
+        .ok_or_else(|| install_error(format!("{jar:?} has no Main-Class")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::set_endpoints;
+    use crate::filesystem::{MemoryFs, with_file_system};
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment, sha1};
+    use async_zip::tokio::write::ZipFileWriter;
+    use async_zip::{Compression, ZipEntryBuilder};
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    pub(super) async fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipFileWriter::with_tokio(Vec::new());
+        for (name, content) in entries {
+            writer
+                .write_entry_whole(
+                    ZipEntryBuilder::new(name.to_string().into(), Compression::Stored),
+                    content,
+                )
+                .await
+                .unwrap();
+        }
+        writer.close().await.unwrap().into_inner()
+    }
+
+    fn binpatcher(output_sha1: Option<&str>) -> Processor {
+        Processor {
+            sides: Vec::new(),
+            jar: "net.minecraftforge:binarypatcher:1.0:fatjar".to_string(),
+            classpath: Vec::new(),
+            args: [
+                "--clean",
+                "{CLEAN}",
+                "--apply",
+                "{PATCHES}",
+                "--output",
+                "{OUTPUT}",
+            ]
+            .map(String::from)
+            .to_vec(),
+            outputs: output_sha1
+                .map(|sha1| HashMap::from([("{OUTPUT}".to_string(), format!("'{sha1}'"))]))
+                .unwrap_or_default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn binpatcher_output_is_verified_or_left_to_java() {
+        let fs = MemoryFs::new();
+        let mut patches = Vec::new();
+        lzma_rs::lzma_compress(&mut zip(&[]).await.as_slice(), &mut patches).unwrap();
+        let data = HashMap::from([
+            ("CLEAN".to_string(), "/game/clean.jar".to_string()),
+            ("PATCHES".to_string(), "/game/client.lzma".to_string()),
+            ("OUTPUT".to_string(), "/game/patched.jar".to_string()),
+        ]);
+        // Sin Java: si hiciera falta el procesador de Java, falla
+        let installer =
+            ForgeInstaller::new("/game", FIXTURE_VERSION, "1.0").java_path("/nonexistent/java");
+        let output = Path::new("/game/patched.jar");
+
+        let (matching, mismatched) = with_file_system(Arc::new(fs.clone()), async {
+            let fs = file_system();
+            fs.create_dir_all(Path::new("/game")).await?;
+            fs.write(
+                Path::new("/game/clean.jar"),
+                &zip(&[("a/A.class", b"A")]).await,
+            )
+            .await?;
+            fs.write(Path::new("/game/client.lzma"), &patches).await?;
+
+            installer.run_processor(&binpatcher(None), &data).await?;
+            let expected = sha1(&fs.read(output).await?);
+            fs.remove_file(output).await?;
+
+            let matching = installer
+                .run_processor(&binpatcher(Some(&expected)), &data)
+                .await;
+            let mismatched = installer
+                .run_processor(&binpatcher(Some(&"0".repeat(40))), &data)
+                .await;
+            Ok::<_, ProtonError>((matching, mismatched))
+        })
+        .await
+        .unwrap();
+
+        assert!(matching.is_ok());
+        assert!(fs.get(output).is_some());
+        assert!(mismatched.is_err());
+    }
+
+    #[tokio::test]
+    async fn installers_come_from_the_configured_forge_maven() {
+        let env = MockEnvironment::start().await;
+        let installer = zip(&[("install_profile.json", b"{}")]).await;
+        let jar_path = format!(
+            "/forge/net/minecraftforge/forge/{FIXTURE_VERSION}-1.0/forge-{FIXTURE_VERSION}-1.0-installer.jar"
+        );
+        for (file_path, body) in [
+            (format!("{jar_path}.sha1"), sha1(&installer).into_bytes()),
+            (jar_path, installer.clone()),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(file_path))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+                .expect(1)
+                .mount(env.server.server())
+                .await;
+        }
+        set_endpoints(endpoints().forge_maven_url(format!("{}/forge/", env.server.uri())));
+
+        let path = env
+            .run(ForgeInstaller::new(&env.game_dir, FIXTURE_VERSION, "1.0").fetch_installer())
+            .await
+            .unwrap();
+
+        assert_eq!(env.fs.get(&path), Some(installer));
+    }
+}
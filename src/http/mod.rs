@@ -0,0 +1,677 @@
+#[cfg(not(target_arch = "wasm32"))]
+use log::warn;
+use once_cell::sync::Lazy;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::{Certificate, Proxy, redirect};
+use reqwest::{Client, ClientBuilder};
+#[cfg(not(target_arch = "wasm32"))]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::net::SocketAddr;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))]
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::errors::ProtonError;
+
+const DEFAULT_USER_AGENT: &str = "Cubic Proton/1.0";
+
+#[cfg(not(any(target_arch = "wasm32", feature = "native-tls", feature = "rustls")))]
+compile_error!("proton needs a TLS backend: enable the `native-tls` or `rustls` feature");
+
+/// Familia de direcciones IP a usar al conectar
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpFamily {
+    /// Lo que devuelva el DNS, probando ambas familias
+    #[default]
+    Auto,
+    /// IPv4 primero, IPv6 como respaldo
+    PreferIpv4,
+    /// IPv6 primero, IPv4 como respaldo
+    PreferIpv6,
+    /// Solo IPv4; útil con redes de doble pila con IPv6 roto
+    Ipv4Only,
+    /// Solo IPv6
+    Ipv6Only,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl IpFamily {
+    /// Filtra y ordena las direcciones resueltas según la preferencia
+    fn arrange(self, addrs: impl Iterator<Item = SocketAddr>) -> Vec<SocketAddr> {
+        let mut addrs: Vec<SocketAddr> = match self {
+            IpFamily::Ipv4Only => addrs.filter(SocketAddr::is_ipv4).collect(),
+            IpFamily::Ipv6Only => addrs.filter(SocketAddr::is_ipv6).collect(),
+            _ => addrs.collect(),
+        };
+        // El conector prueba primero la familia de la primera dirección
+        match self {
+            IpFamily::PreferIpv4 => addrs.sort_by_key(|addr| addr.is_ipv6()),
+            IpFamily::PreferIpv6 => addrs.sort_by_key(|addr| addr.is_ipv4()),
+            _ => {}
+        }
+        addrs
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FromStr for IpFamily {
+    type Err = ProtonError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(IpFamily::Auto),
+            "prefer-ipv4" => Ok(IpFamily::PreferIpv4),
+            "prefer-ipv6" => Ok(IpFamily::PreferIpv6),
+            "ipv4-only" => Ok(IpFamily::Ipv4Only),
+            "ipv6-only" => Ok(IpFamily::Ipv6Only),
+            other => Err(ProtonError::InvalidConfig(format!(
+                "unknown IP family '{other}'"
+            ))),
+        }
+    }
+}
+
+// Resolución del sistema con las direcciones filtradas por familia
+#[cfg(not(target_arch = "wasm32"))]
+struct IpFamilyResolver(IpFamily);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Resolve for IpFamilyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let family = self.0;
+        Box::pin(async move {
+            let host = name.as_str();
+            let resolved = tokio::net::lookup_host((host, 0)).await?;
+            let addrs = family.arrange(resolved);
+            if addrs.is_empty() {
+                let message = format!("no address for {host} allowed by {family:?}");
+                return Err(std::io::Error::new(std::io::ErrorKind::NotFound, message).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Certificado raíz adicional en el que confiar (p. ej. la CA interna de un
+/// mirror corporativo)
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootCertificate {
+    /// Archivo PEM; puede contener varios certificados
+    PemFile(PathBuf),
+    Der(Vec<u8>),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RootCertificate {
+    fn load(&self) -> Result<Vec<Certificate>, ProtonError> {
+        let invalid = |source: String, e: &dyn std::fmt::Display| {
+            ProtonError::InvalidConfig(format!("certificate {source}: {e}"))
+        };
+        match self {
+            RootCertificate::PemFile(path) => {
+                let source = format!("'{}'", path.display());
+                let pem = std::fs::read(path).map_err(|e| invalid(source.clone(), &e))?;
+                let certificates =
+                    Certificate::from_pem_bundle(&pem).map_err(|e| invalid(source.clone(), &e))?;
+                if certificates.is_empty() {
+                    return Err(invalid(source, &"no certificates found"));
+                }
+                Ok(certificates)
+            }
+            RootCertificate::Der(der) => Certificate::from_der(der)
+                .map(|certificate| vec![certificate])
+                .map_err(|e| invalid("(DER)".to_string(), &e)),
+        }
+    }
+}
+
+/// Opciones del cliente HTTP compartido. Se aplican a las peticiones que
+/// empiecen después de fijarlas con `set_http_options`.
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    pub user_agent: String,
+    /// Cabeceras añadidas a todas las peticiones (p. ej. autenticación de un
+    /// mirror privado)
+    pub headers: Vec<(String, String)>,
+    /// Peticiones simultáneas como máximo contra un mismo host (con HTTP/1.1,
+    /// conexiones). `None` deja el límite al descargador.
+    pub max_connections_per_host: Option<usize>,
+    /// Usar HTTP/3 (QUIC) directamente, sin negociarlo antes
+    #[cfg(feature = "http3")]
+    pub http3: bool,
+    /// Certificados raíz añadidos a los del sistema
+    #[cfg(not(target_arch = "wasm32"))]
+    pub root_certificates: Vec<RootCertificate>,
+    /// Hosts a los que se conecta aunque su certificado no sea válido
+    /// (autofirmado, caducado...). Solo para mirrors propios.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub insecure_hosts: Vec<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub ip_family: IpFamily,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: Vec::new(),
+            max_connections_per_host: None,
+            #[cfg(feature = "http3")]
+            http3: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            root_certificates: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            insecure_hosts: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ip_family: IpFamily::Auto,
+        }
+    }
+}
+
+impl HttpOptions {
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Añade una cabecera; se valida en `set_http_options`
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    fn header_map(&self) -> Result<HeaderMap, ProtonError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| ProtonError::InvalidConfig(format!("header '{name}': {e}")))?;
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|e| ProtonError::InvalidConfig(format!("header '{name}': {e}")))?;
+            headers.append(header_name, header_value);
+        }
+        Ok(headers)
+    }
+
+    pub fn max_connections_per_host(mut self, limit: Option<usize>) -> Self {
+        self.max_connections_per_host = limit.filter(|&limit| limit > 0);
+        self
+    }
+
+    #[cfg(feature = "http3")]
+    pub fn http3(mut self, enabled: bool) -> Self {
+        self.http3 = enabled;
+        self
+    }
+
+    /// Confía en los certificados de un archivo PEM; se lee en `set_http_options`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn root_certificate_pem(mut self, path: impl Into<PathBuf>) -> Self {
+        self.root_certificates
+            .push(RootCertificate::PemFile(path.into()));
+        self
+    }
+
+    /// Confía en un certificado en formato DER
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn root_certificate_der(mut self, der: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates
+            .push(RootCertificate::Der(der.into()));
+        self
+    }
+
+    /// Acepta cualquier certificado de `host`. Desactiva la verificación TLS
+    /// para ese host, así que solo debe usarse con servidores de confianza.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs_for(mut self, host: impl Into<String>) -> Self {
+        self.insecure_hosts.push(host.into());
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn ip_family(mut self, family: IpFamily) -> Self {
+        self.ip_family = family;
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn certificates(&self) -> Result<Vec<Certificate>, ProtonError> {
+        let mut certificates = Vec::new();
+        for certificate in &self.root_certificates {
+            certificates.extend(certificate.load()?);
+        }
+        Ok(certificates)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_insecure_host(&self, host: &str) -> bool {
+        self.insecure_hosts
+            .iter()
+            .any(|insecure| insecure.eq_ignore_ascii_case(host))
+    }
+}
+
+static HTTP_OPTIONS: Lazy<RwLock<HttpOptions>> = Lazy::new(|| RwLock::new(HttpOptions::default()));
+
+// Proxy de los clientes HTTP globales
+#[cfg(not(target_arch = "wasm32"))]
+static PROXY: Lazy<RwLock<Option<Proxy>>> = Lazy::new(|| RwLock::new(None));
+
+// Clientes creados con las opciones actuales; se descartan al cambiarlas y
+// el siguiente uso los vuelve a crear
+static HTTP_CLIENT: RwLock<Option<Client>> = RwLock::new(None);
+
+// Cliente sin verificación de certificados, solo para `insecure_hosts`. Las
+// redirecciones solo se siguen dentro del mismo host: otro host se pediría
+// también sin verificar su certificado.
+#[cfg(not(target_arch = "wasm32"))]
+static INSECURE_HTTP_CLIENT: RwLock<Option<Client>> = RwLock::new(None);
+
+fn cached_client(
+    cache: &RwLock<Option<Client>>,
+    builder: fn() -> Result<ClientBuilder, ProtonError>,
+) -> Result<Client, ProtonError> {
+    if let Some(client) = cache.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return Ok(client.clone());
+    }
+    let mut cache = cache.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(client) = cache.as_ref() {
+        return Ok(client.clone());
+    }
+    // Un error (p. ej. el PEM ya no se puede leer) no se guarda: se reintenta
+    // en la siguiente petición
+    let client = builder()?.build()?;
+    Ok(cache.insert(client).clone())
+}
+
+fn http_client() -> Result<Client, ProtonError> {
+    cached_client(&HTTP_CLIENT, client_builder)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn insecure_http_client() -> Result<Client, ProtonError> {
+    cached_client(&INSECURE_HTTP_CLIENT, || {
+        Ok(client_builder()?
+            .danger_accept_invalid_certs(true)
+            .redirect(same_host_redirects()))
+    })
+}
+
+/// Descarta los clientes para que las peticiones siguientes usen las opciones nuevas
+fn reset_clients() {
+    *HTTP_CLIENT.write().unwrap_or_else(|e| e.into_inner()) = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        *INSECURE_HTTP_CLIENT
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+// Mismo límite de saltos que la política por defecto de reqwest
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_REDIRECTS: usize = 10;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn same_host_redirects() -> redirect::Policy {
+    redirect::Policy::custom(|attempt| {
+        let same_host =
+            attempt.previous().last().and_then(|url| url.host_str()) == attempt.url().host_str();
+        if !same_host {
+            attempt.stop()
+        } else if attempt.previous().len() > MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// Cliente para `url`: el compartido o, si el host está en `insecure_hosts`,
+/// uno que no verifica certificados
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn client_for(url: &str) -> Result<Client, ProtonError> {
+    let insecure = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.host_str()
+                .map(|host| http_options().is_insecure_host(host))
+        })
+        .unwrap_or(false);
+    if insecure {
+        insecure_http_client()
+    } else {
+        http_client()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn client_for(_url: &str) -> Result<Client, ProtonError> {
+    http_client()
+}
+
+fn client_builder() -> Result<ClientBuilder, ProtonError> {
+    let options = http_options();
+    let builder = Client::builder()
+        .user_agent(options.user_agent.as_str())
+        .default_headers(options.header_map()?);
+    // Con ambas features activas se prefiere rustls
+    #[cfg(all(feature = "rustls", not(target_arch = "wasm32")))]
+    let builder = builder.use_rustls_tls();
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = options
+        .certificates()?
+        .into_iter()
+        .fold(builder, |builder, certificate| {
+            builder.add_root_certificate(certificate)
+        });
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = match options.ip_family {
+        IpFamily::Auto => builder,
+        family => builder.dns_resolver(Arc::new(IpFamilyResolver(family))),
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = match PROXY.read().unwrap_or_else(|e| e.into_inner()).clone() {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder,
+    };
+    // Miles de assets pequeños contra el mismo host: una conexión HTTP/2
+    // reutilizada con ventana adaptativa evita abrir una por petición
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = builder
+        .http2_adaptive_window(true)
+        .http2_keep_alive_interval(Duration::from_secs(30))
+        .pool_max_idle_per_host(options.max_connections_per_host.unwrap_or(usize::MAX));
+    #[cfg(feature = "http3")]
+    let builder = if options.http3 {
+        builder.http3_prior_knowledge()
+    } else {
+        builder
+    };
+    Ok(builder)
+}
+
+/// Sustituye las opciones HTTP globales. Las peticiones que empiecen después
+/// usan un cliente nuevo con ellas; las que están en curso terminan con el
+/// anterior. Falla si alguna cabecera o certificado no es válido.
+pub fn set_http_options(options: HttpOptions) -> Result<(), ProtonError> {
+    options.header_map()?;
+    #[cfg(not(target_arch = "wasm32"))]
+    options.certificates()?;
+    #[cfg(not(target_arch = "wasm32"))]
+    for host in &options.insecure_hosts {
+        warn!("TLS certificate verification disabled for {host}");
+    }
+    *HTTP_OPTIONS.write().unwrap_or_else(|e| e.into_inner()) = options;
+    reset_clients();
+    HOST_LIMITS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+    Ok(())
+}
+
+/// Opciones HTTP globales actuales
+pub fn http_options() -> HttpOptions {
+    HTTP_OPTIONS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Proxy para las peticiones que empiecen a partir de ahora
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn set_proxy(proxy: Proxy) {
+    *PROXY.write().unwrap_or_else(|e| e.into_inner()) = Some(proxy);
+    reset_clients();
+}
+
+static HOST_LIMITS: Lazy<Mutex<HashMap<String, Arc<Semaphore>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Turno para una petición a `url` según `max_connections_per_host`; el
+/// permiso se libera al soltarlo
+pub(crate) async fn host_permit(url: &str) -> Option<OwnedSemaphorePermit> {
+    let limit = http_options().max_connections_per_host?;
+    let host = reqwest::Url::parse(url).ok()?.host_str()?.to_string();
+
+    let semaphore = Arc::clone(
+        HOST_LIMITS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(limit))),
+    );
+    semaphore.acquire_owned().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::exclusive;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Aplica `options` mientras dura `test` y restaura las anteriores
+    async fn with_options<T>(options: HttpOptions, test: impl Future<Output = T>) -> T {
+        let _guard = exclusive().await;
+        let previous = http_options();
+        set_http_options(options).unwrap();
+        let result = test.await;
+        set_http_options(previous).unwrap();
+        result
+    }
+
+    #[test]
+    fn client_build_errors_are_returned_and_not_cached() {
+        static BUILDS: AtomicUsize = AtomicUsize::new(0);
+        fn failing() -> Result<ClientBuilder, ProtonError> {
+            BUILDS.fetch_add(1, Ordering::SeqCst);
+            Err(ProtonError::InvalidConfig("certificate gone".to_string()))
+        }
+        fn working() -> Result<ClientBuilder, ProtonError> {
+            BUILDS.fetch_add(1, Ordering::SeqCst);
+            Ok(Client::builder())
+        }
+        let cache = RwLock::new(None);
+
+        let result = cached_client(&cache, failing);
+        assert!(matches!(result, Err(ProtonError::InvalidConfig(_))));
+        cached_client(&cache, working).unwrap();
+        cached_client(&cache, working).unwrap();
+
+        assert_eq!(BUILDS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn the_configured_tls_backend_builds_a_client() {
+        client_builder().unwrap().build().unwrap();
+        insecure_http_client().unwrap();
+    }
+
+    #[tokio::test]
+    async fn requests_to_a_host_wait_for_a_free_connection() {
+        let options = HttpOptions::default().max_connections_per_host(Some(1));
+        with_options(options, async {
+            let first = host_permit("https://a.example/one").await;
+            assert!(first.is_some());
+            // Otro host tiene su propio límite
+            assert!(host_permit("https://b.example/one").await.is_some());
+
+            let second = host_permit("https://a.example/two");
+            tokio::pin!(second);
+            let waited = tokio::time::timeout(Duration::from_millis(50), &mut second).await;
+            assert!(waited.is_err());
+            drop(first);
+            assert!(second.await.is_some());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn no_limit_means_no_permits() {
+        assert_eq!(
+            HttpOptions::default()
+                .max_connections_per_host(Some(0))
+                .max_connections_per_host,
+            None
+        );
+        with_options(HttpOptions::default(), async {
+            assert!(host_permit("https://a.example/").await.is_none());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn requests_carry_the_user_agent_and_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("user-agent", "TestLauncher/2.0"))
+            .and(header("authorization", "Bearer mirror-token"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let options = HttpOptions::default()
+            .user_agent("TestLauncher/2.0")
+            .header("Authorization", "Bearer mirror-token");
+
+        let status = with_options(options, async {
+            let url = format!("{}/resource", server.uri());
+            client_for(&url)
+                .unwrap()
+                .get(&url)
+                .send()
+                .await
+                .unwrap()
+                .status()
+        })
+        .await;
+
+        assert_eq!(status, 200);
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        for options in [
+            HttpOptions::default().header("Bad Header", "value"),
+            HttpOptions::default().header("X-Token", "line\nbreak"),
+        ] {
+            assert!(matches!(
+                set_http_options(options),
+                Err(ProtonError::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn unreadable_certificates_are_rejected() {
+        let pem = std::env::temp_dir().join(format!("proton-bad-{}.pem", std::process::id()));
+        std::fs::write(&pem, "-----BEGIN CERTIFICATE-----\nnot base64\n").unwrap();
+
+        for options in [
+            HttpOptions::default().root_certificate_pem("/nonexistent/ca.pem"),
+            HttpOptions::default().root_certificate_pem(&pem),
+            HttpOptions::default().root_certificate_der(b"not a certificate".to_vec()),
+        ] {
+            let result = set_http_options(options);
+            assert!(
+                matches!(&result, Err(ProtonError::InvalidConfig(message)) if message.starts_with("certificate")),
+                "{result:?}"
+            );
+        }
+        std::fs::remove_file(pem).unwrap();
+    }
+
+    #[test]
+    fn insecure_hosts_match_without_case() {
+        let options = HttpOptions::default().danger_accept_invalid_certs_for("Mirror.Local");
+
+        assert!(options.is_insecure_host("mirror.local"));
+        assert!(!options.is_insecure_host("evil.mirror.local"));
+    }
+
+    #[tokio::test]
+    async fn insecure_hosts_only_follow_redirects_to_themselves() {
+        let server = MockServer::start().await;
+        let port = server.address().port();
+        for (from, to) in [
+            ("/same", "/target".to_string()),
+            ("/other", format!("http://localhost:{port}/target")),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(from))
+                .respond_with(ResponseTemplate::new(302).insert_header("location", to.as_str()))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/target"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let options = HttpOptions::default().danger_accept_invalid_certs_for("127.0.0.1");
+
+        let statuses = with_options(options, async {
+            let mut statuses = Vec::new();
+            for from in ["/same", "/other"] {
+                let url = format!("http://127.0.0.1:{port}{from}");
+                let response = client_for(&url).unwrap().get(&url).send().await.unwrap();
+                statuses.push(response.status().as_u16());
+            }
+            statuses
+        })
+        .await;
+
+        assert_eq!(statuses, [200, 302]);
+    }
+
+    #[test]
+    fn ip_families_filter_and_order_addresses() {
+        let v4: SocketAddr = "192.0.2.1:443".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::1]:443".parse().unwrap();
+        let arrange = |family: IpFamily| family.arrange([v6, v4].into_iter());
+
+        assert_eq!(arrange(IpFamily::Auto), [v6, v4]);
+        assert_eq!(arrange(IpFamily::PreferIpv4), [v4, v6]);
+        assert_eq!(IpFamily::PreferIpv6.arrange([v4, v6].into_iter()), [v6, v4]);
+        assert_eq!(arrange(IpFamily::Ipv4Only), [v4]);
+        assert_eq!(arrange(IpFamily::Ipv6Only), [v6]);
+    }
+
+    #[test]
+    fn ip_families_parse_from_config_names() {
+        assert_eq!(
+            "prefer-ipv6".parse::<IpFamily>().unwrap(),
+            IpFamily::PreferIpv6
+        );
+        assert_eq!("ipv4-only".parse::<IpFamily>().unwrap(), IpFamily::Ipv4Only);
+        assert!(matches!(
+            "ipv4".parse::<IpFamily>(),
+            Err(ProtonError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn the_resolver_fails_without_an_allowed_address() {
+        let resolve =
+            |family| IpFamilyResolver(family).resolve(Name::from_str("127.0.0.1").unwrap());
+
+        let addrs: Vec<_> = resolve(IpFamily::Ipv4Only).await.unwrap().collect();
+        assert!(addrs.iter().all(SocketAddr::is_ipv4) && !addrs.is_empty());
+        let error = resolve(IpFamily::Ipv6Only).await.err().unwrap();
+        assert!(error.to_string().contains("no address for 127.0.0.1"));
+    }
+}
@@ -0,0 +1,281 @@
+use async_zip::tokio::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio_util::compat::FuturesAsyncWriteCompatExt;
+
+use super::{INSTANCE_FILE, Instance, InstanceConfig};
+use crate::errors::ProtonError;
+use crate::filesystem::{FileSystem, FileWriter, file_system};
+use crate::utilities::extract_zip;
+
+// Carpeta del juego dentro del archivo exportado
+const GAME_DIR_ENTRY: &str = ".minecraft";
+
+// Contenido que nunca se exporta: se regenera o se vuelve a descargar
+const ALWAYS_EXCLUDED: [&str; 6] = [
+    "logs",
+    "crash-reports",
+    "natives",
+    "versions",
+    "libraries",
+    "assets",
+];
+
+/// Qué contenido de la instancia incluir al exportarla
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub include_saves: bool,
+    pub include_mods: bool,
+    pub include_configs: bool,
+    /// Rutas relativas al directorio del juego que se omiten
+    pub exclude: Vec<String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            include_saves: true,
+            include_mods: true,
+            include_configs: true,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl ExportOptions {
+    pub fn saves(mut self, include: bool) -> Self {
+        self.include_saves = include;
+        self
+    }
+
+    pub fn mods(mut self, include: bool) -> Self {
+        self.include_mods = include;
+        self
+    }
+
+    pub fn configs(mut self, include: bool) -> Self {
+        self.include_configs = include;
+        self
+    }
+
+    pub fn exclude(mut self, path: impl Into<String>) -> Self {
+        self.exclude.push(path.into());
+        self
+    }
+
+    fn includes(&self, relative: &str) -> bool {
+        let top = relative.split('/').next().unwrap_or(relative);
+        let skipped = ALWAYS_EXCLUDED.contains(&top)
+            || (!self.include_saves && top == "saves")
+            || (!self.include_mods && top == "mods")
+            || (!self.include_configs && (top == "config" || top == "defaultconfigs"));
+
+        !skipped
+            && !self.exclude.iter().any(|prefix| {
+                let prefix = prefix.trim_end_matches('/');
+                relative == prefix || relative.starts_with(&format!("{prefix}/"))
+            })
+    }
+}
+
+/// Configuración sin lo que solo vale en la máquina que la creó: rutas
+/// absolutas, la cuenta y el historial de partidas
+fn portable_config(config: &InstanceConfig) -> InstanceConfig {
+    InstanceConfig {
+        game_dir: None,
+        java_path: None,
+        account: None,
+        last_played: None,
+        playtime_secs: 0,
+        sessions: Vec::new(),
+        ..config.clone()
+    }
+}
+
+impl Instance {
+    /// Empaqueta la instancia en un zip portable con su `instance.json` y el
+    /// contenido seleccionado del directorio del juego. El `instance.json`
+    /// exportado no incluye el directorio del juego, Java, la cuenta ni el
+    /// historial de partidas.
+    pub async fn export(
+        &self,
+        zip_path: &Path,
+        options: &ExportOptions,
+    ) -> Result<(), ProtonError> {
+        self.save().await?;
+
+        let fs = file_system();
+        if let Some(parent) = zip_path.parent() {
+            fs.create_dir_all(parent).await?;
+        }
+        let mut writer = ZipFileWriter::with_tokio(fs.create(zip_path).await?);
+
+        let config = serde_json::to_vec_pretty(&portable_config(&self.config))
+            .map_err(|e| ProtonError::Other(format!("Failed to serialize instance: {e}")))?;
+        write_bytes(&mut writer, &config, INSTANCE_FILE).await?;
+
+        let game_dir = self.game_dir();
+        let mut pending = vec![(game_dir, String::new())];
+        while let Some((dir, relative_dir)) = pending.pop() {
+            let Ok(entries) = fs.read_dir(&dir).await else {
+                continue;
+            };
+
+            for path in entries {
+                let Some(name) = path.file_name() else {
+                    continue;
+                };
+                let name = name.to_string_lossy().into_owned();
+                let relative = if relative_dir.is_empty() {
+                    name
+                } else {
+                    format!("{relative_dir}/{name}")
+                };
+                if !options.includes(&relative) {
+                    continue;
+                }
+
+                if fs.metadata(&path).await?.is_dir {
+                    pending.push((path, relative));
+                } else {
+                    let entry_name = format!("{GAME_DIR_ENTRY}/{relative}");
+                    write_file(fs.as_ref(), &mut writer, &path, &entry_name).await?;
+                }
+            }
+        }
+
+        writer.close().await?.into_inner().shutdown().await?;
+        Ok(())
+    }
+
+    /// Recrea en `dir` una instancia exportada con `export` e instala en
+    /// `game_path` la versión, su mod loader y las librerías y assets que
+    /// falten. El contenido del juego va a `<dir>/.minecraft` aunque el
+    /// archivo traiga otro directorio del juego.
+    pub async fn import(
+        zip_path: &Path,
+        dir: impl Into<PathBuf>,
+        game_path: PathBuf,
+    ) -> Result<Instance, ProtonError> {
+        let dir = dir.into();
+        if file_system().exists(&dir.join(INSTANCE_FILE)).await {
+            return Err(ProtonError::InvalidInstance(format!(
+                "{dir:?} already contains an instance"
+            )));
+        }
+
+        extract_zip(zip_path, &dir, |name| {
+            (!name.ends_with('/')).then(|| name.to_string())
+        })
+        .await?;
+
+        // Los archivos de otras versiones de proton pueden traer rutas ajenas
+        let mut instance = Instance::load(dir).await?;
+        instance.config = portable_config(&instance.config);
+        instance.save().await?;
+        instance.install(game_path, None).await?;
+        Ok(instance)
+    }
+}
+
+async fn write_bytes(
+    writer: &mut ZipFileWriter<FileWriter>,
+    data: &[u8],
+    entry_name: &str,
+) -> Result<(), ProtonError> {
+    let builder = ZipEntryBuilder::new(entry_name.to_string().into(), Compression::Deflate);
+    writer.write_entry_whole(builder, data).await?;
+    Ok(())
+}
+
+async fn write_file(
+    fs: &dyn FileSystem,
+    writer: &mut ZipFileWriter<FileWriter>,
+    path: &Path,
+    entry_name: &str,
+) -> Result<(), ProtonError> {
+    let mut file = fs.open(path).await?;
+    let mut builder = ZipEntryBuilder::new(entry_name.to_string().into(), Compression::Deflate);
+
+    // Solo el disco local tiene permisos que conservar
+    #[cfg(unix)]
+    if fs.is_local() {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = tokio::fs::metadata(path).await?.permissions().mode();
+        builder = builder.unix_permissions((mode & 0o777) as u16);
+    }
+
+    let mut entry_writer = writer.write_entry_stream(builder).await?;
+    tokio::io::copy(&mut file, &mut (&mut entry_writer).compat_write()).await?;
+    entry_writer.close().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::with_file_system;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn import_recreates_the_exported_instance() {
+        let env = MockEnvironment::start().await;
+        let (fs, game_path) = (&env.fs, &env.game_dir);
+        let zip_path = Path::new("/exports/shared.zip");
+
+        let imported = with_file_system(Arc::new(fs.clone()), async {
+            let mut config = InstanceConfig::new("Shared", FIXTURE_VERSION)
+                .java_path("/opt/java/bin/java")
+                .memory(None, Some(4096));
+            config.account = Some("steve".to_string());
+            config.playtime_secs = 3600;
+            let instance = Instance::create("/instances/shared", config).await?;
+
+            let game_dir = instance.game_dir();
+            for file in [
+                "saves/world/level.dat",
+                "mods/sodium.jar",
+                "config/sodium.json",
+                "logs/latest.log",
+                "options.txt",
+            ] {
+                let path = game_dir.join(file);
+                file_system().create_dir_all(path.parent().unwrap()).await?;
+                file_system().write(&path, file.as_bytes()).await?;
+            }
+
+            let options = ExportOptions::default().saves(false).exclude("options.txt");
+            instance.export(zip_path, &options).await?;
+            Instance::import(zip_path, "/instances/copy", game_path.clone()).await
+        })
+        .await
+        .unwrap();
+
+        let game_dir = imported.game_dir();
+        assert_eq!(game_dir, Path::new("/instances/copy/.minecraft"));
+        assert_eq!(
+            fs.get(game_dir.join("mods/sodium.jar")).as_deref(),
+            Some(&b"mods/sodium.jar"[..])
+        );
+        assert!(fs.get(game_dir.join("config/sodium.json")).is_some());
+        for skipped in ["saves/world/level.dat", "logs/latest.log", "options.txt"] {
+            assert!(fs.get(game_dir.join(skipped)).is_none(), "{skipped}");
+        }
+
+        // Solo viaja lo que no depende de la máquina
+        assert_eq!(imported.name(), "Shared");
+        assert_eq!(imported.memory(), (None, Some(4096)));
+        assert_eq!(imported.java_path(), None);
+        assert_eq!(imported.account(), None);
+        assert_eq!(imported.playtime(), std::time::Duration::ZERO);
+
+        // La versión se instaló con el resto del pipeline
+        let client = game_path
+            .join("versions")
+            .join(FIXTURE_VERSION)
+            .join(format!("{FIXTURE_VERSION}.jar"));
+        assert!(fs.get(client).is_some());
+    }
+}
@@ -0,0 +1,963 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::Sender;
+
+use crate::downloaders::{MinecraftDownloader, diff_from_disk, diff_versions};
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::forge::ForgeInstaller;
+use crate::launcher::{GameExit, GameProcess, LaunchCommand};
+use crate::liteloader::LiteLoaderInstaller;
+use crate::manifest::{
+    LATEST_RELEASE, LATEST_SNAPSHOT, resolve_version_data, resolve_version_from_file,
+};
+use crate::plan::VersionDelta;
+use crate::types::{DownloadProgress, NormalizedVersion};
+use crate::utilities::{TempGuard, is_safe_relative_path, move_file, temp_path};
+
+mod archive;
+mod prism;
+mod vanilla;
+
+pub use archive::ExportOptions;
+pub use vanilla::LauncherProfilesImport;
+
+/// Nombre del archivo de configuración dentro de la carpeta de la instancia
+pub const INSTANCE_FILE: &str = "instance.json";
+
+// Sesiones conservadas en el historial de cada instancia
+const MAX_SESSIONS: usize = 100;
+
+// Versión actual del formato de `instance.json`
+const INSTANCE_FORMAT: u32 = 1;
+
+// Claves usadas por versiones antiguas del archivo y su equivalente actual
+const LEGACY_KEYS: [(&str, &str); 3] = [
+    ("gameVersion", "version"),
+    ("minecraft_version", "version"),
+    ("javaArgs", "jvm_args"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModLoader {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+    LiteLoader,
+}
+
+/// Mod loader instalado en la instancia
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceLoader {
+    pub kind: ModLoader,
+    pub version: String,
+}
+
+/// Partida registrada, en segundos desde UNIX_EPOCH
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaySession {
+    pub started: u64,
+    pub ended: u64,
+}
+
+impl PlaySession {
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.ended.saturating_sub(self.started))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Contenido de `instance.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceConfig {
+    #[serde(default)]
+    pub format: u32,
+    pub name: String,
+    /// Id de versión, o `latest-release`/`latest-snapshot` para seguir la última
+    pub version: String,
+    /// Versión concreta instalada por `Instance::install` o `update_if_outdated` cuando
+    /// `version` sigue la última
+    #[serde(default)]
+    pub installed_version: Option<String>,
+    #[serde(default)]
+    pub loader: Option<InstanceLoader>,
+    /// Id de `versions/<id>` que instaló el loader; es el que se lanza
+    #[serde(default)]
+    pub loader_version_id: Option<String>,
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    /// Directorio del juego externo; por defecto `<instancia>/.minecraft`
+    #[serde(default)]
+    pub game_dir: Option<PathBuf>,
+    /// Ejecutable de Java preferido; si falta se usa el del sistema
+    #[serde(default)]
+    pub java_path: Option<PathBuf>,
+    #[serde(default)]
+    pub min_memory_mb: Option<u32>,
+    #[serde(default)]
+    pub max_memory_mb: Option<u32>,
+    #[serde(default)]
+    pub resolution: Option<Resolution>,
+    /// Cuenta a usar en lugar de la seleccionada en el launcher
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Segundos desde UNIX_EPOCH de la última partida
+    #[serde(default)]
+    pub last_played: Option<u64>,
+    /// Tiempo total de juego en segundos
+    #[serde(default)]
+    pub playtime_secs: u64,
+    /// Últimas partidas, de la más antigua a la más reciente
+    #[serde(default)]
+    pub sessions: Vec<PlaySession>,
+    /// Campos desconocidos: se conservan para no perder datos de otras versiones
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl InstanceConfig {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            format: INSTANCE_FORMAT,
+            name: name.into(),
+            version: version.into(),
+            installed_version: None,
+            loader: None,
+            loader_version_id: None,
+            jvm_args: Vec::new(),
+            game_dir: None,
+            java_path: None,
+            min_memory_mb: None,
+            max_memory_mb: None,
+            resolution: None,
+            account: None,
+            last_played: None,
+            playtime_secs: 0,
+            sessions: Vec::new(),
+            extra: Map::new(),
+        }
+    }
+
+    /// Si `version` es `latest-release` o `latest-snapshot`
+    pub fn tracks_latest(&self) -> bool {
+        matches!(self.version.as_str(), LATEST_RELEASE | LATEST_SNAPSHOT)
+    }
+
+    pub fn loader(mut self, kind: ModLoader, version: impl Into<String>) -> Self {
+        self.loader = Some(InstanceLoader {
+            kind,
+            version: version.into(),
+        });
+        self
+    }
+
+    pub fn memory(mut self, min_mb: Option<u32>, max_mb: Option<u32>) -> Self {
+        self.min_memory_mb = min_mb;
+        self.max_memory_mb = max_mb;
+        self
+    }
+
+    pub fn jvm_arg(mut self, arg: impl Into<String>) -> Self {
+        self.jvm_args.push(arg.into());
+        self
+    }
+
+    pub fn java_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.java_path = Some(path.into());
+        self
+    }
+
+    pub fn resolution(mut self, width: u32, height: u32) -> Self {
+        self.resolution = Some(Resolution { width, height });
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ProtonError> {
+        let invalid = |msg: &str| Err(ProtonError::InvalidInstance(msg.to_string()));
+
+        if self.name.trim().is_empty() {
+            return invalid("name is empty");
+        }
+        if self.version.trim().is_empty() {
+            return invalid("version is empty");
+        }
+        if let Some(loader) = &self.loader
+            && loader.version.trim().is_empty()
+        {
+            return invalid("loader version is empty");
+        }
+        if self.min_memory_mb == Some(0) || self.max_memory_mb == Some(0) {
+            return invalid("memory must be greater than zero");
+        }
+        if let (Some(min), Some(max)) = (self.min_memory_mb, self.max_memory_mb)
+            && min > max
+        {
+            return invalid("minimum memory is greater than maximum memory");
+        }
+        if let Some(resolution) = self.resolution
+            && (resolution.width == 0 || resolution.height == 0)
+        {
+            return invalid("resolution must be greater than zero");
+        }
+
+        Ok(())
+    }
+}
+
+/// Convierte un `instance.json` antiguo al formato actual
+fn migrate(mut raw: Map<String, Value>, path: &Path) -> Map<String, Value> {
+    let format = raw.get("format").and_then(Value::as_u64).unwrap_or(0);
+
+    if format > u64::from(INSTANCE_FORMAT) {
+        warn!("{path:?} uses a newer format ({format}), unknown fields will be kept as-is");
+        return raw;
+    }
+
+    for (old, new) in LEGACY_KEYS {
+        if let Some(value) = raw.remove(old) {
+            raw.entry(new).or_insert(value);
+        }
+    }
+    raw.insert("format".to_string(), Value::from(INSTANCE_FORMAT));
+    raw
+}
+
+/// Divide una línea de argumentos respetando comillas simples y dobles
+pub(crate) fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_arg = false;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+/// Copia recursivamente un directorio, sin sobrescribir archivos existentes
+pub(crate) async fn copy_dir(source: &Path, target: &Path) -> Result<(), ProtonError> {
+    let fs = file_system();
+    let mut pending = vec![(source.to_path_buf(), target.to_path_buf())];
+
+    while let Some((from, to)) = pending.pop() {
+        fs.create_dir_all(&to).await?;
+
+        for path in fs.read_dir(&from).await? {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let destination = to.join(name);
+            if fs.metadata(&path).await?.is_dir {
+                pending.push((path, destination));
+            } else if !fs.exists(&destination).await {
+                fs.copy(&path, &destination).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Instancia del juego: una carpeta con su `instance.json` y sus archivos
+#[derive(Debug, Clone)]
+pub struct Instance {
+    dir: PathBuf,
+    config: InstanceConfig,
+}
+
+impl Instance {
+    /// Crea la carpeta de la instancia y escribe su configuración
+    pub async fn create(
+        dir: impl Into<PathBuf>,
+        config: InstanceConfig,
+    ) -> Result<Self, ProtonError> {
+        let instance = Self {
+            dir: dir.into(),
+            config,
+        };
+        file_system().create_dir_all(&instance.game_dir()).await?;
+        instance.save().await?;
+        Ok(instance)
+    }
+
+    /// Carga y valida el `instance.json` de una carpeta
+    pub async fn load(dir: impl Into<PathBuf>) -> Result<Self, ProtonError> {
+        let dir = dir.into();
+        let path = dir.join(INSTANCE_FILE);
+        let content = file_system().read(&path).await?;
+
+        let raw: Map<String, Value> = serde_json::from_slice(&content)
+            .map_err(|e| ProtonError::InvalidInstance(format!("{path:?}: {e}")))?;
+        let config: InstanceConfig = serde_json::from_value(Value::Object(migrate(raw, &path)))
+            .map_err(|e| ProtonError::InvalidInstance(format!("{path:?}: {e}")))?;
+        config.validate()?;
+
+        Ok(Self { dir, config })
+    }
+
+    /// Guarda la configuración de forma atómica
+    pub async fn save(&self) -> Result<(), ProtonError> {
+        self.config.validate()?;
+        let fs = file_system();
+        fs.create_dir_all(&self.dir).await?;
+
+        let path = self.config_path();
+        let json = serde_json::to_vec_pretty(&self.config)
+            .map_err(|e| ProtonError::Other(format!("Failed to serialize instance: {e}")))?;
+
+        let temp = temp_path(&path);
+        let mut guard = TempGuard::file(temp.clone());
+        fs.write(&temp, &json).await?;
+        move_file(&temp, &path).await?;
+        guard.disarm();
+
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.dir.join(INSTANCE_FILE)
+    }
+
+    /// Directorio del juego (saves, mods, config...) de la instancia
+    pub fn game_dir(&self) -> PathBuf {
+        self.config
+            .game_dir
+            .clone()
+            .unwrap_or_else(|| self.dir.join(".minecraft"))
+    }
+
+    pub fn config(&self) -> &InstanceConfig {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut InstanceConfig {
+        &mut self.config
+    }
+
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.config.version
+    }
+
+    /// Versión del juego base: la instalada si la instancia sigue la última y
+    /// ya se instaló, si no `version()`
+    pub fn resolved_version(&self) -> &str {
+        match &self.config.installed_version {
+            Some(installed) if self.config.tracks_latest() => installed,
+            _ => &self.config.version,
+        }
+    }
+
+    pub fn loader(&self) -> Option<&InstanceLoader> {
+        self.config.loader.as_ref()
+    }
+
+    pub fn jvm_args(&self) -> &[String] {
+        &self.config.jvm_args
+    }
+
+    pub fn java_path(&self) -> Option<&Path> {
+        self.config.java_path.as_deref()
+    }
+
+    /// Memoria mínima y máxima en MB
+    pub fn memory(&self) -> (Option<u32>, Option<u32>) {
+        (self.config.min_memory_mb, self.config.max_memory_mb)
+    }
+
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.config.resolution
+    }
+
+    pub fn account(&self) -> Option<&str> {
+        self.config.account.as_deref()
+    }
+
+    pub fn last_played(&self) -> Option<SystemTime> {
+        self.config
+            .last_played
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Tiempo total jugado en esta instancia
+    pub fn playtime(&self) -> Duration {
+        Duration::from_secs(self.config.playtime_secs)
+    }
+
+    pub fn sessions(&self) -> &[PlaySession] {
+        &self.config.sessions
+    }
+
+    /// Descarga en `game_path` lo que falte de la versión de la instancia. Forge
+    /// y LiteLoader se instalan con sus instaladores; con el resto de loaders
+    /// solo se instala el juego base. Si la instancia sigue la última versión y
+    /// aún no tiene ninguna instalada, fija y guarda la versión concreta.
+    pub async fn install(
+        &mut self,
+        game_path: PathBuf,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<(), ProtonError> {
+        let minecraft = match self.resolved_version() {
+            LATEST_RELEASE | LATEST_SNAPSHOT => resolve_version_data(self.version()).await?.id,
+            id => id.to_string(),
+        };
+        let loader_id = self
+            .install_version(game_path, &minecraft, None, progress_tx)
+            .await?;
+
+        let mut changed = self.record_loader_version(loader_id);
+        if self.config.tracks_latest() && self.config.installed_version.is_none() {
+            self.config.installed_version = Some(minecraft);
+            changed = true;
+        }
+        if changed {
+            self.save().await?;
+        }
+        Ok(())
+    }
+
+    /// `install` de `minecraft`. Con `delta` el juego base solo descarga
+    /// `delta.added`; los instaladores de loaders instalan su versión entera.
+    /// Devuelve el id de la versión que instaló el loader, si lo hay.
+    async fn install_version(
+        &self,
+        game_path: PathBuf,
+        minecraft: &str,
+        delta: Option<&VersionDelta>,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<Option<String>, ProtonError> {
+        match self.loader() {
+            Some(loader) if loader.kind == ModLoader::Forge => {
+                let mut installer = ForgeInstaller::new(game_path, minecraft, &loader.version);
+                if let Some(java) = self.java_path() {
+                    installer = installer.java_path(java);
+                }
+                return Ok(Some(Box::pin(installer.install(progress_tx)).await?.id));
+            }
+            Some(loader) if loader.kind == ModLoader::LiteLoader => {
+                let mut installer = LiteLoaderInstaller::new(game_path, minecraft);
+                // Importadas del launcher oficial solo traen la versión del juego
+                if loader.version != minecraft {
+                    installer = installer.version(&loader.version);
+                }
+                return Ok(Some(Box::pin(installer.install(progress_tx)).await?.id));
+            }
+            Some(loader) => warn!(
+                "Instance {} uses {:?} {}, only the base game will be installed",
+                self.name(),
+                loader.kind,
+                loader.version
+            ),
+            None => {}
+        }
+
+        let version = resolve_version_data(minecraft).await?;
+        let mut downloader = MinecraftDownloader::new(game_path, version);
+        if let Some(delta) = delta {
+            downloader = downloader.only(&delta.added);
+        }
+        downloader.download_all(progress_tx).await?;
+        Ok(None)
+    }
+
+    /// Guarda en la configuración el id instalado por el loader; devuelve si cambió
+    fn record_loader_version(&mut self, loader_id: Option<String>) -> bool {
+        match loader_id {
+            Some(id) if self.config.loader_version_id.as_ref() != Some(&id) => {
+                self.config.loader_version_id = Some(id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Versión normalizada a lanzar desde `game_path`: con loader, la que este
+    /// instaló en `versions/<id>/<id>.json`; sin él, la del juego base. Fabric
+    /// y Quilt no se instalan desde aquí: si no están en disco se lanza el
+    /// juego base.
+    pub async fn launch_version(&self, game_path: &Path) -> Result<NormalizedVersion, ProtonError> {
+        let Some(loader) = self.loader() else {
+            return resolve_version_data(self.resolved_version()).await;
+        };
+
+        let versions_dir = game_path.join("versions");
+        for id in self.loader_version_ids(loader) {
+            if id.contains('/') || !is_safe_relative_path(&id) {
+                continue;
+            }
+            let json_path = versions_dir.join(&id).join(format!("{id}.json"));
+            if file_system().exists(&json_path).await {
+                return resolve_version_from_file(&json_path).await;
+            }
+        }
+
+        match loader.kind {
+            ModLoader::Fabric | ModLoader::Quilt => {
+                warn!(
+                    "{:?} {} is not installed in {game_path:?}, launching the base game",
+                    loader.kind, loader.version
+                );
+                resolve_version_data(self.resolved_version()).await
+            }
+            _ => Err(ProtonError::InvalidInstance(format!(
+                "{:?} {} is not installed in {game_path:?}; install the instance first",
+                loader.kind, loader.version
+            ))),
+        }
+    }
+
+    /// Ids posibles de la versión del loader: el guardado al instalar y, para
+    /// instancias importadas, los que usan los instaladores oficiales
+    fn loader_version_ids(&self, loader: &InstanceLoader) -> Vec<String> {
+        let minecraft = self.resolved_version();
+        let version = &loader.version;
+        let mut ids: Vec<String> = self.config.loader_version_id.iter().cloned().collect();
+        ids.extend(match loader.kind {
+            ModLoader::Forge => vec![
+                format!("{minecraft}-forge-{version}"),
+                format!("{minecraft}-forge{minecraft}-{version}"),
+                format!("{minecraft}-Forge{version}"),
+                format!("{minecraft}-Forge{version}-{minecraft}"),
+            ],
+            ModLoader::NeoForge => vec![format!("neoforge-{version}")],
+            ModLoader::LiteLoader => vec![format!("{minecraft}-LiteLoader{minecraft}")],
+            ModLoader::Fabric => vec![format!("fabric-loader-{version}-{minecraft}")],
+            ModLoader::Quilt => vec![format!("quilt-loader-{version}-{minecraft}")],
+        });
+        ids
+    }
+
+    /// Si la instancia sigue `latest-release` o `latest-snapshot` y hay una
+    /// versión más nueva que la instalada, descarga lo que cambia en
+    /// `game_path` (con el loader de la instancia, como `install`) y guarda la
+    /// nueva versión. Solo se añaden archivos: saves, opciones y mods no se
+    /// tocan, y los de la versión anterior quedan para `gc`.
+    /// Devuelve `None` si no había nada que actualizar.
+    pub async fn update_if_outdated(
+        &mut self,
+        game_path: PathBuf,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<Option<VersionDelta>, ProtonError> {
+        if !self.config.tracks_latest() {
+            return Ok(None);
+        }
+
+        let target = resolve_version_data(self.version()).await?;
+        if self.config.installed_version.as_deref() == Some(target.id.as_str()) {
+            return Ok(None);
+        }
+        // Las builds de estos loaders son de una versión concreta del juego
+        if let Some(loader) = self.loader()
+            && matches!(
+                loader.kind,
+                ModLoader::Forge | ModLoader::NeoForge | ModLoader::LiteLoader
+            )
+        {
+            return Err(ProtonError::InvalidInstance(format!(
+                "instance {} uses {:?} {}, which does not support Minecraft {}; \
+                 set a loader version for it before updating",
+                self.name(),
+                loader.kind,
+                loader.version,
+                target.id
+            )));
+        }
+
+        let delta = match self.config.installed_version.as_deref() {
+            Some(id) => {
+                let installed = resolve_version_data(id).await?;
+                diff_versions(&game_path, &installed, &target).await?
+            }
+            // Sin registro de lo instalado solo se puede comparar con el disco
+            None => diff_from_disk(&game_path, &target).await?,
+        };
+        info!(
+            "Updating instance {} from {:?} to {}: {} files, {} bytes",
+            self.name(),
+            self.config.installed_version,
+            target.id,
+            delta.added.len(),
+            delta.download_size()
+        );
+
+        let loader_id = self
+            .install_version(game_path, &target.id, Some(&delta), progress_tx)
+            .await?;
+
+        self.record_loader_version(loader_id);
+        self.config.installed_version = Some(target.id);
+        self.save().await?;
+        Ok(Some(delta))
+    }
+
+    /// Registra la partida actual como la última jugada y guarda
+    pub async fn mark_played(&mut self) -> Result<(), ProtonError> {
+        self.config.last_played = Some(unix_secs(SystemTime::now()));
+        self.save().await
+    }
+
+    /// Añade una partida al historial y al tiempo total, y guarda
+    pub async fn record_session(
+        &mut self,
+        started: SystemTime,
+        ended: SystemTime,
+    ) -> Result<(), ProtonError> {
+        let session = PlaySession {
+            started: unix_secs(started),
+            ended: unix_secs(ended),
+        };
+
+        self.config.playtime_secs += session.duration().as_secs();
+        self.config.last_played = Some(session.started);
+        self.config.sessions.push(session);
+        let overflow = self.config.sessions.len().saturating_sub(MAX_SESSIONS);
+        self.config.sessions.drain(..overflow);
+
+        self.save().await
+    }
+
+    /// Ejecuta el juego registrando la partida en el historial de la instancia.
+    /// `on_spawn` recibe el proceso para consumir su salida.
+    pub async fn run(
+        &mut self,
+        command: &LaunchCommand,
+        on_spawn: impl FnOnce(&mut GameProcess),
+    ) -> Result<GameExit, ProtonError> {
+        let started = SystemTime::now();
+        let mut process = command.spawn().await?;
+        self.mark_played().await?;
+        on_spawn(&mut process);
+
+        // La partida se registra aunque no se pueda esperar al proceso
+        let exit = process.wait().await;
+        let recorded = self.record_session(started, SystemTime::now()).await;
+        let exit = exit?;
+        recorded?;
+        Ok(exit)
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{FileSystem, MemoryFs, with_file_system};
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+    use std::sync::Arc;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[tokio::test]
+    async fn save_leaves_only_the_instance_file() {
+        let fs = MemoryFs::new();
+        let instance = with_file_system(Arc::new(fs.clone()), async {
+            let instance =
+                Instance::create("/instances/a", InstanceConfig::new("A", "1.20.1")).await?;
+            instance.save().await?;
+            Instance::load("/instances/a").await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(instance.name(), "A");
+        let files: Vec<_> = fs
+            .files()
+            .into_iter()
+            .filter(|path| path.parent() == Some(Path::new("/instances/a")))
+            .collect();
+        assert_eq!(files, vec![PathBuf::from("/instances/a/instance.json")]);
+    }
+
+    #[tokio::test]
+    async fn updates_download_only_the_delta() {
+        let env = MockEnvironment::start().await;
+        let (server, fs, game_path) = (&env.server, &env.fs, &env.game_dir);
+        env.downloader().download_all(None).await.unwrap();
+        let library = game_path.join("libraries/org/proton/library/1.0/library-1.0.jar");
+
+        let (delta, instance) = with_file_system(Arc::new(fs.clone()), async {
+            file_system().remove_file(&library).await?;
+            let config = InstanceConfig::new("Latest", LATEST_RELEASE);
+            let mut instance = Instance::create("/instances/latest", config).await?;
+            let delta = instance.update_if_outdated(game_path.clone(), None).await?;
+            Ok::<_, ProtonError>((delta, instance))
+        })
+        .await
+        .unwrap();
+
+        let delta = delta.unwrap();
+        let added: Vec<_> = delta.added.iter().map(|file| &file.path).collect();
+        assert_eq!(
+            added,
+            [Path::new(
+                "libraries/org/proton/library/1.0/library-1.0.jar"
+            )]
+        );
+        assert!(fs.get(&library).is_some());
+        assert_eq!(instance.resolved_version(), FIXTURE_VERSION);
+        // Lo que no cambió no se vuelve a pedir
+        let requests = server.server().received_requests().await.unwrap();
+        let client_requests = requests
+            .iter()
+            .filter(|request| request.url.path() == "/client.jar")
+            .count();
+        assert_eq!(client_requests, 1);
+    }
+    #[tokio::test]
+    async fn install_pins_the_latest_version() {
+        let env = MockEnvironment::start().await;
+        let game_path = env.game_dir.clone();
+
+        let instance = env
+            .run(async {
+                let config = InstanceConfig::new("Latest", LATEST_RELEASE);
+                let mut instance = Instance::create("/instances/latest", config).await?;
+                instance.install(game_path.clone(), None).await?;
+                Instance::load("/instances/latest").await
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            instance.config().installed_version.as_deref(),
+            Some(FIXTURE_VERSION)
+        );
+        assert_eq!(instance.resolved_version(), FIXTURE_VERSION);
+        assert!(
+            env.fs
+                .get(game_path.join(format!("versions/{FIXTURE_VERSION}/{FIXTURE_VERSION}.jar")))
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn updates_refuse_loaders_tied_to_the_old_version() {
+        let env = MockEnvironment::start().await;
+        let game_path = env.game_dir.clone();
+
+        let result = env
+            .run(async {
+                let mut config =
+                    InstanceConfig::new("Forge", LATEST_RELEASE).loader(ModLoader::Forge, "47.1.0");
+                config.installed_version = Some("1.0".to_string());
+                let mut instance = Instance::create("/instances/forge", config).await?;
+                instance.update_if_outdated(game_path, None).await
+            })
+            .await;
+
+        assert!(matches!(result, Err(ProtonError::InvalidInstance(_))));
+        let requests = env.server.server().received_requests().await.unwrap();
+        assert!(
+            requests
+                .iter()
+                .all(|request| request.url.path() != "/client.jar")
+        );
+    }
+
+    #[tokio::test]
+    async fn sessions_add_up_and_are_saved() {
+        let fs = MemoryFs::new();
+        let instance = with_file_system(Arc::new(fs.clone()), async {
+            let config = InstanceConfig::new("Played", "1.20.1");
+            let mut instance = Instance::create("/instances/played", config).await?;
+            instance.record_session(at(1_000), at(1_600)).await?;
+            instance.record_session(at(5_000), at(5_060)).await?;
+            // Un reloj que retrocede no resta tiempo
+            instance.record_session(at(9_000), at(8_000)).await?;
+            Instance::load("/instances/played").await
+        })
+        .await
+        .unwrap();
+
+        let config = instance.config();
+        assert_eq!(config.playtime_secs, 660);
+        assert_eq!(config.last_played, Some(9_000));
+        assert_eq!(
+            config.sessions.first(),
+            Some(&PlaySession {
+                started: 1_000,
+                ended: 1_600
+            })
+        );
+        assert_eq!(config.sessions.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn only_the_latest_sessions_are_kept() {
+        let fs = MemoryFs::new();
+        let instance = with_file_system(Arc::new(fs.clone()), async {
+            let config = InstanceConfig::new("Busy", "1.20.1");
+            let mut instance = Instance::create("/instances/busy", config).await?;
+            for i in 0..MAX_SESSIONS as u64 + 5 {
+                instance
+                    .record_session(at(i * 100), at(i * 100 + 10))
+                    .await?;
+            }
+            Ok::<_, ProtonError>(instance)
+        })
+        .await
+        .unwrap();
+
+        let sessions = &instance.config().sessions;
+        assert_eq!(sessions.len(), MAX_SESSIONS);
+        assert_eq!(sessions[0].started, 500);
+        // El tiempo total incluye las partidas descartadas
+        assert_eq!(
+            instance.config().playtime_secs,
+            (MAX_SESSIONS as u64 + 5) * 10
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runs_record_the_session() {
+        use crate::launcher::LaunchOptions;
+        use crate::testing::fixture_version;
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("proton-run-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let java = root.join("java");
+        std::fs::write(&java, "#!/bin/sh\nexit 3\n").unwrap();
+        std::fs::set_permissions(&java, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let mut options = LaunchOptions::new(root.join("game"), "Steve");
+        options.java_path = Some(java);
+        let command = LaunchCommand::build(&fixture_version("http://127.0.0.1:1"), &options);
+
+        let instance_dir = root.join("instance");
+        let mut instance =
+            Instance::create(&instance_dir, InstanceConfig::new("Run", FIXTURE_VERSION))
+                .await
+                .unwrap();
+        let exit = instance.run(&command, |_| {}).await.unwrap();
+        let saved = Instance::load(&instance_dir).await.unwrap();
+
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(exit.code, Some(3));
+        assert_eq!(saved.config().sessions.len(), 1);
+        assert!(saved.config().last_played.is_some());
+    }
+
+    #[tokio::test]
+    async fn legacy_files_are_migrated_and_unknown_fields_kept() {
+        let fs = MemoryFs::new();
+        let legacy = serde_json::json!({
+            "name": "Old",
+            "gameVersion": "1.12.2",
+            "javaArgs": ["-XX:+UseG1GC"],
+            "icon": "grass",
+        });
+        fs.create_dir_all(Path::new("/instances/old"))
+            .await
+            .unwrap();
+        fs.write(
+            Path::new("/instances/old/instance.json"),
+            legacy.to_string().as_bytes(),
+        )
+        .await
+        .unwrap();
+
+        let (instance, saved) = with_file_system(Arc::new(fs.clone()), async {
+            let instance = Instance::load("/instances/old").await?;
+            instance.save().await?;
+            let saved = file_system().read(&instance.config_path()).await?;
+            Ok::<_, ProtonError>((instance, saved))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(instance.version(), "1.12.2");
+        assert_eq!(instance.jvm_args(), ["-XX:+UseG1GC"]);
+        let saved: Value = serde_json::from_slice(&saved).unwrap();
+        assert_eq!(saved["format"], INSTANCE_FORMAT);
+        assert_eq!(saved["version"], "1.12.2");
+        assert_eq!(saved["icon"], "grass");
+        assert!(saved.get("gameVersion").is_none());
+    }
+
+    #[tokio::test]
+    async fn invalid_configs_are_rejected() {
+        let fs = MemoryFs::new();
+        let invalid = [
+            InstanceConfig::new(" ", "1.20.1"),
+            InstanceConfig::new("A", ""),
+            InstanceConfig::new("A", "1.20.1").loader(ModLoader::Forge, ""),
+            InstanceConfig::new("A", "1.20.1").memory(Some(4096), Some(1024)),
+            InstanceConfig::new("A", "1.20.1").resolution(0, 480),
+        ];
+
+        for config in invalid {
+            let result = with_file_system(
+                Arc::new(fs.clone()),
+                Instance::create("/instances/invalid", config.clone()),
+            )
+            .await;
+            assert!(
+                matches!(result, Err(ProtonError::InvalidInstance(_))),
+                "{config:?}"
+            );
+        }
+        assert!(fs.get("/instances/invalid/instance.json").is_none());
+
+        // Un archivo editado a mano tampoco se carga si no es válido
+        let edited = serde_json::json!({ "name": "A", "version": "1.20.1", "max_memory_mb": 0 });
+        fs.create_dir_all(Path::new("/instances/edited"))
+            .await
+            .unwrap();
+        fs.write(
+            Path::new("/instances/edited/instance.json"),
+            edited.to_string().as_bytes(),
+        )
+        .await
+        .unwrap();
+        let loaded =
+            with_file_system(Arc::new(fs.clone()), Instance::load("/instances/edited")).await;
+        assert!(matches!(loaded, Err(ProtonError::InvalidInstance(_))));
+    }
+}
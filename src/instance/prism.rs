@@ -0,0 +1,225 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{Instance, InstanceConfig, ModLoader, copy_dir, split_args};
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+
+// Componentes de `mmc-pack.json` que proton sabe instalar
+const MINECRAFT_UID: &str = "net.minecraft";
+const LOADER_UIDS: [(&str, ModLoader); 5] = [
+    ("net.fabricmc.fabric-loader", ModLoader::Fabric),
+    ("org.quiltmc.quilt-loader", ModLoader::Quilt),
+    ("net.minecraftforge", ModLoader::Forge),
+    ("net.neoforged", ModLoader::NeoForge),
+    ("com.mumfrey.liteloader", ModLoader::LiteLoader),
+];
+
+#[derive(Debug, Deserialize)]
+struct MmcPack {
+    components: Vec<MmcComponent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MmcComponent {
+    uid: String,
+    version: Option<String>,
+}
+
+/// Lee `instance.cfg`: pares `clave=valor`, ignorando secciones y comentarios
+fn parse_cfg(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['[', '#', ';']))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+impl Instance {
+    /// Importa una instancia de MultiMC / Prism Launcher a `dir`, copiando su
+    /// `.minecraft` y traduciendo versión, loader, memoria, Java y ventana
+    pub async fn import_prism(
+        source: &Path,
+        dir: impl Into<PathBuf>,
+    ) -> Result<Instance, ProtonError> {
+        let dir = dir.into();
+        let invalid = |msg: String| ProtonError::InvalidInstance(format!("{source:?}: {msg}"));
+
+        let fs = file_system();
+        let cfg = fs.read(&source.join("instance.cfg")).await?;
+        let cfg = parse_cfg(&String::from_utf8_lossy(&cfg));
+        let pack: MmcPack = serde_json::from_slice(&fs.read(&source.join("mmc-pack.json")).await?)
+            .map_err(|e| invalid(format!("invalid mmc-pack.json: {e}")))?;
+
+        let version_of = |uid: &str| {
+            pack.components
+                .iter()
+                .find(|c| c.uid == uid)
+                .and_then(|c| c.version.clone())
+        };
+        let version = version_of(MINECRAFT_UID)
+            .ok_or_else(|| invalid("no Minecraft component".to_string()))?;
+
+        let name = cfg.get("name").cloned().unwrap_or_else(|| {
+            source
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| version.clone())
+        });
+        let mut config = InstanceConfig::new(name, version);
+
+        if let Some((kind, loader_version)) = LOADER_UIDS
+            .iter()
+            .find_map(|(uid, kind)| version_of(uid).map(|v| (*kind, v)))
+        {
+            config = config.loader(kind, loader_version);
+        }
+
+        // Prism solo aplica estos ajustes si la instancia los sobrescribe
+        let enabled = |key: &str| cfg.get(key).is_some_and(|v| v == "true");
+        let number = |key: &str| cfg.get(key).and_then(|v| v.parse::<u32>().ok());
+
+        if enabled("OverrideMemory") {
+            config = config.memory(number("MinMemAlloc"), number("MaxMemAlloc"));
+        }
+        if enabled("OverrideJavaArgs")
+            && let Some(args) = cfg.get("JvmArgs")
+        {
+            config.jvm_args = split_args(args);
+        }
+        if enabled("OverrideJavaLocation")
+            && let Some(java) = cfg.get("JavaPath").filter(|p| !p.is_empty())
+        {
+            config = config.java_path(java);
+        }
+        if enabled("OverrideWindow")
+            && let (Some(width), Some(height)) =
+                (number("MinecraftWinWidth"), number("MinecraftWinHeight"))
+        {
+            config = config.resolution(width, height);
+        }
+        config.last_played = cfg
+            .get("lastLaunchTime")
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|ms| *ms > 0)
+            .map(|ms| ms / 1000);
+
+        let instance = Instance::create(dir, config).await?;
+
+        for game_dir in [".minecraft", "minecraft"] {
+            let path = source.join(game_dir);
+            if fs.metadata(&path).await.is_ok_and(|m| m.is_dir) {
+                copy_dir(&path, &instance.game_dir()).await?;
+                break;
+            }
+        }
+
+        Ok(instance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{MemoryFs, with_file_system};
+    use crate::instance::Resolution;
+    use std::sync::Arc;
+
+    const INSTANCE_CFG: &str = "[General]
+ConfigVersion=1.2
+name=Fabulously Optimized
+OverrideMemory=true
+MinMemAlloc=1024
+MaxMemAlloc=6144
+OverrideJavaArgs=true
+JvmArgs=-XX:+UseZGC \"-Dfoo=bar baz\"
+OverrideJavaLocation=false
+JavaPath=/usr/lib/jvm/java-21/bin/java
+OverrideWindow=true
+MinecraftWinWidth=1280
+MinecraftWinHeight=720
+lastLaunchTime=1704067200123
+";
+
+    const MMC_PACK: &str = r#"{
+        "formatVersion": 1,
+        "components": [
+            { "uid": "org.lwjgl3", "version": "3.3.3" },
+            { "uid": "net.minecraft", "version": "1.20.4" },
+            { "uid": "net.fabricmc.intermediary", "version": "1.20.4" },
+            { "uid": "net.fabricmc.fabric-loader", "version": "0.15.11" }
+        ]
+    }"#;
+
+    #[tokio::test]
+    async fn imports_version_loader_settings_and_game_files() {
+        let fs = MemoryFs::new();
+        let source = Path::new("/prism/instances/fo");
+
+        let instance = with_file_system(Arc::new(fs.clone()), async {
+            let fs = file_system();
+            fs.create_dir_all(&source.join(".minecraft/mods")).await?;
+            fs.write(&source.join("instance.cfg"), INSTANCE_CFG.as_bytes())
+                .await?;
+            fs.write(&source.join("mmc-pack.json"), MMC_PACK.as_bytes())
+                .await?;
+            fs.write(&source.join(".minecraft/mods/sodium.jar"), b"jar")
+                .await?;
+            fs.write(&source.join(".minecraft/options.txt"), b"fov:0.5")
+                .await?;
+            Instance::import_prism(source, "/proton/instances/fo").await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(instance.name(), "Fabulously Optimized");
+        assert_eq!(instance.version(), "1.20.4");
+        let loader = instance.loader().unwrap();
+        assert_eq!(
+            (loader.kind, loader.version.as_str()),
+            (ModLoader::Fabric, "0.15.11")
+        );
+        assert_eq!(instance.memory(), (Some(1024), Some(6144)));
+        assert_eq!(instance.jvm_args(), ["-XX:+UseZGC", "-Dfoo=bar baz"]);
+        // Sin `OverrideJavaLocation` se usa el Java por defecto
+        assert_eq!(instance.java_path(), None);
+        assert_eq!(
+            instance.resolution(),
+            Some(Resolution {
+                width: 1280,
+                height: 720
+            })
+        );
+        assert_eq!(instance.config().last_played, Some(1_704_067_200));
+
+        let game_dir = instance.game_dir();
+        assert_eq!(
+            fs.get(game_dir.join("mods/sodium.jar")).as_deref(),
+            Some(&b"jar"[..])
+        );
+        assert!(fs.get(game_dir.join("options.txt")).is_some());
+        assert!(fs.get("/proton/instances/fo/instance.json").is_some());
+    }
+
+    #[tokio::test]
+    async fn rejects_packs_without_minecraft() {
+        let fs = MemoryFs::new();
+        let source = Path::new("/prism/instances/broken");
+
+        let result = with_file_system(Arc::new(fs), async {
+            let fs = file_system();
+            fs.create_dir_all(source).await?;
+            fs.write(&source.join("instance.cfg"), b"name=Broken")
+                .await?;
+            fs.write(&source.join("mmc-pack.json"), br#"{ "components": [] }"#)
+                .await?;
+            Instance::import_prism(source, "/proton/instances/broken").await
+        })
+        .await;
+
+        assert!(matches!(result, Err(ProtonError::InvalidInstance(_))));
+    }
+}
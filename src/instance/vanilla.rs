@@ -0,0 +1,386 @@
+use log::warn;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{INSTANCE_FILE, Instance, InstanceConfig, ModLoader, split_args};
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::manifest::{LATEST_RELEASE, LATEST_SNAPSHOT};
+
+#[derive(Debug, Deserialize)]
+struct LauncherProfiles {
+    #[serde(default)]
+    profiles: HashMap<String, LauncherProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LauncherProfile {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type", default)]
+    profile_type: String,
+    last_version_id: Option<String>,
+    game_dir: Option<PathBuf>,
+    java_args: Option<String>,
+    java_dir: Option<PathBuf>,
+    resolution: Option<ProfileResolution>,
+    last_used: Option<String>,
+}
+
+/// Resultado de `Instance::import_launcher_profiles`
+#[derive(Debug)]
+pub struct LauncherProfilesImport {
+    pub instances: Vec<Instance>,
+    /// Perfiles no importados, por su id en `launcher_profiles.json`, con el motivo
+    pub skipped: Vec<(String, ProtonError)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileResolution {
+    width: u32,
+    height: u32,
+}
+
+/// Convierte `-Xmx2G`/`-Xms512M` a MB
+fn parse_memory(value: &str) -> Option<u32> {
+    let (split, unit) = value.char_indices().next_back()?;
+    let number = &value[..split];
+    let multiplier = match unit.to_ascii_lowercase() {
+        'g' => 1024,
+        'm' => 1,
+        'k' => return number.parse::<u32>().ok().map(|kb| kb / 1024),
+        // Sin sufijo el valor está en bytes
+        _ => {
+            return value
+                .parse::<u64>()
+                .ok()
+                .and_then(|bytes| u32::try_from(bytes / (1024 * 1024)).ok());
+        }
+    };
+    number.parse::<u32>().ok()?.checked_mul(multiplier)
+}
+
+/// Reconoce los ids de versión que instalan los loaders en el launcher oficial
+fn parse_modded_id(id: &str) -> Option<(String, ModLoader, String)> {
+    if let Some(rest) = id.strip_prefix("fabric-loader-") {
+        let (loader, game) = rest.split_once('-')?;
+        return Some((game.to_string(), ModLoader::Fabric, loader.to_string()));
+    }
+    if let Some(rest) = id.strip_prefix("quilt-loader-") {
+        let (loader, game) = rest.split_once('-')?;
+        return Some((game.to_string(), ModLoader::Quilt, loader.to_string()));
+    }
+    // `neoforge-20.4.80`: la versión del juego va en las dos primeras cifras
+    if let Some(loader) = id.strip_prefix("neoforge-") {
+        let mut parts = loader.split('.');
+        let (major, minor) = (parts.next()?, parts.next()?);
+        major.parse::<u32>().ok()?;
+        let game = match minor.parse::<u32>().ok()? {
+            0 => format!("1.{major}"),
+            minor => format!("1.{major}.{minor}"),
+        };
+        return Some((game, ModLoader::NeoForge, loader.to_string()));
+    }
+    // `1.12.2-LiteLoader1.12.2`, o sobre Forge `1.12.2-forge-...-LiteLoader1.12.2`
+    if let Some((base, loader)) = id.split_once("-LiteLoader") {
+        let game = parse_forge_id(base).map_or(base.to_string(), |(game, _)| game);
+        return Some((game, ModLoader::LiteLoader, loader.to_string()));
+    }
+    let (game, loader) = parse_forge_id(id)?;
+    Some((game, ModLoader::Forge, loader))
+}
+
+/// Versión del juego y de Forge de los tres formatos de id que ha usado su
+/// instalador: `1.20.1-forge-47.1.0`, `1.12.2-forge1.12.2-14.23.5.2860` y
+/// `1.7.10-Forge10.13.4.1614-1.7.10`
+fn parse_forge_id(id: &str) -> Option<(String, String)> {
+    if let Some((game, loader)) = id.split_once("-forge-") {
+        return Some((game.to_string(), loader.to_string()));
+    }
+    if let Some((game, rest)) = id.split_once("-forge") {
+        let loader = rest.strip_prefix(game)?.strip_prefix('-')?;
+        return Some((game.to_string(), loader.to_string()));
+    }
+    let (game, rest) = id.split_once("-Forge")?;
+    let loader = rest.strip_suffix(&format!("-{game}")).unwrap_or(rest);
+    Some((game.to_string(), loader.to_string()))
+}
+
+/// Segundos desde UNIX_EPOCH de una fecha ISO-8601 (`2024-01-31T12:00:00.000Z`)
+fn parse_timestamp(value: &str) -> Option<u64> {
+    let (date, time) = value.split_once('T')?;
+    let mut date = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let mut time = time.get(..8)?.split(':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+
+    // Días desde 1970-01-01 en el calendario gregoriano proléptico
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    u64::try_from(days * 86400 + hour * 3600 + minute * 60 + second).ok()
+}
+
+/// Nombre de carpeta seguro y libre dentro de `root`
+async fn instance_dir(root: &Path, name: &str, fallback: &str) -> PathBuf {
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let base = match sanitized.trim().trim_matches('.') {
+        "" => fallback.to_string(),
+        trimmed => trimmed.to_string(),
+    };
+
+    let mut candidate = root.join(&base);
+    let mut suffix = 2;
+    while file_system().exists(&candidate.join(INSTANCE_FILE)).await {
+        candidate = root.join(format!("{base} ({suffix})"));
+        suffix += 1;
+    }
+    candidate
+}
+
+impl Instance {
+    /// Convierte cada perfil de `launcher_profiles.json` del launcher oficial
+    /// en una instancia dentro de `instances_root`. El directorio del juego de
+    /// cada perfil se reutiliza sin copiarlo. Los perfiles con una
+    /// configuración no válida se omiten y se devuelven en `skipped`.
+    pub async fn import_launcher_profiles(
+        profiles_path: &Path,
+        instances_root: &Path,
+    ) -> Result<LauncherProfilesImport, ProtonError> {
+        let content = file_system().read(profiles_path).await?;
+        let file: LauncherProfiles = serde_json::from_slice(&content)
+            .map_err(|e| ProtonError::InvalidInstance(format!("{profiles_path:?}: {e}")))?;
+
+        // Orden estable: el HashMap no conserva el del archivo
+        let mut profiles: Vec<_> = file.profiles.into_iter().collect();
+        profiles.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut instances = Vec::with_capacity(profiles.len());
+        let mut skipped = Vec::new();
+        for (id, profile) in profiles {
+            let version = match profile.profile_type.as_str() {
+                "latest-release" => LATEST_RELEASE.to_string(),
+                "latest-snapshot" => LATEST_SNAPSHOT.to_string(),
+                _ => profile
+                    .last_version_id
+                    .clone()
+                    .unwrap_or_else(|| LATEST_RELEASE.to_string()),
+            };
+            let name = match profile.name.as_str() {
+                "" => version.clone(),
+                name => name.to_string(),
+            };
+
+            let mut config = match parse_modded_id(&version) {
+                Some((game, kind, loader)) => {
+                    let mut config = InstanceConfig::new(name, game).loader(kind, loader);
+                    // El launcher ya lo instaló con ese id; se lanza tal cual
+                    config.loader_version_id = Some(version);
+                    config
+                }
+                None => InstanceConfig::new(name, version),
+            };
+
+            for arg in profile
+                .java_args
+                .as_deref()
+                .map(split_args)
+                .unwrap_or_default()
+            {
+                if let Some(max) = arg.strip_prefix("-Xmx").and_then(parse_memory) {
+                    config.max_memory_mb = Some(max);
+                } else if let Some(min) = arg.strip_prefix("-Xms").and_then(parse_memory) {
+                    config.min_memory_mb = Some(min);
+                } else {
+                    config.jvm_args.push(arg);
+                }
+            }
+            if let Some(java) = profile.java_dir {
+                config.java_path = Some(java);
+            }
+            if let Some(resolution) = profile.resolution {
+                config = config.resolution(resolution.width, resolution.height);
+            }
+            config.game_dir = profile.game_dir;
+            config.last_played = profile.last_used.as_deref().and_then(parse_timestamp);
+
+            if let Err(e) = config.validate() {
+                warn!("Skipping launcher profile {id}: {e}");
+                skipped.push((id, e));
+                continue;
+            }
+            let dir = instance_dir(instances_root, &config.name, &id).await;
+            instances.push(Instance::create(dir, config).await?);
+        }
+
+        Ok(LauncherProfilesImport { instances, skipped })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{MemoryFs, with_file_system};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn parses_memory_suffixes() {
+        assert_eq!(parse_memory("2G"), Some(2048));
+        assert_eq!(parse_memory("512m"), Some(512));
+        assert_eq!(parse_memory("2097152k"), Some(2048));
+        assert_eq!(parse_memory("1073741824"), Some(1024));
+    }
+
+    #[test]
+    fn rejects_malformed_memory() {
+        assert_eq!(parse_memory(""), None);
+        assert_eq!(parse_memory("2€"), None);
+        assert_eq!(parse_memory("é"), None);
+        assert_eq!(parse_memory("99999999G"), None);
+    }
+
+    #[test]
+    fn recognises_loader_version_ids() {
+        let expected =
+            |game: &str, kind, loader: &str| Some((game.to_string(), kind, loader.to_string()));
+
+        assert_eq!(
+            parse_modded_id("fabric-loader-0.15.11-1.20.4"),
+            expected("1.20.4", ModLoader::Fabric, "0.15.11")
+        );
+        assert_eq!(
+            parse_modded_id("quilt-loader-0.26.0-1.20.1"),
+            expected("1.20.1", ModLoader::Quilt, "0.26.0")
+        );
+        assert_eq!(
+            parse_modded_id("1.20.1-forge-47.1.0"),
+            expected("1.20.1", ModLoader::Forge, "47.1.0")
+        );
+        assert_eq!(
+            parse_modded_id("1.12.2-forge1.12.2-14.23.5.2860"),
+            expected("1.12.2", ModLoader::Forge, "14.23.5.2860")
+        );
+        assert_eq!(
+            parse_modded_id("1.7.10-Forge10.13.4.1614-1.7.10"),
+            expected("1.7.10", ModLoader::Forge, "10.13.4.1614")
+        );
+        assert_eq!(
+            parse_modded_id("neoforge-20.4.80"),
+            expected("1.20.4", ModLoader::NeoForge, "20.4.80")
+        );
+        assert_eq!(
+            parse_modded_id("neoforge-21.0.167"),
+            expected("1.21", ModLoader::NeoForge, "21.0.167")
+        );
+        assert_eq!(
+            parse_modded_id("1.12.2-forge1.12.2-14.23.5.2860-LiteLoader1.12.2"),
+            expected("1.12.2", ModLoader::LiteLoader, "1.12.2")
+        );
+        assert_eq!(parse_modded_id("1.20.4"), None);
+        assert_eq!(parse_modded_id("24w14a"), None);
+        assert_eq!(parse_modded_id("neoforge-latest"), None);
+    }
+
+    #[test]
+    fn parses_launcher_timestamps() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00.000Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2024-01-31T12:00:00.000Z"),
+            Some(1706702400)
+        );
+        assert_eq!(parse_timestamp("2000-02-29T23:59:59Z"), Some(951868799));
+        assert_eq!(parse_timestamp("2024-01-31"), None);
+        assert_eq!(parse_timestamp("yesterday T noon"), None);
+        assert_eq!(parse_timestamp("1969-12-31T23:59:59.000Z"), None);
+    }
+
+    #[tokio::test]
+    async fn imports_launcher_profiles() {
+        let fs = MemoryFs::new();
+        let profiles = json!({
+            "profiles": {
+                "a-latest": { "type": "latest-release", "lastUsed": "2024-01-31T12:00:00.000Z" },
+                "b-forge": {
+                    "name": "Forge",
+                    "type": "custom",
+                    "lastVersionId": "1.12.2-forge1.12.2-14.23.5.2860",
+                    "gameDir": "/home/steve/forge",
+                    "javaArgs": "-Xmx4G -Xms1G -XX:+UseG1GC",
+                    "resolution": { "width": 1280, "height": 720 }
+                },
+                "c-broken": { "name": "Broken", "type": "custom", "lastVersionId": "1.20.4", "javaArgs": "-Xmx0M" },
+                "d-clash": { "name": "Forge", "type": "custom", "lastVersionId": "1.20.4" }
+            }
+        });
+
+        let import = with_file_system(Arc::new(fs.clone()), async {
+            let path = Path::new("/launcher/launcher_profiles.json");
+            file_system().create_dir_all(path.parent().unwrap()).await?;
+            file_system()
+                .write(path, &serde_json::to_vec(&profiles).unwrap())
+                .await?;
+            Instance::import_launcher_profiles(path, Path::new("/instances")).await
+        })
+        .await
+        .unwrap();
+
+        let [latest, forge, clash] = &import.instances[..] else {
+            panic!("unexpected instances: {:?}", import.instances);
+        };
+        assert_eq!(latest.name(), LATEST_RELEASE);
+        assert_eq!(latest.version(), LATEST_RELEASE);
+        assert_eq!(latest.config().last_played, Some(1706702400));
+
+        let config = forge.config();
+        assert_eq!(config.version, "1.12.2");
+        assert_eq!(
+            config
+                .loader
+                .as_ref()
+                .map(|loader| (loader.kind, loader.version.as_str())),
+            Some((ModLoader::Forge, "14.23.5.2860"))
+        );
+        assert_eq!(
+            config.loader_version_id.as_deref(),
+            Some("1.12.2-forge1.12.2-14.23.5.2860")
+        );
+        assert_eq!(
+            (config.min_memory_mb, config.max_memory_mb),
+            (Some(1024), Some(4096))
+        );
+        assert_eq!(config.jvm_args, ["-XX:+UseG1GC"]);
+        assert_eq!(
+            config.game_dir.as_deref(),
+            Some(Path::new("/home/steve/forge"))
+        );
+        assert_eq!(
+            forge.config_path(),
+            Path::new("/instances/Forge/instance.json")
+        );
+        // Mismo nombre: otra carpeta
+        assert_eq!(
+            clash.config_path(),
+            Path::new("/instances/Forge (2)/instance.json")
+        );
+
+        let skipped: Vec<_> = import.skipped.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(skipped, ["c-broken"]);
+    }
+}
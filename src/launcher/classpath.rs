@@ -0,0 +1,208 @@
+use log::info;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::maven::MavenCoordinate;
+use crate::types::Library;
+
+/// Librería que aparecía en varias versiones; solo `chosen` entra en el classpath
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClasspathConflict {
+    /// `grupo:artefacto[:clasificador]`
+    pub key: String,
+    pub chosen: String,
+    pub discarded: Vec<String>,
+    /// La versión la fijó `LaunchOptions::pin_library` en lugar de ser la más alta
+    pub pinned: bool,
+}
+
+/// Compara versiones maven por segmentos (`1.10` > `1.9`). Los segmentos
+/// numéricos van por valor y un sufijo como `-SNAPSHOT` o `-rc1` queda por
+/// debajo de la versión sin él.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let segments = |version: &str| -> Vec<String> {
+        version
+            .split(['.', '-', '_', '+'])
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (segments(a), segments(b));
+
+    for index in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(index), b.get(index)) {
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Greater,
+                (Err(_), Ok(_)) => Ordering::Less,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+            // `1.0.1` > `1.0`, pero `1.0-SNAPSHOT` < `1.0`
+            (Some(extra), None) => match extra.parse::<u64>() {
+                Ok(_) => Ordering::Greater,
+                Err(_) => Ordering::Less,
+            },
+            (None, Some(extra)) => match extra.parse::<u64>() {
+                Ok(_) => Ordering::Less,
+                Err(_) => Ordering::Greater,
+            },
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Deja una sola versión de cada librería: la fijada en `pins` o, si no, la
+/// más alta. Se conserva la posición de la primera aparición, porque el orden
+/// del classpath importa a los loaders. Los nombres que no son coordenadas
+/// maven se dejan tal cual.
+pub(crate) fn resolve_classpath<'a>(
+    libraries: &'a [Library],
+    pins: &BTreeMap<String, String>,
+) -> (Vec<&'a Library>, Vec<ClasspathConflict>) {
+    let mut versions: HashMap<String, Vec<(&'a Library, String)>> = HashMap::new();
+    for library in libraries {
+        if let Ok(coordinate) = library.name.parse::<MavenCoordinate>() {
+            versions
+                .entry(coordinate.key())
+                .or_default()
+                .push((library, coordinate.version));
+        }
+    }
+
+    let mut chosen: HashMap<String, &'a Library> = HashMap::new();
+    let mut conflicts = Vec::new();
+    for (key, candidates) in &versions {
+        let pinned = pins
+            .get(key)
+            .and_then(|pin| candidates.iter().find(|(_, version)| version == pin));
+        let Some((library, version)) = pinned.or_else(|| {
+            candidates
+                .iter()
+                .max_by(|(_, a), (_, b)| compare_versions(a, b))
+        }) else {
+            continue;
+        };
+        chosen.insert(key.clone(), library);
+
+        let mut discarded: Vec<String> = candidates
+            .iter()
+            .map(|(_, candidate)| candidate.clone())
+            .filter(|candidate| candidate != version)
+            .collect();
+        discarded.sort();
+        discarded.dedup();
+        if !discarded.is_empty() {
+            info!("Classpath: using {key}:{version} instead of {discarded:?}");
+            conflicts.push(ClasspathConflict {
+                key: key.clone(),
+                chosen: version.clone(),
+                discarded,
+                pinned: pinned.is_some(),
+            });
+        }
+    }
+    conflicts.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let mut seen = HashSet::new();
+    let classpath = libraries
+        .iter()
+        .filter_map(|library| {
+            let Ok(coordinate) = library.name.parse::<MavenCoordinate>() else {
+                return Some(library);
+            };
+            let key = coordinate.key();
+            if !seen.insert(key.clone()) {
+                return None;
+            }
+            chosen.get(&key).copied()
+        })
+        .collect();
+
+    (classpath, conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(name: &str) -> Library {
+        Library {
+            name: name.to_string(),
+            url: String::new(),
+            sha1: String::new(),
+            size: 0,
+            path: String::new(),
+        }
+    }
+
+    fn names(classpath: &[&Library]) -> Vec<String> {
+        classpath
+            .iter()
+            .map(|library| library.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn compares_maven_versions_by_segment() {
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("9.7.1", "9.7"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0-SNAPSHOT", "1.0"), Ordering::Less);
+        assert_eq!(compare_versions("2.0-rc1", "2.0"), Ordering::Less);
+        assert_eq!(compare_versions("2.0-RC2", "2.0-rc1"), Ordering::Greater);
+        assert_eq!(compare_versions("3.3.3", "3.3.3"), Ordering::Equal);
+    }
+
+    #[test]
+    fn keeps_the_highest_version_at_the_first_position() {
+        let libraries = [
+            library("org.ow2.asm:asm:9.3"),
+            library("net.minecraft:launchwrapper-custom.jar"),
+            library("com.google.guava:guava:31.1-jre"),
+            library("org.ow2.asm:asm:9.7.1"),
+            library("org.lwjgl:lwjgl:3.3.3"),
+            library("org.lwjgl:lwjgl:3.3.3:natives-linux"),
+        ];
+        let (classpath, conflicts) = resolve_classpath(&libraries, &BTreeMap::new());
+
+        assert_eq!(
+            names(&classpath),
+            [
+                "org.ow2.asm:asm:9.7.1",
+                "net.minecraft:launchwrapper-custom.jar",
+                "com.google.guava:guava:31.1-jre",
+                "org.lwjgl:lwjgl:3.3.3",
+                "org.lwjgl:lwjgl:3.3.3:natives-linux",
+            ]
+        );
+        assert_eq!(
+            conflicts,
+            [ClasspathConflict {
+                key: "org.ow2.asm:asm".to_string(),
+                chosen: "9.7.1".to_string(),
+                discarded: vec!["9.3".to_string()],
+                pinned: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn pins_override_the_highest_version() {
+        let libraries = [
+            library("org.ow2.asm:asm:9.7.1"),
+            library("org.ow2.asm:asm:9.3"),
+        ];
+        let pins = BTreeMap::from([("org.ow2.asm:asm".to_string(), "9.3".to_string())]);
+        let (classpath, conflicts) = resolve_classpath(&libraries, &pins);
+
+        assert_eq!(names(&classpath), ["org.ow2.asm:asm:9.3"]);
+        assert!(conflicts[0].pinned);
+
+        // Un pin que no coincide con ninguna versión no cambia nada
+        let pins = BTreeMap::from([("org.ow2.asm:asm".to_string(), "1.0".to_string())]);
+        let (classpath, _) = resolve_classpath(&libraries, &pins);
+        assert_eq!(names(&classpath), ["org.ow2.asm:asm:9.7.1"]);
+    }
+}
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{Receiver, channel};
+
+const EVENT_START: &str = "<log4j:Event";
+const EVENT_END: &str = "</log4j:Event>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+    /// Líneas fuera del formato XML (por ejemplo, salida directa de la JVM)
+    Unknown,
+}
+
+impl LogLevel {
+    fn parse(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "TRACE" => LogLevel::Trace,
+            "DEBUG" => LogLevel::Debug,
+            "INFO" => LogLevel::Info,
+            "WARN" => LogLevel::Warn,
+            "ERROR" => LogLevel::Error,
+            "FATAL" => LogLevel::Fatal,
+            _ => LogLevel::Unknown,
+        }
+    }
+}
+
+/// Evento de log del juego
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameLogEvent {
+    pub level: LogLevel,
+    pub thread: String,
+    pub logger: String,
+    /// Milisegundos desde UNIX_EPOCH
+    pub timestamp: Option<u64>,
+    pub message: String,
+    pub throwable: Option<String>,
+}
+
+impl GameLogEvent {
+    fn raw(line: String) -> Self {
+        Self {
+            level: LogLevel::Unknown,
+            thread: String::new(),
+            logger: String::new(),
+            timestamp: None,
+            message: line,
+            throwable: None,
+        }
+    }
+}
+
+/// Convierte la salida del juego con `XMLLayout` de log4j en eventos. Las
+/// líneas que no forman parte de un evento se devuelven como `Unknown`.
+#[derive(Debug, Default)]
+pub struct GameLogParser {
+    pending: Option<String>,
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+    let end = tag[start..].find('"')? + start;
+    Some(unescape_xml(&tag[start..end]))
+}
+
+/// Texto de un elemento hijo, con o sin CDATA
+fn element(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{name}>");
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&format!("</{name}>"))? + start;
+    let content = xml[start..end].trim();
+
+    Some(
+        match content
+            .strip_prefix("<![CDATA[")
+            .and_then(|c| c.strip_suffix("]]>"))
+        {
+            Some(cdata) => cdata.to_string(),
+            None => unescape_xml(content),
+        },
+    )
+}
+
+fn parse_event(xml: &str) -> GameLogEvent {
+    let tag_end = xml.find('>').unwrap_or(xml.len());
+    let tag = &xml[..tag_end];
+
+    GameLogEvent {
+        level: attribute(tag, "level").map_or(LogLevel::Unknown, |l| LogLevel::parse(&l)),
+        thread: attribute(tag, "thread").unwrap_or_default(),
+        logger: attribute(tag, "logger").unwrap_or_default(),
+        timestamp: attribute(tag, "timestamp").and_then(|t| t.parse().ok()),
+        message: element(xml, "log4j:Message").unwrap_or_default(),
+        throwable: element(xml, "log4j:Throwable"),
+    }
+}
+
+impl GameLogParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Procesa una línea; devuelve un evento cuando está completo
+    pub fn push_line(&mut self, line: &str) -> Option<GameLogEvent> {
+        let buffer = match self.pending.as_mut() {
+            Some(buffer) => {
+                buffer.push('\n');
+                buffer.push_str(line);
+                buffer
+            }
+            None if line.trim_start().starts_with(EVENT_START) => {
+                self.pending.insert(line.trim_start().to_string())
+            }
+            None => return Some(GameLogEvent::raw(line.to_string())),
+        };
+
+        if buffer.contains(EVENT_END) {
+            let xml = self.pending.take().unwrap_or_default();
+            return Some(parse_event(&xml));
+        }
+        None
+    }
+
+    /// Devuelve lo acumulado de un evento incompleto al terminar el proceso
+    pub fn finish(&mut self) -> Option<GameLogEvent> {
+        self.pending.take().map(GameLogEvent::raw)
+    }
+}
+
+/// Transforma un canal de líneas (por ejemplo, `GameProcess::take_stdout`)
+/// en un canal de eventos de log
+pub fn parse_log_stream(mut lines: Receiver<String>) -> Receiver<GameLogEvent> {
+    let (tx, rx) = channel(256);
+
+    tokio::spawn(async move {
+        let mut parser = GameLogParser::new();
+        while let Some(line) = lines.recv().await {
+            if let Some(event) = parser.push_line(&line)
+                && tx.send(event).await.is_err()
+            {
+                return;
+            }
+        }
+        if let Some(event) = parser.finish() {
+            let _ = tx.send(event).await;
+        }
+    });
+
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Salida real de `XMLLayout`: un evento ocupa varias líneas
+    const CRASH_EVENT: &str = r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1704067200000" level="ERROR" thread="Render thread">
+  <log4j:Message><![CDATA[Unreported exception thrown!]]></log4j:Message>
+  <log4j:Throwable><![CDATA[java.lang.IllegalStateException: <boom>
+	at net.minecraft.client.Minecraft.run(Minecraft.java:42)
+]]></log4j:Throwable>
+</log4j:Event>"#;
+
+    #[test]
+    fn joins_multi_line_events() {
+        let mut parser = GameLogParser::new();
+        let lines: Vec<&str> = CRASH_EVENT.lines().collect();
+        let (last, rest) = lines.split_last().unwrap();
+        for line in rest {
+            assert_eq!(parser.push_line(line), None);
+        }
+
+        let event = parser.push_line(last).unwrap();
+        assert_eq!(event.level, LogLevel::Error);
+        assert_eq!(event.thread, "Render thread");
+        assert_eq!(event.logger, "net.minecraft.client.Minecraft");
+        assert_eq!(event.timestamp, Some(1_704_067_200_000));
+        assert_eq!(event.message, "Unreported exception thrown!");
+        assert_eq!(
+            event.throwable.as_deref(),
+            Some(
+                "java.lang.IllegalStateException: <boom>\n\tat net.minecraft.client.Minecraft.run(Minecraft.java:42)\n"
+            )
+        );
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[test]
+    fn escaped_messages_and_raw_lines() {
+        let mut parser = GameLogParser::new();
+        let raw = parser
+            .push_line("OpenJDK 64-Bit Server VM warning")
+            .unwrap();
+        assert_eq!(raw.level, LogLevel::Unknown);
+        assert_eq!(raw.message, "OpenJDK 64-Bit Server VM warning");
+
+        let event = parser
+            .push_line(r#"<log4j:Event logger="a" level="warn" thread="main"><log4j:Message>x &lt; y &amp;&amp; &quot;z&quot;</log4j:Message></log4j:Event>"#)
+            .unwrap();
+        assert_eq!(event.level, LogLevel::Warn);
+        assert_eq!(event.message, r#"x < y && "z""#);
+        assert_eq!(event.timestamp, None);
+    }
+
+    #[test]
+    fn unfinished_event_is_flushed_as_raw() {
+        let mut parser = GameLogParser::new();
+        assert_eq!(parser.push_line(r#"<log4j:Event level="INFO">"#), None);
+        assert_eq!(parser.push_line("  <log4j:Message>cut"), None);
+
+        let event = parser.finish().unwrap();
+        assert_eq!(event.level, LogLevel::Unknown);
+        assert_eq!(
+            event.message,
+            "<log4j:Event level=\"INFO\">\n  <log4j:Message>cut"
+        );
+    }
+}
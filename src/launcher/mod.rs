@@ -0,0 +1,1105 @@
+use log::warn;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::instance::{Instance, Resolution, copy_dir};
+use crate::system::system_memory;
+use crate::types::{LAUNCHWRAPPER_MAIN_CLASS, NormalizedVersion, TWEAK_CLASS_ARG, mojang_os_name};
+
+mod classpath;
+mod game_log;
+mod process;
+
+pub use classpath::ClasspathConflict;
+pub use game_log::{GameLogEvent, GameLogParser, LogLevel, parse_log_stream};
+pub use process::{GameExit, GameProcess, RestartPolicy};
+
+// Valores usados para jugar sin cuenta
+const OFFLINE_UUID: &str = "00000000-0000-0000-0000-000000000000";
+const OFFLINE_TOKEN: &str = "0";
+
+// Sustituye a los secretos en `LaunchCommand::redacted`
+const REDACTED: &str = "<redacted>";
+
+#[cfg(windows)]
+pub(crate) const CLASSPATH_SEPARATOR: &str = ";";
+#[cfg(not(windows))]
+pub(crate) const CLASSPATH_SEPARATOR: &str = ":";
+#[cfg(windows)]
+const JAVA_BINARY: &str = "java.exe";
+#[cfg(not(windows))]
+const JAVA_BINARY: &str = "java";
+
+// Carpeta del almacén con los directorios de juego aislados por versión
+const ISOLATED_DIRS: &str = "profiles";
+
+// Índice de assets de las versiones anteriores a 1.6, que los leen de `resources/`
+const PRE_1_6_ASSETS: &str = "pre-1.6";
+const RESOURCES_DIR: &str = "resources";
+
+// Feature de los argumentos `--width`/`--height` en el JSON de la versión
+const CUSTOM_RESOLUTION_FEATURE: &str = "has_custom_resolution";
+const QUICK_PLAY_SINGLEPLAYER: &str = "is_quick_play_singleplayer";
+const QUICK_PLAY_MULTIPLAYER: &str = "is_quick_play_multiplayer";
+const QUICK_PLAY_REALMS: &str = "is_quick_play_realms";
+
+/// Destino al que entrar directamente al iniciar el juego
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuickPlay {
+    /// Nombre de la carpeta del mundo dentro de `saves`
+    Singleplayer(String),
+    Multiplayer {
+        host: String,
+        port: Option<u16>,
+    },
+    /// Id del realm
+    Realms(String),
+}
+
+impl QuickPlay {
+    fn feature(&self) -> &'static str {
+        match self {
+            QuickPlay::Singleplayer(_) => QUICK_PLAY_SINGLEPLAYER,
+            QuickPlay::Multiplayer { .. } => QUICK_PLAY_MULTIPLAYER,
+            QuickPlay::Realms(_) => QUICK_PLAY_REALMS,
+        }
+    }
+
+    fn multiplayer_address(&self) -> Option<String> {
+        match self {
+            QuickPlay::Multiplayer {
+                host,
+                port: Some(port),
+            } => Some(format!("{host}:{port}")),
+            QuickPlay::Multiplayer { host, port: None } => Some(host.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Parámetros de lanzamiento que no dependen de la versión
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    /// Almacén compartido con `versions`, `libraries`, `assets` y `natives`
+    pub game_path: PathBuf,
+    /// Directorio de trabajo del juego (`--gameDir`)
+    pub game_dir: PathBuf,
+    /// Usar `<game_path>/profiles/<versión>` como directorio del juego, para
+    /// que configuraciones, mundos y mods no se mezclen entre versiones
+    pub isolated: bool,
+    pub java_path: Option<PathBuf>,
+    pub min_memory_mb: Option<u32>,
+    pub max_memory_mb: Option<u32>,
+    /// Argumentos JVM adicionales; prevalecen sobre los de la versión
+    pub jvm_args: Vec<String>,
+    /// Argumentos JVM de las versiones que no declaran los suyos (las de
+    /// `minecraftArguments`); se sustituyen igual que los de la versión
+    pub legacy_jvm_args: Vec<String>,
+    pub username: String,
+    pub uuid: String,
+    pub access_token: String,
+    pub user_type: String,
+    pub launcher_name: String,
+    pub launcher_version: String,
+    /// Tamaño inicial de la ventana
+    pub resolution: Option<Resolution>,
+    pub fullscreen: bool,
+    pub quick_play: Option<QuickPlay>,
+    /// Variables de entorno añadidas o sobrescritas sobre las del launcher
+    pub env: Vec<(String, String)>,
+    /// Versión que usar de una librería (`grupo:artefacto[:clasificador]`)
+    /// cuando el perfil trae varias; sin entrada gana la más alta
+    pub library_pins: BTreeMap<String, String>,
+    /// Tweakers de launchwrapper añadidos a los de la versión (p. ej. OptiFine
+    /// como tweaker); solo se usan en versiones que arrancan con launchwrapper
+    pub tweak_classes: Vec<String>,
+}
+
+impl LaunchOptions {
+    pub fn new(game_path: impl Into<PathBuf>, username: impl Into<String>) -> Self {
+        let game_path = game_path.into();
+        Self {
+            game_dir: game_path.clone(),
+            isolated: false,
+            game_path,
+            java_path: None,
+            min_memory_mb: None,
+            max_memory_mb: None,
+            jvm_args: Vec::new(),
+            legacy_jvm_args: default_legacy_jvm_args(),
+            username: username.into(),
+            uuid: OFFLINE_UUID.to_string(),
+            access_token: OFFLINE_TOKEN.to_string(),
+            user_type: "legacy".to_string(),
+            launcher_name: "proton".to_string(),
+            launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+            resolution: None,
+            fullscreen: false,
+            quick_play: None,
+            env: Vec::new(),
+            library_pins: BTreeMap::new(),
+            tweak_classes: Vec::new(),
+        }
+    }
+
+    /// Opciones con la configuración de una instancia (Java, memoria,
+    /// argumentos JVM y directorio del juego)
+    pub fn for_instance(
+        instance: &Instance,
+        game_path: impl Into<PathBuf>,
+        username: impl Into<String>,
+    ) -> Self {
+        let (min, max) = instance.memory();
+        let mut options = Self::new(game_path, username)
+            .game_dir(instance.game_dir())
+            .memory(min, max)
+            .jvm_args(instance.jvm_args().iter().cloned());
+        options.java_path = instance.java_path().map(Path::to_path_buf);
+        options.resolution = instance.resolution();
+        options
+    }
+
+    pub fn game_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.game_dir = dir.into();
+        self
+    }
+
+    pub fn isolated(mut self, enabled: bool) -> Self {
+        self.isolated = enabled;
+        self
+    }
+
+    /// Directorio del juego efectivo para una versión
+    pub fn game_dir_for(&self, version_id: &str) -> PathBuf {
+        if self.isolated {
+            self.game_path.join(ISOLATED_DIRS).join(version_id)
+        } else {
+            self.game_dir.clone()
+        }
+    }
+
+    pub fn java_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.java_path = Some(path.into());
+        self
+    }
+
+    pub fn memory(mut self, min_mb: Option<u32>, max_mb: Option<u32>) -> Self {
+        self.min_memory_mb = min_mb;
+        self.max_memory_mb = max_mb;
+        self
+    }
+
+    /// Si no se fijó `max_memory_mb`, usa el `-Xmx` sugerido para la RAM del equipo
+    pub fn auto_memory(mut self) -> Self {
+        if self.max_memory_mb.is_none() {
+            // Nunca por debajo de `-Xms`, o la JVM no arranca
+            self.max_memory_mb = system_memory().map(|memory| {
+                memory
+                    .recommended_max_heap_mb()
+                    .max(self.min_memory_mb.unwrap_or(0))
+            });
+        }
+        self
+    }
+
+    pub fn jvm_args(mut self, args: impl IntoIterator<Item = String>) -> Self {
+        self.jvm_args.extend(args);
+        self
+    }
+
+    /// Cuenta autenticada (por defecto se juega sin conexión)
+    pub fn account(
+        mut self,
+        uuid: impl Into<String>,
+        access_token: impl Into<String>,
+        user_type: impl Into<String>,
+    ) -> Self {
+        self.uuid = uuid.into();
+        self.access_token = access_token.into();
+        self.user_type = user_type.into();
+        self
+    }
+
+    pub fn resolution(mut self, width: u32, height: u32) -> Self {
+        self.resolution = Some(Resolution { width, height });
+        self
+    }
+
+    pub fn fullscreen(mut self, enabled: bool) -> Self {
+        self.fullscreen = enabled;
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.env.retain(|(existing, _)| *existing != key);
+        self.env.push((key, value.into()));
+        self
+    }
+
+    /// Fija la versión de una librería repetida en el classpath, p. ej.
+    /// `pin_library("com.google.guava:guava", "31.1-jre")`
+    pub fn pin_library(mut self, key: impl Into<String>, version: impl Into<String>) -> Self {
+        self.library_pins.insert(key.into(), version.into());
+        self
+    }
+
+    /// Añade un tweaker de launchwrapper después de los de la versión
+    pub fn tweak_class(mut self, class: impl Into<String>) -> Self {
+        let class = class.into();
+        if !self.tweak_classes.contains(&class) {
+            self.tweak_classes.push(class);
+        }
+        self
+    }
+
+    pub fn quick_play(mut self, target: QuickPlay) -> Self {
+        self.quick_play = Some(target);
+        self
+    }
+
+    /// Features de los argumentos condicionales que activan estas opciones
+    fn features(&self) -> HashSet<String> {
+        let mut features = HashSet::new();
+        if self.resolution.is_some() {
+            features.insert(CUSTOM_RESOLUTION_FEATURE.to_string());
+        }
+        if let Some(quick_play) = &self.quick_play {
+            features.insert(quick_play.feature().to_string());
+        }
+        features
+    }
+
+    pub fn launcher(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.launcher_name = name.into();
+        self.launcher_version = version.into();
+        self
+    }
+}
+
+/// Línea de comandos completa para iniciar el juego
+#[derive(Debug, Clone)]
+pub struct LaunchCommand {
+    pub java: PathBuf,
+    pub jvm_args: Vec<String>,
+    pub main_class: String,
+    pub game_args: Vec<String>,
+    /// Directorio desde el que se ejecuta el proceso
+    pub working_dir: PathBuf,
+    /// Variables de entorno extra; el resto se hereda del proceso actual
+    pub env: Vec<(String, String)>,
+    /// Librerías repetidas en varias versiones y la que se usó de cada una
+    pub classpath_conflicts: Vec<ClasspathConflict>,
+    // Posición de los argumentos con el token de acceso y cómo mostrarlos
+    redacted_jvm: Vec<(usize, String)>,
+    redacted_game: Vec<(usize, String)>,
+    // `resources/` del almacén, que las versiones anteriores a 1.6 leen desde
+    // su directorio de juego
+    legacy_resources: Option<PathBuf>,
+}
+
+/// Resuelve el comando de lanzamiento sin iniciar el juego, útil para
+/// depurar o copiar la línea de comandos
+pub fn build_launch_command(version: &NormalizedVersion, options: &LaunchOptions) -> LaunchCommand {
+    LaunchCommand::build(version, options)
+}
+
+/// Argumentos JVM que el launcher oficial usa con `minecraftArguments`
+fn default_legacy_jvm_args() -> Vec<String> {
+    let mut jvm = Vec::new();
+    match mojang_os_name() {
+        // LWJGL 2 necesita el hilo principal en macOS
+        "osx" => jvm.push("-XstartOnFirstThread".to_string()),
+        "windows" => jvm.push(
+            "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump"
+                .to_string(),
+        ),
+        _ => {}
+    }
+    if cfg!(target_arch = "x86") {
+        jvm.push("-Xss1M".to_string());
+    }
+    jvm.extend(
+        [
+            "-Djava.library.path=${natives_directory}",
+            "-Dminecraft.launcher.brand=${launcher_name}",
+            "-Dminecraft.launcher.version=${launcher_version}",
+            "-cp",
+            "${classpath}",
+        ]
+        .map(String::from),
+    );
+    jvm
+}
+
+/// Java configurado, el de `JAVA_HOME` o el del `PATH`
+pub(crate) fn resolve_java(configured: Option<&Path>) -> PathBuf {
+    if let Some(path) = configured {
+        return path.to_path_buf();
+    }
+
+    std::env::var_os("JAVA_HOME")
+        .map(|home| PathBuf::from(home).join("bin").join(JAVA_BINARY))
+        .filter(|java| java.exists())
+        .unwrap_or_else(|| PathBuf::from(JAVA_BINARY))
+}
+
+/// Entrecomilla un argumento para mostrarlo en una shell POSIX
+fn quote_arg(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_./:=,+@%".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+impl LaunchCommand {
+    pub fn build(version: &NormalizedVersion, options: &LaunchOptions) -> Self {
+        let game_dir = options.game_dir_for(&version.id);
+        let natives_dir = options.game_path.join("natives").join(&version.id);
+        let libraries_dir = options.game_path.join("libraries");
+        let assets_dir = options.game_path.join("assets");
+        let client_jar = options
+            .game_path
+            .join("versions")
+            .join(&version.id)
+            .join(format!("{}.jar", version.id));
+
+        let (libraries, classpath_conflicts) =
+            classpath::resolve_classpath(&version.libraries, &options.library_pins);
+        let mut classpath: Vec<String> = libraries
+            .iter()
+            .map(|lib| libraries_dir.join(&lib.path).to_string_lossy().into_owned())
+            .collect();
+        classpath.push(client_jar.to_string_lossy().into_owned());
+
+        // Versiones antiguas leen los assets desde un directorio virtual, o
+        // desde `resources` en su directorio de juego (el descargador los deja
+        // en el almacén y `spawn` los copia si es otro directorio)
+        let legacy_resources = (version.asset_index.id == PRE_1_6_ASSETS)
+            .then(|| options.game_path.join(RESOURCES_DIR))
+            .filter(|resources| *resources != game_dir.join(RESOURCES_DIR));
+        let game_assets = match version.asset_index.id.as_str() {
+            PRE_1_6_ASSETS => game_dir.join(RESOURCES_DIR),
+            id => assets_dir.join("virtual").join(id),
+        };
+        // 1.6 pide la sesión como `token:<token>:<uuid>` para conectarse a servidores
+        let session = if options.access_token == OFFLINE_TOKEN {
+            OFFLINE_TOKEN.to_string()
+        } else {
+            format!("token:{}:{}", options.access_token, options.uuid)
+        };
+
+        let path = |p: &Path| p.to_string_lossy().into_owned();
+        let variables: Vec<(&str, String)> = vec![
+            ("natives_directory", path(&natives_dir)),
+            ("library_directory", path(&libraries_dir)),
+            ("classpath", classpath.join(CLASSPATH_SEPARATOR)),
+            ("classpath_separator", CLASSPATH_SEPARATOR.to_string()),
+            ("launcher_name", options.launcher_name.clone()),
+            ("launcher_version", options.launcher_version.clone()),
+            ("version_name", version.id.clone()),
+            (
+                "version_type",
+                version
+                    .version_type
+                    .as_ref()
+                    .map_or("release", |t| t.as_str())
+                    .to_string(),
+            ),
+            ("game_directory", path(&game_dir)),
+            ("assets_root", path(&assets_dir)),
+            ("game_assets", path(&game_assets)),
+            ("assets_index_name", version.asset_index.id.clone()),
+            ("auth_player_name", options.username.clone()),
+            ("auth_uuid", options.uuid.clone()),
+            ("auth_access_token", options.access_token.clone()),
+            ("auth_session", session),
+            ("user_type", options.user_type.clone()),
+            ("user_properties", "{}".to_string()),
+            ("clientid", String::new()),
+            ("auth_xuid", String::new()),
+            (
+                "quickPlaySingleplayer",
+                match &options.quick_play {
+                    Some(QuickPlay::Singleplayer(world)) => world.clone(),
+                    _ => String::new(),
+                },
+            ),
+            (
+                "quickPlayMultiplayer",
+                options
+                    .quick_play
+                    .as_ref()
+                    .and_then(QuickPlay::multiplayer_address)
+                    .unwrap_or_default(),
+            ),
+            (
+                "quickPlayRealms",
+                match &options.quick_play {
+                    Some(QuickPlay::Realms(id)) => id.clone(),
+                    _ => String::new(),
+                },
+            ),
+            (
+                "resolution_width",
+                options
+                    .resolution
+                    .map(|r| r.width.to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "resolution_height",
+                options
+                    .resolution
+                    .map(|r| r.height.to_string())
+                    .unwrap_or_default(),
+            ),
+        ];
+        // Las mismas variables con el token oculto, para `redacted`
+        let has_token = !options.access_token.is_empty() && options.access_token != OFFLINE_TOKEN;
+        let hidden_variables: Vec<(&str, String)> = variables
+            .iter()
+            .map(|(key, value)| {
+                let value = match *key {
+                    "auth_access_token" if has_token => REDACTED.to_string(),
+                    "auth_session" if has_token => format!("token:{REDACTED}:{}", options.uuid),
+                    _ => value.clone(),
+                };
+                (*key, value)
+            })
+            .collect();
+        let fill = |variables: &[(&str, String)], arg: &String| {
+            variables.iter().fold(arg.clone(), |acc, (key, value)| {
+                acc.replace(&format!("${{{key}}}"), value)
+            })
+        };
+        let substitute = |arg: &String| -> ResolvedArg {
+            let value = fill(&variables, arg);
+            let shown = fill(&hidden_variables, arg);
+            let hidden = (shown != value).then_some(shown);
+            (value, hidden)
+        };
+
+        // Sin `${classpath}` propio se parte de los argumentos del launcher
+        let declares_classpath = version
+            .arguments
+            .jvm
+            .iter()
+            .any(|arg| arg.contains("${classpath}"));
+        let launcher_jvm = options
+            .legacy_jvm_args
+            .iter()
+            .filter(|_| !declares_classpath);
+        let mut version_jvm: Vec<ResolvedArg> = launcher_jvm
+            .chain(&version.arguments.jvm)
+            .map(substitute)
+            .collect();
+        if let Some(logging) = &version.logging {
+            version_jvm.push((logging.jvm_argument(&options.game_path), None));
+        }
+
+        let features = options.features();
+        let game_templates = version.arguments.game.iter().chain(
+            version
+                .arguments
+                .game_features
+                .iter()
+                .filter(|arg| arg.applies(&features))
+                .flat_map(|arg| arg.values.iter()),
+        );
+        let (mut game_args, redacted_game) = split_redacted(game_templates.map(substitute));
+
+        // Las versiones con `minecraftArguments` no declaran la resolución
+        let declares_resolution = version
+            .arguments
+            .game_features
+            .iter()
+            .any(|arg| arg.features.contains_key(CUSTOM_RESOLUTION_FEATURE));
+        if let Some(resolution) = options.resolution
+            && !declares_resolution
+        {
+            game_args.extend([
+                "--width".to_string(),
+                resolution.width.to_string(),
+                "--height".to_string(),
+                resolution.height.to_string(),
+            ]);
+        }
+        // Antes de Quick Play solo se podía entrar a un servidor con `--server`
+        if let Some(quick_play) = &options.quick_play {
+            let declares_quick_play = version
+                .arguments
+                .game_features
+                .iter()
+                .any(|arg| arg.features.contains_key(quick_play.feature()));
+            match quick_play {
+                _ if declares_quick_play => {}
+                QuickPlay::Multiplayer { host, port } => {
+                    game_args.extend(["--server".to_string(), host.clone()]);
+                    game_args.extend(["--port".to_string(), port.unwrap_or(25565).to_string()]);
+                }
+                other => warn!("Version {} does not support {other:?}", version.id),
+            }
+        }
+        if !options.tweak_classes.is_empty() {
+            if version.main_class == LAUNCHWRAPPER_MAIN_CLASS {
+                let declared = version.arguments.tweak_classes();
+                for class in &options.tweak_classes {
+                    if !declared.contains(&class.as_str()) {
+                        game_args.extend([TWEAK_CLASS_ARG.to_string(), class.clone()]);
+                    }
+                }
+            } else {
+                warn!(
+                    "Version {} does not use launchwrapper, ignoring tweakers {:?}",
+                    version.id, options.tweak_classes
+                );
+            }
+        }
+        if options.fullscreen && !game_args.iter().any(|arg| arg == "--fullscreen") {
+            game_args.push("--fullscreen".to_string());
+        }
+
+        let mut overrides = Vec::new();
+        if let Some(min) = options.min_memory_mb {
+            overrides.push(format!("-Xms{min}M"));
+        }
+        if let Some(max) = options.max_memory_mb {
+            overrides.push(format!("-Xmx{max}M"));
+        }
+        overrides.extend(options.jvm_args.iter().cloned());
+        let jvm = version_jvm
+            .into_iter()
+            .chain(overrides.into_iter().map(|arg| (arg, None)));
+        let (jvm_args, redacted_jvm) =
+            split_redacted(keep_last_flags(jvm.collect(), |(arg, _)| arg));
+
+        Self {
+            java: resolve_java(options.java_path.as_deref()),
+            jvm_args,
+            main_class: version.main_class.clone(),
+            game_args,
+            working_dir: game_dir,
+            env: options.env.clone(),
+            classpath_conflicts,
+            redacted_jvm,
+            redacted_game,
+            legacy_resources,
+        }
+    }
+
+    /// Copia con el token de acceso sustituido por `<redacted>`. Solo se
+    /// tocan los argumentos en los que se sustituyó el token, así que un token
+    /// corto o común no altera rutas ni otros valores.
+    pub fn redacted(&self) -> Self {
+        let hide = |args: &[String], redacted: &[(usize, String)]| {
+            let mut args = args.to_vec();
+            for (index, shown) in redacted {
+                if let Some(arg) = args.get_mut(*index) {
+                    *arg = shown.clone();
+                }
+            }
+            args
+        };
+
+        Self {
+            jvm_args: hide(&self.jvm_args, &self.redacted_jvm),
+            game_args: hide(&self.game_args, &self.redacted_game),
+            redacted_jvm: Vec::new(),
+            redacted_game: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Todos los argumentos en orden: JVM, clase principal y juego
+    pub fn args(&self) -> Vec<String> {
+        let mut args = self.jvm_args.clone();
+        args.push(self.main_class.clone());
+        args.extend(self.game_args.iter().cloned());
+        args
+    }
+
+    /// Línea de comandos lista para copiar en una terminal
+    pub fn to_command_line(&self) -> String {
+        self.env
+            .iter()
+            .map(|(key, value)| format!("{key}={}", quote_arg(value)))
+            .chain(
+                std::iter::once(self.java.to_string_lossy().into_owned())
+                    .chain(self.args())
+                    .map(|arg| quote_arg(&arg)),
+            )
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Inicia el juego capturando stdout y stderr
+    pub async fn spawn(&self) -> Result<GameProcess, ProtonError> {
+        // Java solo ve el disco local
+        let fs = file_system();
+        if !fs.is_local() {
+            return Err(ProtonError::Other(
+                "The game can only run from the local file system".to_string(),
+            ));
+        }
+        fs.create_dir_all(&self.working_dir).await?;
+        if let Some(resources) = &self.legacy_resources
+            && fs.exists(resources).await
+        {
+            copy_dir(resources, &self.working_dir.join(RESOURCES_DIR)).await?;
+        }
+        GameProcess::spawn(self)
+    }
+}
+
+/// Clave de un flag JVM que solo puede aparecer una vez (`-Xmx`, `-XX:Foo`,
+/// `-Dprop`), o `None` si el argumento puede repetirse
+fn jvm_flag_key(arg: &str) -> Option<String> {
+    for prefix in ["-Xmx", "-Xms", "-Xss", "-Xmn"] {
+        if arg.starts_with(prefix) {
+            return Some(prefix.to_string());
+        }
+    }
+
+    if let Some(rest) = arg.strip_prefix("-XX:") {
+        let name = rest.trim_start_matches(['+', '-']);
+        let name = name.split('=').next().unwrap_or(name);
+        return Some(format!("-XX:{name}"));
+    }
+
+    arg.strip_prefix("-D")
+        .map(|rest| format!("-D{}", rest.split('=').next().unwrap_or(rest)))
+}
+
+/// Quita de `args` los flags JVM repetidos salvo su última aparición, para
+/// que los argumentos del usuario prevalezcan sobre los de la versión
+fn keep_last_flags<T>(mut args: Vec<T>, arg: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut seen = HashSet::new();
+
+    // Recorrer desde el final para conservar la última aparición de cada flag
+    args.reverse();
+    args.retain(|item| jvm_flag_key(arg(item)).is_none_or(|key| seen.insert(key)));
+    args.reverse();
+    args
+}
+
+/// Argumento ya sustituido y, si lleva el token de acceso, cómo mostrarlo
+type ResolvedArg = (String, Option<String>);
+
+/// Separa los argumentos de la posición de los que hay que ocultar
+fn split_redacted(
+    args: impl IntoIterator<Item = ResolvedArg>,
+) -> (Vec<String>, Vec<(usize, String)>) {
+    let mut values = Vec::new();
+    let mut redacted = Vec::new();
+    for (index, (value, hidden)) in args.into_iter().enumerate() {
+        if let Some(hidden) = hidden {
+            redacted.push((index, hidden));
+        }
+        values.push(value);
+    }
+    (values, redacted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MojangVersionDetails;
+    use serde_json::{Value, json};
+
+    fn version(arguments: (&str, Value), main_class: &str, assets: &str) -> NormalizedVersion {
+        let library = |name: &str, path: &str| {
+            json!({
+                "name": name,
+                "downloads": { "artifact": { "path": path, "sha1": "", "size": 0, "url": "" } },
+            })
+        };
+        let mut version = json!({
+            "id": "test",
+            "type": "release",
+            "mainClass": main_class,
+            "minimumLauncherVersion": 21,
+            "releaseTime": "2024-01-01T00:00:00+00:00",
+            "time": "2024-01-01T00:00:00+00:00",
+            "assets": assets,
+            "assetIndex": { "id": assets, "sha1": "", "size": 0, "totalSize": 0, "url": "" },
+            "downloads": { "client": { "sha1": "", "size": 0, "url": "" } },
+            "libraries": [
+                library("org.ow2.asm:asm:9.3", "org/ow2/asm/asm/9.3/asm-9.3.jar"),
+                library("org.ow2.asm:asm:9.7.1", "org/ow2/asm/asm/9.7.1/asm-9.7.1.jar"),
+            ],
+        });
+        version[arguments.0] = arguments.1;
+        let details: MojangVersionDetails = serde_json::from_value(version).unwrap();
+        NormalizedVersion::try_from(details).unwrap()
+    }
+
+    fn modern() -> NormalizedVersion {
+        let feature = |name: &str, values: Value| json!({ "rules": [{ "action": "allow", "features": { name: true } }], "value": values });
+        let arguments = json!({
+            "game": [
+                "--username", "${auth_player_name}",
+                "--gameDir", "${game_directory}",
+                "--accessToken", "${auth_access_token}",
+                feature("has_custom_resolution", json!(["--width", "${resolution_width}", "--height", "${resolution_height}"])),
+                feature("is_quick_play_singleplayer", json!(["--quickPlaySingleplayer", "${quickPlaySingleplayer}"])),
+                feature("is_quick_play_multiplayer", json!(["--quickPlayMultiplayer", "${quickPlayMultiplayer}"])),
+            ],
+            "jvm": ["-Djava.library.path=${natives_directory}", "-Xss1M", "-cp", "${classpath}"],
+        });
+        version(
+            ("arguments", arguments),
+            "net.minecraft.client.main.Main",
+            "17",
+        )
+    }
+
+    fn legacy() -> NormalizedVersion {
+        let arguments = "--username ${auth_player_name} --session ${auth_session} --gameDir ${game_directory} --assetsDir ${game_assets} --tweakClass net.minecraftforge.fml.common.launcher.FMLTweaker";
+        version(
+            ("minecraftArguments", Value::from(arguments)),
+            LAUNCHWRAPPER_MAIN_CLASS,
+            PRE_1_6_ASSETS,
+        )
+    }
+
+    fn after<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+        let index = args.iter().position(|arg| arg == flag)?;
+        args.get(index + 1).map(String::as_str)
+    }
+
+    #[test]
+    fn user_jvm_flags_replace_the_version_ones() {
+        let options = LaunchOptions::new("store", "Steve")
+            .memory(Some(512), Some(4096))
+            .jvm_args(["-Xss2M".to_string(), "-XX:-UseG1GC".to_string()]);
+        let command = LaunchCommand::build(&modern(), &options);
+
+        assert!(!command.jvm_args.iter().any(|arg| arg == "-Xss1M"));
+        assert!(command.jvm_args.ends_with(&[
+            "-Xms512M".to_string(),
+            "-Xmx4096M".to_string(),
+            "-Xss2M".to_string(),
+            "-XX:-UseG1GC".to_string(),
+        ]));
+        assert_eq!(after(&command.game_args, "--username"), Some("Steve"));
+    }
+
+    #[test]
+    fn merge_keeps_the_last_occurrence_of_each_flag() {
+        let strings = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let merged = keep_last_flags(
+            strings(&[
+                "-Xmx1G",
+                "-XX:+UseG1GC",
+                "-Dlog4j.configurationFile=a.xml",
+                "-Dfml.ignorePatchDiscrepancies",
+                "-cp",
+                "${classpath}",
+                "-Xmx4G",
+                "-XX:-UseG1GC",
+                "-Dlog4j.configurationFile=b.xml",
+            ]),
+            String::as_str,
+        );
+
+        assert_eq!(
+            merged,
+            strings(&[
+                "-Dfml.ignorePatchDiscrepancies",
+                "-cp",
+                "${classpath}",
+                "-Xmx4G",
+                "-XX:-UseG1GC",
+                "-Dlog4j.configurationFile=b.xml",
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn instance_settings_reach_the_command() {
+        use crate::filesystem::{MemoryFs, with_file_system};
+        use crate::instance::InstanceConfig;
+
+        let config = InstanceConfig::new("Modded", "test")
+            .memory(Some(1024), Some(2048))
+            .jvm_arg("-XX:+UseZGC")
+            .java_path("/opt/java/bin/java");
+        let instance = with_file_system(
+            std::sync::Arc::new(MemoryFs::new()),
+            Instance::create("/instances/modded", config),
+        )
+        .await
+        .unwrap();
+        let options = LaunchOptions::for_instance(&instance, "store", "Steve");
+        let command = LaunchCommand::build(&modern(), &options);
+
+        assert_eq!(command.java, Path::new("/opt/java/bin/java"));
+        assert_eq!(
+            command.working_dir,
+            Path::new("/instances/modded/.minecraft")
+        );
+        assert!(command.jvm_args.ends_with(&[
+            "-Xms1024M".to_string(),
+            "-Xmx2048M".to_string(),
+            "-XX:+UseZGC".to_string(),
+        ]));
+    }
+
+    #[test]
+    fn dry_run_hides_only_the_token_arguments() {
+        // Un token que también aparece en rutas y en el id de la versión
+        let options = LaunchOptions::new("store", "Steve").account("uuid-1", "test", "msa");
+        let command = build_launch_command(&modern(), &options);
+        assert_eq!(after(&command.game_args, "--accessToken"), Some("test"));
+
+        let redacted = command.redacted();
+        assert_eq!(
+            after(&redacted.game_args, "--accessToken"),
+            Some("<redacted>")
+        );
+        let natives = Path::new("store").join("natives").join("test");
+        assert!(
+            redacted
+                .jvm_args
+                .contains(&format!("-Djava.library.path={}", natives.display()))
+        );
+        assert_eq!(redacted.jvm_args, command.jvm_args);
+        assert!(
+            redacted
+                .to_command_line()
+                .contains("--accessToken '<redacted>'")
+        );
+
+        // Sin cuenta no hay nada que ocultar
+        let offline = build_launch_command(&modern(), &LaunchOptions::new("store", "Steve"));
+        assert_eq!(offline.redacted().game_args, offline.game_args);
+    }
+
+    #[test]
+    fn resolution_and_fullscreen_use_the_declared_arguments() {
+        let options = LaunchOptions::new("store", "Steve")
+            .resolution(854, 480)
+            .fullscreen(true);
+        let command = LaunchCommand::build(&modern(), &options);
+        assert_eq!(after(&command.game_args, "--width"), Some("854"));
+        assert_eq!(after(&command.game_args, "--height"), Some("480"));
+        assert_eq!(
+            command
+                .game_args
+                .iter()
+                .filter(|arg| *arg == "--width")
+                .count(),
+            1
+        );
+        assert!(command.game_args.ends_with(&["--fullscreen".to_string()]));
+
+        // Sin la feature en el JSON se añaden igualmente
+        let old = version(
+            (
+                "minecraftArguments",
+                Value::from("--username ${auth_player_name}"),
+            ),
+            "net.minecraft.client.main.Main",
+            "legacy",
+        );
+        let command = LaunchCommand::build(&old, &options);
+        assert_eq!(after(&command.game_args, "--width"), Some("854"));
+        assert_eq!(after(&command.game_args, "--height"), Some("480"));
+
+        let command = LaunchCommand::build(&modern(), &LaunchOptions::new("store", "Steve"));
+        assert!(!command.game_args.iter().any(|arg| arg == "--width"));
+        assert!(!command.game_args.iter().any(|arg| arg == "--fullscreen"));
+    }
+
+    #[test]
+    fn quick_play_fills_the_declared_target() {
+        let options = LaunchOptions::new("store", "Steve").quick_play(QuickPlay::Multiplayer {
+            host: "mc.example.org".to_string(),
+            port: Some(25566),
+        });
+        let command = LaunchCommand::build(&modern(), &options);
+        assert_eq!(
+            after(&command.game_args, "--quickPlayMultiplayer"),
+            Some("mc.example.org:25566")
+        );
+        assert!(
+            !command
+                .game_args
+                .iter()
+                .any(|arg| arg == "--quickPlaySingleplayer")
+        );
+        // Con el argumento declarado no hace falta `--server`
+        assert!(!command.game_args.iter().any(|arg| arg == "--server"));
+
+        let options = LaunchOptions::new("store", "Steve")
+            .quick_play(QuickPlay::Singleplayer("My World".to_string()));
+        let command = LaunchCommand::build(&modern(), &options);
+        assert_eq!(
+            after(&command.game_args, "--quickPlaySingleplayer"),
+            Some("My World")
+        );
+    }
+
+    #[test]
+    fn versions_without_quick_play_join_with_server() {
+        let old = version(
+            (
+                "minecraftArguments",
+                Value::from("--username ${auth_player_name}"),
+            ),
+            "net.minecraft.client.main.Main",
+            "legacy",
+        );
+        let options = LaunchOptions::new("store", "Steve").quick_play(QuickPlay::Multiplayer {
+            host: "play.example.org".to_string(),
+            port: None,
+        });
+        let command = LaunchCommand::build(&old, &options);
+        assert_eq!(
+            after(&command.game_args, "--server"),
+            Some("play.example.org")
+        );
+        assert_eq!(after(&command.game_args, "--port"), Some("25565"));
+
+        // Un mundo no se puede abrir sin Quick Play: no se añade nada
+        let options = LaunchOptions::new("store", "Steve")
+            .quick_play(QuickPlay::Singleplayer("My World".to_string()));
+        let command = LaunchCommand::build(&old, &options);
+        assert_eq!(command.game_args, ["--username", "Steve"]);
+    }
+
+    #[test]
+    fn tweakers_chain_without_repeating_the_declared_one() {
+        let options = LaunchOptions::new("store", "Alex")
+            .tweak_class("net.minecraftforge.fml.common.launcher.FMLTweaker")
+            .tweak_class("com.mumfrey.liteloader.launch.LiteLoaderTweaker");
+        let command = LaunchCommand::build(&legacy(), &options);
+
+        assert_eq!(command.main_class, LAUNCHWRAPPER_MAIN_CLASS);
+        let tweakers: Vec<&str> = command
+            .game_args
+            .iter()
+            .enumerate()
+            .filter(|(_, arg)| *arg == TWEAK_CLASS_ARG)
+            .map(|(index, _)| command.game_args[index + 1].as_str())
+            .collect();
+        assert_eq!(
+            tweakers,
+            [
+                "net.minecraftforge.fml.common.launcher.FMLTweaker",
+                "com.mumfrey.liteloader.launch.LiteLoaderTweaker"
+            ]
+        );
+
+        // Sin launchwrapper no hay a quién pasar los tweakers
+        let command = LaunchCommand::build(&modern(), &options);
+        assert!(!command.game_args.iter().any(|arg| arg == TWEAK_CLASS_ARG));
+    }
+
+    #[test]
+    fn pre_1_6_versions_read_assets_from_resources() {
+        let options = LaunchOptions::new("store", "Steve").game_dir("instance");
+        let command = LaunchCommand::build(&legacy(), &options);
+        let resources = Path::new("instance").join(RESOURCES_DIR);
+        assert_eq!(
+            after(&command.game_args, "--assetsDir"),
+            Some(resources.to_string_lossy().as_ref())
+        );
+        // `spawn` copia los del almacén al directorio del juego
+        assert_eq!(
+            command.legacy_resources,
+            Some(Path::new("store").join(RESOURCES_DIR))
+        );
+
+        // Si el juego corre en el almacén no hay nada que copiar
+        let options = LaunchOptions::new("store", "Steve").game_dir("store");
+        let command = LaunchCommand::build(&legacy(), &options);
+        assert_eq!(command.legacy_resources, None);
+
+        // Desde 1.6 se usan los assets virtuales del índice
+        let command = LaunchCommand::build(&modern(), &options);
+        assert_eq!(command.legacy_resources, None);
+    }
+
+    #[test]
+    fn minecraft_arguments_are_split_and_substituted() {
+        let options = LaunchOptions::new("store", "Steve")
+            .game_dir("instance")
+            .account("uuid-1", "secret-token", "msa");
+        let command = LaunchCommand::build(&legacy(), &options);
+        assert_eq!(after(&command.game_args, "--username"), Some("Steve"));
+        assert_eq!(
+            after(&command.game_args, "--session"),
+            Some("token:secret-token:uuid-1")
+        );
+        assert_eq!(after(&command.game_args, "--gameDir"), Some("instance"));
+        assert!(!command.game_args.iter().any(|arg| arg.contains("${")));
+
+        let redacted = command.redacted();
+        assert_eq!(
+            after(&redacted.game_args, "--session"),
+            Some("token:<redacted>:uuid-1")
+        );
+    }
+
+    #[test]
+    fn minecraft_arguments_use_the_launcher_jvm_args() {
+        let mut options = LaunchOptions::new("store", "Steve");
+        let command = LaunchCommand::build(&legacy(), &options);
+        assert_eq!(
+            after(&command.jvm_args, "-cp"),
+            command.jvm_args.last().map(String::as_str)
+        );
+        assert!(
+            command
+                .jvm_args
+                .contains(&"-Dminecraft.launcher.brand=proton".to_string())
+        );
+
+        options.legacy_jvm_args = vec!["-Dlegacy=${launcher_name}".to_string()];
+        let command = LaunchCommand::build(&legacy(), &options);
+        assert_eq!(command.jvm_args, ["-Dlegacy=proton"]);
+
+        // Las versiones que declaran su classpath no los reciben
+        let command = LaunchCommand::build(&modern(), &options);
+        assert!(
+            !command
+                .jvm_args
+                .iter()
+                .any(|arg| arg.starts_with("-Dlegacy"))
+        );
+    }
+
+    #[test]
+    fn isolated_versions_get_their_own_game_dir() {
+        let options = LaunchOptions::new("store", "Steve").isolated(true);
+        let command = LaunchCommand::build(&modern(), &options);
+
+        let isolated = Path::new("store").join(ISOLATED_DIRS).join("test");
+        assert_eq!(command.working_dir, isolated);
+        assert_eq!(
+            after(&command.game_args, "--gameDir"),
+            Some(isolated.to_string_lossy().as_ref())
+        );
+        // Librerías y nativos siguen saliendo del almacén compartido
+        let natives = Path::new("store").join("natives").join("test");
+        assert!(
+            command
+                .jvm_args
+                .contains(&format!("-Djava.library.path={}", natives.display()))
+        );
+        let classpath = after(&command.jvm_args, "-cp").unwrap();
+        assert!(classpath.starts_with(&Path::new("store").join("libraries").display().to_string()));
+
+        let shared = LaunchCommand::build(&modern(), &options.isolated(false));
+        assert_eq!(shared.working_dir, Path::new("store"));
+    }
+}
@@ -0,0 +1,246 @@
+use log::warn;
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc::{Receiver, channel};
+
+use super::LaunchCommand;
+use crate::errors::ProtonError;
+use crate::utilities::forward_lines;
+
+// Líneas en espera por stream; si nadie las consume, las nuevas se descartan
+const OUTPUT_BUFFER: usize = 1024;
+
+/// Resultado de una ejecución del juego
+#[derive(Debug, Clone, Copy)]
+pub struct GameExit {
+    pub status: ExitStatus,
+    /// Código de salida; `None` si el proceso terminó por una señal
+    pub code: Option<i32>,
+    pub duration: Duration,
+}
+
+impl GameExit {
+    pub fn crashed(&self) -> bool {
+        !self.status.success()
+    }
+}
+
+/// Qué hacer cuando el juego termina
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    /// Relanzar tras un cierre con error, hasta `max_restarts` veces. La espera
+    /// empieza en `backoff` y se duplica en cada reinicio.
+    OnCrash {
+        max_restarts: u32,
+        backoff: Duration,
+    },
+}
+
+/// Proceso del juego en ejecución con su salida capturada línea a línea
+pub struct GameProcess {
+    child: Child,
+    started_at: Instant,
+    stdout: Option<Receiver<String>>,
+    stderr: Option<Receiver<String>>,
+}
+
+impl GameProcess {
+    pub(crate) fn spawn(command: &LaunchCommand) -> Result<Self, ProtonError> {
+        let mut child = Command::new(&command.java)
+            .args(&command.jvm_args)
+            .arg(&command.main_class)
+            .args(&command.game_args)
+            .current_dir(&command.working_dir)
+            .envs(command.env.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take().map(|out| {
+            let (tx, rx) = channel(OUTPUT_BUFFER);
+            forward_lines(out, tx);
+            rx
+        });
+        let stderr = child.stderr.take().map(|err| {
+            let (tx, rx) = channel(OUTPUT_BUFFER);
+            forward_lines(err, tx);
+            rx
+        });
+
+        Ok(Self {
+            child,
+            started_at: Instant::now(),
+            stdout,
+            stderr,
+        })
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// Líneas de stdout; solo se pueden tomar una vez. Si el receptor se queda
+    /// atrás más de `OUTPUT_BUFFER` líneas, las siguientes se pierden.
+    pub fn take_stdout(&mut self) -> Option<Receiver<String>> {
+        self.stdout.take()
+    }
+
+    /// Líneas de stderr; solo se pueden tomar una vez
+    pub fn take_stderr(&mut self) -> Option<Receiver<String>> {
+        self.stderr.take()
+    }
+
+    pub async fn kill(&mut self) -> Result<(), ProtonError> {
+        Ok(self.child.kill().await?)
+    }
+
+    /// Espera a que el juego termine
+    pub async fn wait(&mut self) -> Result<GameExit, ProtonError> {
+        let status = self.child.wait().await?;
+        Ok(GameExit {
+            status,
+            code: status.code(),
+            duration: self.started_at.elapsed(),
+        })
+    }
+}
+
+impl LaunchCommand {
+    /// Ejecuta el juego aplicando `policy`. `on_spawn` recibe cada proceso
+    /// nuevo para consumir su salida. Devuelve el resultado de cada ejecución.
+    pub async fn run_supervised<F>(
+        &self,
+        policy: RestartPolicy,
+        mut on_spawn: F,
+    ) -> Result<Vec<GameExit>, ProtonError>
+    where
+        F: FnMut(&mut GameProcess),
+    {
+        let mut exits = Vec::new();
+
+        loop {
+            let mut process = self.spawn().await?;
+            on_spawn(&mut process);
+            let exit = process.wait().await?;
+            exits.push(exit);
+
+            let RestartPolicy::OnCrash {
+                max_restarts,
+                backoff,
+            } = policy
+            else {
+                return Ok(exits);
+            };
+            let restarts = exits.len() as u32 - 1;
+            if !exit.crashed() || restarts >= max_restarts {
+                return Ok(exits);
+            }
+
+            let delay = backoff.saturating_mul(2u32.saturating_pow(restarts));
+            warn!(
+                "Game exited with {:?} after {:?}, restarting in {delay:?} ({}/{max_restarts})",
+                exit.code,
+                exit.duration,
+                restarts + 1
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::launcher::LaunchOptions;
+    use crate::testing::fixture_version;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    /// `java` falso que falla hasta su ejecución número `succeed_on`
+    fn flaky_java(root: &Path, succeed_on: u32) -> LaunchCommand {
+        std::fs::create_dir_all(root).unwrap();
+        let java = root.join("java");
+        let script = format!(
+            "#!/bin/sh\necho run >> {runs:?}\n\
+             [ \"$(wc -l < {runs:?})\" -ge {succeed_on} ] && exit 0\nexit 3\n",
+            runs = root.join("runs")
+        );
+        std::fs::write(&java, script).unwrap();
+        std::fs::set_permissions(&java, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut options = LaunchOptions::new(root.join("game"), "Steve");
+        options.java_path = Some(java);
+        LaunchCommand::build(&fixture_version("http://127.0.0.1:1"), &options)
+    }
+
+    #[tokio::test]
+    async fn crashes_are_restarted_until_the_limit() {
+        let root = std::env::temp_dir().join(format!("proton-restart-{}", std::process::id()));
+        let policy = RestartPolicy::OnCrash {
+            max_restarts: 2,
+            backoff: Duration::from_millis(1),
+        };
+
+        let recovered = flaky_java(&root.join("recovered"), 2);
+        let mut spawned = 0;
+        let exits = recovered
+            .run_supervised(policy, |_| spawned += 1)
+            .await
+            .unwrap();
+        let codes: Vec<_> = exits.iter().map(|exit| exit.code).collect();
+        assert_eq!(codes, [Some(3), Some(0)]);
+        assert_eq!(spawned, 2);
+
+        let broken = flaky_java(&root.join("broken"), 10);
+        let exits = broken.run_supervised(policy, |_| {}).await.unwrap();
+        assert_eq!(exits.len(), 3);
+        assert!(exits.iter().all(GameExit::crashed));
+
+        // Sin política el primer fallo es definitivo
+        let once = flaky_java(&root.join("once"), 10);
+        let exits = once
+            .run_supervised(RestartPolicy::Never, |_| {})
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(exits.len(), 1);
+        assert_eq!(exits[0].code, Some(3));
+    }
+
+    #[tokio::test]
+    async fn env_vars_are_merged_with_the_parent_environment() {
+        let root = std::env::temp_dir().join(format!("proton-env-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let java = root.join("java");
+        std::fs::write(
+            &java,
+            "#!/bin/sh\necho \"$MESA_GL_VERSION_OVERRIDE:$PATH\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&java, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let mut options = LaunchOptions::new(root.join("game"), "Steve")
+            .env("MESA_GL_VERSION_OVERRIDE", "3.3")
+            .env("MESA_GL_VERSION_OVERRIDE", "4.5");
+        options.java_path = Some(java.clone());
+        let command = LaunchCommand::build(&fixture_version("http://127.0.0.1:1"), &options);
+
+        let mut process = command.spawn().await.unwrap();
+        let mut stdout = process.take_stdout().unwrap();
+        process.wait().await.unwrap();
+        let line = stdout.recv().await;
+
+        std::fs::remove_dir_all(&root).unwrap();
+        // La última asignación gana y el resto del entorno se hereda
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(line, Some(format!("4.5:{path}")));
+        assert!(
+            command
+                .to_command_line()
+                .starts_with(&format!("MESA_GL_VERSION_OVERRIDE=4.5 {}", java.display()))
+        );
+    }
+}
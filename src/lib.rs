@@ -1,13 +1,121 @@
+// Módulos con sistema de archivos, procesos o runtime de tokio: solo nativos.
+// En wasm32 quedan los tipos, la resolución de metadatos y la planificación.
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(not(target_arch = "wasm32"))]
+mod config;
+mod diagnostics;
+#[cfg(not(target_arch = "wasm32"))]
 mod downloaders;
+mod endpoints;
 mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(not(target_arch = "wasm32"))]
+mod filesystem;
+#[cfg(feature = "uniffi")]
+pub mod foreign;
+#[cfg(not(target_arch = "wasm32"))]
+mod forge;
+mod http;
+#[cfg(not(target_arch = "wasm32"))]
+mod instance;
+#[cfg(not(target_arch = "wasm32"))]
+mod launcher;
+#[cfg(not(target_arch = "wasm32"))]
+mod liteloader;
 mod manifest;
+mod maven;
+#[cfg(feature = "napi")]
+pub mod node;
+mod plan;
+#[cfg(feature = "cli")]
+mod progress;
+#[cfg(not(target_arch = "wasm32"))]
+mod server;
+#[cfg(not(target_arch = "wasm32"))]
+mod system;
+#[cfg(all(any(test, feature = "testing"), not(target_arch = "wasm32")))]
+pub mod testing;
+mod transport;
 mod types;
 mod utilities;
 
-pub use downloaders::MinecraftDownloader;
-pub use errors::ProtonError;
-pub use manifest::resolve_version_data;
-pub use types::{DownloadProgress, DownloadProgressType, NormalizedVersion};
+#[cfg(not(target_arch = "wasm32"))]
+pub use config::{CONFIG_FILE, Mirrors, ProtonConfig};
+pub use diagnostics::{Diagnostic, diagnostics_channel, set_diagnostics_sender};
+#[cfg(not(target_arch = "wasm32"))]
+pub use downloaders::{
+    AssetOrder, BatchDownloader, ConcurrencyPreset, DownloadEvent, DownloadPriority, EXTRA_FILES,
+    GcReport, INSTALL_MANIFEST, ImportReport, InstallManifest, InstalledFile, MinecraftDownloader,
+    ResumeState, diff_versions, gc, record_extra_files, set_install_manifest_key,
+    verify_installation,
+};
+pub use endpoints::{
+    DEFAULT_MAVEN_REPOSITORIES, EndpointConfig, FORGE_MAVEN_URL, endpoints, set_endpoints,
+};
+pub use errors::{ErrorKind, ErrorReport, ProtonError};
+#[cfg(not(target_arch = "wasm32"))]
+pub use filesystem::{
+    FileMetadata, FileRead, FileReader, FileSystem, FileWriter, LocalFs, MemoryFs, durable_writes,
+    file_system, set_durable_writes, set_file_system, with_file_system,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use forge::ForgeInstaller;
+pub use http::{HttpOptions, http_options, set_http_options};
+#[cfg(not(target_arch = "wasm32"))]
+pub use http::{IpFamily, RootCertificate};
+#[cfg(not(target_arch = "wasm32"))]
+pub use instance::{
+    ExportOptions, INSTANCE_FILE, Instance, InstanceConfig, InstanceLoader, LauncherProfilesImport,
+    ModLoader, PlaySession, Resolution,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use launcher::{
+    ClasspathConflict, GameExit, GameLogEvent, GameLogParser, GameProcess, LaunchCommand,
+    LaunchOptions, LogLevel, QuickPlay, RestartPolicy, build_launch_command, parse_log_stream,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use liteloader::LiteLoaderInstaller;
+pub use manifest::{
+    LATEST_RELEASE, LATEST_SNAPSHOT, ManifestClient, MetadataSource, default_manifest_client,
+    get_manifest, list_versions, metadata_source, normalize_version_json, resolve_latest,
+    resolve_version_data, resolve_version_matching, set_metadata_source,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use manifest::{
+    ManifestUpdate, ManifestUpdates, cache_dir, register_custom_version, resolve_version_from_file,
+    set_cache_dir, watch_manifest,
+};
+pub use maven::MavenCoordinate;
+pub use plan::{DownloadListFormat, DownloadPlan, PlannedDownload, VersionDelta, plan_downloads};
+#[cfg(feature = "cli")]
+pub use progress::ProgressRenderer;
+#[cfg(not(target_arch = "wasm32"))]
+pub use server::{
+    Difficulty, GameMode, SERVER_JAR, ServerHandle, ServerInstaller, ServerOptions,
+    ServerProperties,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use system::{SystemMemory, system_memory};
+#[cfg(not(target_arch = "wasm32"))]
+pub use transport::LocalDirSource;
+pub use transport::{
+    ArtifactSource, ArtifactStream, CacheValidators, ConditionalFetch, HttpSource, MemorySource,
+    artifact_source, set_artifact_source,
+};
+pub use types::{
+    Asset, AssetFilter, AssetPredicate, DownloadProgress, DownloadProgressInfo,
+    DownloadProgressType, FailedFile, FeatureArgument, LoggingConfig, MojangLatestVersions,
+    MojangVersionInfo, MojangVersionManifest, NormalizedArguments, NormalizedVersion,
+    OverallProgress, VersionAssets, VersionFilter, VersionSort, VersionTypes,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use utilities::set_quarantine_dir;
+pub use utilities::set_target_os;
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!("proton");
+
 #[cfg(test)]
 mod tests {
     // #[test]
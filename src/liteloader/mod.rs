@@ -0,0 +1,239 @@
+use log::info;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::sync::mpsc::Sender;
+
+use crate::downloaders::MinecraftDownloader;
+use crate::endpoints::endpoints;
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::manifest::resolve_version_from_file;
+use crate::maven::MavenCoordinate;
+use crate::transport::artifact_source;
+use crate::types::{
+    DownloadProgress, LAUNCHWRAPPER_MAIN_CLASS, NormalizedVersion, TWEAK_CLASS_ARG,
+};
+use crate::utilities::is_safe_relative_path;
+
+const LITELOADER_VERSIONS_URL: &str = "https://dl.liteloader.com/versions/versions.json";
+const LITELOADER_REPOSITORY: &str = "https://dl.liteloader.com/versions/";
+const LITELOADER_ARTIFACT: &str = "com.mumfrey:liteloader";
+
+/// `versions.json` de LiteLoader: builds publicadas por versión de Minecraft
+#[derive(Debug, Deserialize)]
+struct LiteLoaderVersions {
+    #[serde(default)]
+    versions: BTreeMap<String, MinecraftBuilds>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MinecraftBuilds {
+    repo: Option<Repository>,
+    artefacts: Option<BuildChannel>,
+    snapshots: Option<BuildChannel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildChannel {
+    repo: Option<Repository>,
+    /// Librerías comunes a todas las builds del canal
+    #[serde(default)]
+    libraries: Vec<BuildLibrary>,
+    /// Builds por marca de tiempo, más `latest`
+    #[serde(rename = "com.mumfrey:liteloader", default)]
+    builds: BTreeMap<String, Build>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Build {
+    version: String,
+    tweak_class: String,
+    /// Nombre del jar en el repositorio; en snapshots lleva la marca de tiempo
+    file: Option<String>,
+    #[serde(default)]
+    libraries: Vec<BuildLibrary>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct BuildLibrary {
+    name: String,
+    url: Option<String>,
+}
+
+/// Instala LiteLoader (hasta 1.12.2) como una versión que hereda de la
+/// vanilla o de una versión ya instalada, por ejemplo la de Forge. Los
+/// tweakers se encadenan: la versión resultante arranca con launchwrapper y
+/// carga el de Forge y el de LiteLoader.
+#[derive(Debug, Clone)]
+pub struct LiteLoaderInstaller {
+    game_dir: PathBuf,
+    minecraft_version: String,
+    liteloader_version: Option<String>,
+    inherits_from: Option<String>,
+}
+
+impl LiteLoaderInstaller {
+    pub fn new(game_dir: impl Into<PathBuf>, minecraft_version: impl Into<String>) -> Self {
+        Self {
+            game_dir: game_dir.into(),
+            minecraft_version: minecraft_version.into(),
+            liteloader_version: None,
+            inherits_from: None,
+        }
+    }
+
+    /// Build concreta (`1.12.2-SNAPSHOT`); por defecto la más reciente
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.liteloader_version = Some(version.into());
+        self
+    }
+
+    /// Versión instalada sobre la que se añade LiteLoader, como
+    /// `1.12.2-forge-14.23.5.2860`; por defecto la vanilla
+    pub fn inherits_from(mut self, version_id: impl Into<String>) -> Self {
+        self.inherits_from = Some(version_id.into());
+        self
+    }
+
+    fn parent(&self) -> &str {
+        self.inherits_from
+            .as_deref()
+            .unwrap_or(&self.minecraft_version)
+    }
+
+    /// Id de la versión que se instala, como el del instalador oficial
+    /// (`1.12.2-LiteLoader1.12.2`)
+    pub fn version_id(&self) -> String {
+        format!("{}-LiteLoader{}", self.parent(), self.minecraft_version)
+    }
+
+    /// Busca la build pedida, primero entre las estables y después entre las snapshots
+    async fn find_build(&self) -> Result<(Build, Vec<BuildLibrary>, String), ProtonError> {
+        let content = artifact_source().fetch(LITELOADER_VERSIONS_URL).await?;
+        let mut versions: LiteLoaderVersions = serde_json::from_slice(&content).map_err(|e| {
+            ProtonError::Other(format!("Invalid JSON from {LITELOADER_VERSIONS_URL}: {e}"))
+        })?;
+        let not_found = || {
+            let wanted = self.liteloader_version.as_deref().unwrap_or("latest");
+            ProtonError::VersionNotFound(format!(
+                "LiteLoader {wanted} for {}",
+                self.minecraft_version
+            ))
+        };
+        let builds = versions
+            .versions
+            .remove(&self.minecraft_version)
+            .ok_or_else(not_found)?;
+
+        for channel in [&builds.artefacts, &builds.snapshots].into_iter().flatten() {
+            let build = match &self.liteloader_version {
+                Some(version) => channel
+                    .builds
+                    .values()
+                    .find(|build| build.version == *version),
+                None => channel.builds.get("latest"),
+            };
+            if let Some(build) = build {
+                let repository = channel
+                    .repo
+                    .as_ref()
+                    .or(builds.repo.as_ref())
+                    .map_or(LITELOADER_REPOSITORY, |repo| repo.url.as_str());
+                return Ok((
+                    build.clone(),
+                    channel.libraries.clone(),
+                    repository.to_string(),
+                ));
+            }
+        }
+        Err(not_found())
+    }
+
+    /// Escribe `versions/<id>/<id>.json`, descarga lo que falte y devuelve la
+    /// versión normalizada, lista para lanzar
+    pub async fn install(
+        &self,
+        progress_tx: Option<Sender<DownloadProgress>>,
+    ) -> Result<NormalizedVersion, ProtonError> {
+        let id = self.version_id();
+        if id.contains('/') || !is_safe_relative_path(&id) {
+            return Err(ProtonError::UnsafePath(id));
+        }
+        let (build, channel_libraries, repository) = self.find_build().await?;
+        info!("Installing LiteLoader {} as {id}", build.version);
+
+        let coordinate = MavenCoordinate::new("com.mumfrey", "liteloader", build.version.clone());
+        let file = build
+            .file
+            .clone()
+            .unwrap_or_else(|| format!("liteloader-{}.jar", build.version));
+        let artifact_url = format!(
+            "{}/com/mumfrey/liteloader/{}/{file}",
+            repository.trim_end_matches('/'),
+            build.version
+        );
+        let mut libraries = vec![json!({
+            "name": format!("{LITELOADER_ARTIFACT}:{}", build.version),
+            "downloads": {
+                "artifact": {
+                    "path": coordinate.path(),
+                    "url": artifact_url,
+                    "sha1": "",
+                    "size": 0,
+                }
+            }
+        })];
+        // Sin repositorio propio están en el de Mojang (launchwrapper, asm)
+        let libraries_base_url = endpoints().libraries_base_url;
+        libraries.extend(
+            build
+                .libraries
+                .iter()
+                .chain(&channel_libraries)
+                .map(|library| {
+                    json!({
+                        "name": library.name,
+                        "url": library.url.as_deref().unwrap_or(&libraries_base_url),
+                    })
+                }),
+        );
+
+        // El padre aporta el resto de argumentos y sus propios tweakers
+        let version = json!({
+            "id": id,
+            "inheritsFrom": self.parent(),
+            "mainClass": LAUNCHWRAPPER_MAIN_CLASS,
+            "minecraftArguments": format!("{TWEAK_CLASS_ARG} {}", build.tweak_class),
+            "libraries": Value::Array(libraries),
+        });
+
+        let version_dir = self.game_dir.join("versions").join(&id);
+        let fs = file_system();
+        fs.create_dir_all(&version_dir).await?;
+        let json_path = version_dir.join(format!("{id}.json"));
+        let content = serde_json::to_vec_pretty(&version)
+            .map_err(|e| ProtonError::Other(format!("Failed to serialize {id}: {e}")))?;
+        fs.write(&json_path, &content).await?;
+
+        let normalized = match resolve_version_from_file(&json_path).await {
+            Ok(normalized) => normalized,
+            Err(e) => {
+                let _ = fs.remove_file(&json_path).await;
+                return Err(e);
+            }
+        };
+        MinecraftDownloader::new(self.game_dir.clone(), normalized.clone())
+            .download_all(progress_tx)
+            .await?;
+        info!("Installed {id}");
+        Ok(normalized)
+    }
+}
@@ -0,0 +1,464 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use proton::{
+    DownloadListFormat, FailedFile, Instance, LaunchCommand, LaunchOptions, MinecraftDownloader,
+    ProgressRenderer, ProtonConfig, ProtonError, VersionFilter, VersionTypes, gc, list_versions,
+    resolve_version_data, verify_installation,
+};
+use serde_json::json;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Códigos de salida
+const EXIT_ERROR: u8 = 1;
+const EXIT_BROKEN_FILES: u8 = 3;
+
+#[derive(Parser)]
+#[command(name = "proton", version, about = "Minecraft downloader and launcher")]
+struct Cli {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Configuration file (defaults to PROTON_CONFIG or the user config dir)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the versions available in the Mojang manifest
+    Versions {
+        /// Only show versions of this type
+        #[arg(long = "type", value_enum)]
+        version_type: Option<TypeArg>,
+        /// Maximum number of versions to show
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Download and verify every file of a version
+    Install {
+        version: String,
+        /// Game directory (defaults to `game_dir` from config.toml)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Also download the server jar
+        #[arg(long)]
+        server: bool,
+        /// Take matching files from this directory before downloading
+        #[arg(long, value_name = "DIR")]
+        import: Option<PathBuf>,
+    },
+    /// Write the files of a version to a list for an external downloader
+    Export {
+        version: String,
+        /// Output file
+        #[arg(long, short)]
+        output: PathBuf,
+        #[arg(long, value_enum, default_value = "aria2")]
+        format: FormatArg,
+        /// Game directory (defaults to `game_dir` from config.toml)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Also include the server jar
+        #[arg(long)]
+        server: bool,
+    },
+    /// Check installed files without downloading anything
+    Verify {
+        version: String,
+        /// Game directory (defaults to `game_dir` from config.toml)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Check against the install-manifest.json written by install, without network
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Re-download missing or corrupted files
+    Repair {
+        version: String,
+        /// Game directory (defaults to `game_dir` from config.toml)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Delete libraries, natives and assets no installed version uses
+    Gc {
+        /// Game directory (defaults to `game_dir` from config.toml)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Also keep the files of this version (can be repeated)
+        #[arg(long = "keep", value_name = "VERSION")]
+        keep: Vec<String>,
+        /// Only report what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Launch an instance directory
+    Launch {
+        instance: PathBuf,
+        /// Game directory (defaults to `game_dir` from config.toml)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        #[arg(long, default_value = "Player")]
+        username: String,
+        /// Print the command instead of running it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TypeArg {
+    Release,
+    Snapshot,
+    OldBeta,
+    OldAlpha,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Aria2,
+    Json,
+}
+
+impl From<FormatArg> for DownloadListFormat {
+    fn from(value: FormatArg) -> Self {
+        match value {
+            FormatArg::Aria2 => DownloadListFormat::Aria2,
+            FormatArg::Json => DownloadListFormat::Json,
+        }
+    }
+}
+
+impl From<TypeArg> for VersionTypes {
+    fn from(value: TypeArg) -> Self {
+        match value {
+            TypeArg::Release => VersionTypes::Release,
+            TypeArg::Snapshot => VersionTypes::Snapshot,
+            TypeArg::OldBeta => VersionTypes::OldBeta,
+            TypeArg::OldAlpha => VersionTypes::OldAlpha,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+
+    match run(cli).await {
+        Ok(code) => code,
+        Err(error) => {
+            let failed = match &error {
+                ProtonError::PartialFailure(failed) => failed.as_slice(),
+                _ => &[],
+            };
+            if json {
+                println!(
+                    "{}",
+                    json!({
+                        "error": error.to_string(),
+                        "code": error.code(),
+                        "files": failed,
+                    })
+                );
+            } else {
+                eprintln!("error: {error}");
+                print_failed(failed);
+            }
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<ExitCode, ProtonError> {
+    let json = cli.json;
+    let config = match &cli.config {
+        Some(path) => ProtonConfig::load_from(path).await?,
+        None => ProtonConfig::load().await?,
+    };
+    config.apply()?;
+
+    match cli.command {
+        Command::Versions {
+            version_type,
+            limit,
+        } => {
+            let mut filter = VersionFilter::default();
+            if let Some(version_type) = version_type {
+                filter = filter.only(version_type.into());
+            }
+            if let Some(limit) = limit {
+                filter = filter.page(0, limit);
+            }
+
+            let versions = list_versions(&filter).await?;
+            if json {
+                println!("{}", json!(versions));
+            } else {
+                for version in versions {
+                    println!(
+                        "{:<24} {:<10} {}",
+                        version.id,
+                        version.version_type.as_str(),
+                        version.release_time
+                    );
+                }
+            }
+        }
+        Command::Install {
+            version,
+            dir,
+            server,
+            import,
+        } => {
+            let dir = game_dir(dir, &config)?;
+            let version = resolve_version_data(&version).await?;
+            let id = version.id.clone();
+            let mut downloader = MinecraftDownloader::new(dir.clone(), version)
+                .concurrency(config.concurrency)
+                .tolerate_failures(true)
+                .include_server(server);
+
+            // Ctrl+C deja el directorio limpio y el estado para reanudar
+            let cancel = downloader.cancel_token();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    cancel.cancel();
+                }
+            });
+
+            if let Some(import) = import {
+                let report = downloader.import_artifacts(&import).await?;
+                if !json {
+                    println!(
+                        "Imported {} file(s) from {}, {} left to download",
+                        report.imported,
+                        import.display(),
+                        report.missing.len()
+                    );
+                }
+            }
+
+            // Con --json no se dibuja nada en la terminal
+            if json {
+                downloader.download_all(None).await?;
+            } else {
+                let (tx, rx) = mpsc::channel(100);
+                let renderer = ProgressRenderer::new().spawn(rx);
+                let result = downloader.download_all(Some(tx)).await;
+                let _ = renderer.await;
+                result?;
+            }
+
+            if json {
+                println!("{}", json!({ "version": id, "dir": dir }));
+            } else {
+                println!("Installed {id} in {}", dir.display());
+            }
+        }
+        Command::Export {
+            version,
+            output,
+            format,
+            dir,
+            server,
+        } => {
+            let dir = game_dir(dir, &config)?;
+            let version = resolve_version_data(&version).await?;
+            let count = MinecraftDownloader::new(dir, version)
+                .include_server(server)
+                .export_download_list(&output, format.into())
+                .await?;
+
+            if json {
+                println!("{}", json!({ "files": count, "output": output }));
+            } else {
+                println!("Wrote {count} file(s) to {}", output.display());
+            }
+        }
+        Command::Verify {
+            version,
+            dir,
+            offline,
+        } => {
+            let dir = game_dir(dir, &config)?;
+            let broken = if offline {
+                verify_installation(&dir, &version).await?
+            } else if json {
+                let version = resolve_version_data(&version).await?;
+                MinecraftDownloader::new(dir, version).verify().await?
+            } else {
+                let version = resolve_version_data(&version).await?;
+                let downloader = MinecraftDownloader::new(dir, version);
+                let (tx, rx) = mpsc::channel(100);
+                let renderer = ProgressRenderer::new().spawn(rx);
+                let result = downloader.verify_with_progress(Some(tx)).await;
+                let _ = renderer.await;
+                result?
+            };
+
+            if json {
+                println!("{}", json!({ "files": broken }));
+            } else {
+                print_failed(&broken);
+            }
+            if !broken.is_empty() {
+                return Ok(ExitCode::from(EXIT_BROKEN_FILES));
+            }
+            if !json {
+                println!("All files are valid");
+            }
+        }
+        Command::Repair { version, dir } => {
+            let dir = game_dir(dir, &config)?;
+            let version = resolve_version_data(&version).await?;
+            let downloader = MinecraftDownloader::new(dir, version)
+                .concurrency(config.concurrency)
+                .tolerate_failures(true);
+            let broken = downloader.verify().await?;
+            let repaired = broken.len();
+
+            if !broken.is_empty() {
+                downloader.retry_failed(broken).await?;
+            }
+
+            if json {
+                println!("{}", json!({ "repaired": repaired }));
+            } else {
+                println!("Repaired {repaired} file(s)");
+            }
+        }
+        Command::Gc { dir, keep, dry_run } => {
+            let dir = game_dir(dir, &config)?;
+            let report = gc(&dir, &keep, dry_run).await?;
+
+            if json {
+                println!("{}", json!(report));
+            } else {
+                for path in &report.removed {
+                    println!("{}", path.display());
+                }
+                println!(
+                    "{} {} path(s), {:.1} MB",
+                    if dry_run { "Would remove" } else { "Removed" },
+                    report.removed.len(),
+                    report.freed_bytes as f64 / (1024.0 * 1024.0)
+                );
+            }
+        }
+        Command::Launch {
+            instance,
+            dir,
+            username,
+            dry_run,
+        } => {
+            let mut instance = Instance::load(instance).await?;
+            let dir = game_dir(dir, &config)?;
+            // Sin versión instalada `latest-*` lanzaría la más nueva sin descargarla
+            if !dry_run
+                && instance.config().tracks_latest()
+                && instance.config().installed_version.is_none()
+            {
+                instance.install(dir.clone(), None).await?;
+            }
+            let version = instance.launch_version(&dir).await?;
+            let mut options = LaunchOptions::for_instance(&instance, dir, username);
+            // La Java y la memoria de la instancia tienen prioridad sobre las de config.toml
+            if instance.java_path().is_none()
+                && let Some(java) = &config.java_path
+            {
+                options = options.java_path(java);
+            }
+            let (min_mb, max_mb) = instance.memory();
+            options = options.memory(
+                min_mb.or(config.min_memory_mb),
+                max_mb.or(config.max_memory_mb),
+            );
+            let command = LaunchCommand::build(&version, &options);
+
+            if dry_run {
+                let redacted = command.redacted();
+                if json {
+                    println!(
+                        "{}",
+                        json!({
+                            "java": redacted.java,
+                            "args": redacted.args(),
+                            "working_dir": redacted.working_dir,
+                        })
+                    );
+                } else {
+                    println!("{}", redacted.to_command_line());
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            // Con --json la salida del juego va a stderr para no mezclarse con el resultado
+            let mut forwarders = Vec::new();
+            let exit = instance
+                .run(&command, |process| {
+                    if let Some(mut stdout) = process.take_stdout() {
+                        forwarders.push(tokio::spawn(async move {
+                            while let Some(line) = stdout.recv().await {
+                                if json {
+                                    eprintln!("{line}");
+                                } else {
+                                    println!("{line}");
+                                }
+                            }
+                        }));
+                    }
+                    if let Some(mut stderr) = process.take_stderr() {
+                        forwarders.push(tokio::spawn(async move {
+                            while let Some(line) = stderr.recv().await {
+                                eprintln!("{line}");
+                            }
+                        }));
+                    }
+                })
+                .await?;
+            // Las últimas líneas del juego antes que el resultado; un proceso
+            // hijo que herede la salida no debe dejar la CLI esperando
+            for forwarder in forwarders {
+                let _ = tokio::time::timeout(Duration::from_secs(1), forwarder).await;
+            }
+            if json {
+                println!(
+                    "{}",
+                    json!({
+                        "code": exit.code,
+                        "crashed": exit.crashed(),
+                        "duration_secs": exit.duration.as_secs(),
+                    })
+                );
+            }
+
+            // El código del juego se propaga tal cual
+            return Ok(match exit.code {
+                Some(0) => ExitCode::SUCCESS,
+                Some(code) => ExitCode::from(u8::try_from(code).unwrap_or(EXIT_ERROR)),
+                None => ExitCode::from(EXIT_ERROR),
+            });
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `--dir` o, si no se indicó, el `game_dir` de la configuración
+fn game_dir(dir: Option<PathBuf>, config: &ProtonConfig) -> Result<PathBuf, ProtonError> {
+    dir.or_else(|| config.game_dir.clone()).ok_or_else(|| {
+        ProtonError::InvalidConfig("no game directory: pass --dir or set game_dir".to_string())
+    })
+}
+
+fn print_failed(files: &[FailedFile]) {
+    for file in files {
+        eprintln!("{:?} {}: {}", file.download_type, file.name, file.error);
+    }
+}
@@ -0,0 +1,189 @@
+#[cfg(not(target_arch = "wasm32"))]
+use log::info;
+use log::warn;
+#[cfg(not(target_arch = "wasm32"))]
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{path::PathBuf, sync::RwLock};
+
+use super::source::candidate_urls;
+use crate::diagnostics::{Diagnostic, emit};
+use crate::errors::ProtonError;
+use crate::transport::ArtifactSource;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::utilities::temp_path;
+
+#[cfg(not(target_arch = "wasm32"))]
+// Directorio donde se guardan los manifiestos, JSON de versiones e índices de assets
+static CACHE_DIR: Lazy<RwLock<PathBuf>> = Lazy::new(|| RwLock::new(default_cache_dir()));
+
+#[cfg(not(target_arch = "wasm32"))]
+fn default_cache_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Caches"))
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+
+    base.unwrap_or_else(std::env::temp_dir).join("proton")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Cambia el directorio de caché de metadatos (por defecto, la caché del usuario)
+pub fn set_cache_dir(path: PathBuf) {
+    *CACHE_DIR.write().unwrap_or_else(|e| e.into_inner()) = path;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Directorio de caché de metadatos actualmente configurado
+pub fn cache_dir() -> PathBuf {
+    CACHE_DIR.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Descarga un documento JSON guardando una copia en caché. Se prueban los
+/// servidores configurados en orden y, si ninguno responde, se sirve la última
+/// copia guardada.
+pub(crate) async fn fetch_json_cached<T: DeserializeOwned>(
+    source: &dyn ArtifactSource,
+    url: &str,
+    cache_key: &Path,
+) -> Result<T, ProtonError> {
+    let cache_path = cache_dir().join(cache_key);
+
+    match fetch_first_available(source, url).await {
+        Ok(bytes) => {
+            let parsed = serde_json::from_slice::<T>(&bytes)
+                .map_err(|e| ProtonError::Other(format!("Invalid JSON from {url}: {e}")))?;
+            store(&cache_path, &bytes).await;
+            Ok(parsed)
+        }
+        Err(e) => {
+            let Ok(bytes) = tokio::fs::read(&cache_path).await else {
+                return Err(e);
+            };
+            warn!("Request to {url} failed ({e}), using cached copy {cache_path:?}");
+            emit(Diagnostic::CachedMetadataUsed {
+                url: url.to_string(),
+                cache_path: cache_path.clone(),
+            });
+            serde_json::from_slice::<T>(&bytes).map_err(|parse_err| {
+                ProtonError::Other(format!("Corrupted cache file {cache_path:?}: {parse_err}"))
+            })
+        }
+    }
+}
+
+/// Sin sistema de archivos en wasm32 no hay caché en disco: solo se prueban
+/// los servidores configurados en orden
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn fetch_json_cached<T: DeserializeOwned>(
+    source: &dyn ArtifactSource,
+    url: &str,
+    _cache_key: &Path,
+) -> Result<T, ProtonError> {
+    let bytes = fetch_first_available(source, url).await?;
+    serde_json::from_slice::<T>(&bytes)
+        .map_err(|e| ProtonError::Other(format!("Invalid JSON from {url}: {e}")))
+}
+
+async fn fetch_first_available(
+    source: &dyn ArtifactSource,
+    url: &str,
+) -> Result<Vec<u8>, ProtonError> {
+    let mut last_error = None;
+
+    for candidate in candidate_urls(url) {
+        match source.fetch(&candidate).await {
+            Ok(bytes) => return Ok(bytes),
+            Err(e) => {
+                warn!("Metadata request to {candidate} failed: {e}");
+                emit(Diagnostic::MirrorFallback {
+                    url: candidate.clone(),
+                    error: e.to_string(),
+                });
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| ProtonError::Other(format!("No source available for {url}"))))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn store(path: &Path, bytes: &[u8]) {
+    if let Some(parent) = path.parent()
+        && let Err(e) = tokio::fs::create_dir_all(parent).await
+    {
+        warn!("Failed to create cache directory {parent:?}: {e}");
+        return;
+    }
+
+    // Escritura atómica para no dejar copias a medias
+    let temp = temp_path(path);
+    let result = async {
+        tokio::fs::write(&temp, bytes).await?;
+        tokio::fs::rename(&temp, path).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => info!("Cached {path:?}"),
+        Err(e) => {
+            warn!("Failed to write cache file {path:?}: {e}");
+            let _ = tokio::fs::remove_file(&temp).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{diagnostics_channel, set_diagnostics_sender};
+    use crate::manifest::{default_manifest_client, resolve_asset_index, resolve_version_data};
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+
+    #[tokio::test]
+    async fn metadata_is_served_from_disk_when_offline() {
+        let env = MockEnvironment::start().await;
+        let version = resolve_version_data(FIXTURE_VERSION).await.unwrap();
+        let index = resolve_asset_index(&version).await.unwrap();
+        assert!(cache_dir().join("indexes").join("fixture.json").exists());
+
+        // Sin servidor ni caché en memoria solo queda la copia en disco
+        env.server.server().reset().await;
+        default_manifest_client().clear();
+        let mut diagnostics = diagnostics_channel();
+        let offline = resolve_version_data(FIXTURE_VERSION).await;
+        let offline_index = resolve_asset_index(&version).await;
+        set_diagnostics_sender(None);
+
+        assert_eq!(offline.unwrap().client_jar.sha1, version.client_jar.sha1);
+        assert_eq!(offline_index.unwrap().objects.len(), index.objects.len());
+        let mut cached = Vec::new();
+        while let Ok(diagnostic) = diagnostics.try_recv() {
+            if let Diagnostic::CachedMetadataUsed { cache_path, .. } = diagnostic {
+                cached.push(cache_path);
+            }
+        }
+        assert!(cached.len() >= 2, "{cached:?}");
+        assert!(cached.iter().all(|path| path.starts_with(cache_dir())));
+    }
+
+    #[tokio::test]
+    async fn without_a_copy_the_network_error_is_returned() {
+        let env = MockEnvironment::start().await;
+        env.server.server().reset().await;
+
+        let result = resolve_version_data(FIXTURE_VERSION).await;
+
+        assert!(result.is_err());
+        assert!(!cache_dir().join("indexes").exists());
+    }
+}
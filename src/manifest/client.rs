@@ -0,0 +1,250 @@
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+// `std::time::Instant` no está disponible en wasm32-unknown-unknown
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use super::{cache, resolve_alias};
+use crate::endpoints::endpoints;
+use crate::errors::ProtonError;
+use crate::transport::{ArtifactSource, HttpSource, artifact_source};
+use crate::types::{
+    MojangVersionDetails, MojangVersionInfo, MojangVersionManifest, NormalizedVersion,
+};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+static DEFAULT_CLIENT: Lazy<ManifestClient> = Lazy::new(ManifestClient::new);
+
+/// Cliente compartido por las funciones libres de `manifest`
+pub fn default_manifest_client() -> &'static ManifestClient {
+    &DEFAULT_CLIENT
+}
+
+struct CachedEntry<T> {
+    fetched_at: Instant,
+    url: String,
+    value: Arc<T>,
+}
+
+/// Cliente de metadatos con caché en memoria: evita descargar el manifest
+/// completo en cada consulta mientras no expire el TTL
+pub struct ManifestClient {
+    source: Option<Arc<dyn ArtifactSource>>,
+    ttl: Duration,
+    manifest: Mutex<Option<CachedEntry<MojangVersionManifest>>>,
+    versions: Mutex<HashMap<String, CachedEntry<MojangVersionDetails>>>,
+}
+
+impl Default for ManifestClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManifestClient {
+    pub fn new() -> Self {
+        Self {
+            source: None,
+            ttl: DEFAULT_TTL,
+            manifest: Mutex::new(None),
+            versions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Usa un cliente HTTP propio en lugar del compartido
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.source = Some(Arc::new(HttpSource::new(client)));
+        self
+    }
+
+    /// Lee los metadatos desde un origen propio en lugar del global
+    pub fn with_source(mut self, source: Arc<dyn ArtifactSource>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Tiempo durante el cual los documentos en memoria se consideran válidos
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    fn source(&self) -> Arc<dyn ArtifactSource> {
+        self.source.clone().unwrap_or_else(artifact_source)
+    }
+
+    fn is_fresh<T>(&self, entry: &CachedEntry<T>, url: &str) -> bool {
+        entry.url == url && entry.fetched_at.elapsed() < self.ttl
+    }
+
+    pub async fn get_manifest(&self) -> Result<Arc<MojangVersionManifest>, ProtonError> {
+        let manifest_url = endpoints().manifest_url;
+
+        if let Some(entry) = self
+            .manifest
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            && self.is_fresh(entry, &manifest_url)
+        {
+            return Ok(Arc::clone(&entry.value));
+        }
+
+        let manifest: Arc<MojangVersionManifest> = Arc::new(
+            cache::fetch_json_cached(
+                &*self.source(),
+                &manifest_url,
+                Path::new("version_manifest_v2.json"),
+            )
+            .await?,
+        );
+
+        *self.manifest.lock().unwrap_or_else(|e| e.into_inner()) = Some(CachedEntry {
+            fetched_at: Instant::now(),
+            url: manifest_url,
+            value: Arc::clone(&manifest),
+        });
+
+        Ok(manifest)
+    }
+
+    pub async fn resolve_version_in_manifest(
+        &self,
+        version_id: &str,
+    ) -> Result<MojangVersionInfo, ProtonError> {
+        let manifest = self.get_manifest().await?;
+        let id = resolve_alias(&manifest, version_id);
+
+        manifest
+            .versions
+            .iter()
+            .find(|v| v.id == id)
+            .cloned()
+            .ok_or(ProtonError::VersionNotFound(version_id.to_string()))
+    }
+
+    /// JSON de versión de Mojang sin normalizar
+    pub async fn version_details(
+        &self,
+        version_id: &str,
+    ) -> Result<Arc<MojangVersionDetails>, ProtonError> {
+        let info = self.resolve_version_in_manifest(version_id).await?;
+
+        if let Some(entry) = self
+            .versions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&info.id)
+            && self.is_fresh(entry, &info.url)
+        {
+            return Ok(Arc::clone(&entry.value));
+        }
+
+        let cache_key = Path::new("versions").join(format!("{}.json", info.id));
+        let details: Arc<MojangVersionDetails> =
+            Arc::new(cache::fetch_json_cached(&*self.source(), &info.url, &cache_key).await?);
+
+        self.versions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                info.id,
+                CachedEntry {
+                    fetched_at: Instant::now(),
+                    url: info.url,
+                    value: Arc::clone(&details),
+                },
+            );
+
+        Ok(details)
+    }
+
+    pub async fn resolve_version_data(
+        &self,
+        version_id: &str,
+    ) -> Result<NormalizedVersion, ProtonError> {
+        let details = self.version_details(version_id).await?;
+        NormalizedVersion::try_from((*details).clone())
+    }
+
+    /// Sustituye el manifest en memoria por uno descargado fuera del cliente
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn store_manifest(&self, url: String, manifest: Arc<MojangVersionManifest>) {
+        *self.manifest.lock().unwrap_or_else(|e| e.into_inner()) = Some(CachedEntry {
+            fetched_at: Instant::now(),
+            url,
+            value: manifest,
+        });
+    }
+
+    /// Descarta la caché en memoria y vuelve a descargar el manifest
+    pub async fn force_refresh(&self) -> Result<Arc<MojangVersionManifest>, ProtonError> {
+        self.clear();
+        self.get_manifest().await
+    }
+
+    /// Descarta la caché en memoria
+    pub(crate) fn clear(&self) {
+        *self.manifest.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.versions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+
+    /// Peticiones recibidas por ruta: (manifest, JSON de versión)
+    async fn requests(env: &MockEnvironment) -> (usize, usize) {
+        let requests = env.server.server().received_requests().await.unwrap();
+        let count = |suffix: &str| {
+            requests
+                .iter()
+                .filter(|request| request.url.path().ends_with(suffix))
+                .count()
+        };
+        (
+            count("version_manifest_v2.json"),
+            count(&format!("/versions/{FIXTURE_VERSION}.json")),
+        )
+    }
+
+    #[tokio::test]
+    async fn documents_are_reused_until_they_expire() {
+        let env = MockEnvironment::start().await;
+        let client = ManifestClient::new();
+
+        client.get_manifest().await.unwrap();
+        client.resolve_version_data(FIXTURE_VERSION).await.unwrap();
+        client.resolve_version_data(FIXTURE_VERSION).await.unwrap();
+        assert_eq!(requests(&env).await, (1, 1));
+
+        client.force_refresh().await.unwrap();
+        assert_eq!(requests(&env).await, (2, 1));
+        client.resolve_version_data(FIXTURE_VERSION).await.unwrap();
+        assert_eq!(requests(&env).await, (2, 2));
+
+        // Sin TTL cada consulta vuelve a la red
+        let uncached = ManifestClient::new().with_ttl(Duration::ZERO);
+        uncached
+            .resolve_version_data(FIXTURE_VERSION)
+            .await
+            .unwrap();
+        uncached
+            .resolve_version_data(FIXTURE_VERSION)
+            .await
+            .unwrap();
+        assert_eq!(requests(&env).await, (4, 4));
+    }
+}
@@ -0,0 +1,480 @@
+use log::info;
+use serde_json::{Map, Value, json};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::default_manifest_client;
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::maven::MavenCoordinate;
+use crate::types::{MojangVersionDetails, NormalizedVersion, TWEAK_CLASS_ARG};
+use crate::utilities::{file_sha1, is_safe_relative_path};
+
+// Límite de niveles de `inheritsFrom` para evitar ciclos
+const MAX_INHERITANCE_DEPTH: usize = 8;
+
+/// Lee un `versions/<id>/<id>.json` local (vanilla o modded) y lo normaliza.
+/// Los perfiles con `inheritsFrom` se combinan con su versión padre, buscada
+/// primero junto al archivo y, si no existe, en los metadatos de Mojang.
+pub async fn resolve_version_from_file(path: &Path) -> Result<NormalizedVersion, ProtonError> {
+    let mut version = read_json(path).await?;
+    let versions_dir = path.parent().and_then(Path::parent).map(Path::to_path_buf);
+
+    for _ in 0..MAX_INHERITANCE_DEPTH {
+        let Some(parent_id) = version
+            .get("inheritsFrom")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+        else {
+            let details: MojangVersionDetails = serde_json::from_value(version)
+                .map_err(|e| ProtonError::Other(format!("Invalid version JSON {path:?}: {e}")))?;
+            return NormalizedVersion::try_from(details);
+        };
+
+        let parent = load_parent(versions_dir.as_deref(), &parent_id).await?;
+        version = merge_inherited(version, parent);
+    }
+
+    Err(ProtonError::Other(format!(
+        "Too many inheritsFrom levels in {path:?}"
+    )))
+}
+
+/// Registra una versión propia a partir de un jar de cliente y un JSON de
+/// versión sin hashes publicados. Los copia a `versions/<id>` y calcula en
+/// ese momento el SHA-1 del jar y de las librerías que ya estén en
+/// `libraries/`, para que `verify` detecte después si se dañan. El jar no
+/// tiene URL: si se pierde hay que volver a registrarlo. La versión se
+/// instala después con `MinecraftDownloader::download_all` como cualquier otra.
+pub async fn register_custom_version(
+    game_dir: &Path,
+    id: &str,
+    jar: &Path,
+    json: &Path,
+) -> Result<NormalizedVersion, ProtonError> {
+    if id.contains('/') || !is_safe_relative_path(id) {
+        return Err(ProtonError::UnsafePath(id.to_string()));
+    }
+    let mut version = read_json(json).await?;
+    let Value::Object(fields) = &mut version else {
+        return Err(ProtonError::Other(format!(
+            "Invalid version JSON {json:?}: not an object"
+        )));
+    };
+
+    let fs = file_system();
+    let version_dir = game_dir.join("versions").join(id);
+    fs.create_dir_all(&version_dir).await?;
+    let jar_path = version_dir.join(format!("{id}.jar"));
+    let same_file = if fs.is_local() {
+        tokio::fs::canonicalize(&jar_path).await.ok() == Some(tokio::fs::canonicalize(jar).await?)
+    } else {
+        jar_path == jar
+    };
+    if !same_file {
+        fs.copy(jar, &jar_path).await?;
+    }
+    let client = json!({
+        "sha1": file_sha1(&jar_path).await?,
+        "size": fs.metadata(&jar_path).await?.len,
+        "url": "",
+    });
+
+    fields.insert("id".to_string(), Value::from(id));
+    match fields.get_mut("downloads") {
+        Some(Value::Object(downloads)) => {
+            downloads.insert("client".to_string(), client);
+        }
+        _ => {
+            fields.insert("downloads".to_string(), json!({ "client": client }));
+        }
+    }
+    if let Some(Value::Array(libraries)) = fields.get_mut("libraries") {
+        fill_library_hashes(&game_dir.join("libraries"), libraries).await?;
+    }
+
+    let json_path = version_dir.join(format!("{id}.json"));
+    let content = serde_json::to_vec_pretty(&version)
+        .map_err(|e| ProtonError::Other(format!("Failed to serialize {id}: {e}")))?;
+    fs.write(&json_path, &content).await?;
+
+    // Un JSON que no se puede normalizar no debe parecer una versión instalada
+    match resolve_version_from_file(&json_path).await {
+        Ok(normalized) => {
+            info!("Registered custom version {id} in {version_dir:?}");
+            Ok(normalized)
+        }
+        Err(e) => {
+            let _ = fs.remove_file(&json_path).await;
+            Err(e)
+        }
+    }
+}
+
+/// Completa el SHA-1 y el tamaño de las librerías sin hash que ya están en disco
+async fn fill_library_hashes(
+    libraries_dir: &Path,
+    libraries: &mut [Value],
+) -> Result<(), ProtonError> {
+    for library in libraries {
+        // Con `downloads.artifact` el hash va dentro; en formato maven, junto al nombre
+        let pointer = if library.pointer("/downloads/artifact").is_some() {
+            "/downloads/artifact"
+        } else {
+            ""
+        };
+        let has_hash = library
+            .pointer(&format!("{pointer}/sha1"))
+            .and_then(Value::as_str)
+            .is_some_and(|sha1| !sha1.is_empty());
+        let relative = library
+            .pointer("/downloads/artifact/path")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| {
+                let name = library.get("name")?.as_str()?;
+                name.parse::<MavenCoordinate>().ok().map(|c| c.path())
+            });
+        let Some(relative) = relative.filter(|relative| is_safe_relative_path(relative)) else {
+            continue;
+        };
+        let path = libraries_dir.join(&relative);
+        let Ok(metadata) = file_system().metadata(&path).await else {
+            continue;
+        };
+        if has_hash {
+            continue;
+        }
+
+        let sha1 = file_sha1(&path).await?;
+        let size = metadata.len;
+        if let Some(Value::Object(entry)) = library.pointer_mut(pointer) {
+            entry.insert("sha1".to_string(), Value::from(sha1));
+            entry.insert("size".to_string(), Value::from(size));
+        }
+    }
+    Ok(())
+}
+
+async fn read_json(path: &Path) -> Result<Value, ProtonError> {
+    let content = file_system().read(path).await?;
+    serde_json::from_slice(&content)
+        .map_err(|e| ProtonError::Other(format!("Invalid version JSON {path:?}: {e}")))
+}
+
+async fn load_parent(versions_dir: Option<&Path>, parent_id: &str) -> Result<Value, ProtonError> {
+    if let Some(dir) = versions_dir {
+        let local: PathBuf = dir.join(parent_id).join(format!("{parent_id}.json"));
+        if file_system().exists(&local).await {
+            return read_json(&local).await;
+        }
+    }
+
+    let details = default_manifest_client().version_details(parent_id).await?;
+    serde_json::to_value(&*details)
+        .map_err(|e| ProtonError::Other(format!("Failed to serialize {parent_id}: {e}")))
+}
+
+/// Combina un perfil hijo con su padre: el hijo sobrescribe los campos simples,
+/// sus librerías tienen prioridad y los argumentos se concatenan.
+fn merge_inherited(child: Value, parent: Value) -> Value {
+    let (Value::Object(child), Value::Object(mut merged)) = (child, parent) else {
+        return Value::Null;
+    };
+
+    for (key, value) in child {
+        match key.as_str() {
+            "inheritsFrom" => {}
+            "libraries" => {
+                let parent_libraries = merged.remove("libraries");
+                merged.insert(key, merge_libraries(value, parent_libraries));
+            }
+            "arguments" => {
+                let parent_arguments = merged.remove("arguments");
+                merged.insert(key, merge_arguments(value, parent_arguments));
+            }
+            "minecraftArguments" => {
+                let parent_arguments = merged.remove("minecraftArguments");
+                merged.insert(key, merge_legacy_arguments(value, parent_arguments));
+            }
+            _ => {
+                merged.insert(key, value);
+            }
+        }
+    }
+
+    // Se conserva el `inheritsFrom` del padre para seguir subiendo en la cadena
+    Value::Object(merged)
+}
+
+fn library_key(library: &Value) -> Option<String> {
+    let name = library.get("name")?.as_str()?;
+    name.parse::<MavenCoordinate>()
+        .ok()
+        .map(|coordinate| coordinate.key())
+}
+
+fn merge_libraries(child: Value, parent: Option<Value>) -> Value {
+    let mut libraries = match child {
+        Value::Array(libraries) => libraries,
+        _ => Vec::new(),
+    };
+    let overridden: HashSet<String> = libraries.iter().filter_map(library_key).collect();
+
+    if let Some(Value::Array(parent_libraries)) = parent {
+        libraries.extend(
+            parent_libraries
+                .into_iter()
+                .filter(|lib| library_key(lib).is_none_or(|key| !overridden.contains(&key))),
+        );
+    }
+
+    Value::Array(libraries)
+}
+
+/// Separa un `minecraftArguments` en pares `(--opción, valor)`
+fn legacy_options(arguments: &str) -> Vec<(&str, Option<&str>)> {
+    let mut tokens = arguments.split_whitespace().peekable();
+    let mut options = Vec::new();
+    while let Some(name) = tokens.next() {
+        let value = tokens.next_if(|token| !token.starts_with("--"));
+        options.push((name, value));
+    }
+    options
+}
+
+/// Combina los `minecraftArguments` de un hijo con los del padre: cada opción
+/// del hijo sustituye a la del padre, salvo `--tweakClass`, que se acumula
+/// para encadenar loaders (Forge y LiteLoader sobre él)
+fn merge_legacy_arguments(child: Value, parent: Option<Value>) -> Value {
+    let (Value::String(child), Some(Value::String(parent))) = (&child, &parent) else {
+        return child;
+    };
+
+    let mut merged = legacy_options(parent);
+    for (name, value) in legacy_options(child) {
+        let repeatable = name == TWEAK_CLASS_ARG;
+        match merged.iter_mut().find(|(existing, existing_value)| {
+            *existing == name && (!repeatable || *existing_value == value)
+        }) {
+            Some(option) => option.1 = value,
+            None => merged.push((name, value)),
+        }
+    }
+
+    let arguments: Vec<&str> = merged
+        .into_iter()
+        .flat_map(|(name, value)| std::iter::once(name).chain(value))
+        .collect();
+    Value::from(arguments.join(" "))
+}
+
+fn merge_arguments(child: Value, parent: Option<Value>) -> Value {
+    let mut merged = match parent {
+        Some(Value::Object(parent)) => parent,
+        _ => Map::new(),
+    };
+
+    if let Value::Object(child) = child {
+        for (key, value) in child {
+            match (merged.get_mut(&key), value) {
+                (Some(Value::Array(existing)), Value::Array(extra)) => existing.extend(extra),
+                (_, value) => {
+                    merged.insert(key, value);
+                }
+            }
+        }
+    }
+
+    Value::Object(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::downloaders::MinecraftDownloader;
+    use crate::filesystem::with_file_system;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment, fixture_version_details, sha1};
+    use crate::types::DownloadProgressType;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn registered_versions_can_be_verified() {
+        let env = MockEnvironment::start().await;
+        let (server, fs, game_dir) = (&env.server, &env.fs, &env.game_dir);
+
+        // Sin hash del cliente, con una librería maven que ya está en disco
+        let mut details = serde_json::to_value(fixture_version_details(&server.uri())).unwrap();
+        details.as_object_mut().unwrap().remove("downloads");
+        details["libraries"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "name": "com.example:local:1.0", "url": "https://example.invalid/" }));
+        let local_library = game_dir.join("libraries/com/example/local/1.0/local-1.0.jar");
+
+        let (version, broken) = with_file_system(Arc::new(fs.clone()), async {
+            let fs = file_system();
+            fs.create_dir_all(local_library.parent().unwrap()).await?;
+            fs.write(&local_library, b"local library").await?;
+            fs.create_dir_all(Path::new("/src")).await?;
+            fs.write(Path::new("/src/client.jar"), b"custom client")
+                .await?;
+            let json = serde_json::to_vec(&details).map_err(std::io::Error::other)?;
+            fs.write(Path::new("/src/custom.json"), &json).await?;
+
+            let version = register_custom_version(
+                game_dir,
+                "custom",
+                Path::new("/src/client.jar"),
+                Path::new("/src/custom.json"),
+            )
+            .await?;
+            let mut downloader = MinecraftDownloader::new(game_dir.clone(), version.clone());
+            downloader.download_all(None).await?;
+            assert!(downloader.verify().await?.is_empty());
+
+            fs.write(&game_dir.join("versions/custom/custom.jar"), b"damaged")
+                .await?;
+            let broken = downloader.verify().await?;
+            Ok::<_, ProtonError>((version, broken))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(version.id, "custom");
+        assert_eq!(version.client_jar.sha1, sha1(b"custom client"));
+        assert!(version.client_jar.url.is_empty());
+        let local = version
+            .libraries
+            .iter()
+            .find(|library| library.name == "com.example:local:1.0")
+            .unwrap();
+        assert_eq!(local.sha1, sha1(b"local library"));
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].download_type, DownloadProgressType::Client);
+    }
+
+    #[test]
+    fn child_minecraft_arguments_override_the_parent_ones() {
+        let parent = Value::from(
+            "--username ${auth_player_name} --gameDir ${game_directory} --tweakClass net.minecraftforge.fml.common.launcher.FMLTweaker",
+        );
+        let child = Value::from(
+            "--gameDir ${instance_dir} --tweakClass com.mumfrey.liteloader.launch.LiteLoaderTweaker --demo",
+        );
+        assert_eq!(
+            merge_legacy_arguments(child, Some(parent)),
+            Value::from(
+                "--username ${auth_player_name} --gameDir ${instance_dir} --tweakClass net.minecraftforge.fml.common.launcher.FMLTweaker --tweakClass com.mumfrey.liteloader.launch.LiteLoaderTweaker --demo"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn inherited_profiles_merge_with_their_parent() {
+        let env = MockEnvironment::start().await;
+        let versions = env.game_dir.join("versions");
+        let child_path = versions.join("fixture-forge/fixture-forge.json");
+        let child = json!({
+            "id": "fixture-forge",
+            "inheritsFrom": FIXTURE_VERSION,
+            "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+            "libraries": [{
+                "name": "org.proton:library:2.0",
+                "downloads": { "artifact": {
+                    "path": "org/proton/library/2.0/library-2.0.jar",
+                    "url": "https://maven.example/org/proton/library/2.0/library-2.0.jar",
+                    "sha1": sha1(b"library 2.0"),
+                    "size": 11,
+                }},
+            }],
+            "arguments": { "game": ["--launchTarget", "forgeclient"] },
+        });
+        let write = |path: PathBuf, value: Value| async move {
+            let fs = file_system();
+            fs.create_dir_all(path.parent().unwrap()).await?;
+            fs.write(&path, value.to_string().as_bytes()).await
+        };
+
+        // Sin el padre en disco se pide a los metadatos de Mojang
+        let from_network = env
+            .run(async {
+                write(child_path.clone(), child.clone()).await?;
+                resolve_version_from_file(&child_path).await
+            })
+            .await
+            .unwrap();
+        assert_eq!(from_network.id, "fixture-forge");
+        assert_eq!(
+            from_network.main_class,
+            "cpw.mods.bootstraplauncher.BootstrapLauncher"
+        );
+        let libraries: Vec<_> = from_network
+            .libraries
+            .iter()
+            .map(|l| l.name.as_str())
+            .collect();
+        assert!(libraries.contains(&"org.proton:library:2.0"));
+        assert!(!libraries.contains(&"org.proton:library:1.0"));
+        assert!(
+            from_network
+                .arguments
+                .game
+                .ends_with(&["--launchTarget".to_string(), "forgeclient".to_string()])
+        );
+        assert!(
+            from_network
+                .arguments
+                .game
+                .contains(&"--username".to_string())
+        );
+
+        // Un padre local tiene prioridad y sin red sigue funcionando
+        env.server.server().reset().await;
+        let mut parent = serde_json::to_value(fixture_version_details(&env.server.uri())).unwrap();
+        parent["mainClass"] = Value::from("local.Main");
+        let local = env
+            .run(async {
+                write(
+                    versions
+                        .join(FIXTURE_VERSION)
+                        .join(format!("{FIXTURE_VERSION}.json")),
+                    parent,
+                )
+                .await?;
+                let mut child = child.clone();
+                child.as_object_mut().unwrap().remove("mainClass");
+                write(child_path.clone(), child).await?;
+                resolve_version_from_file(&child_path).await
+            })
+            .await
+            .unwrap();
+        assert_eq!(local.main_class, "local.Main");
+    }
+
+    #[tokio::test]
+    async fn inheritance_cycles_are_rejected() {
+        let env = MockEnvironment::start().await;
+        let versions = env.game_dir.join("versions");
+        let result = env
+            .run(async {
+                let fs = file_system();
+                for (id, parent) in [("a", "b"), ("b", "a")] {
+                    let dir = versions.join(id);
+                    fs.create_dir_all(&dir).await?;
+                    let json = json!({ "id": id, "inheritsFrom": parent });
+                    fs.write(&dir.join(format!("{id}.json")), json.to_string().as_bytes())
+                        .await?;
+                }
+                resolve_version_from_file(&versions.join("a/a.json")).await
+            })
+            .await;
+
+        match result {
+            Err(ProtonError::Other(message)) => {
+                assert!(message.contains("inheritsFrom"), "{message}")
+            }
+            other => panic!("expected an inheritance error, got {other:?}"),
+        }
+    }
+}
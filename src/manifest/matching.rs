@@ -0,0 +1,168 @@
+use std::cmp::Ordering;
+
+use crate::errors::ProtonError;
+
+/// Patrón de versión: comodines (`1.20.*`, `1.20.x`) o rangos separados por
+/// comas (`>=1.20, <1.21`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum VersionPattern {
+    Wildcard(Vec<Option<u32>>),
+    Range(Vec<(Comparator, Vec<u32>)>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparator {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// Convierte un id de release (`1.20.4`) en sus componentes numéricos
+pub(crate) fn parse_release(id: &str) -> Option<Vec<u32>> {
+    id.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Compara dos versiones numéricas, tratando los componentes ausentes como 0
+pub(crate) fn compare_release(a: &[u32], b: &[u32]) -> Ordering {
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            x.cmp(&y)
+        })
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+impl VersionPattern {
+    pub(crate) fn parse(pattern: &str) -> Result<Self, ProtonError> {
+        let invalid = || ProtonError::InvalidVersionPattern(pattern.to_string());
+        let pattern = pattern.trim();
+
+        if pattern.starts_with(['>', '<', '=']) {
+            let mut constraints = Vec::new();
+            for part in pattern.split(',') {
+                let part = part.trim();
+                let (comparator, rest) = if let Some(rest) = part.strip_prefix(">=") {
+                    (Comparator::Ge, rest)
+                } else if let Some(rest) = part.strip_prefix("<=") {
+                    (Comparator::Le, rest)
+                } else if let Some(rest) = part.strip_prefix('>') {
+                    (Comparator::Gt, rest)
+                } else if let Some(rest) = part.strip_prefix('<') {
+                    (Comparator::Lt, rest)
+                } else if let Some(rest) = part.strip_prefix('=') {
+                    (Comparator::Eq, rest)
+                } else {
+                    return Err(invalid());
+                };
+                let version = parse_release(rest.trim()).ok_or_else(invalid)?;
+                constraints.push((comparator, version));
+            }
+            return Ok(VersionPattern::Range(constraints));
+        }
+
+        let segments = pattern
+            .split('.')
+            .map(|part| match part {
+                "*" | "x" | "X" => Ok(None),
+                number => number.parse().map(Some).map_err(|_| invalid()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(VersionPattern::Wildcard(segments))
+    }
+
+    pub(crate) fn matches(&self, version: &[u32]) -> bool {
+        match self {
+            VersionPattern::Wildcard(segments) => {
+                // Un comodín final admite cualquier número de componentes restantes
+                let open_ended = segments.last() == Some(&None);
+                if !open_ended && version.len() > segments.len() {
+                    return false;
+                }
+                segments
+                    .iter()
+                    .enumerate()
+                    .all(|(i, segment)| match segment {
+                        None => true,
+                        Some(expected) => version.get(i).copied().unwrap_or(0) == *expected,
+                    })
+            }
+            VersionPattern::Range(constraints) => constraints.iter().all(|(comparator, bound)| {
+                let ord = compare_release(version, bound);
+                match comparator {
+                    Comparator::Eq => ord == Ordering::Equal,
+                    Comparator::Gt => ord == Ordering::Greater,
+                    Comparator::Ge => ord != Ordering::Less,
+                    Comparator::Lt => ord == Ordering::Less,
+                    Comparator::Le => ord != Ordering::Greater,
+                }
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, version: &str) -> bool {
+        VersionPattern::parse(pattern)
+            .unwrap()
+            .matches(&parse_release(version).unwrap())
+    }
+
+    #[test]
+    fn wildcards_match_any_remaining_components() {
+        for pattern in ["1.20.*", "1.20.x", "1.20.X"] {
+            assert!(matches(pattern, "1.20"));
+            assert!(matches(pattern, "1.20.4"));
+            assert!(!matches(pattern, "1.21"));
+        }
+        assert!(matches("1.*.2", "1.19.2"));
+        assert!(!matches("1.*.2", "1.19.2.1"));
+    }
+
+    #[test]
+    fn exact_patterns_do_not_match_longer_versions() {
+        assert!(matches("1.20", "1.20"));
+        assert!(!matches("1.20", "1.20.1"));
+    }
+
+    #[test]
+    fn ranges_combine_every_constraint() {
+        let pattern = ">=1.20, <1.21";
+        assert!(matches(pattern, "1.20"));
+        assert!(matches(pattern, "1.20.6"));
+        assert!(!matches(pattern, "1.21"));
+        assert!(!matches(pattern, "1.19.4"));
+
+        assert!(matches(">1.8.9", "1.9"));
+        assert!(!matches(">1.8.9", "1.8.9"));
+        assert!(matches("<=1.12.2", "1.12.2"));
+        assert!(matches("=1.20.0", "1.20"));
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        for pattern in ["", "1.a", ">=", ">=1.20, 1.21", "~1.20"] {
+            assert!(
+                matches!(
+                    VersionPattern::parse(pattern),
+                    Err(ProtonError::InvalidVersionPattern(_))
+                ),
+                "{pattern:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn snapshots_are_not_releases() {
+        assert_eq!(parse_release("1.20.4"), Some(vec![1, 20, 4]));
+        assert_eq!(parse_release("24w14a"), None);
+        assert_eq!(parse_release("1.21-pre1"), None);
+    }
+}
@@ -1,66 +1,344 @@
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+mod cache;
+mod client;
+#[cfg(not(target_arch = "wasm32"))]
+mod local;
+mod matching;
+mod source;
+#[cfg(not(target_arch = "wasm32"))]
+mod watch;
+
+use std::path::Path;
 
 use crate::errors::ProtonError;
+use crate::transport::artifact_source;
 use crate::types::{
-    MOJANG_MANIFEST_URL, MojangVersionDetails, MojangVersionInfo, MojangVersionManifest,
-    NormalizedVersion, VersionAssets,
+    MojangVersionDetails, MojangVersionInfo, MojangVersionManifest, NormalizedVersion,
+    VersionAssets, VersionFilter, VersionSort, VersionTypes,
 };
-use crate::utilities::HTTP_CLIENT;
+
+// Identificadores especiales que se resuelven contra `latest` del manifest
+pub const LATEST_RELEASE: &str = "latest-release";
+pub const LATEST_SNAPSHOT: &str = "latest-snapshot";
+
+fn resolve_alias<'a>(manifest: &'a MojangVersionManifest, version_id: &'a str) -> &'a str {
+    match version_id {
+        LATEST_RELEASE => &manifest.latest.release,
+        LATEST_SNAPSHOT => &manifest.latest.snapshot,
+        other => other,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::{cache_dir, set_cache_dir};
+pub use client::{ManifestClient, default_manifest_client};
+#[cfg(not(target_arch = "wasm32"))]
+pub use local::{register_custom_version, resolve_version_from_file};
+pub use source::{MetadataSource, metadata_source, set_metadata_source};
+#[cfg(not(target_arch = "wasm32"))]
+pub use watch::{ManifestUpdate, ManifestUpdates, watch_manifest};
 
 pub async fn get_manifest() -> Result<MojangVersionManifest, ProtonError> {
-    let res = HTTP_CLIENT
-        .get(MOJANG_MANIFEST_URL)
-        .send()
-        .await?
-        .json::<MojangVersionManifest>()
-        .await?;
-    Ok(res)
+    let manifest = default_manifest_client().get_manifest().await?;
+    Ok((*manifest).clone())
+}
+
+/// Lista las versiones del manifest filtradas por tipo, ordenadas y paginadas
+pub async fn list_versions(filter: &VersionFilter) -> Result<Vec<MojangVersionInfo>, ProtonError> {
+    let manifest = get_manifest().await?;
+
+    let mut versions: Vec<MojangVersionInfo> = manifest
+        .versions
+        .into_iter()
+        .filter(|v| filter.matches(v))
+        .collect();
+
+    // Las fechas vienen en ISO 8601 con el mismo offset, se pueden comparar como texto
+    match filter.sort {
+        VersionSort::Manifest => {}
+        VersionSort::NewestFirst => versions.sort_by(|a, b| b.release_time.cmp(&a.release_time)),
+        VersionSort::OldestFirst => versions.sort_by(|a, b| a.release_time.cmp(&b.release_time)),
+    }
+
+    Ok(versions
+        .into_iter()
+        .skip(filter.offset)
+        .take(filter.limit.unwrap_or(usize::MAX))
+        .collect())
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 pub async fn resolve_version_in_manifest(
     version_id: &str,
 ) -> Result<MojangVersionInfo, ProtonError> {
+    default_manifest_client()
+        .resolve_version_in_manifest(version_id)
+        .await
+}
+
+/// Resuelve la release más reciente que cumpla un patrón (`1.20.*`, `1.20.x`)
+/// o un rango (`>=1.20, <1.21`)
+pub async fn resolve_version_matching(pattern: &str) -> Result<NormalizedVersion, ProtonError> {
+    let pattern_parsed = matching::VersionPattern::parse(pattern)?;
     let manifest = get_manifest().await?;
 
-    manifest
+    let newest = manifest
         .versions
-        .into_iter()
-        .find(|v| v.id == version_id)
-        .ok_or(ProtonError::VersionNotFound(version_id.to_string()))
+        .iter()
+        .filter(|v| v.version_type == VersionTypes::Release)
+        .filter_map(|v| matching::parse_release(&v.id).map(|parts| (v, parts)))
+        .filter(|(_, parts)| pattern_parsed.matches(parts))
+        .max_by(|(_, a), (_, b)| matching::compare_release(a, b))
+        .map(|(v, _)| v.id.clone())
+        .ok_or(ProtonError::VersionNotFound(pattern.to_string()))?;
+
+    resolve_version_data(&newest).await
+}
+
+/// Resuelve la versión más reciente del tipo indicado
+pub async fn resolve_latest(version_type: VersionTypes) -> Result<NormalizedVersion, ProtonError> {
+    let manifest = get_manifest().await?;
+
+    let version_id = match version_type {
+        VersionTypes::Release => manifest.latest.release.clone(),
+        VersionTypes::Snapshot => manifest.latest.snapshot.clone(),
+        // El manifest está ordenado de más nueva a más antigua
+        other => manifest
+            .versions
+            .iter()
+            .find(|v| v.version_type == other)
+            .map(|v| v.id.clone())
+            .ok_or(ProtonError::VersionNotFound(format!("latest {other:?}")))?,
+    };
+
+    resolve_version_data(&version_id).await
 }
 
+/// Resuelve y normaliza una versión. Acepta además `latest-release` y `latest-snapshot`.
 pub async fn resolve_version_data(version_id: &str) -> Result<NormalizedVersion, ProtonError> {
-    let version_manifest = HTTP_CLIENT
-        .get(MOJANG_MANIFEST_URL)
-        .send()
-        .await?
-        .json::<MojangVersionManifest>()
-        .await?;
-
-    let version = version_manifest
-        .versions
-        .par_iter()
-        .find_any(|version| version.id == version_id)
-        .cloned()
-        .ok_or(ProtonError::VersionNotFound(version_id.to_string()))?;
-
-    let version = HTTP_CLIENT
-        .get(version.url)
-        .send()
-        .await?
-        .json::<MojangVersionDetails>()
-        .await?;
-    NormalizedVersion::try_from(version)
+    default_manifest_client()
+        .resolve_version_data(version_id)
+        .await
+}
+
+/// Normaliza un JSON de versión de Mojang en crudo sin pasar por la red
+pub fn normalize_version_json(json: &str) -> Result<NormalizedVersion, ProtonError> {
+    let details: MojangVersionDetails = serde_json::from_str(json)
+        .map_err(|e| ProtonError::Other(format!("Invalid version JSON: {e}")))?;
+    NormalizedVersion::try_from(details)
 }
 
 pub async fn resolve_asset_index(
     version: &NormalizedVersion,
 ) -> Result<VersionAssets, ProtonError> {
-    let res = HTTP_CLIENT
-        .get(&version.asset_index.url)
-        .send()
-        .await?
-        .json::<VersionAssets>()
-        .await?;
-    Ok(res)
+    let cache_key = Path::new("indexes").join(format!("{}.json", version.asset_index.id));
+    cache::fetch_json_cached(&*artifact_source(), &version.asset_index.url, &cache_key).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::set_endpoints;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment, fixture_version_details};
+    use serde_json::{Value, json};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    const CUSTOM_MANIFEST: &str = "/custom/version_manifest_v2.json";
+
+    fn entry(id: &str, version_type: &str, release_time: &str, url: &str) -> Value {
+        json!({
+            "id": id,
+            "type": version_type,
+            "url": url,
+            "time": release_time,
+            "releaseTime": release_time,
+            "sha1": "",
+        })
+    }
+
+    /// Sirve `versions` como manifest y apunta los endpoints a él
+    async fn serve_manifest(env: &MockEnvironment, latest: (&str, &str), versions: Vec<Value>) {
+        let manifest = json!({
+            "latest": { "release": latest.0, "snapshot": latest.1 },
+            "versions": versions,
+        });
+        Mock::given(method("GET"))
+            .and(path(CUSTOM_MANIFEST))
+            .respond_with(ResponseTemplate::new(200).set_body_json(manifest))
+            .mount(env.server.server())
+            .await;
+        let manifest_url = format!("{}{CUSTOM_MANIFEST}", env.server.uri());
+        set_endpoints(env.server.endpoints().manifest_url(manifest_url));
+    }
+
+    fn ids(versions: &[MojangVersionInfo]) -> Vec<&str> {
+        versions.iter().map(|version| version.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn versions_are_filtered_sorted_and_paginated() {
+        let env = MockEnvironment::start().await;
+        serve_manifest(
+            &env,
+            ("1.20.4", "24w14a"),
+            vec![
+                entry("24w14a", "snapshot", "2024-04-03T12:00:00+00:00", ""),
+                entry("1.20.4", "release", "2023-12-07T12:00:00+00:00", ""),
+                entry("1.20.3", "release", "2023-12-05T12:00:00+00:00", ""),
+                entry("b1.7.3", "old_beta", "2011-07-08T00:00:00+00:00", ""),
+                entry("1.20.2", "release", "2023-09-21T12:00:00+00:00", ""),
+            ],
+        )
+        .await;
+
+        let releases = VersionFilter::default().only(VersionTypes::Release);
+        assert_eq!(
+            ids(&list_versions(&releases).await.unwrap()),
+            ["1.20.4", "1.20.3", "1.20.2"]
+        );
+        let oldest = releases.clone().sort(VersionSort::OldestFirst);
+        assert_eq!(
+            ids(&list_versions(&oldest).await.unwrap()),
+            ["1.20.2", "1.20.3", "1.20.4"]
+        );
+        let second_page = VersionFilter::default()
+            .sort(VersionSort::NewestFirst)
+            .page(2, 2);
+        assert_eq!(
+            ids(&list_versions(&second_page).await.unwrap()),
+            ["1.20.3", "1.20.2"]
+        );
+        let old = VersionFilter::default()
+            .only(VersionTypes::OldBeta)
+            .only(VersionTypes::OldAlpha);
+        assert_eq!(ids(&list_versions(&old).await.unwrap()), ["b1.7.3"]);
+        assert!(
+            list_versions(&releases.page(10, 5))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn latest_aliases_follow_the_manifest() {
+        let env = MockEnvironment::start().await;
+        assert_eq!(
+            resolve_version_data(LATEST_RELEASE).await.unwrap().id,
+            FIXTURE_VERSION
+        );
+        assert_eq!(
+            resolve_latest(VersionTypes::Snapshot).await.unwrap().id,
+            FIXTURE_VERSION
+        );
+
+        let fixture_url = format!("{}/versions/{FIXTURE_VERSION}.json", env.server.uri());
+        let missing_url = format!("{}/versions/missing.json", env.server.uri());
+        serve_manifest(
+            &env,
+            ("1.20.4", "24w14a"),
+            vec![
+                entry(
+                    "24w14a",
+                    "snapshot",
+                    "2024-04-03T12:00:00+00:00",
+                    &missing_url,
+                ),
+                entry(
+                    "1.20.4",
+                    "release",
+                    "2023-12-07T12:00:00+00:00",
+                    &missing_url,
+                ),
+                entry(
+                    "b1.7.3",
+                    "old_beta",
+                    "2011-07-08T00:00:00+00:00",
+                    &fixture_url,
+                ),
+            ],
+        )
+        .await;
+        default_manifest_client().clear();
+
+        // El alias apunta a `latest`, aunque su JSON no se pueda descargar
+        assert!(resolve_version_data(LATEST_SNAPSHOT).await.is_err());
+        // Sin `latest` para el tipo se usa la primera del manifest
+        assert_eq!(
+            resolve_latest(VersionTypes::OldBeta).await.unwrap().id,
+            FIXTURE_VERSION
+        );
+        assert!(matches!(
+            resolve_latest(VersionTypes::OldAlpha).await,
+            Err(ProtonError::VersionNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn raw_version_json_is_normalized_for_this_system() {
+        let os = crate::types::mojang_os_name();
+        let other_os = if os == "windows" { "linux" } else { "windows" };
+        let artifact = |name: &str| json!({ "path": format!("{name}.jar"), "url": format!("https://libraries.example/{name}.jar"), "sha1": "", "size": 1 });
+        let mut version =
+            serde_json::to_value(fixture_version_details("https://example.invalid")).unwrap();
+        version["libraries"] = json!([
+            { "name": "org.proton:everywhere:1.0", "downloads": { "artifact": artifact("everywhere") } },
+            {
+                "name": "org.proton:other-os:1.0",
+                "downloads": { "artifact": artifact("other-os") },
+                "rules": [{ "action": "allow", "os": { "name": other_os } }],
+            },
+            {
+                "name": "org.proton:not-here:1.0",
+                "downloads": { "artifact": artifact("not-here") },
+                "rules": [{ "action": "allow" }, { "action": "disallow", "os": { "name": os } }],
+            },
+            {
+                "name": "org.proton:natives:1.0",
+                "natives": { os: "natives-${arch}" },
+                "downloads": { "classifiers": {
+                    "natives-32": artifact("natives-32"),
+                    "natives-64": artifact("natives-64"),
+                }},
+            },
+        ]);
+        version["arguments"] = json!({
+            "game": [
+                "--username", "${auth_player_name}",
+                { "rules": [{ "action": "allow", "features": { "is_demo_user": true } }], "value": "--demo" },
+            ],
+            "jvm": [
+                { "rules": [{ "action": "allow", "os": { "name": os } }], "value": ["-Dhere=true"] },
+                { "rules": [{ "action": "allow", "os": { "name": other_os } }], "value": "-Dthere=true" },
+                "-cp", "${classpath}",
+            ],
+        });
+
+        let normalized = normalize_version_json(&version.to_string()).unwrap();
+
+        let libraries: Vec<_> = normalized
+            .libraries
+            .iter()
+            .map(|l| l.name.as_str())
+            .collect();
+        assert_eq!(libraries, ["org.proton:everywhere:1.0"]);
+        let bits = if cfg!(target_pointer_width = "64") {
+            "64"
+        } else {
+            "32"
+        };
+        assert_eq!(normalized.natives.len(), 1);
+        assert_eq!(normalized.natives[0].classifier, format!("natives-{bits}"));
+        assert_eq!(
+            normalized.arguments.jvm,
+            ["-Dhere=true", "-cp", "${classpath}"]
+        );
+        assert_eq!(
+            normalized.arguments.game,
+            ["--username", "${auth_player_name}"]
+        );
+        assert_eq!(normalized.arguments.game_features.len(), 1);
+        assert_eq!(normalized.arguments.game_features[0].values, ["--demo"]);
+        assert!(normalize_version_json("{\"id\": 1}").is_err());
+    }
 }
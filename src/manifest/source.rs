@@ -0,0 +1,134 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use crate::errors::ProtonError;
+
+const PISTON_META_HOST: &str = "piston-meta.mojang.com";
+const LAUNCHER_META_HOST: &str = "launchermeta.mojang.com";
+
+/// Servidor de metadatos de Mojang a consultar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetadataSource {
+    /// Solo `piston-meta.mojang.com`
+    PistonMeta,
+    /// Solo `launchermeta.mojang.com`
+    LauncherMeta,
+    /// `piston-meta` y, si falla, `launchermeta`
+    #[default]
+    Auto,
+}
+
+impl FromStr for MetadataSource {
+    type Err = ProtonError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "piston-meta" => Ok(MetadataSource::PistonMeta),
+            "launcher-meta" => Ok(MetadataSource::LauncherMeta),
+            "auto" => Ok(MetadataSource::Auto),
+            other => Err(ProtonError::InvalidConfig(format!(
+                "unknown metadata source '{other}'"
+            ))),
+        }
+    }
+}
+
+static METADATA_SOURCE: Lazy<RwLock<MetadataSource>> =
+    Lazy::new(|| RwLock::new(MetadataSource::default()));
+
+/// Selecciona el servidor de metadatos usado por las funciones de `manifest`
+pub fn set_metadata_source(source: MetadataSource) {
+    *METADATA_SOURCE.write().unwrap_or_else(|e| e.into_inner()) = source;
+}
+
+pub fn metadata_source() -> MetadataSource {
+    *METADATA_SOURCE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// URLs a intentar, en orden, para un documento de metadatos de Mojang.
+/// Ambos hosts sirven los mismos documentos bajo las mismas rutas.
+pub(crate) fn candidate_urls(url: &str) -> Vec<String> {
+    let on_piston = url.contains(PISTON_META_HOST);
+    let on_launcher = url.contains(LAUNCHER_META_HOST);
+
+    // URLs de terceros se usan tal cual
+    if !on_piston && !on_launcher {
+        return vec![url.to_string()];
+    }
+
+    let piston = url.replacen(LAUNCHER_META_HOST, PISTON_META_HOST, 1);
+    let launcher = url.replacen(PISTON_META_HOST, LAUNCHER_META_HOST, 1);
+
+    match metadata_source() {
+        MetadataSource::PistonMeta => vec![piston],
+        MetadataSource::LauncherMeta => vec![launcher],
+        MetadataSource::Auto => vec![piston, launcher],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::cache::fetch_json_cached;
+    use crate::testing::{exclusive, isolate_cache_dir};
+    use crate::transport::MemorySource;
+    use std::path::Path;
+
+    const PISTON_URL: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+    const LAUNCHER_URL: &str = "https://launchermeta.mojang.com/mc/game/version_manifest_v2.json";
+
+    #[tokio::test]
+    async fn each_source_picks_its_mojang_hosts() {
+        let _guard = exclusive().await;
+        let candidates = |source| {
+            set_metadata_source(source);
+            candidate_urls(LAUNCHER_URL)
+        };
+
+        assert_eq!(candidates(MetadataSource::PistonMeta), [PISTON_URL]);
+        assert_eq!(candidates(MetadataSource::LauncherMeta), [LAUNCHER_URL]);
+        assert_eq!(candidates(MetadataSource::Auto), [PISTON_URL, LAUNCHER_URL]);
+        // Los mirrors propios no se reescriben
+        assert_eq!(
+            candidate_urls("https://mirror.example/manifest.json"),
+            ["https://mirror.example/manifest.json"]
+        );
+    }
+
+    #[tokio::test]
+    async fn auto_falls_back_to_launchermeta() {
+        let _guard = exclusive().await;
+        let _cache = isolate_cache_dir();
+        let source = MemorySource::new().with(LAUNCHER_URL, r#"{"from":"launchermeta"}"#);
+        let fetch = |source_kind| {
+            set_metadata_source(source_kind);
+            fetch_json_cached::<serde_json::Value>(&source, PISTON_URL, Path::new("manifest.json"))
+        };
+
+        let piston_only = fetch(MetadataSource::PistonMeta).await;
+        let auto = fetch(MetadataSource::Auto).await;
+        set_metadata_source(MetadataSource::default());
+
+        assert!(piston_only.is_err());
+        assert_eq!(auto.unwrap()["from"], "launchermeta");
+    }
+
+    #[test]
+    fn parses_config_names() {
+        assert_eq!(
+            "piston-meta".parse::<MetadataSource>().unwrap(),
+            MetadataSource::PistonMeta
+        );
+        assert_eq!(
+            "launcher-meta".parse::<MetadataSource>().unwrap(),
+            MetadataSource::LauncherMeta
+        );
+        assert!(matches!(
+            "mojang".parse::<MetadataSource>(),
+            Err(ProtonError::InvalidConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+use futures::stream::{self, BoxStream, StreamExt};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::default_manifest_client;
+use super::source::candidate_urls;
+use crate::endpoints::endpoints;
+use crate::transport::{CacheValidators, ConditionalFetch, artifact_source};
+use crate::types::{MojangLatestVersions, MojangVersionInfo, MojangVersionManifest};
+
+/// Cambio del manifest detectado por `watch_manifest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestUpdate {
+    /// `latest` antes del cambio
+    pub previous: MojangLatestVersions,
+    pub latest: MojangLatestVersions,
+    /// Versiones que no estaban en la consulta anterior, de más nueva a más antigua
+    pub new_versions: Vec<MojangVersionInfo>,
+}
+
+impl ManifestUpdate {
+    pub fn release_changed(&self) -> bool {
+        self.previous.release != self.latest.release
+    }
+
+    pub fn snapshot_changed(&self) -> bool {
+        self.previous.snapshot != self.latest.snapshot
+    }
+}
+
+/// Cambios del manifest, en el orden en que se detectan
+pub type ManifestUpdates = BoxStream<'static, ManifestUpdate>;
+
+#[derive(Default)]
+struct WatchState {
+    // URL que respondió la última vez y sus validadores
+    url: String,
+    validators: CacheValidators,
+    latest: Option<MojangLatestVersions>,
+    known: HashSet<String>,
+}
+
+/// Consulta el manifest cada `interval` y emite un `ManifestUpdate` cuando
+/// cambia `latest` o aparecen versiones nuevas. La primera consulta solo fija
+/// el punto de partida. Usa peticiones condicionales (`ETag`/`Last-Modified`),
+/// así que una consulta sin cambios no vuelve a descargar el manifest; los
+/// errores de red se registran y se reintenta en la siguiente.
+pub fn watch_manifest(interval: Duration) -> ManifestUpdates {
+    stream::unfold(
+        (WatchState::default(), true),
+        move |(mut state, first)| async move {
+            if !first {
+                tokio::time::sleep(interval).await;
+            }
+            loop {
+                if let Some(update) = poll(&mut state).await {
+                    return Some((update, (state, false)));
+                }
+                tokio::time::sleep(interval).await;
+            }
+        },
+    )
+    .boxed()
+}
+
+async fn poll(state: &mut WatchState) -> Option<ManifestUpdate> {
+    let manifest_url = endpoints().manifest_url;
+    let source = artifact_source();
+
+    let mut fetched = None;
+    for candidate in candidate_urls(&manifest_url) {
+        // Los validadores solo sirven para el servidor que los dio
+        let validators = if candidate == state.url {
+            state.validators.clone()
+        } else {
+            CacheValidators::default()
+        };
+        match source.fetch_if_modified(&candidate, &validators).await {
+            Ok(result) => {
+                fetched = Some((candidate, result));
+                break;
+            }
+            Err(e) => warn!("Manifest check against {candidate} failed: {e}"),
+        }
+    }
+
+    let (url, content, validators) = match fetched? {
+        (_, ConditionalFetch::NotModified) => return None,
+        (
+            url,
+            ConditionalFetch::Modified {
+                content,
+                validators,
+            },
+        ) => (url, content, validators),
+    };
+    let manifest: MojangVersionManifest = match serde_json::from_slice(&content) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            warn!("Invalid manifest from {url}: {e}");
+            return None;
+        }
+    };
+    state.url = url;
+    state.validators = validators;
+
+    let new_versions: Vec<MojangVersionInfo> = manifest
+        .versions
+        .iter()
+        .filter(|version| !state.known.contains(&version.id))
+        .cloned()
+        .collect();
+    state
+        .known
+        .extend(new_versions.iter().map(|version| version.id.clone()));
+    let previous = state.latest.replace(manifest.latest.clone());
+
+    let latest = manifest.latest.clone();
+    default_manifest_client().store_manifest(manifest_url, Arc::new(manifest));
+
+    let previous = previous?;
+    if previous == latest && new_versions.is_empty() {
+        return None;
+    }
+    info!(
+        "Manifest changed: latest release {}, latest snapshot {}, {} new versions",
+        latest.release,
+        latest.snapshot,
+        new_versions.len()
+    );
+    Some(ManifestUpdate {
+        previous,
+        latest,
+        new_versions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockEnvironment;
+    use serde_json::json;
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, ResponseTemplate};
+
+    fn manifest(release: &str, ids: &[&str]) -> Vec<u8> {
+        let versions: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                json!({
+                    "id": id,
+                    "type": "release",
+                    "url": format!("https://example.invalid/{id}.json"),
+                    "time": "2024-01-01T00:00:00+00:00",
+                    "releaseTime": "2024-01-01T00:00:00+00:00",
+                    "sha1": "0".repeat(40),
+                })
+            })
+            .collect();
+        let manifest = json!({
+            "latest": { "release": release, "snapshot": release },
+            "versions": versions,
+        });
+        serde_json::to_vec(&manifest).unwrap()
+    }
+
+    #[tokio::test]
+    async fn emits_only_when_the_manifest_changes() {
+        let env = MockEnvironment::start().await;
+        let server = &env.server;
+        // La segunda consulta no tiene cambios; la tercera trae 1.21.9
+        Mock::given(method("GET"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+        Mock::given(method("GET"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v2\"")
+                    .set_body_bytes(manifest("1.21.9", &["1.21.9", "1.21.8"])),
+            )
+            .with_priority(2)
+            .mount(server.server())
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_bytes(manifest("1.21.8", &["1.21.8"])),
+            )
+            .with_priority(3)
+            .mount(server.server())
+            .await;
+
+        let update = tokio::time::timeout(
+            Duration::from_secs(10),
+            watch_manifest(Duration::from_millis(10)).next(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(update.previous.release, "1.21.8");
+        assert_eq!(update.latest.release, "1.21.9");
+        assert!(update.release_changed());
+        let new: Vec<_> = update.new_versions.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(new, ["1.21.9"]);
+        let requests = server.server().received_requests().await.unwrap();
+        assert_eq!(requests.len(), 3);
+    }
+}
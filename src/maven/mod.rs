@@ -0,0 +1,164 @@
+use crate::errors::ProtonError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Coordenada maven `grupo:artefacto:versión[:clasificador][@extensión]`,
+/// como aparece en el `name` de las librerías
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MavenCoordinate {
+    pub group: String,
+    pub artifact: String,
+    pub version: String,
+    pub classifier: Option<String>,
+    /// `jar` si el nombre no indica otra
+    pub extension: String,
+}
+
+impl MavenCoordinate {
+    pub fn new(
+        group: impl Into<String>,
+        artifact: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        Self {
+            group: group.into(),
+            artifact: artifact.into(),
+            version: version.into(),
+            classifier: None,
+            extension: "jar".to_string(),
+        }
+    }
+
+    pub fn with_classifier(mut self, classifier: impl Into<String>) -> Self {
+        self.classifier = Some(classifier.into());
+        self
+    }
+
+    pub fn with_extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into();
+        self
+    }
+
+    /// `artefacto-versión[-clasificador].extensión`
+    pub fn file_name(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!(
+                "{}-{}-{classifier}.{}",
+                self.artifact, self.version, self.extension
+            ),
+            None => format!("{}-{}.{}", self.artifact, self.version, self.extension),
+        }
+    }
+
+    /// Ruta dentro de un repositorio maven (y de `libraries/`), con `/`
+    pub fn path(&self) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.group.replace('.', "/"),
+            self.artifact,
+            self.version,
+            self.file_name()
+        )
+    }
+
+    /// URL del artefacto en el repositorio que empieza en `repository`
+    pub fn url(&self, repository: &str) -> String {
+        format!("{}/{}", repository.trim_end_matches('/'), self.path())
+    }
+
+    /// `grupo:artefacto[:clasificador]`: identifica la librería sin la
+    /// versión, para saber si dos nombres son la misma en versiones distintas
+    pub fn key(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!("{}:{}:{classifier}", self.group, self.artifact),
+            None => format!("{}:{}", self.group, self.artifact),
+        }
+    }
+}
+
+impl FromStr for MavenCoordinate {
+    type Err = ProtonError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let invalid = || ProtonError::InvalidMavenCoordinate(name.to_string());
+        let (coordinate, extension) = name.split_once('@').unwrap_or((name, "jar"));
+        let parts: Vec<&str> = coordinate.split(':').collect();
+        if extension.is_empty() || parts.iter().any(|part| part.is_empty()) {
+            return Err(invalid());
+        }
+
+        let (group, artifact, version, classifier) = match parts.as_slice() {
+            [group, artifact, version] => (group, artifact, version, None),
+            [group, artifact, version, classifier] => (group, artifact, version, Some(classifier)),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            group: group.to_string(),
+            artifact: artifact.to_string(),
+            version: version.to_string(),
+            classifier: classifier.map(|classifier| classifier.to_string()),
+            extension: extension.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for MavenCoordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.artifact, self.version)?;
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{classifier}")?;
+        }
+        if self.extension != "jar" {
+            write!(f, "@{}", self.extension)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_classifier_and_extension() {
+        let coordinate: MavenCoordinate =
+            "org.lwjgl:lwjgl:3.3.3:natives-linux@zip".parse().unwrap();
+        assert_eq!(
+            coordinate,
+            MavenCoordinate::new("org.lwjgl", "lwjgl", "3.3.3")
+                .with_classifier("natives-linux")
+                .with_extension("zip")
+        );
+        assert_eq!(
+            coordinate.path(),
+            "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3-natives-linux.zip"
+        );
+        assert_eq!(coordinate.key(), "org.lwjgl:lwjgl:natives-linux");
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for name in [
+            "net.minecraftforge:forge:1.20.1-47.2.0",
+            "net.minecraftforge:forge:1.20.1-47.2.0:installer",
+            "de.oceanlabs.mcp:mcp_config:1.20.1@zip",
+        ] {
+            let coordinate: MavenCoordinate = name.parse().unwrap();
+            assert_eq!(coordinate.to_string(), name);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_names() {
+        for name in ["", "group:artifact", "a:b:c:d:e", "a::c", "a:b:c@"] {
+            assert!(
+                matches!(
+                    name.parse::<MavenCoordinate>(),
+                    Err(ProtonError::InvalidMavenCoordinate(_))
+                ),
+                "{name:?} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+use crate::downloaders::MinecraftDownloader;
+use crate::errors::ProtonError;
+use crate::instance::Instance;
+use crate::launcher::{LaunchCommand, LaunchOptions};
+use crate::manifest;
+
+type ProgressCallback = ThreadsafeFunction<Value, ErrorStrategy::Fatal>;
+
+fn js_error(error: ProtonError) -> napi::Error {
+    napi::Error::from_reason(error.to_string())
+}
+
+/// Comando de lanzamiento con los secretos ocultos
+#[napi(object)]
+pub struct JsLaunchCommand {
+    pub java: String,
+    pub args: Vec<String>,
+    pub working_dir: String,
+}
+
+#[napi(object)]
+pub struct JsGameExit {
+    pub code: Option<i32>,
+    pub crashed: bool,
+    pub duration_secs: f64,
+}
+
+/// `NormalizedVersion` como objeto JS
+#[napi]
+pub async fn resolve_version_data(version_id: String) -> napi::Result<Value> {
+    let version = manifest::resolve_version_data(&version_id)
+        .await
+        .map_err(js_error)?;
+    serde_json::to_value(version).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+/// Descarga la versión en `gamePath`; `onProgress` recibe cada `DownloadProgress`
+#[napi(ts_args_type = "gamePath: string, versionId: string, onProgress?: (progress: any) => void")]
+pub async fn download_all(
+    game_path: String,
+    version_id: String,
+    on_progress: Option<ProgressCallback>,
+) -> napi::Result<()> {
+    let version = manifest::resolve_version_data(&version_id)
+        .await
+        .map_err(js_error)?;
+    let mut downloader = MinecraftDownloader::new(PathBuf::from(game_path), version);
+
+    let Some(callback) = on_progress else {
+        return downloader.download_all(None).await.map_err(js_error);
+    };
+
+    let (tx, mut rx) = mpsc::channel(100);
+    let forwarder = tokio::spawn(async move {
+        while let Some(progress) = rx.recv().await {
+            if let Ok(value) = serde_json::to_value(progress) {
+                callback.call(value, ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+    });
+
+    let result = downloader.download_all(Some(tx)).await;
+    let _ = forwarder.await;
+    result.map_err(js_error)
+}
+
+async fn instance_command(
+    instance_dir: &str,
+    game_path: String,
+    username: String,
+) -> Result<(Instance, LaunchCommand), ProtonError> {
+    let instance = Instance::load(instance_dir).await?;
+    let version = instance.launch_version(Path::new(&game_path)).await?;
+    let options = LaunchOptions::for_instance(&instance, game_path, username);
+    let command = LaunchCommand::build(&version, &options);
+    Ok((instance, command))
+}
+
+/// Comando que se usaría para lanzar la instancia, sin ejecutarlo
+#[napi]
+pub async fn build_launch_command(
+    instance_dir: String,
+    game_path: String,
+    username: String,
+) -> napi::Result<JsLaunchCommand> {
+    let (_, command) = instance_command(&instance_dir, game_path, username)
+        .await
+        .map_err(js_error)?;
+    let redacted = command.redacted();
+
+    Ok(JsLaunchCommand {
+        java: redacted.java.to_string_lossy().into_owned(),
+        args: redacted.args(),
+        working_dir: redacted.working_dir.to_string_lossy().into_owned(),
+    })
+}
+
+/// Lanza la instancia y espera a que el juego termine
+#[napi]
+pub async fn launch_instance(
+    instance_dir: String,
+    game_path: String,
+    username: String,
+) -> napi::Result<JsGameExit> {
+    let (mut instance, command) = instance_command(&instance_dir, game_path, username)
+        .await
+        .map_err(js_error)?;
+    // Nadie lee la salida del juego: se suelta para que se descarte al momento
+    let exit = instance
+        .run(&command, |process| {
+            drop(process.take_stdout());
+            drop(process.take_stderr());
+        })
+        .await
+        .map_err(js_error)?;
+
+    Ok(JsGameExit {
+        code: exit.code,
+        crashed: exit.crashed(),
+        duration_secs: exit.duration.as_secs_f64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instance::InstanceConfig;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+
+    #[tokio::test]
+    async fn bindings_resolve_versions_and_build_commands() {
+        let env = MockEnvironment::start().await;
+        let game_path = env.game_dir.to_string_lossy().into_owned();
+
+        // `download_all` enlaza con funciones de Node que no existen fuera de él
+        env.downloader().download_all(None).await.unwrap();
+
+        let (version, command) = env
+            .run(async {
+                let version = resolve_version_data(FIXTURE_VERSION.to_string()).await?;
+                let config = InstanceConfig::new("Node", FIXTURE_VERSION);
+                Instance::create("/instances/node", config)
+                    .await
+                    .map_err(js_error)?;
+                let command = build_launch_command(
+                    "/instances/node".to_string(),
+                    game_path.clone(),
+                    "Steve".to_string(),
+                )
+                .await?;
+                Ok::<_, napi::Error>((version, command))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(version["id"], FIXTURE_VERSION);
+        assert!(
+            env.fs
+                .get(
+                    env.game_dir
+                        .join(format!("versions/{FIXTURE_VERSION}/{FIXTURE_VERSION}.jar"))
+                )
+                .is_some()
+        );
+        assert!(command.args.iter().any(|arg| arg == "Steve"));
+        assert!(command.working_dir.starts_with("/instances/node"));
+    }
+
+    #[tokio::test]
+    async fn errors_reach_js_as_their_message() {
+        let env = MockEnvironment::start().await;
+
+        let error = env
+            .run(resolve_version_data("does-not-exist".to_string()))
+            .await
+            .unwrap_err();
+
+        assert!(error.reason.contains("does-not-exist"), "{}", error.reason);
+    }
+}
@@ -0,0 +1,232 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::endpoints::endpoints;
+use crate::errors::ProtonError;
+use crate::manifest::resolve_asset_index;
+use crate::types::{DownloadProgressType, NormalizedVersion, VersionAssets};
+
+/// Archivo que instalaría `MinecraftDownloader`, con la ruta relativa al
+/// directorio del juego. Los nativos apuntan al jar que se extrae en `natives/<id>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedDownload {
+    pub name: String,
+    pub url: String,
+    pub path: PathBuf,
+    pub sha1: String,
+    pub size: u64,
+    pub download_type: DownloadProgressType,
+}
+
+/// Formato de la lista que genera `DownloadPlan::to_download_list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DownloadListFormat {
+    /// Archivo de entrada de `aria2c -i`, con la ruta y el SHA-1 de cada URL
+    #[default]
+    Aria2,
+    /// El propio `DownloadPlan` en JSON, con rutas absolutas
+    Json,
+}
+
+/// Archivos que cambian al pasar de una versión a otra, calculado por
+/// `DownloadPlan::diff` o, teniendo en cuenta el disco, por `diff_versions`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDelta {
+    pub from: String,
+    pub to: String,
+    /// Archivos de la versión destino que hay que descargar
+    pub added: Vec<PlannedDownload>,
+    /// Archivos de la versión instalada que la destino ya no usa. Otras
+    /// versiones pueden seguir usándolos: para borrar solo lo huérfano, `gc`.
+    pub removed: Vec<PlannedDownload>,
+    /// Archivos de la versión destino que no hace falta descargar
+    pub unchanged: usize,
+}
+
+impl VersionDelta {
+    /// Bytes a descargar para la actualización
+    pub fn download_size(&self) -> u64 {
+        self.added.iter().map(|file| file.size).sum()
+    }
+}
+
+/// Lista de archivos de una versión calculada sin tocar el disco
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadPlan {
+    pub version: String,
+    pub files: Vec<PlannedDownload>,
+}
+
+impl DownloadPlan {
+    /// Plan de una versión; sin índice de assets solo incluye el propio índice
+    pub fn new(version: &NormalizedVersion, assets: Option<&VersionAssets>) -> Self {
+        let endpoints = endpoints();
+        let id = &version.id;
+        let version_dir = PathBuf::from("versions").join(id);
+        let mut files = Vec::new();
+
+        files.push(PlannedDownload {
+            name: format!("minecraft-{id}"),
+            url: version.client_jar.url.clone(),
+            path: version_dir.join(format!("{id}.jar")),
+            sha1: version.client_jar.sha1.clone(),
+            size: version.client_jar.size,
+            download_type: DownloadProgressType::Client,
+        });
+
+        if let Some(logging) = &version.logging {
+            files.push(PlannedDownload {
+                name: format!("log-config-{}", logging.id),
+                url: logging.url.clone(),
+                path: logging.path(Path::new("")),
+                sha1: logging.sha1.clone(),
+                size: logging.size,
+                download_type: DownloadProgressType::Manifest,
+            });
+        }
+
+        for library in &version.libraries {
+            files.push(PlannedDownload {
+                name: library.name.clone(),
+                url: endpoints.library_url(&library.url),
+                path: PathBuf::from("libraries").join(&library.path),
+                sha1: library.sha1.clone(),
+                size: library.size,
+                download_type: DownloadProgressType::Library,
+            });
+        }
+
+        for native in &version.natives {
+            files.push(PlannedDownload {
+                name: native.name.clone(),
+                url: endpoints.library_url(&native.url),
+                path: PathBuf::from("natives").join(id).join(&native.path),
+                sha1: native.sha1.clone(),
+                size: native.size,
+                download_type: DownloadProgressType::Native,
+            });
+        }
+
+        let index = &version.asset_index;
+        files.push(PlannedDownload {
+            name: format!("asset-index-{}", index.id),
+            url: index.url.clone(),
+            path: PathBuf::from("assets")
+                .join("indexes")
+                .join(format!("{}.json", index.id)),
+            sha1: index.sha1.clone(),
+            size: index.size,
+            download_type: DownloadProgressType::Manifest,
+        });
+
+        if let Some(assets) = assets {
+            let mut entries: Vec<_> = assets.objects.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, asset) in entries {
+                let hash = &asset.hash;
+                // El descargador rechaza el índice entero; el plan solo lo avisa
+                let Ok(url) = endpoints.asset_url(hash) else {
+                    warn!("Skipping asset {name} with invalid hash '{hash}'");
+                    continue;
+                };
+                files.push(PlannedDownload {
+                    name: name.clone(),
+                    url,
+                    path: PathBuf::from("assets")
+                        .join("objects")
+                        .join(&hash[..2])
+                        .join(hash),
+                    sha1: hash.clone(),
+                    size: asset.size as u64,
+                    download_type: DownloadProgressType::Asset,
+                });
+            }
+        }
+
+        Self {
+            version: id.clone(),
+            files,
+        }
+    }
+
+    /// Bytes totales a descargar
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+
+    /// Qué cambia al pasar de esta versión a `target`. Un archivo se
+    /// conserva si la otra versión tiene el mismo en la misma ruta; los
+    /// nativos nunca se comparten porque cada versión los extrae en su
+    /// propio `natives/<id>`.
+    pub fn diff(&self, target: &DownloadPlan) -> VersionDelta {
+        let key = |file: &PlannedDownload| (file.path.clone(), file.sha1.to_lowercase());
+        let current: HashSet<_> = self.files.iter().map(key).collect();
+        let target_paths: HashSet<&PathBuf> = target.files.iter().map(|file| &file.path).collect();
+
+        let mut delta = VersionDelta {
+            from: self.version.clone(),
+            to: target.version.clone(),
+            ..VersionDelta::default()
+        };
+        for file in &target.files {
+            if current.contains(&key(file)) {
+                delta.unchanged += 1;
+            } else {
+                delta.added.push(file.clone());
+            }
+        }
+        delta.removed = self
+            .files
+            .iter()
+            .filter(|file| !target_paths.contains(&file.path))
+            .cloned()
+            .collect();
+        delta
+    }
+
+    /// Lista de descargas para una herramienta externa, con las rutas dentro
+    /// de `game_dir`
+    pub fn to_download_list(&self, game_dir: &Path, format: DownloadListFormat) -> String {
+        match format {
+            DownloadListFormat::Aria2 => {
+                let dir = game_dir.to_string_lossy();
+                let mut list = String::new();
+                for file in &self.files {
+                    // aria2 crea los subdirectorios de `out` bajo `dir`
+                    let out = file.path.to_string_lossy().replace('\\', "/");
+                    list.push_str(&format!(
+                        "{}\n  dir={dir}\n  out={out}\n  checksum=sha-1={}\n",
+                        file.url, file.sha1
+                    ));
+                }
+                list
+            }
+            DownloadListFormat::Json => {
+                let plan = DownloadPlan {
+                    version: self.version.clone(),
+                    files: self
+                        .files
+                        .iter()
+                        .map(|file| PlannedDownload {
+                            path: game_dir.join(&file.path),
+                            ..file.clone()
+                        })
+                        .collect(),
+                };
+                serde_json::to_string_pretty(&plan).unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Plan completo de una versión, incluyendo los assets de su índice
+pub async fn plan_downloads(version: &NormalizedVersion) -> Result<DownloadPlan, ProtonError> {
+    let assets = resolve_asset_index(version).await?;
+    Ok(DownloadPlan::new(version, Some(&assets)))
+}
@@ -0,0 +1,215 @@
+use crate::types::{DownloadProgress, DownloadProgressType};
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
+use tokio::sync::mpsc::Receiver;
+use tokio::task::JoinHandle;
+
+// Orden de las barras en la terminal
+const KINDS: [DownloadProgressType; 6] = [
+    DownloadProgressType::Client,
+    DownloadProgressType::Library,
+    DownloadProgressType::Native,
+    DownloadProgressType::Asset,
+    DownloadProgressType::Server,
+    DownloadProgressType::Manifest,
+];
+
+fn slot(kind: &DownloadProgressType) -> usize {
+    match kind {
+        DownloadProgressType::Client => 0,
+        DownloadProgressType::Library => 1,
+        DownloadProgressType::Native => 2,
+        DownloadProgressType::Asset => 3,
+        DownloadProgressType::Server => 4,
+        DownloadProgressType::Manifest => 5,
+    }
+}
+
+fn label(kind: &DownloadProgressType) -> &'static str {
+    match kind {
+        DownloadProgressType::Client => "client",
+        DownloadProgressType::Library => "libraries",
+        DownloadProgressType::Native => "natives",
+        DownloadProgressType::Asset => "assets",
+        DownloadProgressType::Server => "server",
+        DownloadProgressType::Manifest => "metadata",
+    }
+}
+
+/// Barras de progreso en terminal (una por tipo de descarga y una total)
+/// alimentadas por el canal de progreso de `download_all`
+pub struct ProgressRenderer {
+    multi: MultiProgress,
+    overall: ProgressBar,
+    bars: [Option<ProgressBar>; KINDS.len()],
+    totals: [usize; KINDS.len()],
+    bytes: u64,
+}
+
+impl ProgressRenderer {
+    pub fn new() -> Self {
+        let multi = MultiProgress::new();
+        let overall = multi.add(ProgressBar::new(0));
+        overall.set_style(
+            ProgressStyle::with_template(
+                "{prefix:>10.bold} [{bar:40.green/white}] {pos}/{len} files, {msg} ({elapsed})",
+            )
+            .expect("valid progress template")
+            .progress_chars("=> "),
+        );
+        overall.set_prefix("total");
+        overall.set_message(HumanBytes(0).to_string());
+
+        Self {
+            multi,
+            overall,
+            bars: Default::default(),
+            totals: [0; KINDS.len()],
+            bytes: 0,
+        }
+    }
+
+    /// Actualiza las barras con un evento de progreso
+    pub fn update(&mut self, progress: &DownloadProgress) {
+        let index = slot(&progress.download_type);
+        let bar = self.bars[index].get_or_insert_with(|| {
+            // La barra total queda siempre al final
+            let bar = self.multi.insert_before(&self.overall, ProgressBar::new(0));
+            bar.set_style(
+                ProgressStyle::with_template("{prefix:>10} [{bar:40.cyan/blue}] {pos}/{len}")
+                    .expect("valid progress template")
+                    .progress_chars("=> "),
+            );
+            bar.set_prefix(label(&progress.download_type));
+            bar
+        });
+
+        // Los eventos pueden llegar desordenados entre tareas concurrentes
+        if progress.total as u64 != bar.length().unwrap_or(0) {
+            bar.set_length(progress.total as u64);
+        }
+        if progress.current as u64 > bar.position() {
+            let advanced = progress.current as u64 - bar.position();
+            bar.set_position(progress.current as u64);
+            self.overall.inc(advanced);
+        }
+        if progress.current >= progress.total {
+            bar.finish();
+        }
+        self.bytes += progress.bytes;
+
+        // `download_all` ya manda el total calculado, con los bytes esperados
+        if let Some(overall) = progress.overall {
+            self.overall.set_length(overall.total_files as u64);
+            self.overall.set_position(overall.completed_files as u64);
+            self.overall.set_message(format!(
+                "{}/{}",
+                HumanBytes(overall.completed_bytes),
+                HumanBytes(overall.total_bytes)
+            ));
+            return;
+        }
+
+        if self.totals[index] != progress.total {
+            self.totals[index] = progress.total;
+            self.overall
+                .set_length(self.totals.iter().sum::<usize>() as u64);
+        }
+        self.overall.set_message(HumanBytes(self.bytes).to_string());
+    }
+
+    /// Marca todas las barras como terminadas
+    pub fn finish(&self) {
+        for bar in self.bars.iter().flatten() {
+            if !bar.is_finished() {
+                bar.abandon();
+            }
+        }
+        self.overall.finish();
+    }
+
+    /// Consume el canal hasta que se cierra y devuelve el total de bytes
+    pub fn spawn(mut self, mut rx: Receiver<DownloadProgress>) -> JoinHandle<u64> {
+        tokio::spawn(async move {
+            while let Some(progress) = rx.recv().await {
+                self.update(&progress);
+            }
+            self.finish();
+            self.bytes
+        })
+    }
+}
+
+impl Default for ProgressRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DownloadProgressInfo, OverallProgress};
+    use indicatif::ProgressDrawTarget;
+    use std::sync::Arc;
+    use tokio::sync::mpsc::channel;
+
+    fn event(kind: DownloadProgressType, current: usize, total: usize) -> DownloadProgress {
+        let info = DownloadProgressInfo::new("file", Arc::new("1.20.1".to_string()));
+        DownloadProgress::new(info, kind, current, total, 100)
+    }
+
+    #[tokio::test]
+    async fn the_channel_is_drained_until_it_closes() {
+        let renderer = ProgressRenderer::new();
+        renderer.multi.set_draw_target(ProgressDrawTarget::hidden());
+        let (tx, rx) = channel(16);
+        let handle = renderer.spawn(rx);
+
+        tx.send(event(DownloadProgressType::Library, 2, 3))
+            .await
+            .unwrap();
+        tx.send(event(DownloadProgressType::Library, 1, 3))
+            .await
+            .unwrap();
+        tx.send(event(DownloadProgressType::Client, 1, 1))
+            .await
+            .unwrap();
+        drop(tx);
+
+        assert_eq!(handle.await.unwrap(), 300);
+    }
+
+    #[test]
+    fn overall_progress_comes_from_download_all() {
+        let mut renderer = ProgressRenderer::new();
+        renderer.multi.set_draw_target(ProgressDrawTarget::hidden());
+
+        renderer.update(&event(DownloadProgressType::Library, 2, 3));
+        renderer.update(&event(DownloadProgressType::Asset, 1, 5));
+        // Un evento atrasado no hace retroceder la barra
+        renderer.update(&event(DownloadProgressType::Library, 1, 3));
+        assert_eq!(renderer.overall.length(), Some(8));
+        assert_eq!(renderer.overall.position(), 3);
+        let libraries = renderer.bars[slot(&DownloadProgressType::Library)]
+            .clone()
+            .unwrap();
+        assert_eq!((libraries.position(), libraries.length()), (2, Some(3)));
+        assert!(renderer.bars[slot(&DownloadProgressType::Native)].is_none());
+
+        let mut progress = event(DownloadProgressType::Asset, 2, 5);
+        progress.overall = Some(OverallProgress {
+            completed_files: 10,
+            total_files: 40,
+            completed_bytes: 1024,
+            total_bytes: 4096,
+        });
+        renderer.update(&progress);
+        assert_eq!(renderer.overall.length(), Some(40));
+        assert_eq!(renderer.overall.position(), 10);
+        assert_eq!(renderer.overall.message(), "1.00 KiB/4.00 KiB");
+
+        renderer.finish();
+        assert!(renderer.overall.is_finished());
+        assert!(libraries.is_finished());
+    }
+}
@@ -0,0 +1,198 @@
+use log::info;
+use std::path::{Path, PathBuf};
+
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::types::{DownloadProgressType, NormalizedVersion};
+use crate::utilities::download_file;
+
+mod process;
+mod properties;
+
+pub use process::{ServerHandle, ServerOptions};
+pub use properties::{Difficulty, GameMode, ServerProperties};
+
+pub const SERVER_JAR: &str = "server.jar";
+
+const EULA_URL: &str = "https://aka.ms/MinecraftEULA";
+
+// Valores por defecto de un server.properties recién generado
+const DEFAULT_PROPERTIES: [(&str, &str); 12] = [
+    ("motd", "A Minecraft Server"),
+    ("server-port", "25565"),
+    ("gamemode", "survival"),
+    ("difficulty", "easy"),
+    ("max-players", "20"),
+    ("online-mode", "true"),
+    ("pvp", "true"),
+    ("white-list", "false"),
+    ("enforce-whitelist", "false"),
+    ("view-distance", "10"),
+    ("level-name", "world"),
+    ("level-seed", ""),
+];
+
+/// Instala un servidor dedicado vanilla en un directorio propio
+#[derive(Debug, Clone)]
+pub struct ServerInstaller {
+    version: NormalizedVersion,
+    server_dir: PathBuf,
+    accept_eula: bool,
+}
+
+impl ServerInstaller {
+    pub fn new(version: NormalizedVersion, server_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            version,
+            server_dir: server_dir.into(),
+            accept_eula: false,
+        }
+    }
+
+    /// Escribe `eula=true`. Solo debe activarse tras mostrar la EULA al usuario.
+    pub fn accept_eula(mut self, accept: bool) -> Self {
+        self.accept_eula = accept;
+        self
+    }
+
+    pub fn server_dir(&self) -> &Path {
+        &self.server_dir
+    }
+
+    pub fn jar_path(&self) -> PathBuf {
+        self.server_dir.join(SERVER_JAR)
+    }
+
+    /// Descarga y verifica `server.jar`, y genera `eula.txt` y un
+    /// `server.properties` por defecto si aún no existe
+    pub async fn install(&self) -> Result<PathBuf, ProtonError> {
+        let server = self
+            .version
+            .server_jar
+            .clone()
+            .ok_or_else(|| ProtonError::ServerJarUnavailable(self.version.id.clone()))?;
+
+        let fs = file_system();
+        fs.create_dir_all(&self.server_dir).await?;
+
+        let jar_path = self.jar_path();
+        download_file(
+            server.url.clone(),
+            &jar_path,
+            Some(&server.sha1),
+            server.size,
+        )
+        .await
+        .map_err(|e| {
+            ProtonError::download(
+                format!("server-{}", self.version.id),
+                server.url,
+                &jar_path,
+                DownloadProgressType::Server,
+                e,
+            )
+        })?;
+        info!(
+            "Server {} installed in {:?}",
+            self.version.id, self.server_dir
+        );
+
+        if self.accept_eula {
+            let eula = format!(
+                "#By changing the setting below to TRUE you are indicating your agreement to our EULA ({EULA_URL}).\neula=true\n"
+            );
+            fs.write(&self.server_dir.join("eula.txt"), eula.as_bytes())
+                .await?;
+        }
+
+        let properties = self.server_dir.join("server.properties");
+        if !fs.exists(&properties).await {
+            let mut defaults = ServerProperties::parse("#Minecraft server properties");
+            for (key, value) in DEFAULT_PROPERTIES {
+                defaults.set(key, value);
+            }
+            defaults.save(&properties).await?;
+        }
+
+        Ok(jar_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockEnvironment, sha1};
+    use crate::types::Downloadable;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    const SERVER_JAR_BODY: &[u8] = b"proton fixture server jar";
+
+    async fn version_with_server(env: &MockEnvironment) -> NormalizedVersion {
+        Mock::given(method("GET"))
+            .and(path("/server.jar"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(SERVER_JAR_BODY))
+            .mount(env.server.server())
+            .await;
+        let mut version = env.server.version();
+        version.server_jar = Some(Downloadable {
+            url: format!("{}/server.jar", env.server.uri()),
+            sha1: sha1(SERVER_JAR_BODY),
+            size: SERVER_JAR_BODY.len() as u64,
+        });
+        version
+    }
+
+    #[tokio::test]
+    async fn installs_the_jar_eula_and_default_properties() {
+        let env = MockEnvironment::start().await;
+        let installer =
+            ServerInstaller::new(version_with_server(&env).await, "/server").accept_eula(true);
+
+        let jar = env.run(installer.install()).await.unwrap();
+
+        assert_eq!(jar, Path::new("/server/server.jar"));
+        assert_eq!(env.fs.get(&jar).as_deref(), Some(SERVER_JAR_BODY));
+        let eula = String::from_utf8(env.fs.get("/server/eula.txt").unwrap()).unwrap();
+        assert!(eula.contains("eula=true"));
+        let properties =
+            String::from_utf8(env.fs.get("/server/server.properties").unwrap()).unwrap();
+        let properties = ServerProperties::parse(&properties);
+        assert_eq!(properties.server_port(), Some(25565));
+        assert_eq!(properties.get("level-name"), Some("world"));
+    }
+
+    #[tokio::test]
+    async fn reinstalling_keeps_the_properties_and_needs_eula_consent() {
+        let env = MockEnvironment::start().await;
+        let installer = ServerInstaller::new(version_with_server(&env).await, "/server");
+
+        let properties = env
+            .run(async {
+                installer.install().await?;
+                let path = Path::new("/server/server.properties");
+                let mut properties = ServerProperties::load(path).await?;
+                properties.set_motd("Proton");
+                properties.save(path).await?;
+                installer.install().await?;
+                ServerProperties::load(path).await
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(properties.motd(), Some("Proton"));
+        assert!(env.fs.get("/server/eula.txt").is_none());
+    }
+
+    #[tokio::test]
+    async fn versions_without_a_server_fail() {
+        let env = MockEnvironment::start().await;
+        let installer = ServerInstaller::new(env.server.version(), "/server");
+
+        let result = env.run(installer.install()).await;
+
+        assert!(
+            matches!(result, Err(ProtonError::ServerJarUnavailable(id)) if id == env.server.version().id)
+        );
+    }
+}
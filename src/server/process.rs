@@ -0,0 +1,242 @@
+use log::warn;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc::{Receiver, channel};
+
+use super::SERVER_JAR;
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::launcher::resolve_java;
+use crate::utilities::forward_lines;
+
+// Líneas de consola en espera; si nadie las consume, las nuevas se descartan
+const OUTPUT_BUFFER: usize = 1024;
+
+/// Parámetros de arranque del servidor
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    pub java_path: Option<PathBuf>,
+    pub min_memory_mb: Option<u32>,
+    pub max_memory_mb: Option<u32>,
+    pub jvm_args: Vec<String>,
+    /// Mostrar la ventana de consola propia del servidor
+    pub gui: bool,
+}
+
+impl ServerOptions {
+    pub fn java_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.java_path = Some(path.into());
+        self
+    }
+
+    pub fn memory(mut self, min_mb: Option<u32>, max_mb: Option<u32>) -> Self {
+        self.min_memory_mb = min_mb;
+        self.max_memory_mb = max_mb;
+        self
+    }
+
+    pub fn jvm_arg(mut self, arg: impl Into<String>) -> Self {
+        self.jvm_args.push(arg.into());
+        self
+    }
+
+    pub fn gui(mut self, enabled: bool) -> Self {
+        self.gui = enabled;
+        self
+    }
+}
+
+/// Proceso de servidor en ejecución: consola, comandos y parada
+pub struct ServerHandle {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    output: Receiver<String>,
+}
+
+impl ServerHandle {
+    /// Arranca `server.jar` dentro de `server_dir`
+    pub async fn start(server_dir: &Path, options: &ServerOptions) -> Result<Self, ProtonError> {
+        // Java solo ve el disco local
+        if !file_system().is_local() {
+            return Err(ProtonError::Other(
+                "The server can only run from the local file system".to_string(),
+            ));
+        }
+        let jar = server_dir.join(SERVER_JAR);
+        if !jar.exists() {
+            return Err(ProtonError::Other(format!(
+                "Server jar not found at {jar:?}"
+            )));
+        }
+
+        let mut command = Command::new(resolve_java(options.java_path.as_deref()));
+        if let Some(min) = options.min_memory_mb {
+            command.arg(format!("-Xms{min}M"));
+        }
+        if let Some(max) = options.max_memory_mb {
+            command.arg(format!("-Xmx{max}M"));
+        }
+        command.args(&options.jvm_args).arg("-jar").arg(SERVER_JAR);
+        if !options.gui {
+            command.arg("nogui");
+        }
+
+        let mut child = command
+            .current_dir(server_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let (tx, output) = channel(OUTPUT_BUFFER);
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(stdout, tx.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(stderr, tx);
+        }
+
+        Ok(Self {
+            stdin: child.stdin.take(),
+            child,
+            output,
+        })
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// Siguiente línea de la consola (stdout y stderr), o `None` al terminar
+    pub async fn next_line(&mut self) -> Option<String> {
+        self.output.recv().await
+    }
+
+    /// Envía un comando a la consola del servidor (sin `/`)
+    pub async fn send_command(&mut self, command: &str) -> Result<(), ProtonError> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| ProtonError::Other("Server stdin is closed".to_string()))?;
+        stdin.write_all(format!("{command}\n").as_bytes()).await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    pub async fn wait(&mut self) -> Result<ExitStatus, ProtonError> {
+        Ok(self.child.wait().await?)
+    }
+
+    pub async fn kill(&mut self) -> Result<(), ProtonError> {
+        Ok(self.child.kill().await?)
+    }
+
+    /// Envía `stop` y espera hasta `timeout` a que el servidor guarde y salga;
+    /// si no lo hace, se fuerza la terminación
+    pub async fn stop(mut self, timeout: Duration) -> Result<ExitStatus, ProtonError> {
+        if self.send_command("stop").await.is_ok() {
+            // Cerrar stdin para que el servidor no espere más entrada
+            self.stdin = None;
+            // Se descarta la consola pendiente mientras el servidor guarda
+            let drain = async {
+                loop {
+                    tokio::select! {
+                        status = self.child.wait() => return status,
+                        line = self.output.recv() => {
+                            if line.is_none() {
+                                return self.child.wait().await;
+                            }
+                        }
+                    }
+                }
+            };
+            if let Ok(status) = tokio::time::timeout(timeout, drain).await {
+                return Ok(status?);
+            }
+            warn!("Server did not stop within {timeout:?}, killing it");
+        }
+
+        self.child.kill().await?;
+        Ok(self.child.wait().await?)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Directorio con `server.jar` y un "java" que imprime sus argumentos y
+    /// responde a la consola como el servidor
+    fn fake_server(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("proton-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(SERVER_JAR), b"not a jar").unwrap();
+        let java = dir.join("java");
+        std::fs::write(
+            &java,
+            "#!/bin/sh\n\
+             echo \"args: $*\"\n\
+             while read line; do\n\
+               echo \"> $line\"\n\
+               [ \"$line\" = stop ] && exit 0\n\
+             done\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&java, std::fs::Permissions::from_mode(0o755)).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn runs_the_jar_with_the_options_and_stops_on_request() {
+        let dir = fake_server("server-run");
+        let options = ServerOptions::default()
+            .java_path(dir.join("java"))
+            .memory(Some(512), Some(2048))
+            .jvm_arg("-Dfile.encoding=UTF-8");
+
+        let mut server = ServerHandle::start(&dir, &options).await.unwrap();
+        assert!(server.pid().is_some());
+        assert_eq!(
+            server.next_line().await.as_deref(),
+            Some("args: -Xms512M -Xmx2048M -Dfile.encoding=UTF-8 -jar server.jar nogui")
+        );
+        server.send_command("list").await.unwrap();
+        assert_eq!(server.next_line().await.as_deref(), Some("> list"));
+        let status = server.stop(Duration::from_secs(10)).await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(status.success());
+    }
+
+    #[tokio::test]
+    async fn a_server_that_ignores_stop_is_killed() {
+        let dir = fake_server("server-kill");
+        let java = dir.join("java");
+        std::fs::write(&java, "#!/bin/sh\nexec sleep 60\n").unwrap();
+        let options = ServerOptions::default().java_path(&java).gui(true);
+
+        let server = ServerHandle::start(&dir, &options).await.unwrap();
+        let status = server.stop(Duration::from_millis(200)).await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!status.success());
+    }
+
+    #[tokio::test]
+    async fn needs_the_server_jar() {
+        let dir = fake_server("server-missing");
+        std::fs::remove_file(dir.join(SERVER_JAR)).unwrap();
+
+        let result = ServerHandle::start(&dir, &ServerOptions::default()).await;
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            matches!(result, Err(ProtonError::Other(message)) if message.contains("server.jar"))
+        );
+    }
+}
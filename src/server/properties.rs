@@ -0,0 +1,395 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::errors::ProtonError;
+use crate::filesystem::file_system;
+use crate::utilities::temp_path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    /// Comentarios y líneas en blanco, conservados tal cual
+    Raw(String),
+    Entry {
+        key: String,
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl GameMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Adventure => "adventure",
+            GameMode::Spectator => "spectator",
+        }
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = ProtonError;
+
+    // Versiones antiguas guardan el modo como número
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "survival" | "0" => Ok(GameMode::Survival),
+            "creative" | "1" => Ok(GameMode::Creative),
+            "adventure" | "2" => Ok(GameMode::Adventure),
+            "spectator" | "3" => Ok(GameMode::Spectator),
+            other => Err(ProtonError::InvalidConfig(format!(
+                "unknown gamemode '{other}'"
+            ))),
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Peaceful => "peaceful",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ProtonError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "peaceful" | "0" => Ok(Difficulty::Peaceful),
+            "easy" | "1" => Ok(Difficulty::Easy),
+            "normal" | "2" => Ok(Difficulty::Normal),
+            "hard" | "3" => Ok(Difficulty::Hard),
+            other => Err(ProtonError::InvalidConfig(format!(
+                "unknown difficulty '{other}'"
+            ))),
+        }
+    }
+}
+
+/// `server.properties` editable que conserva el orden, los comentarios y las
+/// claves desconocidas al guardarse
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerProperties {
+    lines: Vec<Line>,
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                    Some(decoded) => result.push(decoded),
+                    None => result.push_str(&format!("\\u{code}")),
+                }
+            }
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+
+    result
+}
+
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '=' | ':' | '#' | '!' => {
+                result.push('\\');
+                result.push(c);
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+impl ServerProperties {
+    pub fn parse(content: &str) -> Self {
+        let lines = content
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                if trimmed.is_empty() || trimmed.starts_with(['#', '!']) {
+                    return Line::Raw(line.to_string());
+                }
+
+                // El separador es el primer `=` o `:` sin escapar
+                let mut escaped = false;
+                let split = trimmed.char_indices().find(|(_, c)| {
+                    let is_separator = !escaped && matches!(c, '=' | ':');
+                    escaped = !escaped && *c == '\\';
+                    is_separator
+                });
+                let (key, value) = match split {
+                    Some((i, _)) => (&trimmed[..i], &trimmed[i + 1..]),
+                    None => (trimmed, ""),
+                };
+
+                Line::Entry {
+                    key: unescape(key.trim_end()),
+                    value: unescape(value.trim_start()),
+                }
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    pub async fn load(path: &Path) -> Result<Self, ProtonError> {
+        let content = file_system().read(path).await?;
+        Ok(Self::parse(&String::from_utf8_lossy(&content)))
+    }
+
+    /// Guarda de forma atómica
+    pub async fn save(&self, path: &Path) -> Result<(), ProtonError> {
+        let fs = file_system();
+        let temp = temp_path(path);
+        fs.write(&temp, self.to_string().as_bytes()).await?;
+        if let Err(e) = fs.rename(&temp, path).await {
+            let _ = fs.remove_file(&temp).await;
+            return Err(ProtonError::IoError(e));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            Line::Entry { key: k, value } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Cambia el valor de una clave existente o la añade al final
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        for line in &mut self.lines {
+            if let Line::Entry { key: k, value: v } = line
+                && k == key
+            {
+                *v = value;
+                return;
+            }
+        }
+        self.lines.push(Line::Entry {
+            key: key.to_string(),
+            value,
+        });
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, Line::Entry { key: k, .. } if k == key))?;
+        match self.lines.remove(index) {
+            Line::Entry { value, .. } => Some(value),
+            Line::Raw(_) => None,
+        }
+    }
+
+    /// Pares clave/valor en el orden del archivo
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry { key, value } => Some((key.as_str(), value.as_str())),
+            Line::Raw(_) => None,
+        })
+    }
+
+    fn parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key).and_then(|value| value.trim().parse().ok())
+    }
+
+    pub fn server_port(&self) -> Option<u16> {
+        self.parsed("server-port")
+    }
+
+    pub fn set_server_port(&mut self, port: u16) {
+        self.set("server-port", port.to_string());
+    }
+
+    pub fn motd(&self) -> Option<&str> {
+        self.get("motd")
+    }
+
+    pub fn set_motd(&mut self, motd: impl Into<String>) {
+        self.set("motd", motd);
+    }
+
+    pub fn gamemode(&self) -> Option<GameMode> {
+        self.parsed("gamemode")
+    }
+
+    pub fn set_gamemode(&mut self, mode: GameMode) {
+        self.set("gamemode", mode.as_str());
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.parsed("difficulty")
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.set("difficulty", difficulty.as_str());
+    }
+
+    pub fn max_players(&self) -> Option<u32> {
+        self.parsed("max-players")
+    }
+
+    pub fn set_max_players(&mut self, max: u32) {
+        self.set("max-players", max.to_string());
+    }
+
+    pub fn whitelist(&self) -> Option<bool> {
+        self.parsed("white-list")
+    }
+
+    pub fn set_whitelist(&mut self, enabled: bool) {
+        self.set("white-list", enabled.to_string());
+    }
+
+    pub fn online_mode(&self) -> Option<bool> {
+        self.parsed("online-mode")
+    }
+
+    pub fn set_online_mode(&mut self, enabled: bool) {
+        self.set("online-mode", enabled.to_string());
+    }
+
+    pub fn pvp(&self) -> Option<bool> {
+        self.parsed("pvp")
+    }
+
+    pub fn set_pvp(&mut self, enabled: bool) {
+        self.set("pvp", enabled.to_string());
+    }
+
+    pub fn level_name(&self) -> Option<&str> {
+        self.get("level-name")
+    }
+
+    pub fn set_level_name(&mut self, name: impl Into<String>) {
+        self.set("level-name", name);
+    }
+}
+
+impl fmt::Display for ServerProperties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Raw(raw) => writeln!(f, "{raw}")?,
+                Line::Entry { key, value } => writeln!(f, "{}={}", escape(key), escape(value))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{MemoryFs, with_file_system};
+    use std::sync::Arc;
+
+    const VANILLA: &str = "#Minecraft server properties\n#Mon Jan 01 00:00:00 UTC 2024\nmotd=A Minecraft Server\\: welcome\ngamemode=1\nserver-port=25565\nunknown-key=kept\n";
+
+    #[test]
+    fn round_trip_keeps_comments_order_and_unknown_keys() {
+        let properties = ServerProperties::parse(VANILLA);
+        assert_eq!(properties.motd(), Some("A Minecraft Server: welcome"));
+        assert_eq!(properties.gamemode(), Some(GameMode::Creative));
+        assert_eq!(properties.server_port(), Some(25565));
+        assert_eq!(properties.to_string(), VANILLA);
+        assert_eq!(ServerProperties::parse(&properties.to_string()), properties);
+    }
+
+    #[test]
+    fn setters_replace_in_place_and_append_new_keys() {
+        let mut properties = ServerProperties::parse(VANILLA);
+        properties.set_gamemode(GameMode::Survival);
+        properties.set_motd("line\nbreak = é");
+        properties.set_difficulty(Difficulty::Hard);
+
+        let reparsed = ServerProperties::parse(&properties.to_string());
+        assert_eq!(reparsed.motd(), Some("line\nbreak = é"));
+        assert_eq!(
+            reparsed.entries().map(|(key, _)| key).collect::<Vec<_>>(),
+            [
+                "motd",
+                "gamemode",
+                "server-port",
+                "unknown-key",
+                "difficulty"
+            ]
+        );
+        assert_eq!(reparsed.gamemode(), Some(GameMode::Survival));
+        assert_eq!(reparsed.difficulty(), Some(Difficulty::Hard));
+    }
+
+    #[test]
+    fn unknown_gamemode_is_an_invalid_config() {
+        assert!(matches!(
+            "hardcore".parse::<GameMode>(),
+            Err(ProtonError::InvalidConfig(_))
+        ));
+        assert!(
+            ServerProperties::parse("gamemode=hardcore")
+                .gamemode()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn save_and_load_through_the_file_system() {
+        let fs = MemoryFs::new();
+        let path = Path::new("/server/server.properties");
+        let properties = ServerProperties::parse(VANILLA);
+
+        let loaded = with_file_system(Arc::new(fs.clone()), async {
+            file_system().create_dir_all(Path::new("/server")).await?;
+            properties.save(path).await?;
+            ServerProperties::load(path).await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(loaded, properties);
+        assert_eq!(fs.get(path).as_deref(), Some(VANILLA.as_bytes()));
+    }
+}
@@ -0,0 +1,173 @@
+use sysinfo::{
+    CpuRefreshKind, MemoryRefreshKind, Pid, ProcessRefreshKind, ProcessesToUpdate, RefreshKind,
+    System,
+};
+
+const MIB: u64 = 1024 * 1024;
+const GIB: u64 = 1024 * MIB;
+
+/// Memoria física del equipo, en bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemMemory {
+    pub total: u64,
+    pub available: u64,
+}
+
+impl SystemMemory {
+    pub fn available_gb(&self) -> f64 {
+        self.available as f64 / GIB as f64
+    }
+
+    pub fn total_gb(&self) -> f64 {
+        self.total as f64 / GIB as f64
+    }
+
+    /// `-Xmx` sugerido en MB: una cuarta parte de la RAM, entre 2 y 8 GB,
+    /// sin pasar nunca de la mitad del total
+    pub fn recommended_max_heap_mb(&self) -> u32 {
+        let total_mb = self.total / MIB;
+        let heap = (total_mb / 4).clamp(2048, 8192).min(total_mb / 2);
+        u32::try_from(heap).unwrap_or(u32::MAX)
+    }
+}
+
+/// Memoria del equipo, o `None` si el sistema operativo no la informa
+pub fn system_memory() -> Option<SystemMemory> {
+    let system = System::new_with_specifics(
+        RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram()),
+    );
+
+    let total = system.total_memory();
+    if total == 0 {
+        return None;
+    }
+
+    Some(SystemMemory {
+        total,
+        available: system.available_memory(),
+    })
+}
+
+/// El proceso `pid` sigue en ejecución en este equipo
+pub(crate) fn process_running(pid: u32) -> bool {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        false,
+        ProcessRefreshKind::nothing(),
+    );
+    system.process(pid).is_some()
+}
+
+/// Nombre del equipo, si el sistema lo informa
+pub(crate) fn host_name() -> Option<String> {
+    System::host_name()
+}
+
+// Umbrales a partir de los cuales el equipo se considera saturado
+const CPU_SATURATED_PERCENT: u8 = 90;
+const IO_SATURATED_PERCENT: u8 = 40;
+
+/// Carga del equipo en porcentaje (0-100)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct SystemLoad {
+    pub cpu_percent: u8,
+    /// Tiempo con tareas esperando al disco (PSI de Linux); `None` donde no
+    /// hay forma de medirlo
+    pub io_percent: Option<u8>,
+}
+
+impl SystemLoad {
+    pub fn is_saturated(&self) -> bool {
+        self.cpu_percent >= CPU_SATURATED_PERCENT
+            || self.io_percent.is_some_and(|io| io >= IO_SATURATED_PERCENT)
+    }
+}
+
+/// Muestreo periódico de la carga. El uso de CPU se calcula entre dos
+/// llamadas consecutivas a `sample`, así que la primera devuelve 0.
+pub(crate) struct LoadMonitor {
+    system: System,
+}
+
+impl LoadMonitor {
+    pub fn new() -> Self {
+        Self {
+            system: System::new_with_specifics(
+                RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing().with_cpu_usage()),
+            ),
+        }
+    }
+
+    pub fn sample(&mut self) -> SystemLoad {
+        self.system.refresh_cpu_usage();
+        SystemLoad {
+            cpu_percent: self.system.global_cpu_usage().clamp(0.0, 100.0) as u8,
+            io_percent: io_pressure_percent(),
+        }
+    }
+}
+
+/// Valor `some avg10` de `/proc/pressure/io`: porcentaje de los últimos 10 s
+/// en que alguna tarea estuvo bloqueada esperando E/S
+#[cfg(target_os = "linux")]
+fn io_pressure_percent() -> Option<u8> {
+    let pressure = std::fs::read_to_string("/proc/pressure/io").ok()?;
+    let some = pressure.lines().find(|line| line.starts_with("some "))?;
+    let avg10 = some
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse::<f32>()
+        .ok()?;
+    Some(avg10.clamp(0.0, 100.0) as u8)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn io_pressure_percent() -> Option<u8> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launcher::LaunchOptions;
+
+    fn with_total_gb(gb: u64) -> SystemMemory {
+        SystemMemory {
+            total: gb * GIB,
+            available: gb * GIB / 2,
+        }
+    }
+
+    #[test]
+    fn heap_recommendation_scales_with_ram() {
+        assert_eq!(with_total_gb(2).recommended_max_heap_mb(), 1024);
+        assert_eq!(with_total_gb(4).recommended_max_heap_mb(), 2048);
+        assert_eq!(with_total_gb(16).recommended_max_heap_mb(), 4096);
+        assert_eq!(with_total_gb(64).recommended_max_heap_mb(), 8192);
+        assert_eq!(with_total_gb(16).available_gb(), 8.0);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+    #[test]
+    fn memory_is_detected_and_used_for_the_heap() {
+        let memory = system_memory().unwrap();
+        assert!(memory.total > 0 && memory.available <= memory.total);
+
+        let options = LaunchOptions::new("store", "Steve").auto_memory();
+        assert_eq!(
+            options.max_memory_mb,
+            Some(memory.recommended_max_heap_mb())
+        );
+        // Lo que fija el usuario no se toca, y el máximo nunca queda bajo `-Xms`
+        let fixed = LaunchOptions::new("store", "Steve")
+            .memory(None, Some(1024))
+            .auto_memory();
+        assert_eq!(fixed.max_memory_mb, Some(1024));
+        let min = LaunchOptions::new("store", "Steve")
+            .memory(Some(u32::MAX), None)
+            .auto_memory();
+        assert_eq!(min.max_memory_mb, Some(u32::MAX));
+    }
+}
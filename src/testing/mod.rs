@@ -0,0 +1,339 @@
+use ring::digest::{SHA1_FOR_LEGACY_USE_ONLY, digest};
+use serde_json::json;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::downloaders::MinecraftDownloader;
+use crate::endpoints::{EndpointConfig, endpoints, set_endpoints};
+use crate::filesystem::{MemoryFs, with_file_system};
+use crate::manifest::{cache_dir, default_manifest_client, set_cache_dir};
+use crate::types::{MojangVersionDetails, MojangVersionManifest, NormalizedVersion, VersionAssets};
+
+/// Id de la única versión publicada por las fixtures
+pub const FIXTURE_VERSION: &str = "1.0-proton-fixture";
+
+const MANIFEST_PATH: &str = "/mc/game/version_manifest_v2.json";
+const ASSET_INDEX_ID: &str = "fixture";
+
+const CLIENT_JAR: &[u8] = b"proton fixture client jar\n";
+const LIBRARY_JAR: &[u8] = b"proton fixture library jar\n";
+// Zip sin comprimir con un nativo por sistema operativo
+const NATIVES_JAR: &[u8] = include_bytes!("fixtures/natives.jar");
+const ASSETS: [(&str, &[u8]); 2] = [
+    ("minecraft/lang/en_us.json", b"{}\n"),
+    ("minecraft/sounds/fixture.ogg", b"OggS proton fixture\n"),
+];
+
+pub(crate) fn sha1(data: &[u8]) -> String {
+    hex::encode(digest(&SHA1_FOR_LEGACY_USE_ONLY, data))
+}
+
+fn asset_index_json() -> Vec<u8> {
+    let objects: serde_json::Map<String, serde_json::Value> = ASSETS
+        .iter()
+        .map(|(name, content)| {
+            let object = json!({ "hash": sha1(content), "size": content.len() });
+            (name.to_string(), object)
+        })
+        .collect();
+    serde_json::to_vec(&json!({ "objects": objects })).unwrap_or_default()
+}
+
+fn artifact(base_url: &str, path: &str, content: &[u8]) -> serde_json::Value {
+    json!({
+        "path": path,
+        "sha1": sha1(content),
+        "size": content.len(),
+        "url": format!("{base_url}/libraries/{path}"),
+    })
+}
+
+fn version_json(base_url: &str) -> Vec<u8> {
+    let asset_index = asset_index_json();
+    let natives = artifact(
+        base_url,
+        "org/proton/natives/1.0/natives-1.0-natives.jar",
+        NATIVES_JAR,
+    );
+
+    let version = json!({
+        "id": FIXTURE_VERSION,
+        "type": "release",
+        "mainClass": "net.minecraft.client.main.Main",
+        "minimumLauncherVersion": 21,
+        "releaseTime": "2024-01-01T00:00:00+00:00",
+        "time": "2024-01-01T00:00:00+00:00",
+        "assets": ASSET_INDEX_ID,
+        "assetIndex": {
+            "id": ASSET_INDEX_ID,
+            "sha1": sha1(&asset_index),
+            "size": asset_index.len(),
+            "totalSize": ASSETS.iter().map(|(_, content)| content.len()).sum::<usize>(),
+            "url": format!("{base_url}/indexes/{ASSET_INDEX_ID}.json"),
+        },
+        "downloads": {
+            "client": {
+                "sha1": sha1(CLIENT_JAR),
+                "size": CLIENT_JAR.len(),
+                "url": format!("{base_url}/client.jar"),
+            },
+        },
+        "libraries": [
+            {
+                "name": "org.proton:library:1.0",
+                "downloads": {
+                    "artifact": artifact(base_url, "org/proton/library/1.0/library-1.0.jar", LIBRARY_JAR),
+                },
+            },
+            {
+                "name": "org.proton:natives:1.0",
+                "natives": {
+                    "linux": "natives-linux",
+                    "osx": "natives-osx",
+                    "windows": "natives-windows",
+                },
+                "downloads": {
+                    "classifiers": {
+                        "natives-linux": natives,
+                        "natives-osx": natives,
+                        "natives-windows": natives,
+                    },
+                },
+                "extract": { "exclude": ["META-INF/"] },
+            },
+        ],
+        "arguments": {
+            "game": ["--username", "${auth_player_name}", "--version", "${version_name}"],
+            "jvm": ["-cp", "${classpath}"],
+        },
+        "javaVersion": { "component": "java-runtime-delta", "majorVersion": 21 },
+    });
+
+    serde_json::to_vec(&version).unwrap_or_default()
+}
+
+fn manifest_json(base_url: &str) -> Vec<u8> {
+    let manifest = json!({
+        "latest": { "release": FIXTURE_VERSION, "snapshot": FIXTURE_VERSION },
+        "versions": [{
+            "id": FIXTURE_VERSION,
+            "type": "release",
+            "url": format!("{base_url}/versions/{FIXTURE_VERSION}.json"),
+            "time": "2024-01-01T00:00:00+00:00",
+            "releaseTime": "2024-01-01T00:00:00+00:00",
+            "sha1": sha1(&version_json(base_url)),
+        }],
+    });
+
+    serde_json::to_vec(&manifest).unwrap_or_default()
+}
+
+/// Manifest con `FIXTURE_VERSION` como única versión, servida desde `base_url`
+pub fn fixture_manifest(base_url: &str) -> MojangVersionManifest {
+    serde_json::from_slice(&manifest_json(base_url)).expect("fixture manifest is valid")
+}
+
+/// JSON de `FIXTURE_VERSION` tal como lo publica el servidor de prueba
+pub fn fixture_version_details(base_url: &str) -> MojangVersionDetails {
+    serde_json::from_slice(&version_json(base_url)).expect("fixture version is valid")
+}
+
+/// `FIXTURE_VERSION` normalizada, lista para `MinecraftDownloader::new`
+pub fn fixture_version(base_url: &str) -> NormalizedVersion {
+    NormalizedVersion::try_from(fixture_version_details(base_url))
+        .expect("fixture version is valid")
+}
+
+/// Índice de assets de `FIXTURE_VERSION`
+pub fn fixture_asset_index() -> VersionAssets {
+    serde_json::from_slice(&asset_index_json()).expect("fixture asset index is valid")
+}
+
+/// Los endpoints, la caché y el origen de artefactos son globales: los tests
+/// que los cambian (con `MockMinecraftServer::install`, `isolate_cache_dir` o
+/// `set_artifact_source`) deben tomar este candado para ejecutarse de uno en uno
+pub async fn exclusive() -> MutexGuard<'static, ()> {
+    static LOCK: once_cell::sync::Lazy<Mutex<()>> = once_cell::sync::Lazy::new(|| Mutex::new(()));
+    LOCK.lock().await
+}
+
+/// Usa un directorio de caché temporal propio hasta soltar el guard, que
+/// restaura el anterior y borra el temporal
+pub fn isolate_cache_dir() -> CacheDirGuard {
+    let dir = std::env::temp_dir().join(format!("proton-test-{}", uuid::Uuid::new_v4()));
+    let previous = cache_dir();
+    set_cache_dir(dir.clone());
+    CacheDirGuard { dir, previous }
+}
+
+/// Devuelto por `isolate_cache_dir`
+pub struct CacheDirGuard {
+    dir: PathBuf,
+    previous: PathBuf,
+}
+
+impl CacheDirGuard {
+    /// Directorio de caché temporal en uso
+    pub fn path(&self) -> &PathBuf {
+        &self.dir
+    }
+}
+
+impl Drop for CacheDirGuard {
+    fn drop(&mut self) {
+        set_cache_dir(std::mem::take(&mut self.previous));
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Devuelto por `MockMinecraftServer::install`: al soltarse vuelven los
+/// endpoints y el directorio de caché anteriores
+pub struct InstalledServer {
+    previous: EndpointConfig,
+    _cache_dir: CacheDirGuard,
+}
+
+impl Drop for InstalledServer {
+    fn drop(&mut self) {
+        set_endpoints(self.previous.clone());
+        default_manifest_client().clear();
+    }
+}
+
+/// Servidor HTTP local que imita el manifest, los JSON de versión, las
+/// librerías y los assets de Mojang usando las fixtures de este módulo
+pub struct MockMinecraftServer {
+    server: MockServer,
+}
+
+impl MockMinecraftServer {
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        let base_url = server.uri();
+
+        let mut files: Vec<(String, Vec<u8>)> = vec![
+            (MANIFEST_PATH.to_string(), manifest_json(&base_url)),
+            (
+                format!("/versions/{FIXTURE_VERSION}.json"),
+                version_json(&base_url),
+            ),
+            (
+                format!("/indexes/{ASSET_INDEX_ID}.json"),
+                asset_index_json(),
+            ),
+            ("/client.jar".to_string(), CLIENT_JAR.to_vec()),
+            (
+                "/libraries/org/proton/library/1.0/library-1.0.jar".to_string(),
+                LIBRARY_JAR.to_vec(),
+            ),
+            (
+                "/libraries/org/proton/natives/1.0/natives-1.0-natives.jar".to_string(),
+                NATIVES_JAR.to_vec(),
+            ),
+        ];
+        for (_, content) in ASSETS {
+            let hash = sha1(content);
+            files.push((
+                format!("/resources/{}/{hash}", &hash[..2]),
+                content.to_vec(),
+            ));
+        }
+
+        for (file_path, content) in files {
+            Mock::given(method("GET"))
+                .and(path(file_path))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&server)
+                .await;
+        }
+
+        Self { server }
+    }
+
+    /// URL base del servidor (`http://127.0.0.1:<puerto>`)
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Endpoints que apuntan a este servidor
+    pub fn endpoints(&self) -> EndpointConfig {
+        let base_url = self.uri();
+        EndpointConfig::default()
+            .manifest_url(format!("{base_url}{MANIFEST_PATH}"))
+            .resources_base_url(format!("{base_url}/resources"))
+            .libraries_base_url(format!("{base_url}/libraries"))
+            .maven_repositories(Vec::new())
+    }
+
+    /// Redirige los endpoints globales a este servidor hasta soltar el guard.
+    /// También usa un directorio de caché temporal para no mezclar las
+    /// fixtures con la caché real, y vacía la caché en memoria del manifest:
+    /// wiremock reutiliza los puertos, así que otro test pudo dejar un
+    /// manifest distinto con la misma URL. Tómese antes `exclusive()`.
+    #[must_use = "the endpoints are restored when the guard is dropped"]
+    pub fn install(&self) -> InstalledServer {
+        let previous = endpoints();
+        set_endpoints(self.endpoints());
+        default_manifest_client().clear();
+        InstalledServer {
+            previous,
+            _cache_dir: isolate_cache_dir(),
+        }
+    }
+
+    pub fn manifest(&self) -> MojangVersionManifest {
+        fixture_manifest(&self.uri())
+    }
+
+    pub fn version(&self) -> NormalizedVersion {
+        fixture_version(&self.uri())
+    }
+
+    /// Servidor wiremock subyacente, para montar respuestas adicionales o
+    /// inspeccionar las peticiones recibidas
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+}
+
+/// Entorno de prueba completo: un `MockMinecraftServer` instalado como
+/// endpoints globales, un sistema de archivos en memoria y `/game` como
+/// directorio del juego. Mientras viva tiene tomado `exclusive()`.
+pub struct MockEnvironment {
+    pub server: MockMinecraftServer,
+    pub fs: MemoryFs,
+    pub game_dir: PathBuf,
+    // Se sueltan en este orden: primero se restaura el estado global
+    _installed: InstalledServer,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl MockEnvironment {
+    pub async fn start() -> Self {
+        let lock = exclusive().await;
+        let server = MockMinecraftServer::start().await;
+        let installed = server.install();
+        Self {
+            server,
+            fs: MemoryFs::new(),
+            game_dir: PathBuf::from("/game"),
+            _installed: installed,
+            _lock: lock,
+        }
+    }
+
+    /// Descargador de `FIXTURE_VERSION` que escribe en `fs`
+    pub fn downloader(&self) -> MinecraftDownloader {
+        MinecraftDownloader::new(self.game_dir.clone(), self.server.version())
+            .file_system(Arc::new(self.fs.clone()))
+    }
+
+    /// Ejecuta `future` con `fs` como sistema de archivos
+    pub async fn run<F: Future>(&self, future: F) -> F::Output {
+        with_file_system(Arc::new(self.fs.clone()), future).await
+    }
+}
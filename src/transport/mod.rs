@@ -0,0 +1,367 @@
+use bytes::Bytes;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+#[cfg(not(target_arch = "wasm32"))]
+use futures::{future::BoxFuture, stream::BoxStream};
+#[cfg(target_arch = "wasm32")]
+use futures::{future::LocalBoxFuture as BoxFuture, stream::LocalBoxStream as BoxStream};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use crate::errors::ProtonError;
+use crate::http::{client_for, host_permit};
+
+/// Contenido de un artefacto en trozos
+pub type ArtifactStream = BoxStream<'static, Result<Bytes, ProtonError>>;
+
+/// `ETag` y `Last-Modified` de una respuesta anterior, para repetir la
+/// petición de forma condicional
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// Resultado de `ArtifactSource::fetch_if_modified`
+#[derive(Debug, Clone)]
+pub enum ConditionalFetch {
+    /// El servidor respondió 304: el contenido no cambió
+    NotModified,
+    Modified {
+        content: Vec<u8>,
+        validators: CacheValidators,
+    },
+}
+
+/// Origen de los archivos y metadatos que descarga proton. Por defecto es
+/// HTTP; se puede sustituir por una carpeta local o por datos en memoria.
+pub trait ArtifactSource: Send + Sync {
+    /// Abre el artefacto publicado en `url`
+    fn open<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<ArtifactStream, ProtonError>>;
+
+    /// Lee el artefacto completo
+    fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Vec<u8>, ProtonError>> {
+        Box::pin(async move {
+            let mut stream = self.open(url).await?;
+            let mut content = Vec::new();
+            while let Some(chunk) = stream.next().await {
+                content.extend_from_slice(&chunk?);
+            }
+            Ok(content)
+        })
+    }
+
+    /// Lee el artefacto solo si cambió desde la respuesta de `validators`.
+    /// Los orígenes sin peticiones condicionales lo leen siempre.
+    fn fetch_if_modified<'a>(
+        &'a self,
+        url: &'a str,
+        _validators: &'a CacheValidators,
+    ) -> BoxFuture<'a, Result<ConditionalFetch, ProtonError>> {
+        Box::pin(async move {
+            Ok(ConditionalFetch::Modified {
+                content: self.fetch(url).await?,
+                validators: CacheValidators::default(),
+            })
+        })
+    }
+}
+
+/// Descarga por HTTP con reqwest. Por defecto usa el cliente compartido
+/// configurado con `set_http_options`.
+#[derive(Debug, Clone, Default)]
+pub struct HttpSource {
+    client: Option<Client>,
+}
+
+impl HttpSource {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client: Some(client),
+        }
+    }
+}
+
+impl ArtifactSource for HttpSource {
+    fn open<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<ArtifactStream, ProtonError>> {
+        Box::pin(async move {
+            // El turno del host se mantiene hasta terminar de leer el cuerpo
+            let permit = host_permit(url).await;
+            let client = match &self.client {
+                Some(client) => client.clone(),
+                None => client_for(url)?,
+            };
+            let response = client.get(url).send().await?.error_for_status()?;
+            let stream = response.bytes_stream().map(move |chunk| {
+                let _ = &permit;
+                chunk.map_err(ProtonError::from)
+            });
+            Ok(Box::pin(stream) as ArtifactStream)
+        })
+    }
+
+    fn fetch_if_modified<'a>(
+        &'a self,
+        url: &'a str,
+        validators: &'a CacheValidators,
+    ) -> BoxFuture<'a, Result<ConditionalFetch, ProtonError>> {
+        Box::pin(async move {
+            let _permit = host_permit(url).await;
+            let client = match &self.client {
+                Some(client) => client.clone(),
+                None => client_for(url)?,
+            };
+            let mut request = client.get(url);
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+
+            let response = request.send().await?;
+            if response.status() == StatusCode::NOT_MODIFIED {
+                return Ok(ConditionalFetch::NotModified);
+            }
+            let response = response.error_for_status()?;
+            let header = |name| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            };
+            let validators = CacheValidators {
+                etag: header(ETAG),
+                last_modified: header(LAST_MODIFIED),
+            };
+            Ok(ConditionalFetch::Modified {
+                content: response.bytes().await?.to_vec(),
+                validators,
+            })
+        })
+    }
+}
+
+/// Carpeta con los artefactos ya descargados, organizada como un espejo de
+/// las URLs: `https://host/a/b.jar` se lee de `<raíz>/host/a/b.jar`
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct LocalDirSource {
+    root: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LocalDirSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Ruta local de una URL, o `None` si la URL intenta salir de la raíz
+    pub fn path_for(&self, url: &str) -> Option<PathBuf> {
+        let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let without_query = without_scheme.split(['?', '#']).next().unwrap_or_default();
+
+        let mut path = self.root.clone();
+        for segment in without_query.split('/').filter(|s| !s.is_empty()) {
+            if segment == "." || segment == ".." || segment.contains('\\') {
+                return None;
+            }
+            path.push(segment);
+        }
+        Some(path)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ArtifactSource for LocalDirSource {
+    fn open<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<ArtifactStream, ProtonError>> {
+        Box::pin(async move {
+            let path = self
+                .path_for(url)
+                .ok_or_else(|| ProtonError::ArtifactNotFound(url.to_string()))?;
+            let file = match tokio::fs::File::open(&path).await {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(ProtonError::ArtifactNotFound(url.to_string()));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let stream = tokio_util::io::ReaderStream::new(file)
+                .map(|chunk| chunk.map_err(ProtonError::from));
+            Ok(Box::pin(stream) as ArtifactStream)
+        })
+    }
+}
+
+/// Artefactos en memoria indexados por URL, para pruebas deterministas
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    files: HashMap<String, Bytes>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, url: impl Into<String>, content: impl Into<Bytes>) -> Self {
+        self.insert(url, content);
+        self
+    }
+
+    pub fn insert(&mut self, url: impl Into<String>, content: impl Into<Bytes>) {
+        self.files.insert(url.into(), content.into());
+    }
+}
+
+impl ArtifactSource for MemorySource {
+    fn open<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<ArtifactStream, ProtonError>> {
+        Box::pin(async move {
+            let content = self
+                .files
+                .get(url)
+                .cloned()
+                .ok_or_else(|| ProtonError::ArtifactNotFound(url.to_string()))?;
+            Ok(Box::pin(futures::stream::once(async move { Ok(content) })) as ArtifactStream)
+        })
+    }
+}
+
+static ARTIFACT_SOURCE: Lazy<RwLock<Option<Arc<dyn ArtifactSource>>>> =
+    Lazy::new(|| RwLock::new(None));
+
+/// Sustituye el origen global de artefactos; `None` vuelve a HTTP
+pub fn set_artifact_source(source: Option<Arc<dyn ArtifactSource>>) {
+    *ARTIFACT_SOURCE.write().unwrap_or_else(|e| e.into_inner()) = source;
+}
+
+/// Origen de artefactos global actual
+pub fn artifact_source() -> Arc<dyn ArtifactSource> {
+    ARTIFACT_SOURCE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| Arc::new(HttpSource::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::downloaders::MinecraftDownloader;
+    use crate::endpoints::endpoints;
+    use crate::manifest::resolve_version_data;
+    use crate::plan::plan_downloads;
+    use crate::testing::{FIXTURE_VERSION, MockEnvironment};
+
+    /// Vuelve a HTTP al soltarse, aunque el test falle
+    struct SourceGuard;
+
+    impl Drop for SourceGuard {
+        fn drop(&mut self) {
+            set_artifact_source(None);
+        }
+    }
+
+    #[tokio::test]
+    async fn installs_offline_from_a_local_dir() {
+        let env = MockEnvironment::start().await;
+        let root = std::env::temp_dir().join(format!("proton-source-{}", uuid::Uuid::new_v4()));
+        let source = LocalDirSource::new(&root);
+
+        // Copia de todo lo que publica el servidor, con la estructura de sus URLs
+        let version = env.server.version();
+        let mut urls = vec![
+            endpoints().manifest_url,
+            env.server.manifest().versions[0].url.clone(),
+            version.asset_index.url.clone(),
+        ];
+        urls.extend(
+            plan_downloads(&version)
+                .await
+                .unwrap()
+                .files
+                .into_iter()
+                .map(|file| file.url),
+        );
+        for url in &urls {
+            let path = source.path_for(url).unwrap();
+            tokio::fs::create_dir_all(path.parent().unwrap())
+                .await
+                .unwrap();
+            let content = HttpSource::default().fetch(url).await.unwrap();
+            tokio::fs::write(&path, content).await.unwrap();
+        }
+        env.server.server().reset().await;
+
+        set_artifact_source(Some(Arc::new(source)));
+        let _guard = SourceGuard;
+        let installed = async {
+            let version = resolve_version_data(FIXTURE_VERSION).await?;
+            MinecraftDownloader::new(env.game_dir.clone(), version)
+                .file_system(Arc::new(env.fs.clone()))
+                .download_all(None)
+                .await
+        }
+        .await;
+        let _ = tokio::fs::remove_dir_all(&root).await;
+
+        installed.unwrap();
+        let client = format!("versions/{FIXTURE_VERSION}/{FIXTURE_VERSION}.jar");
+        assert!(env.fs.get(env.game_dir.join(client)).is_some());
+        assert!(
+            env.server
+                .server()
+                .received_requests()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn local_dir_reports_missing_and_escaping_files() {
+        let root = std::env::temp_dir().join(format!("proton-source-{}", uuid::Uuid::new_v4()));
+        let source = LocalDirSource::new(&root);
+
+        assert_eq!(
+            source.path_for("https://host/a/b.jar?x=1"),
+            Some(root.join("host").join("a").join("b.jar"))
+        );
+        assert_eq!(source.path_for("https://host/../secret"), None);
+        assert!(matches!(
+            source.fetch("https://host/missing.jar").await,
+            Err(ProtonError::ArtifactNotFound(_))
+        ));
+        assert!(matches!(
+            source.fetch("https://host/../secret").await,
+            Err(ProtonError::ArtifactNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn memory_source_serves_its_files() {
+        let source = MemorySource::new().with("https://host/a.jar", b"a".to_vec());
+
+        assert_eq!(source.fetch("https://host/a.jar").await.unwrap(), b"a");
+        assert!(matches!(
+            source.fetch("https://host/b.jar").await,
+            Err(ProtonError::ArtifactNotFound(_))
+        ));
+        // Sin peticiones condicionales siempre se lee entero
+        let fetched = source
+            .fetch_if_modified("https://host/a.jar", &CacheValidators::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            fetched,
+            ConditionalFetch::Modified { content, .. } if content == b"a"
+        ));
+    }
+}
@@ -1,12 +1,23 @@
-use crate::errors::ProtonError;
-use crate::utilities::get_os_name_runtime;
+use crate::errors::{ErrorKind, ProtonError};
+use crate::maven::MavenCoordinate;
+use crate::utilities::{get_os_name_runtime, get_os_version_runtime, is_safe_relative_path};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 // URLs de los manifiestos oficiales de Mojang
 pub const MOJANG_MANIFEST_URL: &str =
     "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
 pub const RESOURCES_BASE_URL: &str = "https://resources.download.minecraft.net/";
+pub const LIBRARIES_BASE_URL: &str = "https://libraries.minecraft.net/";
+
+// Clase principal de launchwrapper, que carga los tweakers de los loaders antiguos
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) const LAUNCHWRAPPER_MAIN_CLASS: &str = "net.minecraft.launchwrapper.Launch";
+pub(crate) const TWEAK_CLASS_ARG: &str = "--tweakClass";
 
 // Tipos de versión
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -20,6 +31,61 @@ pub enum VersionTypes {
     OldAlpha,
 }
 
+impl VersionTypes {
+    /// Nombre usado por Mojang (`${version_type}` en los argumentos)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VersionTypes::Snapshot => "snapshot",
+            VersionTypes::Release => "release",
+            VersionTypes::OldBeta => "old_beta",
+            VersionTypes::OldAlpha => "old_alpha",
+        }
+    }
+}
+
+// Orden para el listado de versiones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionSort {
+    /// Orden original del manifest
+    #[default]
+    Manifest,
+    NewestFirst,
+    OldestFirst,
+}
+
+/// Filtro para `list_versions`
+#[derive(Debug, Clone, Default)]
+pub struct VersionFilter {
+    /// Tipos admitidos; vacío admite todos
+    pub types: Vec<VersionTypes>,
+    pub sort: VersionSort,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl VersionFilter {
+    pub fn only(mut self, version_type: VersionTypes) -> Self {
+        self.types.push(version_type);
+        self
+    }
+
+    pub fn sort(mut self, sort: VersionSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Paginación: salta `offset` entradas y devuelve como máximo `limit`
+    pub fn page(mut self, offset: usize, limit: usize) -> Self {
+        self.offset = offset;
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn matches(&self, version: &MojangVersionInfo) -> bool {
+        self.types.is_empty() || self.types.contains(&version.version_type)
+    }
+}
+
 // Manifest principal de versiones
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -28,7 +94,7 @@ pub struct MojangVersionManifest {
     pub versions: Vec<MojangVersionInfo>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct MojangLatestVersions {
     pub release: String,
@@ -99,14 +165,19 @@ pub struct MojangDownloadArtifact {
 #[serde(rename_all = "camelCase")]
 pub struct MojangLibrary {
     pub name: String,
+    #[serde(default)]
     pub downloads: MojangLibraryDownloads,
+    // Formato de perfiles modded (Fabric, Quilt...): repositorio maven sin `downloads`
+    pub url: Option<String>,
+    pub sha1: Option<String>,
+    pub size: Option<u64>,
     #[serde(default)]
     pub rules: Vec<MojangRule>,
     pub natives: Option<HashMap<String, String>>,
     pub extract: Option<MojangExtract>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct MojangLibraryDownloads {
     pub artifact: Option<MojangArtifact>,
@@ -204,6 +275,8 @@ pub struct MojangJavaVersion {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct NormalizedVersion {
     pub id: String,
+    #[serde(default)]
+    pub version_type: Option<VersionTypes>,
     pub release_time: String,
     pub java_version: u8,
     pub main_class: String,
@@ -214,6 +287,32 @@ pub struct NormalizedVersion {
     pub natives: Vec<NativeLibrary>,
     pub arguments: NormalizedArguments,
     pub requires_extraction: Vec<ExtractionHint>,
+    #[serde(default)]
+    pub logging: Option<LoggingConfig>,
+}
+
+/// Configuración log4j declarada por la versión (`logging.client`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LoggingConfig {
+    pub id: String,
+    pub url: String,
+    pub sha1: String,
+    pub size: u64,
+    /// Argumento JVM con el marcador `${path}`
+    pub argument: String,
+}
+
+impl LoggingConfig {
+    /// Ruta del archivo dentro de `assets/log_configs/`
+    pub fn path(&self, game_dir: &Path) -> PathBuf {
+        game_dir.join("assets").join("log_configs").join(&self.id)
+    }
+
+    /// Argumento JVM con la ruta ya sustituida
+    pub fn jvm_argument(&self, game_dir: &Path) -> String {
+        self.argument
+            .replace("${path}", &self.path(game_dir).to_string_lossy())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -248,6 +347,17 @@ pub struct NativeLibrary {
     pub sha1: String,
     pub size: u64,
     pub path: String,
+    /// Prefijos de `extract.exclude` que no deben extraerse
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// Archivo escrito al extraer un jar de nativos
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ExtractedFile {
+    pub path: String,
+    pub size: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -256,37 +366,167 @@ pub struct ExtractionHint {
     pub requires_extraction: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct NormalizedArguments {
     pub game: Vec<String>,
     pub jvm: Vec<String>,
+    /// Argumentos del juego que dependen de opciones de lanzamiento
+    /// (`has_custom_resolution`, `is_demo_user`...)
+    #[serde(default)]
+    pub game_features: Vec<FeatureArgument>,
 }
 
-#[derive(Debug, Clone)]
+impl NormalizedArguments {
+    /// Tweakers de launchwrapper (`--tweakClass`) en el orden en que se cargan
+    pub fn tweak_classes(&self) -> Vec<&str> {
+        self.game
+            .windows(2)
+            .filter(|pair| pair[0] == TWEAK_CLASS_ARG)
+            .map(|pair| pair[1].as_str())
+            .collect()
+    }
+}
+
+/// Argumentos que solo se añaden si las features coinciden con las indicadas
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FeatureArgument {
+    pub features: HashMap<String, bool>,
+    pub values: Vec<String>,
+}
+
+impl FeatureArgument {
+    /// Comprueba las features contra el conjunto de features activadas
+    pub fn applies(&self, enabled: &HashSet<String>) -> bool {
+        self.features
+            .iter()
+            .all(|(name, expected)| enabled.contains(name) == *expected)
+    }
+}
+
+// Tipos de progreso: se serializan en camelCase para reenviarlos por IPC (Tauri/Electron)
+
+/// Archivo al que se refiere un evento de progreso
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DownloadProgressInfo {
+    /// Ruta relativa o identificador del archivo (`manifest-1.21.8`, ...)
     pub name: String,
+    /// Versión que se está instalando; distingue instalaciones que comparten canal
     pub version: Arc<String>,
 }
 
-#[derive(Debug, Clone)]
+impl DownloadProgressInfo {
+    pub fn new(name: impl Into<String>, version: Arc<String>) -> Self {
+        Self {
+            name: name.into(),
+            version,
+        }
+    }
+}
+
+/// Evento de progreso de una fase de descarga: llega uno por cada archivo
+/// terminado (descargado, ya presente o fallido) y `current` cuenta los de toda
+/// la fase. Los metadatos sueltos (manifest, índice de assets) avisan además al
+/// empezar, con `current == 0`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DownloadProgress {
+    /// Archivos terminados de la categoría
     pub current: usize,
+    /// Archivos de la categoría
     pub total: usize,
+    /// Tamaño en disco del archivo completado (0 al empezar o si falló)
+    pub bytes: u64,
     pub info: DownloadProgressInfo,
     pub download_type: DownloadProgressType,
+    /// Progreso conjunto de la instalación; solo en los eventos de `download_all`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overall: Option<OverallProgress>,
 }
-#[derive(Debug, Clone)]
+
+impl DownloadProgress {
+    pub fn new(
+        info: DownloadProgressInfo,
+        download_type: DownloadProgressType,
+        current: usize,
+        total: usize,
+        bytes: u64,
+    ) -> Self {
+        Self {
+            current,
+            total,
+            bytes,
+            info,
+            download_type,
+            overall: None,
+        }
+    }
+
+    /// Aviso de que empieza la descarga de un metadato, sin nada terminado
+    pub fn is_start(&self) -> bool {
+        self.current == 0
+    }
+
+    /// Si con este evento la fase queda completa
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.total
+    }
+}
+
+/// Progreso de todas las categorías juntas (cliente, librerías, nativos,
+/// assets y metadatos). Los assets cuentan desde que se conoce su índice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverallProgress {
+    pub completed_files: usize,
+    pub total_files: usize,
+    pub completed_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Categoría de descarga a la que pertenece un evento o un archivo fallido
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DownloadProgressType {
+    /// Librerías Java del classpath
     Library,
+    /// Assets del juego (texturas, sonidos, idiomas)
     Asset,
+    /// Librerías nativas extraídas para el sistema
     Native,
+    /// JAR del cliente
     Client,
+    /// JAR del servidor (con `include_server`)
+    Server,
+    /// JSON de versión e índices de assets
     Manifest,
 }
 
+/// Archivo que no se pudo descargar en modo tolerante a fallos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedFile {
+    pub name: String,
+    pub url: String,
+    pub path: PathBuf,
+    /// Vacío si el archivo no publica hash
+    pub sha1: String,
+    pub download_type: DownloadProgressType,
+    pub error: String,
+    /// Tipo de fallo; con `ErrorKind::is_retryable` se sabe si merece la pena
+    /// pasarlo a `retry_failed`
+    #[serde(default)]
+    pub kind: ErrorKind,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct VersionAssets {
     pub objects: HashMap<String, Asset>,
+    // Índices antiguos (<= 1.7.2): copiar los assets con su nombre real
+    #[serde(default, rename = "virtual")]
+    pub is_virtual: bool,
+    #[serde(default)]
+    pub map_to_resources: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -295,6 +535,121 @@ pub struct Asset {
     pub size: usize,
 }
 
+/// Predicado de `AssetFilter::Custom`: recibe el nombre y el asset
+pub type AssetPredicate = Arc<dyn Fn(&str, &Asset) -> bool + Send + Sync>;
+
+/// Filtro aplicado a las entradas del índice de assets antes de descargarlas
+#[derive(Clone)]
+pub enum AssetFilter {
+    /// Conserva solo los idiomas indicados (`es_es`, ...); `en_us` siempre se conserva
+    Languages(Vec<String>),
+    /// Omite la música y los discos (`minecraft/sounds/music`, `minecraft/sounds/records`)
+    SkipMusic,
+    /// Predicado propio: devuelve `true` para descargar el asset
+    Custom(AssetPredicate),
+}
+
+impl std::fmt::Debug for AssetFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetFilter::Languages(languages) => {
+                f.debug_tuple("Languages").field(languages).finish()
+            }
+            AssetFilter::SkipMusic => f.write_str("SkipMusic"),
+            AssetFilter::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl AssetFilter {
+    pub fn keeps(&self, name: &str, asset: &Asset) -> bool {
+        match self {
+            AssetFilter::Languages(languages) => {
+                let Some(file) = name.strip_prefix("minecraft/lang/") else {
+                    return true;
+                };
+                let code = file.split('.').next().unwrap_or(file).to_lowercase();
+                code == "en_us" || languages.iter().any(|l| l.to_lowercase() == code)
+            }
+            AssetFilter::SkipMusic => {
+                !name.starts_with("minecraft/sounds/music/")
+                    && !name.starts_with("minecraft/sounds/records/")
+            }
+            AssetFilter::Custom(predicate) => predicate(name, asset),
+        }
+    }
+}
+
+impl NormalizedVersion {
+    /// Rechaza rutas y nombres que se saldrían del directorio del juego al
+    /// unirlos a él: rutas absolutas, `..` o caracteres no válidos
+    pub fn check_paths(&self) -> Result<(), ProtonError> {
+        let file_names = [Some(&self.id), Some(&self.asset_index.id)]
+            .into_iter()
+            .chain([self.logging.as_ref().map(|logging| &logging.id)])
+            .flatten();
+        for name in file_names {
+            if name.contains('/') || !is_safe_relative_path(name) {
+                return Err(ProtonError::UnsafePath(name.clone()));
+            }
+        }
+
+        let paths = self
+            .libraries
+            .iter()
+            .map(|library| &library.path)
+            .chain(self.natives.iter().map(|native| &native.path));
+        for path in paths {
+            if !is_safe_relative_path(path) {
+                return Err(ProtonError::UnsafePath(path.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Persistencia de los metadatos ya resueltos
+#[cfg(not(target_arch = "wasm32"))]
+impl NormalizedVersion {
+    /// Ruta de `versions/<id>/<id>.proton.json` dentro de un directorio de juego
+    pub fn metadata_path(game_dir: &Path, version_id: &str) -> PathBuf {
+        game_dir
+            .join("versions")
+            .join(version_id)
+            .join(format!("{version_id}.proton.json"))
+    }
+
+    /// Guarda la versión normalizada para poder reutilizarla sin red
+    pub async fn save_to(&self, game_dir: &Path) -> Result<PathBuf, ProtonError> {
+        let fs = crate::filesystem::file_system();
+        let path = Self::metadata_path(game_dir, &self.id);
+        if let Some(parent) = path.parent() {
+            fs.create_dir_all(parent).await?;
+        }
+
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| ProtonError::Other(format!("Failed to serialize version: {e}")))?;
+
+        // Escritura atómica: nunca dejar un archivo a medias
+        let temp = crate::utilities::temp_path(&path);
+        fs.write(&temp, &json).await?;
+        if let Err(e) = fs.rename(&temp, &path).await {
+            let _ = fs.remove_file(&temp).await;
+            return Err(ProtonError::IoError(e));
+        }
+
+        Ok(path)
+    }
+
+    /// Carga una versión guardada previamente con `save_to`
+    pub async fn load_from(game_dir: &Path, version_id: &str) -> Result<Self, ProtonError> {
+        let path = Self::metadata_path(game_dir, version_id);
+        let content = crate::filesystem::file_system().read(&path).await?;
+        serde_json::from_slice(&content)
+            .map_err(|e| ProtonError::Other(format!("Invalid metadata file {path:?}: {e}")))
+    }
+}
+
 // Implementación de utilidad para VersionAssets
 impl VersionAssets {
     /// Obtiene todos los assets como vector de tuplas (ruta, asset)
@@ -311,6 +666,21 @@ impl VersionAssets {
     pub fn len(&self) -> usize {
         self.objects.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Los objetos se guardan en `objects/<2 primeros>/<hash>`: solo se
+    /// admiten hashes SHA-1 en hexadecimal
+    pub fn check_hashes(&self) -> Result<(), ProtonError> {
+        for asset in self.objects.values() {
+            if asset.hash.len() != 40 || !asset.hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ProtonError::UnsafePath(asset.hash.clone()));
+            }
+        }
+        Ok(())
+    }
 }
 
 // Implementación de conversión de Mojang a Normalized
@@ -340,42 +710,62 @@ impl TryFrom<MojangVersionDetails> for NormalizedVersion {
                     size: artifact.size,
                     path: artifact.path,
                 });
+            } else if let Some(repository) = &lib.url {
+                let coordinate: MavenCoordinate = lib.name.parse()?;
+                libraries.push(Library {
+                    name: lib.name.clone(),
+                    url: coordinate.url(repository),
+                    sha1: lib.sha1.clone().unwrap_or_default(),
+                    size: lib.size.unwrap_or(0),
+                    path: coordinate.path(),
+                });
             }
 
             // Procesar nativos
-            if let Some(natives_map) = lib.natives {
-                if let Some(classifier) = natives_map.get(get_os_name_runtime()) {
-                    if let Some(native_artifact) = lib.downloads.classifiers.get(classifier) {
-                        natives.push(NativeLibrary {
-                            name: lib.name,
-                            classifier: classifier.clone(),
-                            url: native_artifact.url.clone(),
-                            sha1: native_artifact.sha1.clone(),
-                            size: native_artifact.size,
-                            path: native_artifact.path.clone(),
-                        });
-
-                        requires_extraction.push(ExtractionHint {
-                            path: native_artifact.path.clone(),
-                            requires_extraction: true,
-                        });
-                    }
-                }
+            if let Some(natives_map) = lib.natives
+                && let Some(classifier) = natives_map.get(mojang_os_name())
+                && let classifier = classifier.replace("${arch}", mojang_arch_bits())
+                && let Some((classifier, native_artifact)) =
+                    select_native_classifier(&lib.downloads.classifiers, classifier)
+            {
+                natives.push(NativeLibrary {
+                    name: lib.name,
+                    classifier,
+                    url: native_artifact.url.clone(),
+                    sha1: native_artifact.sha1.clone(),
+                    size: native_artifact.size,
+                    path: native_artifact.path.clone(),
+                    exclude: lib
+                        .extract
+                        .as_ref()
+                        .map(|extract| extract.exclude.clone())
+                        .unwrap_or_default(),
+                });
+
+                requires_extraction.push(ExtractionHint {
+                    path: native_artifact.path.clone(),
+                    requires_extraction: true,
+                });
             }
         }
 
+        // Con LWJGL 3 los nativos son librerías con clasificador por arquitectura
+        let libraries = select_native_arch(libraries);
+
         // Convertir argumentos
         let arguments = match (mojang_version.arguments, mojang_version.minecraft_arguments) {
-            (Some(args), _) => normalize_arguments(args),
+            (Some(args), Some(legacy_args)) => combine_arguments(
+                normalize_arguments(args),
+                parse_legacy_arguments(legacy_args),
+            ),
+            (Some(args), None) => normalize_arguments(args),
             (None, Some(legacy_args)) => parse_legacy_arguments(legacy_args),
-            (None, None) => NormalizedArguments {
-                game: Vec::new(),
-                jvm: Vec::new(),
-            },
+            (None, None) => NormalizedArguments::default(),
         };
 
-        Ok(NormalizedVersion {
+        let version = NormalizedVersion {
             id: mojang_version.id,
+            version_type: Some(mojang_version.version_type),
             release_time: mojang_version.release_time,
             java_version: mojang_version
                 .java_version
@@ -402,36 +792,170 @@ impl TryFrom<MojangVersionDetails> for NormalizedVersion {
             natives,
             arguments,
             requires_extraction,
-        })
+            logging: mojang_version.logging.map(|logging| LoggingConfig {
+                id: logging.client.file.id,
+                url: logging.client.file.url,
+                sha1: logging.client.file.sha1,
+                size: logging.client.file.size,
+                argument: logging.client.argument,
+            }),
+        };
+        version.check_paths()?;
+        Ok(version)
     }
 }
 
 // Funciones helper
+
 fn library_applies(lib: &MojangLibrary) -> bool {
-    if lib.rules.is_empty() {
+    rules_allow(&lib.rules)
+}
+
+/// Nombre del sistema operativo tal y como aparece en los JSON de Mojang
+pub(crate) fn mojang_os_name() -> &'static str {
+    match get_os_name_runtime() {
+        "macos" => "osx",
+        other => other,
+    }
+}
+
+/// Valor de `${arch}` en los clasificadores de nativos antiguos
+fn mojang_arch_bits() -> &'static str {
+    if cfg!(target_pointer_width = "64") {
+        "64"
+    } else {
+        "32"
+    }
+}
+
+/// Arquitectura del host con la nomenclatura de los clasificadores de LWJGL
+fn host_native_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "aarch64" => "arm64",
+        "arm" => "arm32",
+        "x86" => "x86",
+        _ => "x64",
+    }
+}
+
+/// Arquitectura de un clasificador `natives-<os>[-<arch>]`; sin sufijo es x64
+fn native_classifier_arch(classifier: &str) -> Option<&'static str> {
+    let rest = classifier.strip_prefix("natives-")?;
+    let arch = rest.split_once('-').map_or("", |(_, arch)| arch);
+    Some(match arch {
+        "arm64" | "aarch64" | "aarch_64" => "arm64",
+        "arm32" => "arm32",
+        "x86" | "32" => "x86",
+        _ => "x64",
+    })
+}
+
+/// Elige el clasificador de nativos para el host, prefiriendo una variante
+/// de la arquitectura actual si el JSON la declara
+fn select_native_classifier(
+    classifiers: &HashMap<String, MojangArtifact>,
+    classifier: String,
+) -> Option<(String, &MojangArtifact)> {
+    let arch = host_native_arch();
+    if arch != "x64" {
+        let variant = format!("{classifier}-{arch}");
+        if let Some(artifact) = classifiers.get(&variant) {
+            return Some((variant, artifact));
+        }
+    }
+
+    classifiers
+        .get(&classifier)
+        .map(|artifact| (classifier, artifact))
+}
+
+/// Descarta las librerías de nativos de otras arquitecturas cuando existe una
+/// variante para la arquitectura del host
+fn select_native_arch(libraries: Vec<Library>) -> Vec<Library> {
+    let arch = host_native_arch();
+
+    // `grupo:artefacto:versión:natives-<os>` -> hay variante para el host
+    let native_key = |library: &Library| -> Option<(String, &'static str)> {
+        let coordinate: MavenCoordinate = library.name.parse().ok()?;
+        let classifier = coordinate.classifier.as_deref()?;
+        let library_arch = native_classifier_arch(classifier)?;
+        let os = classifier
+            .trim_start_matches("natives-")
+            .split('-')
+            .next()
+            .unwrap_or_default();
+        Some((
+            format!(
+                "{}:{}:{}:{os}",
+                coordinate.group, coordinate.artifact, coordinate.version
+            ),
+            library_arch,
+        ))
+    };
+
+    let available: std::collections::HashSet<String> = libraries
+        .iter()
+        .filter_map(native_key)
+        .filter(|(_, library_arch)| *library_arch == arch)
+        .map(|(key, _)| key)
+        .collect();
+
+    libraries
+        .into_iter()
+        .filter(|library| match native_key(library) {
+            Some((key, library_arch)) => !available.contains(&key) || library_arch == arch,
+            None => true,
+        })
+        .collect()
+}
+
+fn rule_matches(rule: &MojangRule, enabled: &HashSet<String>) -> bool {
+    let os_matches = rule.os.as_ref().is_none_or(|os_rule| {
+        let name_matches = os_rule.name.as_ref().is_none_or(|n| n == mojang_os_name());
+        let arch_matches = os_rule
+            .arch
+            .as_ref()
+            .is_none_or(|a| a == "x86" && cfg!(target_arch = "x86"));
+        // Las versiones antiguas eligen LWJGL según la versión de macOS
+        // (`^10\.5\.\d$`); sin versión conocida la regla no aplica
+        let version_matches = os_rule.version.as_ref().is_none_or(|pattern| {
+            get_os_version_runtime().is_some_and(|version| {
+                regex_lite::Regex::new(pattern).is_ok_and(|regex| regex.is_match(version))
+            })
+        });
+        name_matches && arch_matches && version_matches
+    });
+
+    let features_match = rule.features.as_ref().is_none_or(|features| {
+        features
+            .iter()
+            .all(|(name, expected)| enabled.contains(name) == *expected)
+    });
+
+    os_matches && features_match
+}
+
+/// Evalúa una lista de reglas de Mojang: la última regla que aplica decide.
+/// Sin opciones de lanzamiento todas las features están desactivadas.
+fn rules_allow(rules: &[MojangRule]) -> bool {
+    rules_allow_with(rules, &HashSet::new())
+}
+
+fn rules_allow_with(rules: &[MojangRule], enabled: &HashSet<String>) -> bool {
+    if rules.is_empty() {
         return true;
     }
 
-    let os_name = get_os_name_runtime();
     let mut allow = false;
 
-    for rule in &lib.rules {
-        let applies = match &rule.os {
-            Some(os_rule) => os_rule.name.as_ref().is_none_or(|n| n == os_name),
-            None => true,
-        };
+    for rule in rules {
+        if !rule_matches(rule, enabled) {
+            continue;
+        }
 
         match rule.action.as_str() {
-            "allow" => {
-                if applies {
-                    allow = true;
-                }
-            }
-            "disallow" => {
-                if applies {
-                    return false;
-                }
-            }
+            "allow" => allow = true,
+            "disallow" => allow = false,
             _ => {}
         }
     }
@@ -440,15 +964,56 @@ fn library_applies(lib: &MojangLibrary) -> bool {
 }
 
 fn normalize_arguments(args: MojangArguments) -> NormalizedArguments {
-    let game = flatten_arguments(args.game);
+    let (game, game_features) = split_feature_arguments(args.game);
     let jvm = flatten_arguments(args.jvm);
 
-    NormalizedArguments { game, jvm }
+    NormalizedArguments {
+        game,
+        jvm,
+        game_features,
+    }
+}
+
+/// Separa los argumentos condicionados por features, que se resuelven al
+/// lanzar, de los que ya pueden evaluarse para este sistema
+fn split_feature_arguments(args: Vec<MojangArgumentValue>) -> (Vec<String>, Vec<FeatureArgument>) {
+    let mut plain = Vec::new();
+    let mut gated = Vec::new();
+
+    for arg in args {
+        match arg {
+            MojangArgumentValue::Conditional { rules, value }
+                if rules.iter().any(|rule| rule.features.is_some()) =>
+            {
+                let features: HashMap<String, bool> = rules
+                    .iter()
+                    .filter_map(|rule| rule.features.clone())
+                    .flatten()
+                    .collect();
+                let required: HashSet<String> = features
+                    .iter()
+                    .filter(|(_, enabled)| **enabled)
+                    .map(|(name, _)| name.clone())
+                    .collect();
+
+                // Descartar los que no aplican a este sistema ni con las features activas
+                if rules_allow_with(&rules, &required) {
+                    let values = match value {
+                        MojangConditionalValue::Single(s) => vec![s],
+                        MojangConditionalValue::Multiple(v) => v,
+                    };
+                    gated.push(FeatureArgument { features, values });
+                }
+            }
+            other => plain.extend(flatten_arguments(vec![other])),
+        }
+    }
+
+    (plain, gated)
 }
 
 fn flatten_arguments(args: Vec<MojangArgumentValue>) -> Vec<String> {
     let mut result = Vec::new();
-    let os_name = get_os_name_runtime();
 
     for arg in args {
         match arg {
@@ -456,7 +1021,7 @@ fn flatten_arguments(args: Vec<MojangArgumentValue>) -> Vec<String> {
                 result.push(s);
             }
             MojangArgumentValue::Conditional { rules, value } => {
-                if rule_set_applies(&rules, os_name) {
+                if rules_allow(&rules) {
                     match value {
                         MojangConditionalValue::Single(s) => result.push(s),
                         MojangConditionalValue::Multiple(v) => result.extend(v),
@@ -469,53 +1034,201 @@ fn flatten_arguments(args: Vec<MojangArgumentValue>) -> Vec<String> {
     result
 }
 
-fn rule_set_applies(rules: &[MojangRule], os_name: &str) -> bool {
-    if rules.is_empty() {
-        return true;
+/// Argumentos de las versiones anteriores a 1.13 (`minecraftArguments`). Sus
+/// JSON no traen argumentos JVM: se usan los que añadía el launcher oficial,
+/// los mismos que declaran las versiones nuevas.
+/// Los argumentos JVM de estas versiones los pone el launcher
+/// (`LaunchOptions::legacy_jvm_args`)
+fn parse_legacy_arguments(args: String) -> NormalizedArguments {
+    NormalizedArguments {
+        game: args.split_whitespace().map(str::to_string).collect(),
+        jvm: Vec::new(),
+        game_features: Vec::new(),
     }
+}
 
-    let mut allow = false;
-
-    for rule in rules {
-        let applies = match &rule.os {
-            Some(os_rule) => os_rule.name.as_ref().is_none_or(|n| n == os_name),
-            None => true,
+/// Perfiles con `arguments` y `minecraftArguments` a la vez, como los modded
+/// que heredan de una versión anterior a 1.13: se parte de los argumentos
+/// antiguos y se añaden las opciones nuevas que no estén ya
+fn combine_arguments(
+    modern: NormalizedArguments,
+    legacy: NormalizedArguments,
+) -> NormalizedArguments {
+    let mut combined = legacy;
+    let mut game = modern.game.into_iter().peekable();
+    while let Some(arg) = game.next() {
+        let value = game.next_if(|next| !next.starts_with("--"));
+        // `--tweakClass` puede repetirse con tweakers distintos
+        let repeated = if arg == TWEAK_CLASS_ARG {
+            combined
+                .game
+                .windows(2)
+                .any(|pair| pair[0] == arg && Some(&pair[1]) == value.as_ref())
+        } else {
+            arg.starts_with("--") && combined.game.contains(&arg)
         };
-
-        match rule.action.as_str() {
-            "allow" => {
-                if applies {
-                    allow = true;
-                }
-            }
-            "disallow" => {
-                if applies {
-                    return false;
-                }
-            }
-            _ => {}
+        if !repeated {
+            combined.game.push(arg);
+            combined.game.extend(value);
         }
     }
-
-    allow
+    combined.game_features = modern.game_features;
+    combined.jvm = modern.jvm;
+    combined
 }
 
-fn parse_legacy_arguments(args: String) -> NormalizedArguments {
-    let mut game_args = Vec::new();
-    let mut jvm_args = Vec::new();
-
-    // Parsear argumentos del juego
-    for arg in args.split_whitespace() {
-        game_args.push(arg.to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{FileSystem, MemoryFs, with_file_system};
+    use crate::testing::{FIXTURE_VERSION, fixture_version};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn versions_round_trip_through_their_metadata_file() {
+        let fs = MemoryFs::new();
+        let game_dir = Path::new("/game");
+        let version = fixture_version("https://example.invalid");
+
+        let (path, loaded, missing) = with_file_system(Arc::new(fs.clone()), async {
+            let path = version.save_to(game_dir).await?;
+            let loaded = NormalizedVersion::load_from(game_dir, FIXTURE_VERSION).await?;
+            let missing = NormalizedVersion::load_from(game_dir, "missing").await;
+            Ok::<_, ProtonError>((path, loaded, missing))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            path,
+            game_dir
+                .join("versions")
+                .join(FIXTURE_VERSION)
+                .join(format!("{FIXTURE_VERSION}.proton.json"))
+        );
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&version).unwrap()
+        );
+        assert!(missing.is_err());
+        // La escritura atómica no deja temporales junto al archivo
+        let files = fs.read_dir(path.parent().unwrap()).await.unwrap();
+        assert_eq!(files, [path]);
     }
 
-    // Argumentos JVM estándar para versiones antiguas
-    jvm_args.push("-Djava.library.path=${natives_directory}".to_string());
-    jvm_args.push("-cp".to_string());
-    jvm_args.push("${classpath}".to_string());
+    #[test]
+    fn natives_follow_the_host_architecture() {
+        assert_eq!(native_classifier_arch("natives-linux"), Some("x64"));
+        assert_eq!(native_classifier_arch("natives-macos-arm64"), Some("arm64"));
+        assert_eq!(
+            native_classifier_arch("natives-linux-aarch_64"),
+            Some("arm64")
+        );
+        assert_eq!(native_classifier_arch("natives-windows-x86"), Some("x86"));
+        assert_eq!(native_classifier_arch("sources"), None);
+
+        let artifact = |path: &str| MojangArtifact {
+            path: path.to_string(),
+            sha1: String::new(),
+            size: 0,
+            url: String::new(),
+        };
+        let classifiers = HashMap::from([
+            ("natives-osx".to_string(), artifact("x64.jar")),
+            ("natives-osx-arm64".to_string(), artifact("arm64.jar")),
+        ]);
+        let (classifier, selected) =
+            select_native_classifier(&classifiers, "natives-osx".to_string()).unwrap();
+        if host_native_arch() == "arm64" {
+            assert_eq!(
+                (classifier.as_str(), selected.path.as_str()),
+                ("natives-osx-arm64", "arm64.jar")
+            );
+        } else {
+            assert_eq!(
+                (classifier.as_str(), selected.path.as_str()),
+                ("natives-osx", "x64.jar")
+            );
+        }
 
-    NormalizedArguments {
-        game: game_args,
-        jvm: jvm_args,
+        // Con LWJGL 3 solo queda la variante del host de cada librería
+        let library = |name: &str| Library {
+            name: name.to_string(),
+            url: String::new(),
+            sha1: String::new(),
+            size: 0,
+            path: String::new(),
+        };
+        let libraries = [
+            "org.lwjgl:lwjgl:3.3.1",
+            "org.lwjgl:lwjgl:3.3.1:natives-linux",
+            "org.lwjgl:lwjgl:3.3.1:natives-linux-arm64",
+            "org.lwjgl:lwjgl:3.3.1:natives-linux-arm32",
+            "org.lwjgl:lwjgl:3.3.1:natives-linux-x86",
+            "org.lwjgl:lwjgl-glfw:3.3.1:natives-linux",
+        ]
+        .map(library);
+        let host_native = match host_native_arch() {
+            "x64" => "org.lwjgl:lwjgl:3.3.1:natives-linux".to_string(),
+            arch => format!("org.lwjgl:lwjgl:3.3.1:natives-linux-{arch}"),
+        };
+
+        let selected: Vec<_> = select_native_arch(libraries.to_vec())
+            .into_iter()
+            .map(|library| library.name)
+            .collect();
+        assert_eq!(
+            selected,
+            [
+                "org.lwjgl:lwjgl:3.3.1".to_string(),
+                host_native,
+                "org.lwjgl:lwjgl-glfw:3.3.1:natives-linux".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn progress_events_use_stable_json_names() {
+        let info = DownloadProgressInfo::new(
+            "minecraft/sounds/ambient/cave/cave1.ogg",
+            Arc::new("1.21.8".to_string()),
+        );
+        let mut progress = DownloadProgress::new(info, DownloadProgressType::Asset, 12, 3400, 5120);
+        let json = serde_json::json!({
+            "current": 12,
+            "total": 3400,
+            "bytes": 5120,
+            "info": { "name": "minecraft/sounds/ambient/cave/cave1.ogg", "version": "1.21.8" },
+            "downloadType": "asset",
+        });
+        assert_eq!(serde_json::to_value(&progress).unwrap(), json);
+
+        progress.overall = Some(OverallProgress {
+            completed_files: 1,
+            total_files: 2,
+            completed_bytes: 3,
+            total_bytes: 4,
+        });
+        let value = serde_json::to_value(&progress).unwrap();
+        assert_eq!(
+            value["overall"],
+            serde_json::json!({ "completedFiles": 1, "totalFiles": 2, "completedBytes": 3, "totalBytes": 4 })
+        );
+        let parsed: DownloadProgress = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.overall, progress.overall);
+        assert_eq!(parsed.info.version.as_str(), "1.21.8");
+
+        let failed: FailedFile = serde_json::from_value(serde_json::json!({
+            "name": "client.jar",
+            "url": "https://example.invalid/client.jar",
+            "path": "versions/1.21.8/1.21.8.jar",
+            "sha1": "",
+            "downloadType": "client",
+            "error": "connection reset",
+        }))
+        .unwrap();
+        assert!(matches!(failed.download_type, DownloadProgressType::Client));
+        // Los informes anteriores a `kind` siguen cargando
+        assert_eq!(failed.kind, ErrorKind::Other);
     }
 }
@@ -0,0 +1,1334 @@
+use crate::diagnostics::{Diagnostic, emit};
+use crate::errors::ProtonError;
+use crate::types::ExtractedFile;
+use async_zip::tokio::read::seek::ZipFileReader;
+use futures::TryStreamExt;
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use ring::digest::{Context, SHA1_FOR_LEGACY_USE_ONLY};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    time::Duration,
+};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+use crate::filesystem::{durable_writes, file_system};
+use crate::system::process_running;
+use crate::transport::artifact_source;
+use crate::utilities::hash_cache::{FileStamp, cached_sha1, remember_sha1};
+use crate::utilities::{check_no_parent_dirs, escapes_base, is_safe_relative_path};
+
+const MAX_DOWNLOAD_ATTEMPTS: usize = 3;
+
+// Sufijo de los archivos temporales; permite reconocerlos al limpiar
+const TEMP_SUFFIX: &str = ".proton-tmp";
+
+static QUARANTINE_DIR: Lazy<RwLock<Option<PathBuf>>> = Lazy::new(|| RwLock::new(None));
+
+/// Guarda en `dir` (p. ej. `<juego>/quarantine`) los archivos descargados cuyo
+/// hash no coincide, en lugar de borrarlos, para poder inspeccionarlos.
+/// `None` (por defecto) los borra.
+pub fn set_quarantine_dir(dir: Option<PathBuf>) {
+    *QUARANTINE_DIR.write().unwrap_or_else(|e| e.into_inner()) = dir;
+}
+
+fn quarantine_dir() -> Option<PathBuf> {
+    QUARANTINE_DIR
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Mueve un temporal con hash incorrecto a la cuarentena como
+/// `<hash obtenido>-<nombre>`; `None` si no hay cuarentena o no se pudo mover
+async fn quarantine(temp_file: &Path, path: &Path, actual_hash: &str) -> Option<PathBuf> {
+    let dir = quarantine_dir()?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let target = dir.join(format!("{actual_hash}-{file_name}"));
+    let moved = async {
+        file_system().create_dir_all(&dir).await?;
+        move_file(temp_file, &target).await
+    }
+    .await;
+    match moved {
+        Ok(()) => {
+            warn!("Quarantined corrupted download as {target:?}");
+            Some(target)
+        }
+        Err(e) => {
+            warn!("Failed to quarantine {temp_file:?}: {e}");
+            None
+        }
+    }
+}
+
+/// Ruta temporal junto a `path` para escribirla y luego renombrarla:
+/// `<nombre>.<pid>.<aleatorio>.proton-tmp`. El pid y el sufijo aleatorio evitan
+/// choques entre procesos y entre reintentos de una misma tarea.
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let random = uuid::Uuid::new_v4().simple().to_string();
+    path.with_file_name(format!(
+        "{file_name}.{}.{}{TEMP_SUFFIX}",
+        std::process::id(),
+        &random[..12]
+    ))
+}
+
+/// Borra un temporal (archivo o directorio) al soltarlo, también cuando se
+/// cancela la tarea que lo escribía. En el sistema de archivos local se borra
+/// en el acto; en otros, en segundo plano.
+pub(crate) struct TempGuard {
+    path: PathBuf,
+    is_dir: bool,
+    armed: bool,
+}
+
+impl TempGuard {
+    pub(crate) fn file(path: PathBuf) -> Self {
+        Self {
+            path,
+            is_dir: false,
+            armed: true,
+        }
+    }
+
+    pub(crate) fn dir(path: PathBuf) -> Self {
+        Self {
+            path,
+            is_dir: true,
+            armed: true,
+        }
+    }
+
+    /// El temporal ya se movió a su destino: no hay nada que borrar
+    pub(crate) fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for TempGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let path = std::mem::take(&mut self.path);
+        let is_dir = self.is_dir;
+        let fs = file_system();
+        if fs.is_local() {
+            let _ = if is_dir {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+        } else if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = if is_dir {
+                    fs.remove_dir_all(&path).await
+                } else {
+                    fs.remove_file(&path).await
+                };
+            });
+        }
+    }
+}
+
+/// Mueve `from` a `to`. Si están en sistemas de archivos distintos (EXDEV) el
+/// rename no es posible: se copia a un temporal junto a `to`, se sincroniza,
+/// se renombra y se borra el original.
+pub(crate) async fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    let fs = file_system();
+    let moved = match fs.rename(from, to).await {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            info!("Cross-device rename of {from:?}, copying instead");
+            let temp = temp_path(to);
+            let copied = async {
+                fs.copy(from, &temp).await?;
+                fs.sync(&temp).await?;
+                fs.rename(&temp, to).await
+            }
+            .await;
+            if let Err(e) = copied {
+                let _ = fs.remove_file(&temp).await;
+                return Err(e);
+            }
+            fs.remove_file(from).await
+        }
+        result => result,
+    };
+
+    // El nombre nuevo solo sobrevive a un corte si se sincroniza el directorio
+    if moved.is_ok()
+        && durable_writes()
+        && let Some(parent) = to.parent()
+    {
+        fs.sync(parent).await?;
+    }
+    moved
+}
+
+/// Pid del proceso que creó un archivo temporal de `temp_path`
+fn temp_owner(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?.strip_suffix(TEMP_SUFFIX)?;
+    let mut parts = name.rsplitn(3, '.');
+    let _random = parts.next()?;
+    parts.next()?.parse().ok()
+}
+
+/// Borra bajo `dir` los temporales de procesos que ya no existen (descargas
+/// interrumpidas por un cierre o un fallo del sistema)
+pub(crate) async fn remove_orphan_temp_files(dir: &Path) {
+    let fs = file_system();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        // Directorios inexistentes o sistemas sin `read_dir`: nada que limpiar
+        let Ok(entries) = fs.read_dir(&dir).await else {
+            continue;
+        };
+        for entry in entries {
+            if let Some(pid) = temp_owner(&entry) {
+                if !process_running(pid) {
+                    match fs.remove_file(&entry).await {
+                        Ok(()) => info!("Removed orphaned temp file {entry:?}"),
+                        Err(e) => warn!("Failed to remove orphaned temp file {entry:?}: {e}"),
+                    }
+                }
+            } else if fs.metadata(&entry).await.is_ok_and(|meta| meta.is_dir) {
+                pending.push(entry);
+            }
+        }
+    }
+}
+
+/// Descarga `url` en `path` y comprueba su SHA-1. `expected_hash` es `None`
+/// solo para archivos que no publican hash (librerías de algunos perfiles
+/// modded), que se descargan sin verificar; un hash vacío es un error.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(expected_hash), fields(path = ?path))
+)]
+pub async fn download_file(
+    url: String,
+    path: &PathBuf,
+    expected_hash: Option<&str>,
+    expected_size: u64,
+) -> Result<(), ProtonError> {
+    check_no_parent_dirs(path)?;
+    if expected_hash == Some("") {
+        return Err(ProtonError::Other(format!("{path:?} has an empty hash")));
+    }
+
+    let fs = file_system();
+
+    if expected_hash.is_none() && fs.exists(path).await {
+        info!("File already exists and has no published hash: {path:?}");
+        return Ok(());
+    }
+
+    // Archivos registrados a mano (versiones propias): sin URL solo se comprueban
+    if url.is_empty() {
+        if !fs.exists(path).await {
+            return Err(ProtonError::Other(format!(
+                "{path:?} is missing and has no URL to download it from"
+            )));
+        }
+        if let Some(expected_hash) = expected_hash
+            && verify_file_hash(path, expected_hash, expected_size).await?
+        {
+            return Ok(());
+        }
+        return Err(ProtonError::Other(format!(
+            "{path:?} is corrupted and has no URL to download it again"
+        )));
+    }
+
+    // Verificar si el archivo ya existe y tiene el hash correcto
+    if let Some(expected_hash) = expected_hash
+        && fs.exists(path).await
+    {
+        info!("File already exists, verifying hash: {path:?}");
+
+        match verify_file_hash(path, expected_hash, expected_size).await {
+            Ok(true) => {
+                info!("File already exists with correct hash: {path:?}");
+                return Ok(());
+            }
+            Ok(false) => {
+                warn!("File exists but hash doesn't match, re-downloading: {path:?}");
+                emit(Diagnostic::CorruptedFile { path: path.clone() });
+                // Eliminar archivo corrupto
+                if let Err(e) = fs.remove_file(path).await {
+                    warn!("Failed to remove corrupted file: {e}");
+                }
+            }
+            Err(e) => {
+                warn!("Failed to verify existing file hash: {e}, re-downloading");
+                // Eliminar archivo que no se puede verificar
+                if let Err(e) = fs.remove_file(path).await {
+                    warn!("Failed to remove unverifiable file: {e}");
+                }
+            }
+        }
+    }
+
+    // Generar nombre único para archivo temporal
+    let temp_file = temp_path(path);
+    let mut temp_guard = TempGuard::file(temp_file.clone());
+    let mut mismatch = None;
+
+    for attempt in 1..=MAX_DOWNLOAD_ATTEMPTS {
+        // Crear directorio padre si no existe
+        if let Some(parent_dir) = path.parent()
+            && let Err(e) = fs.create_dir_all(parent_dir).await
+        {
+            error!("Failed to create directory {parent_dir:?}: {e}");
+            return Err(ProtonError::IoError(e));
+        }
+
+        // Limpiar archivo temporal si existe de intentos anteriores
+        if fs.exists(&temp_file).await {
+            let _ = fs.remove_file(&temp_file).await;
+        }
+
+        // Abrir el artefacto (HTTP salvo que se haya configurado otro origen)
+        let mut stream = match artifact_source().open(&url).await {
+            Ok(stream) => stream,
+            // Un 404 o un error que no es de red no se arregla reintentando
+            Err(e) if !e.is_retryable() => return Err(e),
+            Err(e) => {
+                warn!("Request failed on attempt {attempt}: {e}");
+                emit(Diagnostic::DownloadRetry {
+                    url: url.clone(),
+                    attempt,
+                    error: e.to_string(),
+                });
+                if attempt == MAX_DOWNLOAD_ATTEMPTS {
+                    return Err(e);
+                }
+                continue;
+            }
+        };
+
+        // Crear archivo temporal
+        let mut file = match fs.create(&temp_file).await {
+            Ok(f) => f,
+            Err(e) => {
+                error!("Failed to create temp file {temp_file:?}: {e}");
+                return Err(ProtonError::IoError(e));
+            }
+        };
+
+        // Reservar el tamaño publicado: así la falta de espacio se detecta
+        // antes de escribir y el archivo no crece a trozos. Sin hash no se
+        // podría detectar un tamaño erróneo, así que entonces no se reserva.
+        let preallocate = expected_size > 0 && expected_hash.is_some();
+        if preallocate && let Err(e) = fs.allocate(&temp_file, expected_size).await {
+            if e.kind() != std::io::ErrorKind::Unsupported {
+                error!("Failed to preallocate {expected_size} bytes for {temp_file:?}: {e}");
+                drop(file);
+                let _ = fs.remove_file(&temp_file).await;
+                return Err(ProtonError::IoError(e));
+            }
+            debug!("Preallocation not supported for {temp_file:?}");
+        }
+
+        // Prepara para cálculo de hash SHA1
+        let mut sha1_context = Context::new(&SHA1_FOR_LEGACY_USE_ONLY);
+        let mut bytes_written = 0u64;
+
+        let write_result: Result<(), ProtonError> = async {
+            loop {
+                match stream.try_next().await {
+                    Ok(Some(chunk)) => {
+                        sha1_context.update(&chunk);
+                        file.write_all(&chunk).await?;
+                        bytes_written += chunk.len() as u64;
+                    }
+                    Ok(None) => break,
+                    Err(e) => return Err(e),
+                }
+            }
+            file.shutdown().await?;
+            Ok(())
+        }
+        .await;
+
+        match write_result {
+            Ok(()) => {
+                // Verificar hash
+                let actual_hash = hex::encode(sha1_context.finish());
+                if expected_hash.is_none_or(|expected| actual_hash == expected) {
+                    // Si el tamaño publicado era mayor, sobra la cola reservada
+                    if preallocate
+                        && bytes_written < expected_size
+                        && let Err(e) = fs.allocate(&temp_file, bytes_written).await
+                    {
+                        let _ = fs.remove_file(&temp_file).await;
+                        return Err(ProtonError::IoError(e));
+                    }
+
+                    // El contenido se vuelca antes del rename para que el
+                    // destino nunca apunte a datos sin escribir
+                    if durable_writes()
+                        && let Err(e) = fs.sync(&temp_file).await
+                    {
+                        let _ = fs.remove_file(&temp_file).await;
+                        return Err(ProtonError::IoError(e));
+                    }
+
+                    // Mover archivo temporal al destino final
+                    match move_file(&temp_file, path).await {
+                        Ok(()) => {
+                            temp_guard.disarm();
+                            info!("File downloaded successfully: {path:?}");
+                            return Ok(());
+                        }
+                        Err(e) => {
+                            error!("Failed to rename temp file: {e}");
+                            let _ = fs.remove_file(&temp_file).await;
+                            return Err(ProtonError::IoError(e));
+                        }
+                    }
+                } else {
+                    let expected_hash = expected_hash.unwrap_or_default().to_string();
+                    warn!(
+                        "Hash mismatch on attempt {attempt}: expected {expected_hash}, got {actual_hash}"
+                    );
+                    emit(Diagnostic::HashMismatch {
+                        url: url.clone(),
+                        attempt,
+                        expected: expected_hash.clone(),
+                        actual: actual_hash.clone(),
+                    });
+
+                    // Sin la cola reservada la copia es exactamente lo recibido
+                    drop(file);
+                    if preallocate && bytes_written < expected_size {
+                        let _ = fs.allocate(&temp_file, bytes_written).await;
+                    }
+                    let quarantined = quarantine(&temp_file, path, &actual_hash).await;
+                    mismatch = Some(ProtonError::HashMismatch {
+                        expected: expected_hash.clone(),
+                        actual: actual_hash,
+                        bytes: bytes_written,
+                        quarantined,
+                    });
+                }
+            }
+            Err(e) if !e.is_retryable() => {
+                error!("Write error on attempt {attempt}: {e}");
+                let _ = fs.remove_file(&temp_file).await;
+                return Err(e);
+            }
+            Err(e) => {
+                warn!("Write error on attempt {attempt}: {e}");
+                emit(Diagnostic::DownloadRetry {
+                    url: url.clone(),
+                    attempt,
+                    error: e.to_string(),
+                });
+                if attempt == MAX_DOWNLOAD_ATTEMPTS {
+                    // Limpiar archivo temporal antes de retornar error
+                    let _ = fs.remove_file(&temp_file).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        // Limpiar archivo temporal antes del siguiente intento
+        if fs.exists(&temp_file).await
+            && let Err(e) = fs.remove_file(&temp_file).await
+        {
+            warn!("Failed to remove temp file: {e}");
+        }
+
+        // Opcional: delay exponencial entre intentos
+        if attempt < MAX_DOWNLOAD_ATTEMPTS {
+            let delay = Duration::from_millis(100 * (1 << (attempt - 1)));
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    // Solo se llega aquí si el último intento no coincidió con el hash
+    Err(mismatch.unwrap_or_else(|| ProtonError::Other(format!("Failed to download {url}"))))
+}
+
+// Función auxiliar para verificar el hash de un archivo existente. Si se
+// conoce el tamaño (distinto de 0) se compara antes: un archivo truncado o de
+// otra versión se descarta sin leerlo entero
+pub(crate) async fn verify_file_hash(
+    path: &Path,
+    expected_hash: &str,
+    expected_size: u64,
+) -> Result<bool, ProtonError> {
+    if expected_size > 0 && file_system().metadata(path).await?.len != expected_size {
+        return Ok(false);
+    }
+    Ok(file_sha1(path).await? == expected_hash)
+}
+
+/// SHA-1 en hexadecimal del contenido de un archivo. En disco local se
+/// calcula en el pool de hilos bloqueantes para no ocupar el runtime, y se
+/// reutiliza el último hash si el archivo no ha cambiado desde entonces.
+pub(crate) async fn file_sha1(path: &Path) -> Result<String, ProtonError> {
+    let fs = file_system();
+    if fs.is_local() {
+        let path = path.to_path_buf();
+        return tokio::task::spawn_blocking(move || local_file_sha1(&path))
+            .await
+            .map_err(|e| ProtonError::Other(format!("Hash task failed: {e}")))?;
+    }
+
+    let stamp = FileStamp::other(&fs.metadata(path).await?);
+    if let Some(sha1) = stamp.and_then(|stamp| cached_sha1(path, stamp)) {
+        return Ok(sha1);
+    }
+    let mut file = fs.open(path).await.map_err(ProtonError::IoError)?;
+
+    let mut sha1_context = Context::new(&SHA1_FOR_LEGACY_USE_ONLY);
+    let mut buffer = [0u8; 8192]; // Buffer de 8KB para lectura eficiente
+
+    loop {
+        let bytes_read = file.read(&mut buffer).await.map_err(ProtonError::IoError)?;
+
+        if bytes_read == 0 {
+            break;
+        }
+
+        sha1_context.update(&buffer[..bytes_read]);
+    }
+
+    let sha1 = hex::encode(sha1_context.finish());
+    if let Some(stamp) = stamp {
+        remember_sha1(path, stamp, &sha1);
+    }
+    Ok(sha1)
+}
+
+fn local_file_sha1(path: &Path) -> Result<String, ProtonError> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let stamp = FileStamp::local(&file.metadata()?);
+    if let Some(sha1) = stamp.and_then(|stamp| cached_sha1(path, stamp)) {
+        return Ok(sha1);
+    }
+
+    let mut sha1_context = Context::new(&SHA1_FOR_LEGACY_USE_ONLY);
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        sha1_context.update(&buffer[..bytes_read]);
+    }
+
+    let sha1 = hex::encode(sha1_context.finish());
+    if let Some(stamp) = stamp {
+        remember_sha1(path, stamp, &sha1);
+    }
+    Ok(sha1)
+}
+
+// Extensiones de librerías nativas: se extraen en la raíz del directorio de nativos
+const NATIVE_EXTENSIONS: [&str; 4] = [".so", ".dll", ".dylib", ".jnilib"];
+
+/// Ruta relativa de salida para una entrada del jar de nativos, o `None` si
+/// la entrada debe omitirse
+fn native_entry_target(nombre: &str, exclude: &[String]) -> Option<String> {
+    // Directorios, metadatos del jar y exclusiones declaradas por la versión
+    if nombre.ends_with('/')
+        || nombre.starts_with("META-INF/")
+        || exclude
+            .iter()
+            .any(|prefix| nombre.starts_with(prefix.as_str()))
+    {
+        return None;
+    }
+
+    let file_name = nombre.rsplit('/').next().unwrap_or(nombre);
+    let lower = file_name.to_lowercase();
+
+    // Basura habitual en los jars de nativos
+    if file_name == "module-info.class"
+        || lower.ends_with("git")
+        || lower.ends_with("sha1")
+        || lower.starts_with("license")
+        || lower.starts_with("readme")
+        || lower.ends_with(".txt")
+        || lower.ends_with(".md")
+    {
+        return None;
+    }
+
+    if NATIVE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext)) {
+        Some(file_name.to_string())
+    } else {
+        Some(nombre.to_string())
+    }
+}
+
+/// Ruta de salida de una entrada del archivo, rechazando rutas absolutas o
+/// con `..` que escaparían del directorio de destino (zip-slip)
+async fn safe_output_path(destino: &Path, relativa: &str) -> Result<PathBuf, ProtonError> {
+    let unsafe_path = || ProtonError::UnsafeArchivePath(relativa.to_string());
+
+    if !is_safe_relative_path(relativa) {
+        return Err(unsafe_path());
+    }
+
+    let ruta_salida = destino.join(relativa);
+    let parent = ruta_salida.parent().ok_or_else(unsafe_path)?;
+    let fs = file_system();
+    fs.create_dir_all(parent).await?;
+
+    // Comprobación final con rutas canónicas por si hay enlaces simbólicos
+    let destino_canonico = fs.canonicalize(destino).await?;
+    if !fs
+        .canonicalize(parent)
+        .await?
+        .starts_with(&destino_canonico)
+    {
+        return Err(unsafe_path());
+    }
+
+    Ok(ruta_salida)
+}
+
+/// Aplica los bits de permisos guardados en el archivo (solo en Unix)
+async fn apply_unix_permissions(path: &Path, mode: u16) -> Result<(), ProtonError> {
+    let mode = u32::from(mode) & 0o777;
+    if mode != 0 {
+        file_system().set_permissions(path, mode).await?;
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(exclude)))]
+pub async fn extract_native(
+    jar_path: &Path,
+    destino: &Path,
+    exclude: &[String],
+) -> Result<Vec<ExtractedFile>, ProtonError> {
+    extract_zip(jar_path, destino, |nombre| {
+        native_entry_target(nombre, exclude)
+    })
+    .await
+}
+
+// Mitad alta de CP437, la codificación de los nombres de zip sin la marca UTF-8
+const CP437_HIGH: &str = concat!(
+    "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒ",
+    "áíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐",
+    "└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+    "αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}",
+);
+
+/// Nombre de una entrada del zip. Los que no son UTF-8 se leen como CP437,
+/// como indica la especificación, en lugar de abortar la extracción.
+fn entry_name(filename: &async_zip::ZipString) -> Cow<'_, str> {
+    if let Ok(name) = filename.as_str() {
+        return Cow::Borrowed(name);
+    }
+    let name: String = filename
+        .as_bytes()
+        .iter()
+        .map(|&byte| match byte {
+            0..0x80 => char::from(byte),
+            _ => CP437_HIGH
+                .chars()
+                .nth(usize::from(byte - 0x80))
+                .unwrap_or(char::REPLACEMENT_CHARACTER),
+        })
+        .collect();
+    warn!("Zip entry name is not valid UTF-8, decoded as CP437: {name}");
+    Cow::Owned(name)
+}
+
+/// Extrae un zip de forma segura. `target` decide la ruta relativa de salida
+/// de cada entrada, o `None` para omitirla.
+pub(crate) async fn extract_zip(
+    zip_path: &Path,
+    destino: &Path,
+    target: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<ExtractedFile>, ProtonError> {
+    // Abrir zip
+    let fs = file_system();
+    let mut reader = ZipFileReader::with_tokio(BufReader::new(fs.open(zip_path).await?)).await?;
+    let mut extraidos = Vec::new();
+
+    for i in 0..reader.file().entries().len() {
+        let entry = &reader.file().entries()[i];
+        let nombre = entry_name(entry.filename());
+
+        let Some(relativa) = target(&nombre) else {
+            continue;
+        };
+
+        // Un nombre raro no impide extraer el resto; salirse del destino sí
+        if !is_safe_relative_path(&relativa) && !escapes_base(&relativa) {
+            warn!("Skipping zip entry with a name not valid on this system: {relativa:?}");
+            continue;
+        }
+
+        let crc32 = entry.crc32();
+        let permisos = entry.unix_permissions();
+        let ruta_salida = safe_output_path(destino, &relativa).await?;
+
+        // Copiar la entrada en streaming, sin fiarse del tamaño declarado
+        let mut entry_reader = reader.reader_with_entry(i).await?;
+        let mut archivo = fs.create(&ruta_salida).await?;
+        let copiado = async {
+            let size = tokio::io::copy(&mut (&mut entry_reader).compat(), &mut archivo).await?;
+            archivo.shutdown().await?;
+            Ok::<u64, ProtonError>(size)
+        }
+        .await;
+
+        let size = match copiado {
+            Ok(size) if entry_reader.compute_hash() == crc32 => size,
+            Ok(_) => {
+                let _ = fs.remove_file(&ruta_salida).await;
+                return Err(async_zip::error::ZipError::CRC32CheckError.into());
+            }
+            Err(e) => {
+                let _ = fs.remove_file(&ruta_salida).await;
+                return Err(e);
+            }
+        };
+
+        if let Some(mode) = permisos {
+            apply_unix_permissions(&ruta_salida, mode).await?;
+        }
+        if durable_writes() {
+            fs.sync(&ruta_salida).await?;
+        }
+
+        extraidos.push(ExtractedFile {
+            path: relativa,
+            size,
+        });
+    }
+
+    Ok(extraidos)
+}
+
+/// Reenvía cada línea de `reader` por el canal hasta EOF. Nunca espera al
+/// receptor: si el canal está lleno o cerrado la línea se descarta, para que
+/// el proceso no se bloquee al escribir. Las líneas que no son UTF-8 se
+/// decodifican con pérdida.
+pub(crate) fn forward_lines<R>(reader: R, tx: tokio::sync::mpsc::Sender<String>)
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(reader);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let line = String::from_utf8_lossy(strip_line_ending(&buffer)).into_owned();
+            let _ = tx.try_send(line);
+        }
+    });
+}
+
+fn strip_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{
+        FileMetadata, FileReader, FileSystem, FileWriter, MemoryFs, set_durable_writes,
+        with_file_system,
+    };
+    use crate::testing::{exclusive, sha1};
+    use async_zip::tokio::write::ZipFileWriter;
+    use async_zip::{Compression, StringEncoding, ZipEntryBuilder, ZipString};
+    use futures::future::BoxFuture;
+    use std::sync::{Arc, Mutex};
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// `MemoryFs` que anota las llamadas a `sync`, `allocate`, `rename` y
+    /// `set_permissions`, y aparte los archivos abiertos para leer. Con
+    /// `cross_device` cada directorio raíz es un dispositivo distinto y
+    /// `rename` falla entre ellos.
+    #[derive(Default)]
+    struct RecordingFs {
+        inner: MemoryFs,
+        calls: Mutex<Vec<String>>,
+        opened: Mutex<Vec<PathBuf>>,
+        cross_device: bool,
+    }
+
+    impl RecordingFs {
+        fn record(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl FileSystem for RecordingFs {
+        fn create_dir_all<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, std::io::Result<()>> {
+            self.inner.create_dir_all(path)
+        }
+
+        fn create<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, std::io::Result<FileWriter>> {
+            self.inner.create(path)
+        }
+
+        fn open<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, std::io::Result<FileReader>> {
+            self.opened.lock().unwrap().push(path.to_path_buf());
+            self.inner.open(path)
+        }
+
+        fn write<'a>(
+            &'a self,
+            path: &'a Path,
+            contents: &'a [u8],
+        ) -> BoxFuture<'a, std::io::Result<()>> {
+            self.inner.write(path, contents)
+        }
+
+        fn rename<'a>(
+            &'a self,
+            from: &'a Path,
+            to: &'a Path,
+        ) -> BoxFuture<'a, std::io::Result<()>> {
+            self.record(format!("rename {} {}", from.display(), to.display()));
+            let device = |path: &'a Path| path.components().nth(1);
+            if self.cross_device && device(from) != device(to) {
+                return Box::pin(async { Err(std::io::ErrorKind::CrossesDevices.into()) });
+            }
+            self.inner.rename(from, to)
+        }
+
+        fn remove_file<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, std::io::Result<()>> {
+            self.inner.remove_file(path)
+        }
+
+        fn remove_dir_all<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, std::io::Result<()>> {
+            self.inner.remove_dir_all(path)
+        }
+
+        fn metadata<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, std::io::Result<FileMetadata>> {
+            self.inner.metadata(path)
+        }
+
+        fn sync<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, std::io::Result<()>> {
+            self.record(format!("sync {}", path.display()));
+            self.inner.sync(path)
+        }
+
+        fn allocate<'a>(&'a self, path: &'a Path, len: u64) -> BoxFuture<'a, std::io::Result<()>> {
+            self.record(format!("allocate {} {len}", path.display()));
+            self.inner.allocate(path, len)
+        }
+
+        fn set_permissions<'a>(
+            &'a self,
+            path: &'a Path,
+            mode: u32,
+        ) -> BoxFuture<'a, std::io::Result<()>> {
+            self.record(format!("chmod {} {mode:o}", path.display()));
+            self.inner.set_permissions(path, mode)
+        }
+    }
+
+    /// Servidor que responde `body` a cualquier petición
+    async fn serve(body: &[u8]) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body.to_vec()))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[test]
+    fn decodes_non_utf8_entry_names_as_cp437() {
+        let raw = ZipString::new(
+            b"mods/caf\x82/Espa\xa4a \xe1.txt".to_vec(),
+            StringEncoding::Raw,
+        );
+        assert_eq!(entry_name(&raw), "mods/café/España ß.txt");
+
+        let utf8 = ZipString::new("mods/café.txt".as_bytes().to_vec(), StringEncoding::Utf8);
+        assert!(matches!(entry_name(&utf8), Cow::Borrowed("mods/café.txt")));
+    }
+
+    #[tokio::test]
+    async fn forward_lines_never_blocks_on_a_full_channel() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(3);
+        let output: &[u8] = b"first\r\nsecond\ninvalid \xff\nthird\n";
+        forward_lines(output, tx);
+
+        // Sin nadie leyendo, el reenvío termina y descarta lo que no cabe
+        let mut lines = Vec::new();
+        while let Some(line) = rx.recv().await {
+            lines.push(line);
+        }
+        assert_eq!(lines, ["first", "second", "invalid \u{fffd}"]);
+    }
+
+    #[tokio::test]
+    async fn only_missing_hashes_skip_verification() {
+        let _guard = exclusive().await;
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"library".to_vec()))
+            .mount(&server)
+            .await;
+        let url = format!("{}/library.jar", server.uri());
+        let fs = MemoryFs::new();
+
+        let (empty, missing, published) = with_file_system(Arc::new(fs.clone()), async {
+            let empty = download_file(url.clone(), &PathBuf::from("/a.jar"), Some(""), 0).await;
+            let missing = download_file(url.clone(), &PathBuf::from("/b.jar"), None, 0).await;
+            let hash = sha1(b"library");
+            let published =
+                download_file(url.clone(), &PathBuf::from("/c.jar"), Some(&hash), 7).await;
+            (empty, missing, published)
+        })
+        .await;
+
+        assert!(empty.is_err());
+        assert!(fs.get("/a.jar").is_none());
+        missing.unwrap();
+        published.unwrap();
+        assert_eq!(fs.get("/b.jar").as_deref(), Some(&b"library"[..]));
+        assert_eq!(fs.get("/c.jar").as_deref(), Some(&b"library"[..]));
+    }
+
+    #[tokio::test]
+    async fn durable_writes_sync_the_file_before_moving_it_and_then_its_directory() {
+        let _guard = exclusive().await;
+        let server = serve(b"library").await;
+        let url = format!("{}/library.jar", server.uri());
+        let hash = sha1(b"library");
+        let download = |durable: bool| {
+            let (url, hash) = (url.clone(), hash.clone());
+            async move {
+                let fs = Arc::new(RecordingFs::default());
+                fs.create_dir_all(Path::new("/libs")).await.unwrap();
+                set_durable_writes(durable);
+                let path = PathBuf::from("/libs/library.jar");
+                let result =
+                    with_file_system(fs.clone(), download_file(url, &path, Some(&hash), 7)).await;
+                set_durable_writes(false);
+                result.unwrap();
+                assert_eq!(fs.inner.get(&path).as_deref(), Some(&b"library"[..]));
+                fs.calls()
+            }
+        };
+
+        let calls = download(true).await;
+        let syncs: Vec<_> = calls
+            .iter()
+            .filter(|call| !call.starts_with("allocate"))
+            .collect();
+        let [sync_temp, rename, sync_dir] = &syncs[..] else {
+            panic!("unexpected calls: {calls:?}");
+        };
+        let temp = sync_temp.strip_prefix("sync ").unwrap();
+        assert!(temp.starts_with("/libs/") && temp != "/libs/library.jar");
+        assert_eq!(*rename, &format!("rename {temp} /libs/library.jar"));
+        assert_eq!(*sync_dir, "sync /libs");
+
+        let calls = download(false).await;
+        assert!(
+            !calls.iter().any(|call| call.starts_with("sync")),
+            "{calls:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn verified_downloads_reserve_the_published_size() {
+        let server = serve(b"library").await;
+        let hash = sha1(b"library");
+        let download = |hash: Option<String>, size: u64| {
+            let url = format!("{}/library.jar", server.uri());
+            async move {
+                let fs = Arc::new(RecordingFs::default());
+                let path = PathBuf::from("/libs/library.jar");
+                fs.create_dir_all(Path::new("/libs")).await.unwrap();
+                with_file_system(fs.clone(), download_file(url, &path, hash.as_deref(), size))
+                    .await
+                    .unwrap();
+                assert_eq!(fs.inner.get(&path).as_deref(), Some(&b"library"[..]));
+                fs.calls()
+                    .into_iter()
+                    .filter_map(|call| {
+                        let len = call.strip_prefix("allocate ")?.rsplit_once(' ')?.1;
+                        len.parse::<u64>().ok()
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(download(Some(hash.clone()), 7).await, [7]);
+        // Si el tamaño publicado sobraba, la cola reservada se recorta
+        assert_eq!(download(Some(hash), 64).await, [64, 7]);
+        // Sin hash no se puede confiar en el tamaño y no se reserva nada
+        assert!(download(None, 7).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn natives_skip_excluded_entries_and_jar_junk() {
+        let mut writer = ZipFileWriter::with_tokio(Vec::new());
+        for name in [
+            "META-INF/MANIFEST.MF",
+            "module-info.class",
+            "LICENSE.txt",
+            "org/excluded/Skipped.class",
+            "lib/linux/",
+            "lib/linux/liblwjgl.so",
+            "lib/linux/liblwjgl.so.sha1",
+            "org/lwjgl/Version.class",
+        ] {
+            writer
+                .write_entry_whole(
+                    ZipEntryBuilder::new(name.to_string().into(), Compression::Stored),
+                    name.as_bytes(),
+                )
+                .await
+                .unwrap();
+        }
+        let jar = writer.close().await.unwrap().into_inner();
+        let fs = MemoryFs::new();
+        fs.write(Path::new("/natives.jar"), &jar).await.unwrap();
+        fs.create_dir_all(Path::new("/natives")).await.unwrap();
+
+        let extracted = with_file_system(
+            Arc::new(fs.clone()),
+            extract_native(
+                Path::new("/natives.jar"),
+                Path::new("/natives"),
+                &["org/excluded/".to_string()],
+            ),
+        )
+        .await
+        .unwrap();
+
+        let paths: Vec<_> = extracted.iter().map(|file| file.path.as_str()).collect();
+        // Las bibliotecas nativas se aplanan; el resto conserva su ruta
+        assert_eq!(paths, ["liblwjgl.so", "org/lwjgl/Version.class"]);
+        assert_eq!(
+            fs.get(Path::new("/natives/liblwjgl.so")).as_deref(),
+            Some(&b"lib/linux/liblwjgl.so"[..])
+        );
+        for skipped in ["module-info.class", "LICENSE.txt", "META-INF/MANIFEST.MF"] {
+            assert!(fs.get(Path::new("/natives").join(skipped)).is_none());
+        }
+        assert!(
+            fs.get(Path::new("/natives/org/excluded/Skipped.class"))
+                .is_none()
+        );
+    }
+
+    /// Zip en memoria con las entradas dadas
+    async fn zip(entries: &[(&str, &[u8], Compression)]) -> Vec<u8> {
+        let mut writer = ZipFileWriter::with_tokio(Vec::new());
+        for (name, content, compression) in entries {
+            writer
+                .write_entry_whole(
+                    ZipEntryBuilder::new(name.to_string().into(), *compression),
+                    content,
+                )
+                .await
+                .unwrap();
+        }
+        writer.close().await.unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn zip_entries_are_streamed_and_checked_against_their_crc() {
+        let big = vec![b'x'; 4 << 20];
+        let stored = b"stored entry contents";
+        let jar = zip(&[
+            ("big.bin", &big, Compression::Deflate),
+            ("stored.bin", stored, Compression::Stored),
+        ])
+        .await;
+        let fs = MemoryFs::new();
+        fs.write(Path::new("/good.jar"), &jar).await.unwrap();
+
+        // Un byte alterado en los datos de la entrada sin comprimir
+        let mut corrupted = jar.clone();
+        let offset = corrupted
+            .windows(stored.len())
+            .position(|window| window == stored)
+            .unwrap();
+        corrupted[offset] ^= 0xff;
+        fs.write(Path::new("/bad.jar"), &corrupted).await.unwrap();
+
+        let (good, bad) = with_file_system(Arc::new(fs.clone()), async {
+            let good = extract_zip(Path::new("/good.jar"), Path::new("/good"), |name| {
+                Some(name.to_string())
+            })
+            .await;
+            let bad = extract_zip(Path::new("/bad.jar"), Path::new("/bad"), |name| {
+                Some(name.to_string())
+            })
+            .await;
+            (good, bad)
+        })
+        .await;
+
+        let sizes: Vec<_> = good.unwrap().iter().map(|file| file.size).collect();
+        assert_eq!(sizes, [big.len() as u64, stored.len() as u64]);
+        assert_eq!(fs.get("/good/big.bin"), Some(big));
+        assert!(bad.is_err());
+        // La entrada corrupta no se queda a medias en el destino
+        assert!(fs.get("/bad/stored.bin").is_none());
+    }
+
+    #[tokio::test]
+    async fn entries_cannot_escape_the_destination() {
+        let fs = MemoryFs::new();
+        for (i, name) in ["../../evil.class", "/etc/evil.class", "..\\evil.class"]
+            .into_iter()
+            .enumerate()
+        {
+            let jar = PathBuf::from(format!("/evil-{i}.jar"));
+            fs.write(&jar, &zip(&[(name, b"evil", Compression::Stored)]).await)
+                .await
+                .unwrap();
+            let result = with_file_system(
+                Arc::new(fs.clone()),
+                extract_native(&jar, Path::new("/game/natives"), &[]),
+            )
+            .await;
+            assert!(
+                matches!(result, Err(ProtonError::UnsafeArchivePath(ref path)) if path == name),
+                "{name}: {result:?}"
+            );
+        }
+        assert!(fs.get("/evil.class").is_none());
+        assert!(fs.get("/etc/evil.class").is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn symlinks_cannot_lead_entries_out_of_the_destination() {
+        let root = std::env::temp_dir().join(format!("proton-zip-slip-{}", std::process::id()));
+        let natives = root.join("natives");
+        let outside = root.join("outside");
+        std::fs::create_dir_all(&natives).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, natives.join("link")).unwrap();
+        let jar = root.join("evil.jar");
+        std::fs::write(
+            &jar,
+            zip(&[("link/evil.class", b"evil", Compression::Stored)]).await,
+        )
+        .unwrap();
+
+        let result = extract_native(&jar, &natives, &[]).await;
+
+        let escaped = outside.join("evil.class").exists();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(
+            matches!(result, Err(ProtonError::UnsafeArchivePath(_))),
+            "{result:?}"
+        );
+        assert!(!escaped);
+    }
+
+    #[tokio::test]
+    async fn unix_permission_bits_are_applied_on_extraction() {
+        let mut writer = ZipFileWriter::with_tokio(Vec::new());
+        for (name, mode) in [("bin/helper", Some(0o100755)), ("plain.txt", None)] {
+            let mut builder = ZipEntryBuilder::new(name.to_string().into(), Compression::Stored);
+            if let Some(mode) = mode {
+                builder = builder.unix_permissions(mode);
+            }
+            writer
+                .write_entry_whole(builder, b"#!/bin/sh")
+                .await
+                .unwrap();
+        }
+        let jar = writer.close().await.unwrap().into_inner();
+        let fs = Arc::new(RecordingFs::default());
+        fs.write(Path::new("/archive.zip"), &jar).await.unwrap();
+
+        with_file_system(
+            fs.clone(),
+            extract_zip(Path::new("/archive.zip"), Path::new("/out"), |name| {
+                Some(name.to_string())
+            }),
+        )
+        .await
+        .unwrap();
+
+        // Solo se aplican los bits de permisos, sin el tipo de archivo
+        let chmods: Vec<_> = fs
+            .calls()
+            .into_iter()
+            .filter(|call| call.starts_with("chmod"))
+            .collect();
+        assert_eq!(chmods, ["chmod /out/bin/helper 755"]);
+    }
+
+    #[tokio::test]
+    async fn moves_across_devices_copy_and_sync_instead() {
+        // Sin escrituras duraderas, que añadirían la sincronización del directorio
+        let _guard = exclusive().await;
+        let fs = Arc::new(RecordingFs {
+            cross_device: true,
+            ..Default::default()
+        });
+        for dir in ["/cache", "/game"] {
+            fs.create_dir_all(Path::new(dir)).await.unwrap();
+        }
+        fs.write(Path::new("/cache/client.jar"), b"client")
+            .await
+            .unwrap();
+        fs.write(Path::new("/game/staged.jar"), b"staged")
+            .await
+            .unwrap();
+
+        with_file_system(fs.clone(), async {
+            move_file(
+                Path::new("/cache/client.jar"),
+                Path::new("/game/client.jar"),
+            )
+            .await?;
+            move_file(Path::new("/game/staged.jar"), Path::new("/game/final.jar")).await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            fs.inner.get("/game/client.jar").as_deref(),
+            Some(&b"client"[..])
+        );
+        assert_eq!(
+            fs.inner.get("/game/final.jar").as_deref(),
+            Some(&b"staged"[..])
+        );
+        assert_eq!(fs.inner.files().len(), 2, "{:?}", fs.inner.files());
+        let calls = fs.calls();
+        let [failed, sync, rename, same_device] = calls.as_slice() else {
+            panic!("{calls:?}");
+        };
+        assert_eq!(failed, "rename /cache/client.jar /game/client.jar");
+        // La copia se vuelca al disco antes de ocupar el nombre final
+        let temp = sync.strip_prefix("sync ").unwrap();
+        assert!(temp.starts_with("/game/"), "{temp}");
+        assert_eq!(*rename, format!("rename {temp} /game/client.jar"));
+        assert_eq!(same_device, "rename /game/staged.jar /game/final.jar");
+    }
+
+    #[tokio::test]
+    async fn only_files_of_the_right_size_are_hashed() {
+        let fs = Arc::new(RecordingFs::default());
+        let path = Path::new("/client.jar");
+        fs.write(path, b"client").await.unwrap();
+        let hash = sha1(b"client");
+
+        let checks = with_file_system(fs.clone(), async {
+            let mut checks = Vec::new();
+            for (expected, size) in [(&hash, 999), (&hash, 6), (&sha1(b"other"), 6), (&hash, 0)] {
+                let valid = verify_file_hash(path, expected, size).await?;
+                let hashed = std::mem::take(&mut *fs.opened.lock().unwrap());
+                checks.push((valid, !hashed.is_empty()));
+            }
+            Ok::<_, ProtonError>(checks)
+        })
+        .await
+        .unwrap();
+
+        // (válido, se leyó el archivo): sin tamaño publicado se calcula el hash
+        assert_eq!(
+            checks,
+            [(false, false), (true, true), (false, true), (true, true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn local_files_hash_like_in_memory_ones() {
+        let content = vec![7u8; 200 * 1024];
+        let path = std::env::temp_dir().join(format!("proton-sha1-{}", std::process::id()));
+        std::fs::write(&path, &content).unwrap();
+        let memory = MemoryFs::new();
+        memory
+            .write(Path::new("/client.jar"), &content)
+            .await
+            .unwrap();
+
+        // En disco local el hash se calcula en el pool bloqueante
+        let local = file_sha1(&path).await.unwrap();
+        let in_memory = with_file_system(Arc::new(memory), file_sha1(Path::new("/client.jar")))
+            .await
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(local, sha1(&content));
+        assert_eq!(in_memory, local);
+    }
+
+    #[tokio::test]
+    async fn mismatches_report_both_hashes_and_can_be_quarantined() {
+        let _guard = exclusive().await;
+        let server = serve(b"library").await;
+        let url = format!("{}/library.jar", server.uri());
+        let expected = sha1(b"other");
+        let fs = MemoryFs::new();
+        fs.create_dir_all(Path::new("/libs")).await.unwrap();
+        let target = PathBuf::from("/libs/library.jar");
+        let download = || {
+            with_file_system(
+                Arc::new(fs.clone()),
+                download_file(url.clone(), &target, Some(&expected), 7),
+            )
+        };
+
+        set_quarantine_dir(Some(PathBuf::from("/quarantine")));
+        let kept = download().await;
+        set_quarantine_dir(None);
+        let discarded = download().await;
+
+        let Err(ProtonError::HashMismatch {
+            expected: reported,
+            actual,
+            bytes,
+            quarantined: Some(quarantined),
+        }) = kept
+        else {
+            panic!("expected a quarantined mismatch, got {kept:?}");
+        };
+        assert_eq!((reported, bytes), (expected, 7));
+        assert_eq!(actual, sha1(b"library"));
+        assert_eq!(
+            quarantined,
+            Path::new("/quarantine").join(format!("{actual}-library.jar"))
+        );
+        assert_eq!(fs.get(&quarantined).as_deref(), Some(&b"library"[..]));
+
+        assert!(matches!(
+            discarded,
+            Err(ProtonError::HashMismatch {
+                quarantined: None,
+                ..
+            })
+        ));
+        // Ni el destino ni los temporales quedan en el directorio
+        assert!(fs.read_dir(Path::new("/libs")).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+use crate::filesystem::FileMetadata;
+use crate::manifest::cache_dir;
+use crate::utilities::temp_path;
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Archivo de la caché dentro del directorio de caché de metadatos
+const CACHE_FILE: &str = "file-hashes.json";
+
+// Un archivo escrito hace menos de esto aún puede cambiar sin que cambie su
+// fecha de modificación (resolución de la marca de tiempo), así que no se guarda
+const MIN_AGE: Duration = Duration::from_secs(2);
+
+/// Último SHA-1 calculado de un archivo, válido mientras no cambien su
+/// tamaño ni su fecha de modificación
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+    sha1: String,
+}
+
+impl Entry {
+    fn matches(&self, size: u64, modified: Duration) -> bool {
+        self.size == size
+            && self.modified_secs == modified.as_secs()
+            && self.modified_nanos == modified.subsec_nanos()
+    }
+}
+
+/// Tamaño y fecha de modificación con los que se valida un hash guardado
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FileStamp {
+    len: u64,
+    modified: Duration,
+    local: bool,
+}
+
+impl FileStamp {
+    /// Archivo del disco local; su hash se guarda en `file-hashes.json`
+    pub(crate) fn local(metadata: &Metadata) -> Option<Self> {
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?,
+            local: true,
+        })
+    }
+
+    /// Archivo de otro `FileSystem`; su hash solo se recuerda en memoria
+    pub(crate) fn other(metadata: &FileMetadata) -> Option<Self> {
+        Some(Self {
+            len: metadata.len,
+            modified: metadata.modified?.duration_since(UNIX_EPOCH).ok()?,
+            local: false,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct HashCache {
+    /// Directorio del que se cargó; si cambia se vuelve a cargar
+    dir: Option<PathBuf>,
+    entries: HashMap<PathBuf, Entry>,
+    /// Hashes de archivos de otros sistemas de archivos, que no se persisten
+    other: HashMap<PathBuf, Entry>,
+    dirty: bool,
+}
+
+impl HashCache {
+    /// Carga la caché si aún no está cargada o cambió el directorio de caché
+    fn load(&mut self) {
+        let dir = cache_dir();
+        if self.dir.as_ref() == Some(&dir) {
+            return;
+        }
+        self.entries = std::fs::read(dir.join(CACHE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+        self.dir = Some(dir);
+        self.dirty = false;
+    }
+}
+
+static HASH_CACHE: Lazy<Mutex<HashCache>> = Lazy::new(|| Mutex::new(HashCache::default()));
+
+fn cache() -> std::sync::MutexGuard<'static, HashCache> {
+    let mut cache = HASH_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.load();
+    cache
+}
+
+fn key(path: &Path, stamp: FileStamp) -> PathBuf {
+    if !stamp.local {
+        return path.to_path_buf();
+    }
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// SHA-1 guardado para el archivo si no ha cambiado desde que se calculó.
+/// La primera llamada lee la caché del disco.
+pub(crate) fn cached_sha1(path: &Path, stamp: FileStamp) -> Option<String> {
+    let cache = cache();
+    let entries = if stamp.local {
+        &cache.entries
+    } else {
+        &cache.other
+    };
+    entries
+        .get(&key(path, stamp))
+        .filter(|entry| entry.matches(stamp.len, stamp.modified))
+        .map(|entry| entry.sha1.clone())
+}
+
+/// Guarda el SHA-1 recién calculado de un archivo
+pub(crate) fn remember_sha1(path: &Path, stamp: FileStamp, sha1: &str) {
+    let recent = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(true, |now| now.saturating_sub(stamp.modified) < MIN_AGE);
+    if recent {
+        return;
+    }
+
+    let entry = Entry {
+        size: stamp.len,
+        modified_secs: stamp.modified.as_secs(),
+        modified_nanos: stamp.modified.subsec_nanos(),
+        sha1: sha1.to_string(),
+    };
+    let mut cache = cache();
+    if !stamp.local {
+        cache.other.insert(key(path, stamp), entry);
+    } else if cache.entries.get(&key(path, stamp)) != Some(&entry) {
+        cache.entries.insert(key(path, stamp), entry);
+        cache.dirty = true;
+    }
+}
+
+/// Escribe la caché en disco si cambió, descartando los archivos que ya no
+/// existen
+pub(crate) async fn save_hash_cache() {
+    let result = tokio::task::spawn_blocking(|| {
+        let mut cache = cache();
+        if !cache.dirty {
+            return Ok(());
+        }
+        cache.entries.retain(|path, _| path.exists());
+
+        let Some(dir) = cache.dir.clone() else {
+            return Ok(());
+        };
+        let path = dir.join(CACHE_FILE);
+        let json = serde_json::to_vec(&cache.entries).map_err(std::io::Error::other)?;
+        std::fs::create_dir_all(&dir)?;
+        let temp = temp_path(&path);
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, &path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp);
+        })?;
+        cache.dirty = false;
+        Ok::<(), std::io::Error>(())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("Failed to save hash cache: {e}"),
+        Err(e) => warn!("Failed to save hash cache: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ProtonError;
+    use crate::filesystem::{FileSystem, MemoryFs, with_file_system};
+    use crate::testing::{exclusive, isolate_cache_dir, sha1};
+    use crate::utilities::file_sha1;
+    use std::sync::Arc;
+
+    fn write_old(path: &Path, content: &[u8], age: Duration) -> FileStamp {
+        std::fs::write(path, content).unwrap();
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+        FileStamp::local(&file.metadata().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn hashes_are_reused_until_the_file_changes() {
+        // La caché es global: se usa un directorio propio mientras dura el test
+        let _guard = exclusive().await;
+        let cache = isolate_cache_dir();
+        std::fs::create_dir_all(cache.path()).unwrap();
+        let path = cache.path().join("client.jar");
+
+        let metadata = write_old(&path, b"original", Duration::from_secs(60));
+        assert_eq!(cached_sha1(&path, metadata), None);
+        assert_eq!(file_sha1(&path).await.unwrap(), sha1(b"original"));
+        assert_eq!(cached_sha1(&path, metadata), Some(sha1(b"original")));
+
+        // Mismo tamaño, otra fecha: el hash guardado ya no vale
+        let metadata = write_old(&path, b"modified", Duration::from_secs(30));
+        assert_eq!(cached_sha1(&path, metadata), None);
+        assert_eq!(file_sha1(&path).await.unwrap(), sha1(b"modified"));
+
+        // Lo recién escrito aún puede cambiar sin que cambie su fecha
+        let metadata = write_old(&path, b"recent", Duration::ZERO);
+        assert_eq!(file_sha1(&path).await.unwrap(), sha1(b"recent"));
+        assert_eq!(cached_sha1(&path, metadata), None);
+
+        save_hash_cache().await;
+        assert!(cache.path().join(CACHE_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn other_file_systems_use_their_modification_times() {
+        let _guard = exclusive().await;
+        let cache = isolate_cache_dir();
+        let fs = MemoryFs::new();
+        let path = Path::new("/hash-cache/client.jar");
+        let old = SystemTime::now() - Duration::from_secs(60);
+
+        let hashes = with_file_system(Arc::new(fs.clone()), async {
+            fs.create_dir_all(path.parent().unwrap()).await?;
+            fs.write(path, b"original").await?;
+            fs.set_modified(path, old)?;
+            let original = file_sha1(path).await?;
+
+            // Mismo tamaño y misma fecha: se da por no modificado
+            fs.write(path, b"modified").await?;
+            fs.set_modified(path, old)?;
+            let cached = file_sha1(path).await?;
+
+            fs.set_modified(path, old + Duration::from_secs(1))?;
+            let rehashed = file_sha1(path).await?;
+            Ok::<_, ProtonError>([original, cached, rehashed])
+        })
+        .await
+        .unwrap();
+        save_hash_cache().await;
+
+        assert_eq!(
+            hashes,
+            [sha1(b"original"), sha1(b"original"), sha1(b"modified")]
+        );
+        // Solo se persisten los hashes del disco local
+        assert!(!cache.path().join(CACHE_FILE).exists());
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::errors::ProtonError;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::path::{Component, Path};
+
+// Caracteres que Windows no admite en nombres de archivo
+const WINDOWS_INVALID_CHARS: [char; 7] = ['<', '>', ':', '"', '|', '?', '*'];
+
+// Nombres de dispositivo reservados en Windows, con o sin extensión
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Si un segmento de ruta se puede crear en el sistema actual
+fn is_valid_segment(segment: &str) -> bool {
+    if segment.is_empty() || segment == "." || segment == ".." || segment.contains('\0') {
+        return false;
+    }
+    if cfg!(windows) {
+        let stem = segment.split('.').next().unwrap_or(segment);
+        if segment
+            .chars()
+            .any(|c| c.is_control() || WINDOWS_INVALID_CHARS.contains(&c))
+            || segment.ends_with(['.', ' '])
+            || WINDOWS_RESERVED_NAMES
+                .iter()
+                .any(|name| stem.eq_ignore_ascii_case(name))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// Comprueba una ruta relativa con `/` leída de metadatos o de un archivo:
+/// sin rutas absolutas, segmentos `.`/`..`, `\` ni caracteres que el sistema
+/// no admita
+pub(crate) fn is_safe_relative_path(relative: &str) -> bool {
+    !relative.contains('\\')
+        && relative.split('/').all(is_valid_segment)
+        && Path::new(relative)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Si la ruta se sale de su directorio (absoluta, con `..` o con `\`), a
+/// diferencia de las que solo tienen nombres que el sistema no admite
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn escapes_base(relative: &str) -> bool {
+    relative.contains('\\')
+        || Path::new(relative)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Une una ruta relativa de metadatos a `base` sin salirse de ella
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn safe_join(base: &Path, relative: &str) -> Result<PathBuf, ProtonError> {
+    if is_safe_relative_path(relative) {
+        Ok(base.join(relative))
+    } else {
+        Err(ProtonError::UnsafePath(relative.to_string()))
+    }
+}
+
+/// Comprueba que una ruta ya unida no vuelva atrás con `..`
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn check_no_parent_dirs(path: &Path) -> Result<(), ProtonError> {
+    if path
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        return Err(ProtonError::UnsafePath(path.display().to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_relative_paths() {
+        for relative in [
+            "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3.jar",
+            "minecraft/sounds/ambient/cave/cave1.ogg",
+            "file.name.with.dots",
+        ] {
+            assert!(is_safe_relative_path(relative), "{relative:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_paths_leaving_the_base() {
+        for relative in [
+            "",
+            "/etc/passwd",
+            "../outside",
+            "libs/../../outside",
+            "libs/./file",
+            "libs//file",
+            "libs\\file",
+            "nul\0byte",
+        ] {
+            assert!(!is_safe_relative_path(relative), "{relative:?}");
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn rejects_names_windows_cannot_create() {
+        for relative in ["CON", "aux.txt", "libs/a:b", "trailing.", "trailing "] {
+            assert!(!is_safe_relative_path(relative), "{relative:?}");
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn safe_join_stays_inside_base() {
+        let base = Path::new("game").join("libraries");
+        assert_eq!(
+            safe_join(&base, "org/proton/lib.jar").unwrap(),
+            base.join("org/proton/lib.jar")
+        );
+        assert!(matches!(
+            safe_join(&base, "../versions/evil.jar"),
+            Err(ProtonError::UnsafePath(path)) if path == "../versions/evil.jar"
+        ));
+        assert!(escapes_base("../x") && escapes_base("a\\b") && !escapes_base("CON"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Pruebas del binario `proton` contra `MockMinecraftServer`
+
+use proton::testing::{FIXTURE_VERSION, MockMinecraftServer};
+use proton::{Instance, InstanceConfig, ModLoader};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::process::Command;
+
+const EXIT_ERROR: i32 = 1;
+const EXIT_BROKEN_FILES: i32 = 3;
+
+/// Servidor simulado, `config.toml` que apunta a él y directorios temporales
+/// propios para el juego y la caché
+struct Cli {
+    _server: MockMinecraftServer,
+    root: PathBuf,
+}
+
+impl Cli {
+    async fn start(extra_config: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "proton-cli-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let server = MockMinecraftServer::start().await;
+        let endpoints = server.endpoints();
+        let config = format!(
+            "game_dir = {:?}\n{extra_config}\n\
+             [mirrors]\nmanifest = {:?}\nresources = {:?}\nlibraries = {:?}\n",
+            root.join("game"),
+            endpoints.manifest_url,
+            endpoints.resources_base_url,
+            endpoints.libraries_base_url
+        );
+        std::fs::write(root.join("config.toml"), config).unwrap();
+        Self {
+            _server: server,
+            root,
+        }
+    }
+
+    fn game_dir(&self) -> PathBuf {
+        self.root.join("game")
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_proton"));
+        command
+            .arg("--config")
+            .arg(self.root.join("config.toml"))
+            .args(args)
+            .env("XDG_CACHE_HOME", self.root.join("cache"))
+            .env_remove("PROTON_GAME_DIR")
+            .env_remove("PROTON_MANIFEST_URL");
+        command
+    }
+
+    async fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().await.unwrap()
+    }
+
+    async fn run_json(&self, args: &[&str]) -> (i32, Value) {
+        let mut args = args.to_vec();
+        args.push("--json");
+        let output = self.run(&args).await;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let value = serde_json::from_str(stdout.trim()).unwrap_or_else(|e| {
+            panic!("{args:?} printed invalid JSON ({e}): {stdout}");
+        });
+        (output.status.code().unwrap(), value)
+    }
+}
+
+impl Drop for Cli {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+fn client_jar(game_dir: &Path) -> PathBuf {
+    game_dir
+        .join("versions")
+        .join(FIXTURE_VERSION)
+        .join(format!("{FIXTURE_VERSION}.jar"))
+}
+
+#[tokio::test]
+async fn versions_lists_the_manifest() {
+    let cli = Cli::start("").await;
+
+    let (code, versions) = cli.run_json(&["versions"]).await;
+
+    assert_eq!(code, 0);
+    assert_eq!(versions[0]["id"], FIXTURE_VERSION);
+}
+
+#[tokio::test]
+async fn install_verify_and_repair_report_through_exit_codes() {
+    let cli = Cli::start("").await;
+
+    let (code, installed) = cli.run_json(&["install", FIXTURE_VERSION]).await;
+    assert_eq!(code, 0);
+    assert_eq!(installed["version"], FIXTURE_VERSION);
+    assert!(client_jar(&cli.game_dir()).exists());
+
+    let (code, report) = cli.run_json(&["verify", FIXTURE_VERSION]).await;
+    assert_eq!(code, 0);
+    assert_eq!(report, json!({ "files": [] }));
+
+    std::fs::write(client_jar(&cli.game_dir()), b"corrupted").unwrap();
+    let (code, report) = cli.run_json(&["verify", FIXTURE_VERSION]).await;
+    assert_eq!(code, EXIT_BROKEN_FILES);
+    assert_eq!(report["files"].as_array().unwrap().len(), 1);
+
+    let (code, report) = cli.run_json(&["repair", FIXTURE_VERSION]).await;
+    assert_eq!(code, 0);
+    assert_eq!(report, json!({ "repaired": 1 }));
+    let (code, _) = cli.run_json(&["verify", FIXTURE_VERSION]).await;
+    assert_eq!(code, 0);
+}
+
+#[tokio::test]
+async fn errors_exit_with_one_and_a_code() {
+    let cli = Cli::start("").await;
+
+    let (code, error) = cli.run_json(&["install", "does-not-exist"]).await;
+    assert_eq!(code, EXIT_ERROR);
+    assert!(error["code"].is_string());
+
+    // Los errores de uso los informa clap con su propio código
+    let output = cli.run(&["verify", FIXTURE_VERSION, "--dir"]).await;
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[tokio::test]
+async fn empty_environment_variables_are_ignored() {
+    let cli = Cli::start("").await;
+
+    let output = cli
+        .command(&["install", FIXTURE_VERSION])
+        .env("PROTON_GAME_DIR", "")
+        .env("PROTON_MANIFEST_URL", "")
+        .env("PROTON_RESOURCES_URL", "")
+        .output()
+        .await
+        .unwrap();
+
+    assert!(output.status.success(), "{output:?}");
+    assert!(client_jar(&cli.game_dir()).exists());
+}
+
+#[tokio::test]
+async fn launch_dry_run_uses_the_loader_version_and_config_memory() {
+    let cli = Cli::start("max_memory_mb = 3072").await;
+    let forge_id = format!("{FIXTURE_VERSION}-forge-47.1.0");
+    let version_dir = cli.game_dir().join("versions").join(&forge_id);
+    std::fs::create_dir_all(&version_dir).unwrap();
+    let forge = json!({
+        "id": forge_id,
+        "inheritsFrom": FIXTURE_VERSION,
+        "type": "release",
+        "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+        "libraries": [],
+    });
+    std::fs::write(
+        version_dir.join(format!("{forge_id}.json")),
+        forge.to_string(),
+    )
+    .unwrap();
+    let instance_dir = cli.root.join("instance");
+    let config = InstanceConfig::new("Forge", FIXTURE_VERSION).loader(ModLoader::Forge, "47.1.0");
+    Instance::create(&instance_dir, config).await.unwrap();
+
+    let (code, command) = cli
+        .run_json(&["launch", instance_dir.to_str().unwrap(), "--dry-run"])
+        .await;
+
+    assert_eq!(code, 0);
+    let args: Vec<_> = command["args"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    assert!(args.contains(&"cpw.mods.bootstraplauncher.BootstrapLauncher"));
+    assert!(args.contains(&"-Xmx3072M"));
+}
+
+#[tokio::test]
+async fn launch_fails_when_the_loader_is_not_installed() {
+    let cli = Cli::start("").await;
+    let instance_dir = cli.root.join("instance");
+    let config = InstanceConfig::new("Forge", FIXTURE_VERSION).loader(ModLoader::Forge, "47.1.0");
+    Instance::create(&instance_dir, config).await.unwrap();
+
+    let (code, error) = cli
+        .run_json(&["launch", instance_dir.to_str().unwrap(), "--dry-run"])
+        .await;
+
+    assert_eq!(code, EXIT_ERROR);
+    assert_eq!(error["code"], "E_INVALID_INSTANCE");
+}